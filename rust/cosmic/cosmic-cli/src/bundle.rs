@@ -0,0 +1,179 @@
+use cosmic_space::artifact::manifest::{BundleManifest, MANIFEST_PATH, MANIFEST_SIGNATURE_PATH};
+use cosmic_space::err::SpaceErr;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// lists the files of a bundle directory that are left out of the published bundle
+pub const IGNORE_FILE: &str = ".cosmicignore";
+
+/// patterns from the `.cosmicignore` of a bundle directory, one per line (`#` starts a comment).
+/// A pattern containing a `/` is matched against the path relative to the bundle directory,
+/// otherwise against every file & directory name. `*` matches any run of characters and a
+/// trailing `/` only matches directories
+pub struct Ignore {
+    patterns: Vec<String>,
+}
+
+impl Ignore {
+    pub fn load(dir: &Path) -> Result<Self, SpaceErr> {
+        let mut patterns = vec![IGNORE_FILE.to_string()];
+        match fs::read_to_string(dir.join(IGNORE_FILE)) {
+            Ok(src) => patterns.extend(
+                src.lines()
+                    .map(|line| line.trim())
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(|line| line.trim_start_matches('/').to_string()),
+            ),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        Ok(Self { patterns })
+    }
+
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.patterns.iter().any(|pattern| {
+            let (pattern, dir_only) = match pattern.strip_suffix('/') {
+                Some(pattern) => (pattern, true),
+                None => (pattern.as_str(), false),
+            };
+            if dir_only && !is_dir {
+                return false;
+            }
+            if pattern.contains('/') {
+                glob(pattern, path)
+            } else {
+                glob(pattern, name)
+            }
+        })
+    }
+}
+
+fn glob(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => match text.strip_prefix(prefix) {
+            None => false,
+            Some(text) => (0..=text.len())
+                .filter(|index| text.is_char_boundary(*index))
+                .any(|index| glob(rest, &text[index..])),
+        },
+    }
+}
+
+/// the files under `dir` keyed by their `/` separated path relative to `dir`
+pub fn files(dir: &Path) -> Result<BTreeMap<String, Vec<u8>>, SpaceErr> {
+    let ignore = Ignore::load(dir)?;
+    let mut rtn = BTreeMap::new();
+    let mut dirs = vec![(dir.to_path_buf(), String::new())];
+    while let Some((path, prefix)) = dirs.pop() {
+        for entry in fs::read_dir(&path)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let relative = format!("{}{}", prefix, name);
+            let is_dir = entry.file_type()?.is_dir();
+            if ignore.is_ignored(relative.as_str(), is_dir) {
+                continue;
+            }
+            if is_dir {
+                dirs.push((entry.path(), format!("{}/", relative)));
+            } else {
+                rtn.insert(relative, fs::read(entry.path())?);
+            }
+        }
+    }
+    Ok(rtn)
+}
+
+/// zip the bundle directory `dir` in memory. A `bundle.manifest` is generated unless the
+/// directory carries a signed one (regenerating it would break the signature)
+pub fn zip(dir: &Path) -> Result<Vec<u8>, SpaceErr> {
+    let mut files = files(dir)?;
+    if !files.contains_key(MANIFEST_SIGNATURE_PATH) {
+        let manifest = BundleManifest::create(&files);
+        files.insert(MANIFEST_PATH.to_string(), manifest.to_string().into_bytes());
+    }
+
+    let map_err = |e: zip::result::ZipError| {
+        SpaceErr::server_error(format!("could not zip '{}': {}", dir.display(), e))
+    };
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (path, bin) in files {
+        zip.start_file(path, options).map_err(map_err)?;
+        zip.write_all(bin.as_slice())?;
+    }
+    Ok(zip.finish().map_err(map_err)?.into_inner())
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{files, glob, Ignore, IGNORE_FILE};
+    use std::fs;
+
+    #[test]
+    pub fn test_glob() {
+        assert!(glob("target", "target"));
+        assert!(!glob("target", "targets"));
+        assert!(glob("*.log", "debug.log"));
+        assert!(!glob("*.log", "debug.log.old"));
+        assert!(glob("*", ""));
+        assert!(glob("a*b*c", "aXbYc"));
+        assert!(!glob("a*b*c", "aXcYb"));
+        assert!(glob("build/*.wasm", "build/app.wasm"));
+        // `*` spans multibyte characters without splitting them
+        assert!(glob("*é", "café"));
+    }
+
+    #[test]
+    pub fn test_ignore() {
+        let ignore = Ignore {
+            patterns: vec![
+                "*.tmp".to_string(),
+                "target/".to_string(),
+                "config/secret.bind".to_string(),
+            ],
+        };
+        assert!(ignore.is_ignored("scratch.tmp", false));
+        assert!(ignore.is_ignored("config/scratch.tmp", false));
+        assert!(ignore.is_ignored("target", true));
+        assert!(ignore.is_ignored("sub/target", true));
+        // a trailing `/` only matches directories
+        assert!(!ignore.is_ignored("target", false));
+        // a pattern with a `/` is matched against the whole relative path
+        assert!(ignore.is_ignored("config/secret.bind", false));
+        assert!(!ignore.is_ignored("other/config/secret.bind", false));
+        assert!(!ignore.is_ignored("config/app.bind", false));
+    }
+
+    #[test]
+    pub fn test_ignore_file() {
+        let dir = std::env::temp_dir().join(format!("cosmic-bundle-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::create_dir_all(dir.join("config")).unwrap();
+        fs::write(
+            dir.join(IGNORE_FILE),
+            "# build output\n\n/target/\n*.tmp\n",
+        )
+        .unwrap();
+        fs::write(dir.join("target").join("app.wasm"), b"wasm").unwrap();
+        fs::write(dir.join("config").join("app.bind"), b"bind").unwrap();
+        fs::write(dir.join("config").join("app.tmp"), b"tmp").unwrap();
+
+        let ignore = Ignore::load(&dir).unwrap();
+        // the ignore file itself is never bundled
+        assert!(ignore.is_ignored(IGNORE_FILE, false));
+
+        let files = files(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            files.keys().cloned().collect::<Vec<String>>(),
+            vec!["config/app.bind".to_string()]
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use crate::script;
+use cosmic_space::config::check::{check, format, DocKind};
+use cosmic_space::err::SpaceErr;
+use std::collections::HashMap;
+
+/// read `file` and determine what kind of document it is
+fn load(file: &str) -> Result<(DocKind, String), SpaceErr> {
+    let src = std::fs::read_to_string(file)
+        .map_err(|e| SpaceErr::not_found(format!("could not read '{}': {}", file, e)))?;
+    Ok((DocKind::detect(file, src.as_str()), src))
+}
+
+fn report(file: &str, err: SpaceErr) {
+    eprintln!("{}: invalid", file);
+    err.print();
+}
+
+/// parse & validate every file without connecting to a server.
+/// Scripts have their `$VAR`s substituted from `vars` first.  Returns the exit code
+pub fn run(files: Vec<String>, vars: &HashMap<String, String>) -> i32 {
+    let mut invalid = 0;
+    for file in &files {
+        let result = load(file).and_then(|(kind, src)| match kind {
+            DocKind::Script => {
+                let substitution = script::substitute(src.as_str(), vars)?;
+                check(kind, substitution.text.as_str())
+                    .map_err(|err| substitution.remap(src.as_str(), err))
+            }
+            kind => check(kind, src.as_str()),
+        });
+        match result {
+            Ok(_) => eprintln!("{}: ok", file),
+            Err(err) => {
+                report(file, err);
+                invalid = invalid + 1;
+            }
+        }
+    }
+    match invalid {
+        0 => 0,
+        _ => {
+            eprintln!("{} of {} files invalid", invalid, files.len());
+            1
+        }
+    }
+}
+
+/// print the canonical formatting of each file, or rewrite the files when `write` is set.
+/// With `verify` nothing is written and the exit code says whether every file was
+/// already formatted
+pub fn fmt(files: Vec<String>, write: bool, verify: bool) -> i32 {
+    let mut code = 0;
+    for file in &files {
+        let formatted = load(file).and_then(|(kind, src)| Ok((format(kind, src.as_str())?, src)));
+        match formatted {
+            Err(err) => {
+                report(file, err);
+                code = 1;
+            }
+            Ok((formatted, src)) if verify => {
+                if formatted != src {
+                    eprintln!("{}: not formatted", file);
+                    code = 1;
+                }
+            }
+            Ok((formatted, src)) if write => {
+                if formatted != src {
+                    if let Err(err) = std::fs::write(file, formatted) {
+                        eprintln!("{}: could not write: {}", file, err);
+                        code = 1;
+                    }
+                }
+            }
+            Ok((formatted, _)) => print!("{}", formatted),
+        }
+    }
+    code
+}
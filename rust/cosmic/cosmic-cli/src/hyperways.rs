@@ -0,0 +1,349 @@
+use crate::output::Output;
+use crate::Session;
+use clap::ArgMatches;
+use cosmic_space::err::SpaceErr;
+use cosmic_space::hyper::{
+    ControlPattern, HyperlaneReport, HyperwayReport, InterchangeKind, InterchangeReport,
+    Interchanges,
+};
+use cosmic_space::loc::{Point, ToPoint};
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// list, show, kick, ban & unban the hyperways of the host's interchanges
+pub async fn run(session: &Session, args: &ArgMatches) -> Result<(), SpaceErr> {
+    let point = |args: &ArgMatches| Point::from_str(args.get_one::<String>("point").unwrap());
+    match args.subcommand() {
+        None | Some(("list", _)) => {
+            let interchanges = session.client.interchanges().await?;
+            out(session, &interchanges);
+        }
+        Some(("show", args)) => {
+            let point = point(args)?;
+            let interchanges = show(session.client.interchanges().await?, &point)?;
+            out(session, &interchanges);
+        }
+        Some(("kick", args)) => {
+            let point = point(args)?;
+            let kicked = session.client.kick(point.clone()).await?;
+            eprintln!("kicked {} hyperways of {}", kicked, point.to_string());
+        }
+        Some(("ban", args)) => {
+            let point = point(args)?;
+            let kicked = session.client.ban(point.clone()).await?;
+            eprintln!("banned {} ({} hyperways kicked)", point.to_string(), kicked);
+        }
+        Some(("unban", args)) => {
+            let point = point(args)?;
+            session.client.unban(point.clone()).await?;
+            eprintln!("unbanned {}", point.to_string());
+        }
+        Some((what, _)) => {
+            return Err(SpaceErr::bad_request(format!("unknown hyperways command '{}'", what)));
+        }
+    }
+    Ok(())
+}
+
+/// only the hyperways whose remote or agent is `point`, and the bans of `point` &
+/// of the peers of those hyperways
+fn show(mut interchanges: Interchanges, point: &Point) -> Result<Interchanges, SpaceErr> {
+    for interchange in interchanges.iter_mut() {
+        interchange.hyperways.retain(|hyperway| {
+            hyperway.remote.point == *point || hyperway.agent.to_point() == *point
+        });
+        interchange.banned.retain(|banned| banned == point);
+        let peers: Vec<Option<String>> = interchange
+            .hyperways
+            .iter()
+            .map(|hyperway| hyperway.peer.clone())
+            .collect();
+        interchange
+            .banned_peers
+            .retain(|peer| peers.contains(&Some(peer.clone())));
+    }
+    interchanges.retain(|interchange| {
+        !interchange.hyperways.is_empty() || !interchange.banned.is_empty()
+    });
+    if interchanges.is_empty() {
+        return Err(SpaceErr::not_found(format!(
+            "no hyperway of {}",
+            point.to_string()
+        )));
+    }
+    Ok(interchanges)
+}
+
+fn out(session: &Session, interchanges: &Interchanges) {
+    match session.output {
+        Output::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&interchanges_json(interchanges)).unwrap()
+        ),
+        Output::Yaml => match serde_yaml::to_string(&interchanges_json(interchanges)) {
+            Ok(yaml) => print!("{}", yaml),
+            Err(err) => eprintln!("cosmic-cli could not render yaml: {}", err.to_string()),
+        },
+        _ => print!("{}", interchanges_table(interchanges)),
+    }
+}
+
+/// unlike its `Display` this tells the interchanges of different stars apart
+fn kind(kind: &InterchangeKind) -> String {
+    let pattern = |pattern: &ControlPattern| match pattern {
+        ControlPattern::Any => "*".to_string(),
+        ControlPattern::Star(star) => star.to_string(),
+    };
+    match kind {
+        InterchangeKind::Control(control) => format!("Control<{}>", pattern(control)),
+        InterchangeKind::Portal(portal) => format!("Portal<{}>", pattern(portal)),
+        InterchangeKind::Star(star) => format!("Star<{}>", star.to_string()),
+        other => other.to_string(),
+    }
+}
+
+fn interchanges_json(interchanges: &Interchanges) -> Value {
+    Value::Array(
+        interchanges
+            .iter()
+            .map(|interchange| {
+                json!({
+                    "kind": kind(&interchange.kind),
+                    "hyperways": interchange.hyperways.iter().map(hyperway_json).collect::<Vec<Value>>(),
+                    "banned": interchange.banned.iter().map(|point| point.to_string()).collect::<Vec<String>>(),
+                    "banned_peers": interchange.banned_peers
+                })
+            })
+            .collect(),
+    )
+}
+
+fn hyperway_json(hyperway: &HyperwayReport) -> Value {
+    json!({
+        "remote": hyperway.remote.to_string(),
+        "agent": hyperway.agent.to_point().to_string(),
+        "age": hyperway.age,
+        "peer": hyperway.peer,
+        "inbound": lane_json(&hyperway.inbound),
+        "outbound": lane_json(&hyperway.outbound)
+    })
+}
+
+fn lane_json(lane: &HyperlaneReport) -> Value {
+    json!({
+        "queued": lane.queued,
+        "dropped": lane.dropped,
+        "bounced": lane.bounced,
+        "idle": lane.idle
+    })
+}
+
+/// a table of hyperways under each interchange kind.  Queues & drops are given as
+/// inbound/outbound, ages & idle times in seconds
+fn interchanges_table(interchanges: &Interchanges) -> String {
+    let mut rtn = String::new();
+    for interchange in interchanges.iter() {
+        rtn.push_str(interchange_table(interchange).as_str());
+    }
+    rtn
+}
+
+fn interchange_table(interchange: &InterchangeReport) -> String {
+    let header = [
+        "REMOTE", "AGENT", "AGE", "IDLE", "QUEUED", "DROPPED", "BOUNCED",
+    ]
+    .map(|cell| cell.to_string());
+    let rows: Vec<[String; 7]> = interchange
+        .hyperways
+        .iter()
+        .map(|hyperway| {
+            let lanes = |f: fn(&HyperlaneReport) -> u64| {
+                format!("{}/{}", f(&hyperway.inbound), f(&hyperway.outbound))
+            };
+            [
+                hyperway.remote.to_string(),
+                hyperway.agent.to_point().to_string(),
+                hyperway.age.to_string(),
+                hyperway.inbound.idle.min(hyperway.outbound.idle).to_string(),
+                lanes(|lane| lane.queued as u64),
+                lanes(|lane| lane.dropped),
+                lanes(|lane| lane.bounced),
+            ]
+        })
+        .collect();
+    let mut widths = [0usize; 7];
+    for r in std::iter::once(&header).chain(rows.iter()) {
+        for (i, cell) in r.iter().enumerate() {
+            widths[i] = widths[i].max(cell.len());
+        }
+    }
+    let mut rtn = format!("{}\n", kind(&interchange.kind));
+    for r in std::iter::once(&header).chain(rows.iter()) {
+        let line: Vec<String> = r
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| format!("{:width$}", cell, width = widths[i]))
+            .collect();
+        rtn.push_str(format!("  {}\n", line.join("  ").trim_end()).as_str());
+    }
+    if !interchange.banned.is_empty() {
+        let banned: Vec<String> = interchange
+            .banned
+            .iter()
+            .map(|point| point.to_string())
+            .collect();
+        rtn.push_str(format!("  banned: {}\n", banned.join(", ")).as_str());
+    }
+    if !interchange.banned_peers.is_empty() {
+        rtn.push_str(
+            format!("  banned peers: {}\n", interchange.banned_peers.join(", ")).as_str(),
+        );
+    }
+    rtn
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::str::FromStr;
+
+    use cosmic_space::err::StatusErr;
+    use cosmic_space::hyper::{
+        ControlPattern, HyperlaneReport, HyperwayReport, InterchangeKind, InterchangeReport,
+        Interchanges,
+    };
+    use cosmic_space::loc::{Point, StarKey, ToPoint, ToSurface};
+    use cosmic_space::wave::Agent;
+
+    use crate::hyperways::{interchanges_json, interchanges_table, kind, show};
+
+    fn lane(queued: usize, dropped: u64, idle: u64) -> HyperlaneReport {
+        HyperlaneReport {
+            queued,
+            dropped,
+            bounced: 0,
+            idle,
+        }
+    }
+
+    fn hyperway(remote: &str, agent: Agent, peer: Option<&str>) -> HyperwayReport {
+        HyperwayReport {
+            remote: Point::from_str(remote).unwrap().to_surface(),
+            agent,
+            age: 30,
+            peer: peer.map(|peer| peer.to_string()),
+            inbound: lane(1, 2, 5),
+            outbound: lane(3, 4, 7),
+        }
+    }
+
+    /// a control interchange with two hyperways, one of whose points & peers is banned
+    fn interchanges() -> Interchanges {
+        let mut interchanges = Interchanges::new();
+        interchanges.push(InterchangeReport {
+            kind: InterchangeKind::Control(ControlPattern::Any),
+            hyperways: vec![
+                hyperway(
+                    "GLOBAL::control:one",
+                    Agent::Point(Point::from_str("user").unwrap()),
+                    Some("10.0.0.1:4343"),
+                ),
+                hyperway("GLOBAL::control:two", Agent::HyperUser, Some("10.0.0.2:4343")),
+            ],
+            banned: vec![Point::from_str("banned").unwrap()],
+            banned_peers: vec!["10.0.0.2:4343".to_string()],
+        });
+        interchanges.push(InterchangeReport {
+            kind: InterchangeKind::Star(StarKey::central()),
+            hyperways: vec![],
+            banned: vec![],
+            banned_peers: vec![],
+        });
+        interchanges
+    }
+
+    #[test]
+    pub fn test_kind() {
+        let star = Point::from_str("star").unwrap();
+        assert_eq!(
+            kind(&InterchangeKind::Control(ControlPattern::Any)),
+            "Control<*>"
+        );
+        assert_eq!(
+            kind(&InterchangeKind::Portal(ControlPattern::Star(star.clone()))),
+            format!("Portal<{}>", star.to_string())
+        );
+        assert_eq!(
+            kind(&InterchangeKind::Star(StarKey::central())),
+            format!("Star<{}>", StarKey::central().to_string())
+        );
+        assert_eq!(kind(&InterchangeKind::Singleton), "Singleton");
+    }
+
+    #[test]
+    pub fn test_show() {
+        // by the remote: the bans of other points & peers are left out
+        let one = Point::from_str("GLOBAL::control:one").unwrap();
+        let shown = show(interchanges(), &one).unwrap();
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].hyperways.len(), 1);
+        assert_eq!(shown[0].hyperways[0].remote.point, one);
+        assert!(shown[0].banned.is_empty());
+        assert!(shown[0].banned_peers.is_empty());
+
+        // by the agent: the ban of the hyperway's peer is kept
+        let shown = show(interchanges(), &Agent::HyperUser.to_point()).unwrap();
+        assert_eq!(shown[0].hyperways.len(), 1);
+        assert_eq!(
+            shown[0].hyperways[0].remote.point,
+            Point::from_str("GLOBAL::control:two").unwrap()
+        );
+        assert_eq!(shown[0].banned_peers, vec!["10.0.0.2:4343".to_string()]);
+
+        // a banned point without a hyperway is still shown
+        let banned = Point::from_str("banned").unwrap();
+        let shown = show(interchanges(), &banned).unwrap();
+        assert!(shown[0].hyperways.is_empty());
+        assert_eq!(shown[0].banned, vec![banned]);
+
+        let err = show(interchanges(), &Point::from_str("nobody").unwrap()).unwrap_err();
+        assert_eq!(err.status(), 404);
+    }
+
+    #[test]
+    pub fn test_json() {
+        let json = interchanges_json(&interchanges());
+        let control = &json[0];
+        assert_eq!(control["kind"], "Control<*>");
+        assert_eq!(control["banned"][0], "banned");
+        assert_eq!(control["banned_peers"][0], "10.0.0.2:4343");
+        let hyperway = &control["hyperways"][0];
+        assert_eq!(
+            hyperway["remote"],
+            Point::from_str("GLOBAL::control:one")
+                .unwrap()
+                .to_surface()
+                .to_string()
+        );
+        assert_eq!(hyperway["agent"], "user");
+        assert_eq!(hyperway["peer"], "10.0.0.1:4343");
+        assert_eq!(hyperway["inbound"]["queued"], 1);
+        assert_eq!(hyperway["outbound"]["dropped"], 4);
+        assert!(json[1]["hyperways"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_table() {
+        let table = interchanges_table(&interchanges());
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "Control<*>");
+        assert!(lines[1].trim_start().starts_with("REMOTE"));
+        let cells: Vec<&str> = lines[2].split_whitespace().collect();
+        // age, the least idle lane & inbound/outbound queues, drops and bounces
+        assert_eq!(&cells[2..], ["30", "5", "1/3", "2/4", "0/0"]);
+        assert_eq!(lines[4], "  banned: banned");
+        assert_eq!(lines[5], "  banned peers: 10.0.0.2:4343");
+        assert_eq!(lines[6], format!("Star<{}>", StarKey::central().to_string()));
+        assert!(lines[7].trim_start().starts_with("REMOTE"));
+        assert_eq!(lines.len(), 8);
+    }
+}
@@ -14,21 +14,36 @@ use cosmic_hyperlane::HyperwayEndpointFactory;
 use cosmic_hyperlane_tcp::HyperlaneTcpClient;
 use cosmic_hyperspace::driver::control::{ControlCliSession, ControlClient};
 use cosmic_nom::new_span;
-use cosmic_space::command::{CmdTransfer, Command, RawCommand};
+use cosmic_space::command::{CmdTransfer, Command, RawCommand, UPLOAD_CHUNK_SIZE};
 use cosmic_space::err::SpaceErr;
 use cosmic_space::hyper::{InterchangeKind, Knock};
 use cosmic_space::loc::{Point, ToSurface};
 use cosmic_space::log::RootLogger;
+use cosmic_space::particle::Progress;
 use cosmic_space::parse::error::result;
 use cosmic_space::parse::{command_line, upload_blocks};
 use cosmic_space::substance::Substance;
 use cosmic_space::util::{log, ToResolved};
 use cosmic_space::wave::core::ReflectedCore;
+use std::io::Write;
+use std::path::Path;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tokio::fs;
 
+mod bundle;
+mod check;
+mod hyperways;
+mod output;
+mod repl;
+mod replay;
+mod script;
+
+use crate::output::{
+    err_status, exit_code, table_command, to_json, to_table, write_raw, Output,
+};
+
 #[tokio::main]
 async fn main() -> Result<(), SpaceErr> {
     let home_dir: String = match dirs::home_dir() {
@@ -54,17 +69,239 @@ async fn main() -> Result<(), SpaceErr> {
                 .required(false)
                 .default_value(format!("{}/.starlane/localhost/certs", home_dir).as_str()),
         )
-        .subcommand(ClapCommand::new("script"))
+        .arg(
+            Arg::new("token")
+                .long("token")
+                .takes_value(true)
+                .value_name("token")
+                .help("the control token of the host, needed to administer hyperways (defaults to $COSMIC_CONTROL_TOKEN)"),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .takes_value(true)
+                .value_name("format")
+                .possible_values(["text", "json", "yaml", "table", "raw"])
+                .default_value("text"),
+        )
+        .arg(
+            Arg::new("raw-file")
+                .long("raw-file")
+                .takes_value(true)
+                .value_name("file")
+                .help("with --output raw write Bin & Text bodies to this file instead of stdout"),
+        )
+        .subcommand(
+            ClapCommand::new("script")
+                .about("run a file of ';' terminated commands")
+                .arg(Arg::new("file").required(true).value_name("file"))
+                .arg(
+                    Arg::new("define")
+                        .short('D')
+                        .long("define")
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .value_name("key=value")
+                        .help("define a $variable for the script (overrides the environment)"),
+                )
+                .arg(
+                    Arg::new("continue-on-error")
+                        .long("continue-on-error")
+                        .takes_value(false)
+                        .help("run the remaining commands after a command fails"),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("replay")
+                .about("send the directed waves of a capture file to the host")
+                .arg(Arg::new("file").required(true).value_name("file"))
+                .arg(
+                    Arg::new("site")
+                        .long("site")
+                        .takes_value(true)
+                        .value_name("site")
+                        .help("replay the waves recorded at this site (default: the first inbound hyperlane)"),
+                )
+                .arg(
+                    Arg::new("speed")
+                        .long("speed")
+                        .takes_value(true)
+                        .value_name("factor")
+                        .default_value("1")
+                        .help("divide the recorded spacing of waves by this (0 sends them at once)"),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("hyperways")
+                .about("list & administer the hyperways of the host (needs a HyperUser control)")
+                .subcommand(ClapCommand::new("list").about("the hyperways of every interchange"))
+                .subcommand(
+                    ClapCommand::new("show")
+                        .about("the hyperways & bans of a remote or agent")
+                        .arg(Arg::new("point").required(true).value_name("point")),
+                )
+                .subcommand(
+                    ClapCommand::new("kick")
+                        .about("disconnect the hyperways of a remote or agent")
+                        .arg(Arg::new("point").required(true).value_name("point")),
+                )
+                .subcommand(
+                    ClapCommand::new("ban")
+                        .about("kick a remote or agent & refuse it (and the addresses it connected from) until it is unbanned or the host restarts")
+                        .arg(Arg::new("point").required(true).value_name("point")),
+                )
+                .subcommand(
+                    ClapCommand::new("unban")
+                        .about("admit a banned remote or agent again")
+                        .arg(Arg::new("point").required(true).value_name("point")),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("check")
+                .about("validate bind, mechtron config & script files without a server")
+                .arg(
+                    Arg::new("files")
+                        .required(true)
+                        .multiple_values(true)
+                        .value_name("files"),
+                )
+                .arg(
+                    Arg::new("define")
+                        .short('D')
+                        .long("define")
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .value_name("key=value")
+                        .help("define a $variable for scripts (overrides the environment)"),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("fmt")
+                .about("print bind, mechtron config & script files in canonical format")
+                .arg(
+                    Arg::new("files")
+                        .required(true)
+                        .multiple_values(true)
+                        .value_name("files"),
+                )
+                .arg(
+                    Arg::new("write")
+                        .short('w')
+                        .long("write")
+                        .takes_value(false)
+                        .help("rewrite the files in place"),
+                )
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .takes_value(false)
+                        .help("write nothing & exit non-zero if a file is not formatted"),
+                ),
+        )
         .allow_external_subcommands(true)
         .get_matches();
 
     let host = matches.get_one::<String>("host").unwrap().clone();
     let certs = matches.get_one::<String>("certs").unwrap().clone();
-    let session = Session::new(host, certs).await?;
+    let token = matches
+        .get_one::<String>("token")
+        .cloned()
+        .or_else(|| std::env::var("COSMIC_CONTROL_TOKEN").ok());
+    let output = Output::from_str(matches.get_one::<String>("output").unwrap())?;
+    let raw_file = matches.get_one::<String>("raw-file").cloned();
+
+    // check & fmt work offline
+    if let Some(("check", args)) = matches.subcommand() {
+        let files = args.get_many::<String>("files").unwrap().cloned().collect();
+        let defines = args
+            .get_many::<String>("define")
+            .map(|defines| defines.cloned().collect())
+            .unwrap_or_default();
+        let vars = match script::vars(defines) {
+            Ok(vars) => vars,
+            Err(err) => {
+                err.print();
+                std::process::exit(1);
+            }
+        };
+        std::process::exit(check::run(files, &vars));
+    }
+    if let Some(("fmt", args)) = matches.subcommand() {
+        let files = args.get_many::<String>("files").unwrap().cloned().collect();
+        std::process::exit(check::fmt(
+            files,
+            args.is_present("write"),
+            args.is_present("check"),
+        ));
+    }
+
+    if let Some(("script", args)) = matches.subcommand() {
+        let file = args.get_one::<String>("file").unwrap();
+        let defines = args
+            .get_many::<String>("define")
+            .map(|defines| defines.cloned().collect())
+            .unwrap_or_default();
+        // the whole script is validated before connecting so a typo never half runs a script
+        let lines = match script::vars(defines).and_then(|vars| script::load(file, &vars)) {
+            Ok(lines) => lines,
+            Err(err) => {
+                err.print();
+                std::process::exit(1);
+            }
+        };
+        let session = Session::new(host, certs, token, output, raw_file).await?;
+        return match script::run(&session, lines, args.is_present("continue-on-error")).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                let code = exit_code(err_status(&err));
+                session.out_err(err);
+                std::process::exit(code);
+            }
+        };
+    }
+
+    if let Some(("replay", args)) = matches.subcommand() {
+        let file = args.get_one::<String>("file").unwrap();
+        let speed = match f64::from_str(args.get_one::<String>("speed").unwrap()) {
+            Ok(speed) if speed >= 0.0 => speed,
+            _ => {
+                eprintln!("--speed expects a number of at least 0");
+                std::process::exit(1);
+            }
+        };
+        let session = Session::new(host, certs, token, output, raw_file).await?;
+        let site = args.get_one::<String>("site").map(|site| site.as_str());
+        let replayed = replay::run(&session.client, file, site, speed).await?;
+        eprintln!(
+            "replayed {} waves: {} ok, {} failed",
+            replayed.sent, replayed.ok, replayed.failed
+        );
+        std::process::exit(match replayed.failed {
+            0 => 0,
+            _ => 1,
+        });
+    }
+
+    let session = Session::new(host, certs, token, output, raw_file).await?;
+
+    if let Some(("hyperways", args)) = matches.subcommand() {
+        return match hyperways::run(&session, args).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                let code = exit_code(err_status(&err));
+                session.out_err(err);
+                std::process::exit(code);
+            }
+        };
+    }
 
     if matches.subcommand_name().is_some() {
         session.command(matches.subcommand_name().unwrap()).await
+    } else if atty::is(atty::Stream::Stdin) {
+        repl::run(&session).await
     } else {
+        // commands piped through stdin i.e. `cosmic < install.script`
         loop {
             let line: String = text_io::try_read!("{};").map_err(|e| SpaceErr::new(500, "err"))?;
 
@@ -80,19 +317,53 @@ async fn main() -> Result<(), SpaceErr> {
     }
 }
 
+/// redraw the upload progress bar of `name` on stderr so it never mixes with the output
+fn show_progress(name: &str, progress: &Progress) {
+    const WIDTH: usize = 30;
+    let total = (progress.total as usize).max(1);
+    let filled = (WIDTH * progress.step as usize / total).min(WIDTH);
+    let mut stderr = std::io::stderr();
+    write!(
+        stderr,
+        "\ruploading {} [{}{}] {}",
+        name,
+        "#".repeat(filled),
+        " ".repeat(WIDTH.saturating_sub(filled)),
+        progress.to_string()
+    )
+    .unwrap_or_default();
+    if progress.step >= progress.total {
+        writeln!(stderr).unwrap_or_default();
+    }
+    stderr.flush().unwrap_or_default();
+}
+
 pub struct Session {
     pub client: ControlClient,
     pub cli: ControlCliSession,
+    pub output: Output,
+    pub raw_file: Option<String>,
+    working: RwLock<Option<Point>>,
 }
 
 impl Session {
-    pub async fn new(host: String, certs: String) -> Result<Self, SpaceErr> {
+    pub async fn new(
+        host: String,
+        certs: String,
+        token: Option<String>,
+        output: Output,
+        raw_file: Option<String>,
+    ) -> Result<Self, SpaceErr> {
         let logger = RootLogger::default();
         let logger = logger.point(Point::from_str("cosmic-cli")?);
+        let mut knock = Knock::default();
+        if let Some(token) = token {
+            knock.auth = Box::new(Substance::Text(token));
+        }
         let tcp_client: Box<dyn HyperwayEndpointFactory> = Box::new(HyperlaneTcpClient::new(
             format!("{}:{}", host, 4343),
             certs,
-            Knock::default(),
+            knock,
             false,
             logger,
         ));
@@ -103,40 +374,111 @@ impl Session {
 
         let cli = client.new_cli_session().await?;
 
-        Ok(Self { client, cli })
+        Ok(Self {
+            client,
+            cli,
+            output,
+            raw_file,
+            working: RwLock::new(None),
+        })
     }
 
     async fn command(&self, command: &str) -> Result<(), SpaceErr> {
+        let core = self.exec(command).await?;
+        self.core_out(core);
+
+        Ok(())
+    }
+
+    /// the point relative points in commands resolve against (`None` for the session default)
+    pub fn working(&self) -> Option<Point> {
+        self.working.read().unwrap().clone()
+    }
+
+    pub fn set_working(&self, working: Option<Point>) {
+        *self.working.write().unwrap() = working;
+    }
+
+    /// send `command` (and the files of its upload blocks) to the cosmos
+    pub async fn exec(&self, command: &str) -> Result<ReflectedCore, SpaceErr> {
+        let command = match self.output {
+            Output::Table => table_command(command),
+            _ => command.to_string(),
+        };
+        let command = command.as_str();
         let blocks = result(upload_blocks(new_span(command)))?;
         let mut command = RawCommand::new(command.to_string());
+        command.working = self.working();
         for block in blocks {
-            let content = Arc::new(fs::read(block.name.as_str()).await?);
+            let path = Path::new(block.name.as_str());
+            let content = if fs::metadata(path).await?.is_dir() {
+                let dir = path.to_path_buf();
+                tokio::task::spawn_blocking(move || bundle::zip(&dir))
+                    .await
+                    .map_err(|e| SpaceErr::server_error(e.to_string()))??
+            } else {
+                fs::read(path).await?
+            };
+            // large transfers are streamed ahead of the command that uses them
+            let content = if content.len() > UPLOAD_CHUNK_SIZE {
+                self.cli
+                    .upload(block.name.as_str(), content.as_slice(), |progress| {
+                        show_progress(block.name.as_str(), &progress)
+                    })
+                    .await?;
+                Arc::new(vec![])
+            } else {
+                Arc::new(content)
+            };
             command
                 .transfers
                 .push(CmdTransfer::new(block.name, content));
         }
 
-        let core = self.cli.raw(command).await?;
-        self.core_out(core);
-
-        Ok(())
+        self.cli.raw(command).await
     }
 
     pub fn core_out(&self, core: ReflectedCore) {
         match core.is_ok() {
             true => self.out(core.body),
             false => {
+                let code = exit_code(core.status.as_u16());
                 if core.body != Substance::Empty {
                     self.out(core.body);
                 } else {
                     self.out_err(core.ok_or().unwrap_err());
-                    std::process::exit(1);
                 }
+                std::process::exit(code);
             },
         }
     }
 
     pub fn out(&self, substance: Substance) {
+        match self.output {
+            Output::Text => self.out_text(substance),
+            Output::Json => {
+                println!("{}", serde_json::to_string_pretty(&to_json(&substance)).unwrap())
+            }
+            Output::Yaml => match serde_yaml::to_string(&to_json(&substance)) {
+                Ok(yaml) => print!("{}", yaml),
+                Err(err) => eprintln!("cosmic-cli could not render yaml: {}", err.to_string()),
+            },
+            Output::Table => match to_table(&substance) {
+                Some(table) => print!("{}", table),
+                None => self.out_text(substance),
+            },
+            Output::Raw => match write_raw(&substance, self.raw_file.as_ref()) {
+                Ok(true) => {}
+                Ok(false) => self.out_text(substance),
+                Err(err) => {
+                    self.out_err(err);
+                    std::process::exit(1);
+                }
+            },
+        }
+    }
+
+    fn out_text(&self, substance: Substance) {
         match substance {
             Substance::Empty => {
                 println!("Ok");
@@ -146,7 +488,7 @@ impl Session {
             }
             Substance::List(list) => {
                 for i in list.list {
-                    self.out(*i);
+                    self.out_text(*i);
                 }
             }
             Substance::Point(point) => {
@@ -164,11 +506,11 @@ impl Session {
             Substance::Details(details) => {
                 println!("{}<{}>", details.stub.point.to_string(), details.stub.kind.to_string())
             }
+            Substance::Bin(bin) => {
+                println!("<{} bytes> (use --output raw to write the bytes)", bin.len());
+            }
             what => {
-                eprintln!(
-                    "cosmic-cli not sure how to output {}",
-                    what.kind().to_string()
-                )
+                println!("{}", serde_json::to_string_pretty(&to_json(&what)).unwrap());
             }
         }
     }
@@ -0,0 +1,391 @@
+use cosmic_space::command::direct::select::SelectIntoSubstance;
+use cosmic_space::command::CommandVar;
+use cosmic_space::err::SpaceErr;
+use cosmic_space::hyper::ParticleLocation;
+use cosmic_space::particle::{Details, Stub};
+use cosmic_space::substance::Substance;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::str::FromStr;
+
+/// how `cosmic-cli` renders the Substance of a reflected core
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Output {
+    /// human readable text (the default)
+    Text,
+    Json,
+    Yaml,
+    /// columns of point, kind, status & star for select results
+    Table,
+    /// Bin & Text bodies exactly as received
+    Raw,
+}
+
+impl FromStr for Output {
+    type Err = SpaceErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Output::Text),
+            "json" => Ok(Output::Json),
+            "yaml" => Ok(Output::Yaml),
+            "table" => Ok(Output::Table),
+            "raw" => Ok(Output::Raw),
+            other => Err(SpaceErr::bad_request(format!(
+                "unknown output '{}' (expected text|json|yaml|table|raw)",
+                other
+            ))),
+        }
+    }
+}
+
+/// the exit code of a failed command: 4 for a 4xx status, 5 for a 5xx status & 1 otherwise
+pub fn exit_code(status: u16) -> i32 {
+    match status {
+        400..=499 => 4,
+        500..=599 => 5,
+        _ => 1,
+    }
+}
+
+pub fn err_status(err: &SpaceErr) -> u16 {
+    match err {
+        SpaceErr::Status { status, .. } => *status,
+        SpaceErr::ParseErrs(_) => 400,
+    }
+}
+
+/// a json view of `substance` meant for scripting: particles are flattened to their
+/// point, kind & status and points are rendered as strings
+pub fn to_json(substance: &Substance) -> Value {
+    match substance {
+        Substance::Empty => Value::Null,
+        Substance::List(list) => Value::Array(list.list.iter().map(|s| to_json(s)).collect()),
+        Substance::Map(map) => Value::Object(
+            map.map
+                .iter()
+                .map(|(key, value)| (key.clone(), to_json(value)))
+                .collect(),
+        ),
+        Substance::Point(point) => Value::String(point.to_string()),
+        Substance::Surface(surface) => Value::String(surface.to_string()),
+        Substance::Text(text) => Value::String(text.clone()),
+        Substance::Stub(stub) => stub_json(stub),
+        Substance::Details(details) => details_json(details),
+        Substance::Bin(bin) => json!({ "size": bin.len(), "base64": base64::encode(bin.as_slice()) }),
+        Substance::Boolean(boolean) => Value::Bool(*boolean),
+        Substance::Int(int) => json!(int),
+        Substance::Status(status) => Value::String(status.to_string()),
+        Substance::Particle(particle) => {
+            let mut rtn = stub_json(&particle.stub);
+            if let Value::Object(map) = &mut rtn {
+                map.insert("state".to_string(), to_json(&particle.state));
+            }
+            rtn
+        }
+        Substance::Location(location) => location_json(location),
+        Substance::Json(json) => json.clone(),
+        Substance::Err(err) => json!({ "status": err_status(err), "message": err.to_string() }),
+        // everything else has no friendlier form than its serde representation
+        other => serde_json::to_value(other).unwrap_or_else(|e| {
+            json!({ "kind": other.kind().to_string(), "error": e.to_string() })
+        }),
+    }
+}
+
+fn stub_json(stub: &Stub) -> Value {
+    json!({
+        "point": stub.point.to_string(),
+        "kind": stub.kind.to_string(),
+        "status": stub.status.to_string()
+    })
+}
+
+fn details_json(details: &Details) -> Value {
+    let mut rtn = stub_json(&details.stub);
+    let properties: Map<String, Value> = details
+        .properties
+        .iter()
+        .map(|(key, property)| (key.clone(), Value::String(property.value.clone())))
+        .collect();
+    if let Value::Object(map) = &mut rtn {
+        map.insert("properties".to_string(), Value::Object(properties));
+    }
+    rtn
+}
+
+fn location_json(location: &ParticleLocation) -> Value {
+    json!({
+        "star": location.star.as_ref().map(|star| star.to_string()),
+        "host": location.host.as_ref().map(|host| host.to_string())
+    })
+}
+
+/// the point, kind, status & star of a select result row or `None` if `substance` isn't one
+fn row(substance: &Substance) -> Option<[String; 4]> {
+    let unknown = || "-".to_string();
+    match substance {
+        Substance::Stub(stub) => Some([
+            stub.point.to_string(),
+            stub.kind.to_string(),
+            stub.status.to_string(),
+            unknown(),
+        ]),
+        Substance::Details(details) => row(&Substance::Stub(details.stub.clone())),
+        Substance::Point(point) => Some([point.to_string(), unknown(), unknown(), unknown()]),
+        // a record from `select ... into records`
+        Substance::Map(map) => {
+            let mut row = match map.map.get("stub") {
+                Some(stub) => row(stub)?,
+                None => return None,
+            };
+            if let Some(Substance::Location(location)) = map.map.get("location") {
+                if let Some(star) = &location.star {
+                    row[3] = star.to_string();
+                }
+            }
+            Some(row)
+        }
+        _ => None,
+    }
+}
+
+/// `command` as it is sent for `Output::Table`: a table needs the star of each selected
+/// particle which only records carry, so a select of stubs selects records instead
+pub fn table_command(command: &str) -> String {
+    match CommandVar::from_str(command) {
+        Ok(CommandVar::Select(select)) if select.into_substance == SelectIntoSubstance::Stubs => {
+            format!("{} into records", command.trim_end().trim_end_matches(';'))
+        }
+        _ => command.to_string(),
+    }
+}
+
+/// render `substance` as a table or `None` if it isn't a list of select results
+pub fn to_table(substance: &Substance) -> Option<String> {
+    let rows: Vec<[String; 4]> = match substance {
+        Substance::List(list) => list
+            .list
+            .iter()
+            .map(|s| row(s))
+            .collect::<Option<Vec<[String; 4]>>>()?,
+        other => vec![row(other)?],
+    };
+    let header = [
+        "POINT".to_string(),
+        "KIND".to_string(),
+        "STATUS".to_string(),
+        "STAR".to_string(),
+    ];
+    let mut widths = [0usize; 4];
+    for r in std::iter::once(&header).chain(rows.iter()) {
+        for (i, cell) in r.iter().enumerate() {
+            widths[i] = widths[i].max(cell.len());
+        }
+    }
+    let mut rtn = String::new();
+    for r in std::iter::once(&header).chain(rows.iter()) {
+        let line: Vec<String> = r
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| format!("{:width$}", cell, width = widths[i]))
+            .collect();
+        rtn.push_str(line.join("  ").trim_end());
+        rtn.push('\n');
+    }
+    Some(rtn)
+}
+
+/// write a Bin or Text body exactly as received to `file` or stdout
+pub fn write_raw(substance: &Substance, file: Option<&String>) -> Result<bool, SpaceErr> {
+    let bytes: &[u8] = match substance {
+        Substance::Bin(bin) => bin.as_slice(),
+        Substance::Text(text) => text.as_bytes(),
+        _ => return Ok(false),
+    };
+    match file {
+        Some(file) => std::fs::write(file, bytes)?,
+        None => {
+            let mut stdout = std::io::stdout();
+            stdout.write_all(bytes)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use cosmic_space::err::{ParseErrs, SpaceErr};
+    use cosmic_space::hyper::ParticleLocation;
+    use cosmic_space::kind::Kind;
+    use cosmic_space::loc::{Point, Uuid};
+    use cosmic_space::particle::{Status, Stub};
+    use cosmic_space::substance::{Substance, SubstanceList, SubstanceMap};
+    use serde_json::json;
+
+    use crate::output::{err_status, exit_code, table_command, to_json, to_table, write_raw};
+
+    fn stub(point: &str) -> Stub {
+        Stub {
+            point: Point::from_str(point).unwrap(),
+            kind: Kind::Base,
+            status: Status::Ready,
+        }
+    }
+
+    fn list(substances: Vec<Substance>) -> Substance {
+        Substance::List(SubstanceList {
+            list: substances.into_iter().map(Box::new).collect(),
+        })
+    }
+
+    /// a row of `select ... into records`
+    fn record(point: &str, star: Option<&str>) -> Substance {
+        let mut map = SubstanceMap::new();
+        map.map.insert("stub".to_string(), Substance::Stub(stub(point)));
+        map.map.insert(
+            "location".to_string(),
+            Substance::Location(ParticleLocation {
+                star: star.map(|star| Point::from_str(star).unwrap()),
+                host: None,
+            }),
+        );
+        Substance::Map(map)
+    }
+
+    #[test]
+    pub fn test_exit_code() {
+        assert_eq!(exit_code(400), 4);
+        assert_eq!(exit_code(404), 4);
+        assert_eq!(exit_code(499), 4);
+        assert_eq!(exit_code(500), 5);
+        assert_eq!(exit_code(503), 5);
+        assert_eq!(exit_code(200), 1);
+        assert_eq!(exit_code(302), 1);
+        assert_eq!(exit_code(0), 1);
+    }
+
+    #[test]
+    pub fn test_err_status() {
+        assert_eq!(err_status(&SpaceErr::not_found("nope")), 404);
+        assert_eq!(err_status(&SpaceErr::server_error("oops")), 500);
+        // a parse error is the fault of the request
+        let parse = ParseErrs::from_range("bad", "here", 0..1, Arc::new("x".to_string()));
+        assert_eq!(err_status(&parse), 400);
+        assert_eq!(exit_code(err_status(&parse)), 4);
+    }
+
+    #[test]
+    pub fn test_to_json() {
+        assert_eq!(to_json(&Substance::Empty), json!(null));
+        assert_eq!(to_json(&Substance::Text("hi".to_string())), json!("hi"));
+        assert_eq!(to_json(&Substance::Boolean(true)), json!(true));
+        assert_eq!(to_json(&Substance::Int(7)), json!(7));
+        assert_eq!(
+            to_json(&Substance::Point(Point::from_str("localhost:app").unwrap())),
+            json!("localhost:app")
+        );
+        assert_eq!(
+            to_json(&Substance::Bin(Arc::new(b"hi".to_vec()))),
+            json!({ "size": 2, "base64": "aGk=" })
+        );
+        let stub = stub("localhost:app");
+        assert_eq!(
+            to_json(&list(vec![Substance::Stub(stub.clone())])),
+            json!([{
+                "point": "localhost:app",
+                "kind": stub.kind.to_string(),
+                "status": stub.status.to_string()
+            }])
+        );
+        assert_eq!(
+            to_json(&Substance::Err(SpaceErr::not_found("nope"))),
+            json!({ "status": 404, "message": SpaceErr::not_found("nope").to_string() })
+        );
+        match to_json(&record("localhost:app", Some("localhost:star"))) {
+            serde_json::Value::Object(map) => {
+                assert_eq!(map["location"], json!({ "star": "localhost:star", "host": null }));
+                assert_eq!(map["stub"]["point"], json!("localhost:app"));
+            }
+            other => panic!("expected an object got: {}", other),
+        }
+    }
+
+    #[test]
+    pub fn test_to_table() {
+        let kind = Kind::Base.to_string();
+        let status = Status::Ready.to_string();
+        let table = to_table(&list(vec![
+            record("localhost:app", Some("localhost:star")),
+            record("localhost:application", None),
+        ]))
+        .unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let header: Vec<&str> = lines[0].split_whitespace().collect();
+        assert_eq!(header, vec!["POINT", "KIND", "STATUS", "STAR"]);
+        let row: Vec<&str> = lines[1].split_whitespace().collect();
+        assert_eq!(
+            row,
+            vec!["localhost:app", kind.as_str(), status.as_str(), "localhost:star"]
+        );
+        let row: Vec<&str> = lines[2].split_whitespace().collect();
+        assert_eq!(row[3], "-");
+        // the columns line up
+        assert_eq!(lines[0].find("KIND"), lines[1].find(kind.as_str()));
+
+        // a single stub is a table of one row
+        let table = to_table(&Substance::Stub(stub("localhost:app"))).unwrap();
+        assert_eq!(table.lines().count(), 2);
+        // anything that isn't a select result is not a table
+        assert!(to_table(&Substance::Text("hi".to_string())).is_none());
+        assert!(to_table(&list(vec![Substance::Text("hi".to_string())])).is_none());
+    }
+
+    #[test]
+    pub fn test_write_raw() {
+        let file = std::env::temp_dir()
+            .join(format!("cosmic-cli-raw-{}", Uuid::rnd().to_string()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        let bin = vec![0u8, 159, 146, 150];
+        assert!(write_raw(&Substance::Bin(Arc::new(bin.clone())), Some(&file)).unwrap());
+        assert_eq!(std::fs::read(&file).unwrap(), bin);
+        assert!(write_raw(&Substance::Text("text".to_string()), Some(&file)).unwrap());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "text");
+        // other substances are left to the other outputs
+        assert!(!write_raw(&Substance::Int(7), Some(&file)).unwrap());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "text");
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    pub fn test_table_command() {
+        assert_eq!(
+            table_command("select localhost:app:**"),
+            "select localhost:app:** into records"
+        );
+        assert_eq!(
+            table_command("select localhost:app:**;"),
+            "select localhost:app:** into records"
+        );
+        assert_eq!(
+            table_command("select localhost:app:** into records"),
+            "select localhost:app:** into records"
+        );
+        // only a select is changed, whatever the points are named
+        assert_eq!(
+            table_command("create localhost:into<Base>"),
+            "create localhost:into<Base>"
+        );
+        assert_eq!(
+            table_command("select localhost:into:**"),
+            "select localhost:into:** into records"
+        );
+    }
+}
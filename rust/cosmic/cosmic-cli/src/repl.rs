@@ -0,0 +1,260 @@
+use crate::Session;
+use cosmic_space::err::SpaceErr;
+use cosmic_space::kind::BaseKind;
+use cosmic_space::loc::{Point, PointVar};
+use cosmic_space::parse::Env;
+use cosmic_space::substance::Substance;
+use cosmic_space::util::ToResolved;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Editor, Helper};
+use std::path::PathBuf;
+use std::str::FromStr;
+use tokio::runtime::Handle;
+
+/// the first word of a line that is completed as a keyword
+const KEYWORDS: [&str; 10] = [
+    "create", "publish", "select", "set", "get", "cd", "pwd", "help", "exit", "quit",
+];
+
+const HELP: &str = r#"commands are sent to the cosmos, i.e.:
+  create? localhost:app<App>{ +config=repo:app:1.0.0:/config/app.app };
+  select localhost:*
+a command continues on the next line while a { block is open.
+relative points (.:child, ..) resolve against the working point.
+shell commands:
+  cd <point>   change the working point (cd .. to pop, cd alone for root)
+  pwd          print the working point
+  exit         leave the shell"#;
+
+/// where the shell history is persisted
+pub fn history_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".cosmic").join("history"))
+}
+
+pub struct ReplHelper<'a> {
+    session: &'a Session,
+}
+
+impl<'a> ReplHelper<'a> {
+    /// children of `parent` (or the root) fetched live via `select`
+    fn children(&self, parent: Option<&str>) -> Vec<String> {
+        let selector = match parent {
+            None => "*".to_string(),
+            Some(parent) => format!("{}:*", parent),
+        };
+        // readline runs inside `block_in_place` so blocking on the runtime here is allowed
+        let core = Handle::current().block_on(self.session.cli.exec(format!("select {}", selector)));
+        match core {
+            Ok(core) if core.is_ok() => match core.body {
+                Substance::List(list) => list
+                    .list
+                    .into_iter()
+                    .filter_map(|s| match *s {
+                        Substance::Stub(stub) => Some(stub.point.to_string()),
+                        Substance::Point(point) => Some(point.to_string()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            },
+            _ => vec![],
+        }
+    }
+}
+
+impl<'a> Completer for ReplHelper<'a> {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos]
+            .rfind(|c: char| c.is_whitespace() || c == '{' || c == '=' || c == ',')
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let word = &line[start..pos];
+
+        let pairs = |candidates: Vec<String>, prefix: &str| -> Vec<Pair> {
+            candidates
+                .into_iter()
+                .filter(|c| c.starts_with(prefix))
+                .map(|c| Pair {
+                    display: c.clone(),
+                    replacement: c,
+                })
+                .collect()
+        };
+
+        if line[..start].trim().is_empty() {
+            let keywords = KEYWORDS.iter().map(|k| k.to_string()).collect();
+            return Ok((start, pairs(keywords, word)));
+        }
+
+        if let Some(index) = word.rfind('<') {
+            let kinds = BaseKind::all().into_iter().map(|k| k.to_string()).collect();
+            return Ok((start + index + 1, pairs(kinds, &word[index + 1..])));
+        }
+
+        let parent = word.rfind(':').map(|index| &word[..index]);
+        let children = self.children(parent);
+        Ok((start, pairs(children, word)))
+    }
+}
+
+impl<'a> Hinter for ReplHelper<'a> {
+    type Hint = String;
+}
+
+impl<'a> Highlighter for ReplHelper<'a> {}
+
+impl<'a> Validator for ReplHelper<'a> {
+    /// a command with an open `{ +prop=... }` block continues on the next line
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        let input = ctx.input();
+        let open = input.matches('{').count();
+        let close = input.matches('}').count();
+        if open > close {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+impl<'a> Helper for ReplHelper<'a> {}
+
+/// resolve the argument of `cd` against the current working point
+fn cd(working: Option<Point>, arg: &str) -> Result<Option<Point>, SpaceErr> {
+    let arg = arg.trim();
+    if arg.is_empty() {
+        return Ok(None);
+    }
+    let working = working.unwrap_or(Point::root());
+    let point = PointVar::from_str(arg)?.to_resolved(&Env::new(working))?;
+    if point.segments.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(point))
+    }
+}
+
+/// `point` exists in the registry, the root always does
+async fn exists(session: &Session, point: Option<&Point>) -> Result<bool, SpaceErr> {
+    let point = match point {
+        None => return Ok(true),
+        Some(point) => point,
+    };
+    let core = session
+        .cli
+        .exec(format!("select {}", point.to_string()))
+        .await?;
+    match core.body {
+        Substance::List(list) if core.is_ok() => Ok(!list.list.is_empty()),
+        _ => Ok(false),
+    }
+}
+
+pub async fn run(session: &Session) -> Result<(), SpaceErr> {
+    let mut editor = Editor::<ReplHelper>::new()
+        .map_err(|e| SpaceErr::server_error(format!("cannot start shell: {}", e)))?;
+    editor.set_helper(Some(ReplHelper { session }));
+    let history = history_file();
+    if let Some(history) = &history {
+        editor.load_history(history).unwrap_or_default();
+    }
+
+    loop {
+        let prompt = match session.working() {
+            None => "cosmic> ".to_string(),
+            Some(working) => format!("cosmic {}> ", working.to_string()),
+        };
+        // completion blocks on the runtime so readline must not run on a worker thread
+        let line = tokio::task::block_in_place(|| editor.readline(prompt.as_str()));
+        let line = match line {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(SpaceErr::server_error(err.to_string())),
+        };
+        let command = line.trim().trim_end_matches(';').trim();
+        if command.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line.as_str());
+
+        let mut words = command.splitn(2, char::is_whitespace);
+        match (words.next().unwrap_or_default(), words.next().unwrap_or_default()) {
+            ("exit", _) | ("quit", _) => break,
+            ("help", _) => println!("{}", HELP),
+            ("pwd", _) => match session.working() {
+                None => println!("ROOT"),
+                Some(working) => println!("{}", working.to_string()),
+            },
+            ("cd", arg) => match cd(session.working(), arg) {
+                Ok(working) => match exists(session, working.as_ref()).await {
+                    Ok(true) => session.set_working(working),
+                    Ok(false) => session.out_err(SpaceErr::not_found(format!(
+                        "cd: {} does not exist",
+                        working.map(|p| p.to_string()).unwrap_or_default()
+                    ))),
+                    Err(err) => session.out_err(err),
+                },
+                Err(err) => err.print(),
+            },
+            _ => match session.exec(command).await {
+                Ok(core) if core.is_ok() => session.out(core.body),
+                Ok(core) if core.body != Substance::Empty => session.out(core.body),
+                Ok(core) => session.out_err(core.ok_or().unwrap_err()),
+                Err(err) => session.out_err(err),
+            },
+        }
+    }
+
+    if let Some(history) = &history {
+        if let Some(dir) = history.parent() {
+            std::fs::create_dir_all(dir).unwrap_or_default();
+        }
+        editor.save_history(history).unwrap_or_default();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::str::FromStr;
+
+    use cosmic_space::loc::Point;
+
+    use crate::repl::cd;
+
+    #[test]
+    pub fn test_cd() {
+        let app = Point::from_str("localhost:app").unwrap();
+
+        assert_eq!(cd(None, "localhost:app").unwrap(), Some(app.clone()));
+        assert_eq!(
+            cd(Some(app.clone()), ".:child").unwrap(),
+            Some(Point::from_str("localhost:app:child").unwrap())
+        );
+        assert_eq!(
+            cd(Some(app.clone()), "..").unwrap(),
+            Some(Point::from_str("localhost").unwrap())
+        );
+        // popping past the top goes back to the root
+        assert_eq!(cd(Some(Point::from_str("localhost").unwrap()), "..").unwrap(), None);
+        assert_eq!(cd(Some(app.clone()), "").unwrap(), None);
+        // an absolute point ignores the working point
+        assert_eq!(
+            cd(Some(app), "other:thing").unwrap(),
+            Some(Point::from_str("other:thing").unwrap())
+        );
+        assert!(cd(None, "not a point!").is_err());
+    }
+}
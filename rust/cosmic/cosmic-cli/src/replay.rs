@@ -0,0 +1,190 @@
+use cosmic_hyperspace::driver::control::ControlClient;
+use cosmic_space::capture::{CaptureReader, CaptureRecord};
+use cosmic_space::err::SpaceErr;
+use cosmic_space::loc::Surface;
+use cosmic_space::wave::{Agent, ReflectedAggregate, UltraWave, WaveId};
+use std::fs::File;
+use std::io::BufReader;
+use std::time::{Duration, Instant};
+
+/// the suffix of the site a hyperlane records the waves coming into the machine at
+pub const INBOUND_SITE: &str = "<Inbound>";
+
+/// what became of the waves of a replay
+pub struct Replayed {
+    pub sent: usize,
+    pub ok: usize,
+    pub failed: usize,
+}
+
+/// the records of a capture worth replaying: the directed waves recorded at `site`, by
+/// default the first inbound hyperlane of the capture.  Recorded reflections and
+/// whatever else crossed the machine are skipped, the machine makes its own
+pub fn select<I>(records: I, site: Option<&str>) -> Result<Vec<CaptureRecord>, SpaceErr>
+where
+    I: Iterator<Item = Result<CaptureRecord, SpaceErr>>,
+{
+    let mut site = site.map(|site| site.to_string());
+    let mut rtn = vec![];
+    for record in records {
+        let record = record?;
+        if !record.wave.is_directed() {
+            continue;
+        }
+        if site.is_none() && record.site.ends_with(INBOUND_SITE) {
+            site = Some(record.site.clone());
+        }
+        if site.as_ref() == Some(&record.site) {
+            rtn.push(record);
+        }
+    }
+    Ok(rtn)
+}
+
+/// a recorded `wave` as it is sent again: with a fresh id so it never collides with
+/// the recorded wave (or another replay of it) & from `surface` as `agent`
+pub fn resend(mut wave: UltraWave, surface: Surface, agent: Agent) -> UltraWave {
+    wave.set_id(WaveId::new(wave.kind()));
+    wave.set_from(surface);
+    wave.set_agent(agent);
+    wave
+}
+
+/// send the waves of the capture at `file` that `select` picks to the machine of `client`.
+/// Waves keep their recorded spacing divided by `speed` (`0` sends them as fast as
+/// possible).  Waves are sent from the surface & as the agent of the control of this
+/// session rather than their recorded sender
+pub async fn run(
+    client: &ControlClient,
+    file: &str,
+    site: Option<&str>,
+    speed: f64,
+) -> Result<Replayed, SpaceErr> {
+    let reader = CaptureReader::new(BufReader::new(File::open(file).map_err(|e| {
+        SpaceErr::not_found(format!("could not read '{}': {}", file, e))
+    })?))?;
+    let records = select(reader, site)?;
+    let greet = client.wait_for_greet().await?;
+    let transmitter = client.transmitter_builder().await?.build();
+    let started = Instant::now();
+    let mut first = None;
+    let mut exchanges = vec![];
+    let mut sent = 0;
+    for record in records {
+        // `route_directed` applies no `SetStrategy` of the transmitter
+        let wave = resend(record.wave, greet.surface.clone(), greet.agent.clone());
+        let directed = match wave.to_directed() {
+            Ok(directed) => directed,
+            Err(_) => continue,
+        };
+        let first = *first.get_or_insert(record.timestamp.millis);
+        if speed > 0.0 {
+            let offset = (record.timestamp.millis - first).max(0) as u64;
+            let due = Duration::from_millis(offset).div_f64(speed);
+            if let Some(wait) = due.checked_sub(started.elapsed()) {
+                tokio::time::sleep(wait).await;
+            }
+        }
+        if let Some(reflected_rx) = transmitter.route_directed(directed).await {
+            exchanges.push(reflected_rx);
+        }
+        sent = sent + 1;
+    }
+
+    // every exchange resolves, if need be with the exchanger's 408
+    let mut replayed = Replayed {
+        sent,
+        ok: 0,
+        failed: 0,
+    };
+    for reflected_rx in exchanges {
+        let ok = match reflected_rx.await {
+            Ok(ReflectedAggregate::Single(reflected)) => reflected.is_success(),
+            Ok(ReflectedAggregate::Multi(reflected)) => {
+                reflected.iter().all(|reflected| reflected.is_success())
+            }
+            Ok(ReflectedAggregate::None) => true,
+            Err(_) => false,
+        };
+        match ok {
+            true => replayed.ok = replayed.ok + 1,
+            false => replayed.failed = replayed.failed + 1,
+        }
+    }
+    Ok(replayed)
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::str::FromStr;
+
+    use cosmic_space::capture::CaptureRecord;
+    use cosmic_space::loc::{Point, ToSurface};
+    use cosmic_space::wasm::Timestamp;
+    use cosmic_space::wave::core::ext::ExtMethod;
+    use cosmic_space::wave::core::ReflectedCore;
+    use cosmic_space::wave::{Agent, DirectedProto, UltraWave};
+
+    use crate::replay::{resend, select};
+
+    fn ping(method: &str) -> UltraWave {
+        let mut ping = DirectedProto::ping();
+        ping.to(Point::from_str("fae").unwrap().to_surface());
+        ping.from(Point::from_str("less").unwrap().to_surface());
+        ping.method(ExtMethod::new(method).unwrap());
+        ping.build().unwrap().to_ultra()
+    }
+
+    fn record(site: &str, wave: UltraWave) -> CaptureRecord {
+        CaptureRecord {
+            timestamp: Timestamp::new(0),
+            site: site.to_string(),
+            wave,
+        }
+    }
+
+    #[test]
+    pub fn test_select() {
+        let hello = ping("Hello");
+        let pong = hello
+            .clone()
+            .to_directed()
+            .unwrap()
+            .reflection()
+            .unwrap()
+            .make(ReflectedCore::ok(), Point::from_str("fae").unwrap().to_surface())
+            .to_ultra();
+        let records = vec![
+            record("fae<Outbound>", ping("Out")),
+            record("less<Inbound>", hello.clone()),
+            record("less<Inbound>", pong),
+            record("less<Outbound>", ping("Reply")),
+            record("other<Inbound>", ping("Other")),
+            record("less<Inbound>", ping("Bye")),
+        ];
+
+        // by default the first inbound hyperlane & only its directed waves
+        let selected = select(records.clone().into_iter().map(Ok), None).unwrap();
+        let methods: Vec<String> = selected
+            .iter()
+            .map(|record| record.wave.method().unwrap().to_string())
+            .collect();
+        assert_eq!(methods, vec!["Ext<Hello>".to_string(), "Ext<Bye>".to_string()]);
+
+        let selected = select(records.into_iter().map(Ok), Some("other<Inbound>")).unwrap();
+        assert_eq!(selected.len(), 1);
+    }
+
+    #[test]
+    pub fn test_resend() {
+        let hello = ping("Hello");
+        let surface = Point::from_str("control").unwrap().to_surface();
+        let first = resend(hello.clone(), surface.clone(), Agent::HyperUser);
+        let second = resend(hello.clone(), surface.clone(), Agent::HyperUser);
+        assert_ne!(first.id(), hello.id());
+        assert_ne!(first.id(), second.id());
+        assert_eq!(first.kind(), hello.kind());
+        assert_eq!(*first.from(), surface);
+        assert_eq!(*first.agent(), Agent::HyperUser);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::output::{err_status, Output};
+use crate::Session;
+use cosmic_space::err::{ParseErrs, SpaceErr};
+use cosmic_space::parse::{script_lines, ScriptLine};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// the variables available to a script: the environment overridden by `-D key=value` defines
+pub fn vars(defines: Vec<String>) -> Result<HashMap<String, String>, SpaceErr> {
+    let mut vars: HashMap<String, String> = std::env::vars().collect();
+    for define in defines {
+        let (key, value) = define.split_once('=').ok_or(SpaceErr::bad_request(format!(
+            "expected -D key=value, encountered: '{}'",
+            define
+        )))?;
+        vars.insert(key.trim().to_string(), value.to_string());
+    }
+    Ok(vars)
+}
+
+/// a script after its variables were substituted, with the span of every replacement so
+/// an offset into `text` can be mapped back to the script
+pub struct Substitution {
+    pub text: String,
+    /// the span of each replacement in `text` & of the `$VAR` it replaced in the script
+    replacements: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl Substitution {
+    /// the offset in the script of `offset` in `text`.  An offset within a substituted
+    /// value maps to the start of its `$VAR`
+    pub fn original(&self, offset: usize) -> usize {
+        let mut original = offset;
+        for (text, src) in self.replacements.iter() {
+            if offset >= text.end {
+                original = offset - text.end + src.end;
+            } else if offset >= text.start {
+                return src.start;
+            } else {
+                break;
+            }
+        }
+        original
+    }
+
+    /// `err` raised for `text` with its reports pointing into `src`, the script itself
+    pub fn remap(&self, src: &str, err: SpaceErr) -> SpaceErr {
+        match err {
+            SpaceErr::ParseErrs(errs) => errs
+                .map_offsets(|offset| self.original(offset), Arc::new(src.to_string()))
+                .into(),
+            err => err,
+        }
+    }
+}
+
+/// replace `$VAR` and `${VAR}` with the value of `VAR` (`$$` is a literal `$`).
+/// Every undefined variable is reported in the returned `ParseErrs`
+pub fn substitute(src: &str, vars: &HashMap<String, String>) -> Result<Substitution, SpaceErr> {
+    let extra = Arc::new(src.to_string());
+    let mut rtn = String::with_capacity(src.len());
+    let mut replacements = vec![];
+    let mut errs = vec![];
+    let mut chars = src.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if c != '$' {
+            rtn.push(c);
+            continue;
+        }
+        let braced = match chars.peek() {
+            Some((_, '$')) => {
+                chars.next();
+                replacements.push((rtn.len()..rtn.len() + 1, index..index + 2));
+                rtn.push('$');
+                continue;
+            }
+            Some((_, '{')) => {
+                chars.next();
+                true
+            }
+            _ => false,
+        };
+        let mut name = String::new();
+        while let Some((_, c)) = chars.peek() {
+            if c.is_ascii_alphanumeric() || *c == '_' {
+                name.push(*c);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        let mut end = index + 1 + name.len();
+        if braced {
+            end = end + 1;
+            match chars.peek() {
+                Some((_, '}')) => {
+                    chars.next();
+                    end = end + 1;
+                }
+                _ => {
+                    errs.push(ParseErrs::from_range(
+                        "unterminated variable",
+                        "expected '}'",
+                        index..end,
+                        extra.clone(),
+                    ));
+                    continue;
+                }
+            }
+        }
+        if name.is_empty() {
+            errs.push(ParseErrs::from_range(
+                "expected a variable name after '$' (use '$$' for a literal '$')",
+                "variable name expected",
+                index..end,
+                extra.clone(),
+            ));
+            continue;
+        }
+        match vars.get(&name) {
+            Some(value) => {
+                replacements.push((rtn.len()..rtn.len() + value.len(), index..end));
+                rtn.push_str(value.as_str());
+            }
+            None => errs.push(ParseErrs::from_range(
+                format!("undefined variable '{}'", name).as_str(),
+                "define it in the environment or with -D key=value",
+                index..end,
+                extra.clone(),
+            )),
+        }
+    }
+
+    if errs.is_empty() {
+        Ok(Substitution {
+            text: rtn,
+            replacements,
+        })
+    } else {
+        let errs: Vec<ParseErrs> = errs
+            .into_iter()
+            .filter_map(|err| match err {
+                SpaceErr::ParseErrs(errs) => Some(errs),
+                SpaceErr::Status { .. } => None,
+            })
+            .collect();
+        Err(ParseErrs::fold(errs).into())
+    }
+}
+
+/// substitute & parse the whole script so nothing runs unless every line is valid.
+/// Lines & errors refer to `src` rather than the substituted script
+pub fn parse(src: &str, vars: &HashMap<String, String>) -> Result<Vec<ScriptLine>, SpaceErr> {
+    let substitution = substitute(src, vars)?;
+    let lines = script_lines(substitution.text.as_str())
+        .map_err(|err| substitution.remap(src, err))?;
+    Ok(lines
+        .into_iter()
+        .map(|mut line| {
+            line.offset = substitution.original(line.offset);
+            line.line = src[..line.offset].matches('\n').count() as u32 + 1;
+            line
+        })
+        .collect())
+}
+
+/// read & `parse` a script
+pub fn load(file: &str, vars: &HashMap<String, String>) -> Result<Vec<ScriptLine>, SpaceErr> {
+    let src = std::fs::read_to_string(file)
+        .map_err(|e| SpaceErr::not_found(format!("could not read script '{}': {}", file, e)))?;
+    parse(src.as_str(), vars)
+}
+
+/// run `lines` in order. If `continue_on_error` is false the script stops at the first
+/// failing command, otherwise every command runs and the failures are counted
+pub async fn run(
+    session: &Session,
+    lines: Vec<ScriptLine>,
+    continue_on_error: bool,
+) -> Result<(), SpaceErr> {
+    let total = lines.len();
+    let mut failures = 0;
+    let mut status = 500;
+    for line in lines {
+        // keep stdout parseable when the output is meant for machines
+        match session.output {
+            Output::Text => println!("> {}", line.text),
+            _ => eprintln!("> {}", line.text),
+        }
+        match session.exec(line.text.as_str()).await {
+            Ok(core) if core.is_ok() => session.out(core.body),
+            result => {
+                let err = match result {
+                    Ok(core) => core.ok_or().unwrap_err(),
+                    Err(err) => err,
+                };
+                eprintln!("line {}: {}", line.line, err.to_string());
+                status = err_status(&err);
+                if !continue_on_error {
+                    return Err(SpaceErr::new(
+                        status,
+                        format!("script stopped at line {}", line.line),
+                    ));
+                }
+                failures = failures + 1;
+            }
+        }
+    }
+    if failures > 0 {
+        return Err(SpaceErr::new(
+            status,
+            format!("{} of {} commands failed", failures, total),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashMap;
+
+    use cosmic_space::err::SpaceErr;
+
+    use crate::script::{parse, substitute};
+
+    fn vars() -> HashMap<String, String> {
+        let mut vars = HashMap::new();
+        vars.insert("SPACE".to_string(), "localhost".to_string());
+        vars.insert("APP".to_string(), "my-app".to_string());
+        vars
+    }
+
+    fn text(src: &str) -> String {
+        substitute(src, &vars()).unwrap().text
+    }
+
+    /// the number of reports of a rejected substitution
+    fn reports(src: &str) -> usize {
+        match substitute(src, &vars()) {
+            Err(SpaceErr::ParseErrs(errs)) => errs.report.len(),
+            Err(err) => panic!("expected ParseErrs got: {}", err.to_string()),
+            Ok(substitution) => panic!("expected an error got: {}", substitution.text),
+        }
+    }
+
+    #[test]
+    pub fn test_substitute() {
+        assert_eq!(text("create $SPACE<Space>"), "create localhost<Space>");
+        assert_eq!(text("create ${SPACE}:${APP}x"), "create localhost:my-appx");
+        assert_eq!(text("echo $$SPACE costs $$5"), "echo $SPACE costs $5");
+        assert_eq!(text("no variables"), "no variables");
+    }
+
+    #[test]
+    pub fn test_substitute_errors() {
+        // every undefined variable is reported, not just the first
+        assert_eq!(reports("create $NOPE:${NADA}"), 2);
+        assert_eq!(reports("create ${SPACE"), 1);
+        assert_eq!(reports("create ${SPACE x}"), 1);
+        assert_eq!(reports("create $ alone"), 1);
+        assert_eq!(reports("create ${}"), 1);
+    }
+
+    #[test]
+    pub fn test_original_offsets() {
+        let src = "create ${SPACE}:$APP<App>;";
+        let substitution = substitute(src, &vars()).unwrap();
+        assert_eq!(substitution.text, "create localhost:my-app<App>;");
+        assert_eq!(substitution.original(0), 0);
+        // within a value maps to the start of its variable
+        assert_eq!(substitution.original(7), 7);
+        assert_eq!(substitution.original(12), 7);
+        // past a value the offset shifts by the difference in length
+        let colon = substitution.text.find(':').unwrap();
+        assert_eq!(substitution.original(colon), src.find(':').unwrap());
+        let app = substitution.text.find("<App>").unwrap();
+        assert_eq!(substitution.original(app), src.find("<App>").unwrap());
+
+        let substitution = substitute("$$x", &vars()).unwrap();
+        assert_eq!(substitution.original(1), 2);
+    }
+
+    #[test]
+    pub fn test_parse() {
+        let src = "create ${SPACE}<Space>;\n\ncreate $SPACE:$APP<Base>;";
+        let lines = parse(src, &vars()).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].text, "create localhost:my-app<Base>");
+        assert_eq!(lines[1].line, 3);
+        assert_eq!(lines[1].offset, src.rfind("create").unwrap());
+
+        // errors point into the script as written
+        let src = "create ${SPACE}<Space>;\ncreate $SPACE<<Nope>;";
+        match parse(src, &vars()) {
+            Err(SpaceErr::ParseErrs(errs)) => {
+                assert_eq!(errs.source.unwrap().as_str(), src);
+            }
+            Err(err) => panic!("expected ParseErrs got: {}", err.to_string()),
+            Ok(_) => panic!("expected the script to be rejected"),
+        }
+    }
+}
@@ -10,7 +10,7 @@ use std::pin::Pin;
 use std::str::FromStr;
 use std::string::FromUtf8Error;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use rcgen::{generate_simple_self_signed, Certificate, RcgenError};
 use rustls::internal::msgs::codec::Codec;
@@ -18,23 +18,22 @@ use rustls::{server, ClientConfig, RootCertStore, ServerConfig, ServerName};
 use tls_api_rustls::TlsConnectorBuilder;
 use tokio::fs::File;
 use tokio::io;
-use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf, ReadHalf, WriteHalf};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
 use tokio::time::error::Elapsed;
 use tokio_rustls::{TlsAcceptor, TlsConnector, TlsStream};
 
 use cosmic_hyperlane::{
-    HyperConnectionDetails, HyperConnectionStatus, HyperGate, HyperGateSelector, HyperwayEndpoint,
-    HyperwayEndpointFactory, VersionGate,
+    HeartbeatConfig, HyperConnectionDetails, HyperConnectionStatus, HyperGate, HyperGateSelector,
+    HyperwayEndpoint, HyperwayEndpointFactory, VersionGate,
 };
 use cosmic_space::err::SpaceErr;
-use cosmic_space::hyper::Knock;
+use cosmic_space::hyper::{at_revision, Knock, ProtocolRange, LEGACY_VERSION, PROTOCOL};
 use cosmic_space::log::PointLogger;
 use cosmic_space::substance::Substance;
 use cosmic_space::wave::{Ping, UltraWave, Wave};
-use cosmic_space::VERSION;
 
 pub struct HyperlaneTcpClient {
     host: String,
@@ -42,6 +41,7 @@ pub struct HyperlaneTcpClient {
     knock: Knock,
     logger: PointLogger,
     verify: bool,
+    heartbeat: HeartbeatConfig,
 }
 
 impl HyperlaneTcpClient {
@@ -56,8 +56,14 @@ impl HyperlaneTcpClient {
             knock,
             verify,
             logger,
+            heartbeat: HeartbeatConfig::default(),
         }
     }
+
+    pub fn with_heartbeat(mut self, heartbeat: HeartbeatConfig) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
 }
 
 #[async_trait]
@@ -95,8 +101,13 @@ impl HyperwayEndpointFactory for HyperlaneTcpClient {
 
         let mut stream = FrameStream::new(tokio_tls_connector.into());
 
-        let endpoint =
-            FrameMuxer::handshake(stream, status_tx.clone(), self.logger.clone()).await?;
+        let endpoint = FrameMuxer::handshake(
+            stream,
+            self.heartbeat.clone(),
+            status_tx.clone(),
+            self.logger.clone(),
+        )
+        .await?;
 
         let wave: Wave<Ping> = self.knock.clone().into();
         let wave = wave.to_ultra();
@@ -179,7 +190,20 @@ impl Frame {
         )?)
     }
 
-    pub async fn from_stream<'a>(read: &'a mut TlsStream<TcpStream>) -> Result<Frame, SpaceErr> {
+    pub fn from_protocol(protocol: &ProtocolRange) -> Frame {
+        Frame {
+            data: protocol.to_string().as_bytes().to_vec(),
+        }
+    }
+
+    pub fn to_protocol(self) -> Result<ProtocolRange, SpaceErr> {
+        ProtocolRange::from_str(String::from_utf8(self.data)?.as_str())
+    }
+
+    pub async fn from_stream<'a, R>(read: &'a mut R) -> Result<Frame, SpaceErr>
+    where
+        R: AsyncRead + Unpin,
+    {
         let size = read.read_u32().await? as usize;
         let mut data = Vec::with_capacity(size as usize);
 
@@ -190,7 +214,10 @@ impl Frame {
         Ok(Self { data })
     }
 
-    pub async fn to_stream<'a>(&self, write: &'a mut TlsStream<TcpStream>) -> Result<(), SpaceErr> {
+    pub async fn to_stream<'a, W>(&self, write: &'a mut W) -> Result<(), SpaceErr>
+    where
+        W: AsyncWrite + Unpin,
+    {
         write.write_u32(self.data.len() as u32).await?;
         write.write_all(self.data.as_slice()).await?;
         write.flush().await?;
@@ -208,43 +235,177 @@ impl Frame {
     }
 }
 
+/// what a `FrameMuxer` sends once the handshake is done: waves and, from protocol
+/// revision 2, the heartbeat.  Below revision 2 a frame is nothing but the wave as it
+/// was in 0.3.4
+pub enum MuxFrame {
+    Wave(UltraWave),
+    Ping,
+    Pong,
+}
+
+impl MuxFrame {
+    const WAVE: u8 = 0;
+    const PING: u8 = 1;
+    const PONG: u8 = 2;
+
+    /// the first protocol revision whose frames start with a tag
+    const TAGGED: u16 = 2;
+
+    /// `protocol` is the negotiated revision whose layout the wave is serialized in
+    pub fn to_frame(self, protocol: u16) -> Result<Frame, SpaceErr> {
+        if protocol < Self::TAGGED {
+            return match self {
+                MuxFrame::Wave(wave) => Ok(Frame {
+                    data: at_revision(protocol, || bincode::serialize(&wave))?,
+                }),
+                _ => Err(format!(
+                    "heartbeat frames need protocol revision {} but revision {} was negotiated",
+                    Self::TAGGED,
+                    protocol
+                )
+                .into()),
+            };
+        }
+        let data = match self {
+            MuxFrame::Wave(wave) => {
+                let mut data = vec![Self::WAVE];
+                data.append(&mut at_revision(protocol, || bincode::serialize(&wave))?);
+                data
+            }
+            MuxFrame::Ping => vec![Self::PING],
+            MuxFrame::Pong => vec![Self::PONG],
+        };
+        Ok(Frame { data })
+    }
+
+    pub fn from_frame(frame: Frame, protocol: u16) -> Result<Self, SpaceErr> {
+        if protocol < Self::TAGGED {
+            return Ok(MuxFrame::Wave(at_revision(protocol, || {
+                bincode::deserialize(frame.data.as_slice())
+            })?));
+        }
+        match frame.data.split_first() {
+            Some((&Self::WAVE, wave)) => Ok(MuxFrame::Wave(at_revision(protocol, || {
+                bincode::deserialize(wave)
+            })?)),
+            Some((&Self::PING, _)) => Ok(MuxFrame::Ping),
+            Some((&Self::PONG, _)) => Ok(MuxFrame::Pong),
+            Some((kind, _)) => Err(format!("unknown mux frame kind {}", kind).into()),
+            None => Err("empty mux frame".into()),
+        }
+    }
+}
+
+/// the first frame of a handshake: a range of protocol revisions or, from a 0.3.4
+/// peer, its version
+pub enum Offer {
+    Protocol(ProtocolRange),
+    Legacy(semver::Version),
+}
+
+impl Offer {
+    pub fn from_frame(frame: Frame) -> Result<Self, SpaceErr> {
+        let offer = frame.to_string()?;
+        match ProtocolRange::from_str(offer.as_str()) {
+            Ok(range) => Ok(Offer::Protocol(range)),
+            Err(err) => match semver::Version::from_str(offer.as_str()) {
+                Ok(version) => Ok(Offer::Legacy(version)),
+                Err(_) => Err(err),
+            },
+        }
+    }
+}
+
 pub struct FrameMuxer {
     stream: FrameStream,
     tx: mpsc::Sender<UltraWave>,
     rx: mpsc::Receiver<UltraWave>,
     terminate_rx: mpsc::Receiver<()>,
+    /// the negotiated revision whose layout waves are (de)serialized in
+    protocol: u16,
+    heartbeat: HeartbeatConfig,
+    status_tx: mpsc::Sender<HyperConnectionDetails>,
+    liveness_tx: watch::Sender<HyperConnectionStatus>,
     logger: PointLogger,
 }
 impl FrameMuxer {
+    /// the connecting side offers its protocol revisions first.  A 0.3.4 server
+    /// cannot read the offer so a newer client can only connect to a newer server
     pub async fn handshake(
         mut stream: FrameStream,
+        heartbeat: HeartbeatConfig,
         status_tx: mpsc::Sender<HyperConnectionDetails>,
         logger: PointLogger,
     ) -> Result<HyperwayEndpoint, SpaceErr> {
-        stream.write_version(&VERSION.clone()).await?;
-        let in_version =
-            tokio::time::timeout(Duration::from_secs(30), stream.read_version()).await??;
-
-        if in_version == *VERSION {
-            //            logger.info("version match");
-
-            stream.write_string("Ok".to_string()).await?;
-        } else {
-            logger.warn("version mismatch");
-            status_tx
-                .send(HyperConnectionDetails::new(
-                    HyperConnectionStatus::Handshake,
-                    "version mismatch",
-                ))
-                .await?;
-            let msg = format!(
-                "Err(\"expected version {}. encountered version {}\")",
-                VERSION.to_string(),
-                in_version.to_string()
-            );
-            stream.write_string(msg.clone()).await?;
-            return Err(msg.into());
-        }
+        stream.write_protocol(&PROTOCOL).await?;
+        let frame = tokio::time::timeout(Duration::from_secs(30), stream.frame()).await??;
+        let offered = match Offer::from_frame(frame)? {
+            Offer::Protocol(offered) => offered,
+            Offer::Legacy(version) => {
+                let msg = format!(
+                    "server speaks the protocol of version {} which cannot read the handshake of this client (upgrade the server)",
+                    version.to_string()
+                );
+                return Self::refuse(&status_tx, &logger, msg).await;
+            }
+        };
+        let protocol = Self::settle(&mut stream, &offered, &status_tx, &logger).await?;
+        Ok(Self::new(stream, protocol, heartbeat, status_tx, logger))
+    }
+
+    /// the accepting side waits for the offer of the client so a 0.3.4 client, which
+    /// sends its version instead, can be answered in kind and served revision 1
+    pub async fn accept(
+        mut stream: FrameStream,
+        heartbeat: HeartbeatConfig,
+        status_tx: mpsc::Sender<HyperConnectionDetails>,
+        logger: PointLogger,
+    ) -> Result<HyperwayEndpoint, SpaceErr> {
+        let frame = tokio::time::timeout(Duration::from_secs(30), stream.frame()).await??;
+        let offered = match Offer::from_frame(frame)? {
+            Offer::Protocol(offered) => {
+                stream.write_protocol(&PROTOCOL).await?;
+                offered
+            }
+            Offer::Legacy(version) => {
+                stream
+                    .write_version(&semver::Version::from_str(LEGACY_VERSION)?)
+                    .await?;
+                if version.to_string() != LEGACY_VERSION {
+                    let msg = format!(
+                        "expected version {}. encountered version {}",
+                        LEGACY_VERSION,
+                        version.to_string()
+                    );
+                    stream.write_string(format!("Err(\"{}\")", msg)).await?;
+                    return Self::refuse(&status_tx, &logger, msg).await;
+                }
+                ProtocolRange::new(1, 1)
+            }
+        };
+        let protocol = Self::settle(&mut stream, &offered, &status_tx, &logger).await?;
+        Ok(Self::new(stream, protocol, heartbeat, status_tx, logger))
+    }
+
+    /// both sides settle on the same revision, the `Greet` reports it to the client
+    async fn settle(
+        stream: &mut FrameStream,
+        offered: &ProtocolRange,
+        status_tx: &mpsc::Sender<HyperConnectionDetails>,
+        logger: &PointLogger,
+    ) -> Result<u16, SpaceErr> {
+        let protocol = match PROTOCOL.negotiate(offered) {
+            Ok(protocol) => {
+                stream.write_string("Ok".to_string()).await?;
+                protocol
+            }
+            Err(mismatch) => {
+                let msg = mismatch.to_string();
+                stream.write_string(format!("Err(\"{}\")", msg)).await?;
+                return Self::refuse(status_tx, logger, msg).await;
+            }
+        };
 
         let result = tokio::time::timeout(Duration::from_secs(30), stream.read_string()).await??;
         if "Ok".to_string() != result {
@@ -254,19 +415,44 @@ impl FrameMuxer {
             )
             .into()));
         }
+        Ok(protocol)
+    }
 
-        Ok(Self::new(stream, logger))
+    async fn refuse<R>(
+        status_tx: &mpsc::Sender<HyperConnectionDetails>,
+        logger: &PointLogger,
+        msg: String,
+    ) -> Result<R, SpaceErr> {
+        logger.warn(msg.as_str());
+        status_tx
+            .send(HyperConnectionDetails::new(
+                HyperConnectionStatus::Handshake,
+                msg.as_str(),
+            ))
+            .await?;
+        Err(SpaceErr::new(426, msg))
     }
 
-    pub fn new(stream: FrameStream, logger: PointLogger) -> HyperwayEndpoint {
+    pub fn new(
+        stream: FrameStream,
+        protocol: u16,
+        heartbeat: HeartbeatConfig,
+        status_tx: mpsc::Sender<HyperConnectionDetails>,
+        logger: PointLogger,
+    ) -> HyperwayEndpoint {
         let (in_tx, in_rx) = mpsc::channel(1024);
         let (out_tx, out_rx) = mpsc::channel(1024);
         let (terminate_tx, mut terminate_rx) = mpsc::channel(1);
-        let mut muxer = Self {
+        let (liveness_tx, liveness_rx) = watch::channel(HyperConnectionStatus::Ready);
+        let muxer = Self {
             stream,
             tx: in_tx,
             rx: out_rx,
             terminate_rx,
+            protocol,
+            heartbeat,
+            status_tx,
+            liveness_tx,
             logger: logger.clone(),
         };
         {
@@ -281,41 +467,159 @@ impl FrameMuxer {
             oneshot_terminate_rx.await.unwrap_or_default();
             terminate_tx.send(()).await.unwrap_or_default();
         });
-        HyperwayEndpoint::new_with_drop(out_tx, in_rx, oneshot_terminate_tx, logger)
+        let mut endpoint =
+            HyperwayEndpoint::new_with_drop(out_tx, in_rx, oneshot_terminate_tx, logger);
+        endpoint.add_liveness(liveness_rx);
+        endpoint
     }
 
-    pub async fn mux(mut self) -> Result<(), SpaceErr> {
-        loop {
-            tokio::select! {
-                wave = self.rx.recv() => {
-                    match wave {
-                        None => {
-                            self.logger.warn("rx discon");
-                            break
-                        },
-                        Some(wave) => {
-                           self.stream.write_wave(wave.clone()).await?;
-                        }
+    pub async fn mux(self) -> Result<(), SpaceErr> {
+        // the fields move to the reader, the writer & the loop below
+        let Self {
+            stream,
+            tx,
+            mut rx,
+            mut terminate_rx,
+            protocol,
+            heartbeat,
+            status_tx,
+            liveness_tx,
+            logger,
+        } = self;
+        // frames are read on their own task because a read cancelled by `select!`
+        // part way through a frame would desync the stream
+        let (mut read, mut write) = io::split(stream.stream);
+        let (frame_tx, mut frame_rx) = mpsc::channel(1024);
+        let reader = tokio::spawn(async move {
+            loop {
+                let frame = Frame::from_stream(&mut read)
+                    .await
+                    .and_then(|frame| MuxFrame::from_frame(frame, protocol));
+                let end = frame.is_err();
+                if frame_tx.send(frame).await.is_err() || end {
+                    break;
+                }
+            }
+        });
+
+        // frames are written on their own task as well so a peer that stops reading
+        // cannot stall the heartbeat.  A write that outlasts the heartbeat timeout ends
+        // the connection
+        let (ctrl_tx, mut ctrl_rx) = mpsc::channel(16);
+        let (sent_tx, sent_rx) = watch::channel(Instant::now());
+        let (written_tx, mut written_rx) = oneshot::channel();
+        let timeout = heartbeat.timeout;
+        let writer = {
+            let logger = logger.clone();
+            tokio::spawn(async move {
+                let result: Result<(), SpaceErr> = async {
+                    loop {
+                        let frame = tokio::select! {
+                            wave = rx.recv() => match wave {
+                                Some(wave) => {
+                                    sent_tx.send(Instant::now()).unwrap_or_default();
+                                    MuxFrame::Wave(wave)
+                                }
+                                None => {
+                                    logger.warn("rx discon");
+                                    break;
+                                }
+                            },
+                            frame = ctrl_rx.recv() => match frame {
+                                Some(frame) => frame,
+                                None => break,
+                            },
+                        };
+                        let frame = frame.to_frame(protocol)?;
+                        tokio::time::timeout(timeout, frame.to_stream(&mut write))
+                            .await
+                            .map_err(|_| {
+                                SpaceErr::new(408, format!("write stalled for {:?}", timeout))
+                            })??;
                     }
+                    Ok(())
                 }
-                wave = self.stream.read_wave() => {
-                    match wave {
-                       Ok(wave) => {
-                            self.tx.send(wave).await?;
-                       },
-                       Err(err) => {
-                            self.logger.error(format!("read stream err: {}",err.to_string()));
+                .await;
+                written_tx.send(result).unwrap_or_default();
+            })
+        };
+
+        let mut interval = tokio::time::interval(heartbeat.interval);
+        // a 0.3.4 peer neither sends nor answers pings
+        let pings = protocol >= MuxFrame::TAGGED;
+        let mut heard = Instant::now();
+        let mut received = Instant::now();
+        let result: Result<(), SpaceErr> = async {
+            loop {
+                tokio::select! {
+                    written = &mut written_rx => {
+                        // the writer ends once the hyperway is gone or the stream fails
+                        match written {
+                            Ok(Err(err)) => return Err(err),
+                            _ => break,
+                        }
+                    }
+                    frame = frame_rx.recv() => {
+                        heard = Instant::now();
+                        match frame {
+                           Some(Ok(MuxFrame::Wave(wave))) => {
+                                received = Instant::now();
+                                tx.send(wave).await?;
+                           },
+                           Some(Ok(MuxFrame::Ping)) => {
+                                // with the writer backed up the pong is dropped, the peer
+                                // pings again
+                                ctrl_tx.try_send(MuxFrame::Pong).unwrap_or_default();
+                           },
+                           Some(Ok(MuxFrame::Pong)) => {},
+                           Some(Err(err)) => {
+                                logger.error(format!("read stream err: {}",err.to_string()));
+                                break;
+                           }
+                           None => break
+                        }
+                    }
+                    _ = interval.tick() => {
+                        if pings && heard.elapsed() > heartbeat.timeout {
+                            logger.warn(format!("no heartbeat for {:?}", heard.elapsed()));
+                            Self::disconnect(&status_tx, &liveness_tx, HyperConnectionStatus::Stale, "remote stopped answering heartbeats").await;
+                            break;
+                        }
+                        let active = received.max(*sent_rx.borrow());
+                        if heartbeat.is_idle(Some(active.elapsed())) {
+                            Self::disconnect(&status_tx, &liveness_tx, HyperConnectionStatus::Idle, "no waves in either direction").await;
                             break;
-                       }
+                        }
+                        if pings {
+                            ctrl_tx.try_send(MuxFrame::Ping).unwrap_or_default();
+                        }
                     }
+                    _ = terminate_rx.recv() => {
+                         logger.warn(format!("terminated"));
+                         break
+                        }
                 }
-                _ = self.terminate_rx.recv() => {
-                     self.logger.warn(format!("terminated"));
-                     return Ok(())
-                    }
             }
+            Ok::<(), SpaceErr>(())
         }
-        Ok(())
+        .await;
+        // a half open connection would otherwise keep the reader waiting forever
+        reader.abort();
+        writer.abort();
+        result
+    }
+
+    async fn disconnect(
+        status_tx: &mpsc::Sender<HyperConnectionDetails>,
+        liveness_tx: &watch::Sender<HyperConnectionStatus>,
+        status: HyperConnectionStatus,
+        info: &str,
+    ) {
+        status_tx
+            .send(HyperConnectionDetails::new(status.clone(), info))
+            .await
+            .unwrap_or_default();
+        liveness_tx.send(status).unwrap_or_default();
     }
 }
 
@@ -336,6 +640,10 @@ impl FrameStream {
         self.frame().await?.to_version()
     }
 
+    pub async fn read_protocol(&mut self) -> Result<ProtocolRange, SpaceErr> {
+        self.frame().await?.to_protocol()
+    }
+
     pub async fn read_string(&mut self) -> Result<String, SpaceErr> {
         self.frame().await?.to_string()
     }
@@ -356,6 +664,10 @@ impl FrameStream {
         self.write_frame(Frame::from_version(version)).await
     }
 
+    pub async fn write_protocol(&mut self, protocol: &ProtocolRange) -> Result<(), SpaceErr> {
+        self.write_frame(Frame::from_protocol(protocol)).await
+    }
+
     pub async fn write_wave(&mut self, wave: UltraWave) -> Result<(), SpaceErr> {
         self.write_frame(Frame::from_wave(wave)?).await
     }
@@ -376,6 +688,7 @@ pub struct HyperlaneTcpServer {
     acceptor: TlsAcceptor,
     server_kill_tx: broadcast::Sender<()>,
     server_kill_rx: broadcast::Receiver<()>,
+    heartbeat: HeartbeatConfig,
 }
 
 impl HyperlaneTcpServer {
@@ -429,9 +742,15 @@ impl HyperlaneTcpServer {
             logger,
             server_kill_tx,
             server_kill_rx,
+            heartbeat: HeartbeatConfig::default(),
         })
     }
 
+    pub fn with_heartbeat(mut self, heartbeat: HeartbeatConfig) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
     pub fn start(mut self) -> Result<HyperlaneTcpServerApi, Error> {
         tokio::spawn(async move {
             self.run().await;
@@ -445,6 +764,7 @@ impl HyperlaneTcpServer {
             let acceptor = self.acceptor.clone();
             let gate = self.gate.clone();
             let logger = self.logger.clone();
+            let heartbeat = self.heartbeat.clone();
             let mut server_kill_rx = self.server_kill_tx.subscribe();
 
             tokio::spawn(async move {
@@ -453,8 +773,11 @@ impl HyperlaneTcpServer {
                     acceptor: TlsAcceptor,
                     gate: Arc<HyperGateSelector>,
                     server_kill_rx: broadcast::Receiver<()>,
+                    heartbeat: HeartbeatConfig,
                     logger: PointLogger,
                 ) -> Result<(), Error> {
+                    // the address outlasts the remote a control is assigned so bans hold
+                    let peer = stream.peer_addr().ok().map(|addr| addr.ip().to_string());
                     let mut stream = acceptor.accept(stream).await.unwrap();
 
                     let mut stream = FrameStream::new(stream.into());
@@ -475,14 +798,17 @@ impl HyperlaneTcpServer {
                             }
                         });
                     }
-                    let mut mux = FrameMuxer::handshake(stream, status_tx, logger.clone()).await?;
+                    let mut mux =
+                        FrameMuxer::accept(stream, heartbeat, status_tx, logger.clone()).await?;
 
                     let knock = tokio::time::timeout(Duration::from_secs(30), mux.rx.recv())
                         .await?
                         .ok_or("expected wave")?;
                     let knock = knock.to_directed()?;
                     if let Substance::Knock(knock) = knock.body() {
-                        let mut endpoint = gate.knock(knock.clone()).await?;
+                        let mut knock = knock.clone();
+                        knock.peer = peer;
+                        let mut endpoint = gate.knock(knock).await?;
                         mux.connect(endpoint);
                     } else {
                         let msg = format!(
@@ -494,7 +820,7 @@ impl HyperlaneTcpServer {
 
                     Ok(())
                 }
-                serve(stream, acceptor, gate, server_kill_rx, logger).await;
+                serve(stream, acceptor, gate, server_kill_rx, heartbeat, logger).await;
             });
         }
     }
@@ -591,6 +917,49 @@ mod tests {
         Utc::now()
     }
 
+    #[test]
+    fn test_offer() {
+        match Offer::from_frame(Frame::from_protocol(&PROTOCOL)).unwrap() {
+            Offer::Protocol(offered) => assert_eq!(offered, PROTOCOL),
+            Offer::Legacy(_) => panic!("expected a protocol range"),
+        }
+        let legacy = semver::Version::from_str(LEGACY_VERSION).unwrap();
+        match Offer::from_frame(Frame::from_version(&legacy)).unwrap() {
+            Offer::Legacy(version) => assert_eq!(version, legacy),
+            Offer::Protocol(_) => panic!("expected a version"),
+        }
+        assert!(Offer::from_frame(Frame::from_string("hello".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_mux_frame_revision() {
+        let wave: Wave<Ping> = Knock::default().into();
+        let wave = wave.to_ultra();
+
+        // revision 1 frames carry the bare wave just like 0.3.4 did
+        let frame = MuxFrame::Wave(wave.clone()).to_frame(1).unwrap();
+        assert_eq!(
+            frame.data,
+            at_revision(1, || bincode::serialize(&wave)).unwrap()
+        );
+        match MuxFrame::from_frame(frame, 1).unwrap() {
+            MuxFrame::Wave(read) => assert_eq!(read.id(), wave.id()),
+            _ => panic!("expected a wave"),
+        }
+        assert!(MuxFrame::Ping.to_frame(1).is_err());
+
+        let frame = MuxFrame::Wave(wave.clone()).to_frame(2).unwrap();
+        assert_eq!(frame.data[0], MuxFrame::WAVE);
+        match MuxFrame::from_frame(frame, 2).unwrap() {
+            MuxFrame::Wave(read) => assert_eq!(read, wave),
+            _ => panic!("expected a wave"),
+        }
+        match MuxFrame::from_frame(MuxFrame::Ping.to_frame(2).unwrap(), 2).unwrap() {
+            MuxFrame::Ping => {}
+            _ => panic!("expected a ping"),
+        }
+    }
+
     //#[tokio::test]
     async fn test_tcp() -> Result<(), Error> {
         let platform = SingleInterchangePlatform::new().await;
@@ -10,10 +10,11 @@ use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::atomic::{AtomicU16, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
 use futures::future::select_all;
@@ -23,21 +24,31 @@ use tokio::select;
 use tokio::sync::mpsc::error::{SendError, SendTimeoutError, TrySendError};
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::oneshot::Sender;
-use tokio::sync::{broadcast, mpsc, oneshot, watch, Mutex, RwLock};
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Mutex, Notify, RwLock};
 
+use cosmic_space::capture::{
+    write_capture_header, write_capture_record, CaptureFilter, CaptureRecord,
+};
 use cosmic_space::command::direct::create::{PointFactoryU64, PointSegTemplate};
 use cosmic_space::err::SpaceErr;
 use cosmic_space::frame::PrimitiveFrame;
-use cosmic_space::hyper::{Greet, HyperSubstance, InterchangeKind, Knock};
+use cosmic_space::hyper::{
+    Greet, HyperSubstance, HyperlaneReport, HyperwayReport, InterchangeKind, Knock,
+    ProtocolMismatch, ProtocolRange, PROTOCOL,
+};
+use cosmic_space::limit::RateLimiter;
 use cosmic_space::loc::{Layer, Point, PointFactory, Surface, ToPoint, ToSurface, Version};
 use cosmic_space::log::{PointLogger, RootLogger, Tracker};
+use cosmic_space::metrics::{Counter, Gauge, METRICS};
 use cosmic_space::particle::Status;
+use cosmic_space::selector::Selector;
 use cosmic_space::settings::Timeouts;
 use cosmic_space::substance::{FormErrs, Substance, SubstanceKind, Token};
 use cosmic_space::util::uuid;
+use cosmic_space::wasm::Timestamp;
 use cosmic_space::wave::core::ext::ExtMethod;
 use cosmic_space::wave::core::hyp::HypMethod;
-use cosmic_space::wave::core::Method;
+use cosmic_space::wave::core::{Method, ReflectedCore};
 use cosmic_space::wave::exchange::asynch::{
     Exchanger, ProtoTransmitter, ProtoTransmitterBuilder, Router, TxRouter,
 };
@@ -46,7 +57,6 @@ use cosmic_space::wave::{
     Agent, DirectedKind, DirectedProto, Handling, HyperWave, Ping, Pong, Reflectable,
     ReflectedKind, ReflectedProto, ReflectedWave, UltraWave, Wave, WaveId, WaveKind,
 };
-use cosmic_space::VERSION;
 
 lazy_static! {
     pub static ref LOCAL_CLIENT: Point = Point::from_str("LOCAL::client").expect("point");
@@ -62,11 +72,16 @@ pub enum HyperwayKind {
 
 pub struct Hyperway {
     pub remote: Surface,
+    agent: Agent,
+    created: Instant,
     outbound: Hyperlane,
     inbound: Hyperlane,
     logger: PointLogger,
-
-    #[cfg(test)]
+    heartbeat: Option<HeartbeatConfig>,
+    limiter: Option<RateLimiter>,
+    kind: Option<InterchangeKind>,
+    /// the address of the transport peer of the remote, if it has one
+    peer: Option<String>,
     pub diagnostic: HyperwayDiagnostic,
 }
 
@@ -84,16 +99,116 @@ impl Hyperway {
             .try_send(HyperlaneCall::Transform(Box::new(AgentTransform::new(
                 agent,
             ))));
+        let outbound = Hyperlane::new(format!("{}<Outbound>", remote.to_string()));
+        // a wave dropped on its way out is reflected back in & vice versa
+        inbound.bounce_to(&outbound);
+        outbound.bounce_to(&inbound);
         Self {
-            outbound: Hyperlane::new(format!("{}<Outbound>", remote.to_string())),
+            diagnostic: HyperwayDiagnostic::new(inbound.stats(), outbound.stats()),
+            outbound,
             remote,
+            agent,
+            created: Instant::now(),
             inbound,
             logger,
-            #[cfg(test)]
-            diagnostic: HyperwayDiagnostic::new(),
+            heartbeat: None,
+            limiter: None,
+            kind: None,
+            peer: None,
+        }
+    }
+
+    /// replace the queue limit & overflow policy of both lanes
+    pub fn configure(&self, config: HyperlaneConfig) {
+        self.inbound.configure(config.clone());
+        self.outbound.configure(config);
+    }
+
+    /// record the waves crossing both lanes for as long as the hyperway lives
+    pub fn capture(&self, capture: Capture) {
+        self.inbound.capture(capture.clone());
+        self.outbound.capture(capture);
+    }
+
+    /// both lanes refuse waves that need a later revision than the negotiated `protocol`
+    pub fn protocol(&self, protocol: u16) {
+        self.inbound.protocol(protocol);
+        self.outbound.protocol(protocol);
+    }
+
+    /// the interchange refuses inbound waves over the limits of `limiter` with a 429
+    pub fn limit(&mut self, limiter: RateLimiter, kind: Option<InterchangeKind>) {
+        self.limiter = Some(limiter);
+        self.kind = kind;
+    }
+
+    /// the address of the transport peer (see `Knock::peer`) a ban of this hyperway
+    /// also refuses
+    pub fn peer(&mut self, peer: Option<String>) {
+        self.peer = peer;
+    }
+
+    pub fn report(&self) -> HyperwayReport {
+        HyperwayReport {
+            remote: self.remote.clone(),
+            agent: self.agent.clone(),
+            age: self.created.elapsed().as_secs(),
+            peer: self.peer.clone(),
+            inbound: self.inbound.stats().report(),
+            outbound: self.outbound.stats().report(),
         }
     }
 
+    /// `point` is the remote or the agent of this hyperway
+    pub fn is(&self, point: &Point) -> bool {
+        self.remote.point == *point || self.agent == Agent::Point(point.clone())
+    }
+
+    /// drop the endpoints of both lanes so the far side sees the connection close
+    async fn disconnect(&self) {
+        for lane in [&self.inbound, &self.outbound] {
+            if lane.tx.send(HyperlaneCall::ResetExt).await.is_err() {
+                self.logger.warn(format!(
+                    "could not disconnect hyperlane {}: it already stopped",
+                    lane.label
+                ));
+            }
+        }
+    }
+
+    /// how the liveness of endpoints created from now on is checked. `None`, the
+    /// default, never checks: a remote connection has the heartbeat of its transport
+    /// (i.e. the tcp `FrameMuxer`) and an in process one is only as stale as the
+    /// endpoint that holds it
+    pub fn heartbeat(&mut self, heartbeat: Option<HeartbeatConfig>) {
+        self.heartbeat = heartbeat;
+    }
+
+    /// in process there is nothing to ping: the other side is `Stale` once waves queue
+    /// up in `lane` without being taken and `Idle` once neither lane carries any
+    fn liveness(&self, lane: &Hyperlane) -> Option<watch::Receiver<HyperConnectionStatus>> {
+        let heartbeat = self.heartbeat.clone()?;
+        let (status_tx, status_rx) = watch::channel(HyperConnectionStatus::Ready);
+        let sending = lane.stats();
+        let lanes = vec![self.inbound.stats(), self.outbound.stats()];
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(heartbeat.interval).await;
+                let status = if sending.stalled(heartbeat.timeout) {
+                    HyperConnectionStatus::Stale
+                } else if heartbeat.is_idle(lanes.iter().map(|lane| lane.since_taken()).min()) {
+                    HyperConnectionStatus::Idle
+                } else {
+                    HyperConnectionStatus::Ready
+                };
+                if status_tx.send(status).is_err() {
+                    break;
+                }
+            }
+        });
+        Some(status_rx)
+    }
+
     pub fn transform_inbound(&self, transform: Box<dyn HyperTransform>) {
         self.inbound
             .tx
@@ -113,6 +228,7 @@ impl Hyperway {
             tx: self.outbound.tx(),
             rx: self.inbound.rx(init_wave).await,
             drop_tx,
+            liveness: self.liveness(&self.outbound),
             logger: self.logger.clone(),
         }
     }
@@ -128,6 +244,7 @@ impl Hyperway {
             tx: self.outbound.tx(),
             rx: self.inbound.rx(init_wave).await,
             drop_tx,
+            liveness: self.liveness(&self.outbound),
             logger: self.logger.clone(),
         }
     }
@@ -137,6 +254,7 @@ impl Hyperway {
             tx: self.inbound.tx(),
             rx: self.outbound.rx(init_wave).await,
             drop_tx: None,
+            liveness: self.liveness(&self.inbound),
             logger: self.logger.clone(),
         }
     }
@@ -150,26 +268,66 @@ impl Hyperway {
             tx: self.inbound.tx(),
             rx: self.outbound.rx(init_wave).await,
             drop_tx: Some(drop_tx),
+            liveness: self.liveness(&self.inbound),
             logger: self.logger.clone(),
         }
     }
 }
 
-#[cfg(test)]
+/// how a hyperway checks that the other side is still there
+#[derive(Debug, Clone)]
+pub struct HeartbeatConfig {
+    /// how often the other side is pinged (or in process how often the lanes are checked)
+    pub interval: Duration,
+    /// the other side is `Stale` once it has not answered for this long
+    pub timeout: Duration,
+    /// a connection that carried no waves for this long is `Idle` & disconnected.
+    /// `None` never disconnects
+    pub idle: Option<Duration>,
+}
+
+impl HeartbeatConfig {
+    pub fn is_idle(&self, quiet: Option<Duration>) -> bool {
+        match (&self.idle, quiet) {
+            (Some(idle), Some(quiet)) => quiet > *idle,
+            _ => false,
+        }
+    }
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            timeout: Duration::from_secs(15),
+            idle: None,
+        }
+    }
+}
+
 pub struct HyperwayDiagnostic {
+    #[cfg(test)]
     pub replaced_ext: broadcast::Sender<Result<(), SpaceErr>>,
+    pub inbound: HyperlaneStats,
+    pub outbound: HyperlaneStats,
 }
 
-#[cfg(test)]
 impl HyperwayDiagnostic {
-    pub fn new() -> Self {
+    pub fn new(inbound: HyperlaneStats, outbound: HyperlaneStats) -> Self {
+        #[cfg(test)]
         let (replaced_ext, _) = broadcast::channel(128);
-        Self { replaced_ext }
+        Self {
+            #[cfg(test)]
+            replaced_ext,
+            inbound,
+            outbound,
+        }
     }
 }
 
 pub struct HyperwayEndpoint {
     drop_tx: Option<oneshot::Sender<()>>,
+    liveness: Option<watch::Receiver<HyperConnectionStatus>>,
     pub tx: mpsc::Sender<UltraWave>,
     pub rx: mpsc::Receiver<UltraWave>,
     pub logger: PointLogger,
@@ -186,6 +344,7 @@ impl HyperwayEndpoint {
             tx,
             rx,
             drop_tx,
+            liveness: None,
             logger,
         }
     }
@@ -201,6 +360,7 @@ impl HyperwayEndpoint {
             tx,
             rx,
             drop_tx,
+            liveness: None,
             logger,
         }
     }
@@ -238,6 +398,16 @@ impl HyperwayEndpoint {
         self.drop_tx.replace(drop_tx);
     }
 
+    pub fn add_liveness(&mut self, liveness: watch::Receiver<HyperConnectionStatus>) {
+        self.liveness.replace(liveness);
+    }
+
+    /// `Ready`, `Stale` or `Idle` as seen by the heartbeat of this endpoint's
+    /// connection (if it has one)
+    pub fn liveness(&self) -> Option<watch::Receiver<HyperConnectionStatus>> {
+        self.liveness.clone()
+    }
+
     pub fn router(&self) -> TxRouter {
         TxRouter::new(self.tx.clone())
     }
@@ -286,6 +456,12 @@ pub enum HyperwayInterchangeCall {
     Wave(UltraWave),
     Internal(Hyperway),
     Remove(Surface),
+    Hyperways(oneshot::Sender<Vec<HyperwayReport>>),
+    /// replies with the peer of each hyperway kicked
+    Kick {
+        point: Point,
+        rtn: oneshot::Sender<Vec<Option<String>>>,
+    },
     Mount {
         stub: HyperwayStub,
         init_wave: Option<UltraWave>,
@@ -421,9 +597,116 @@ impl HyperTransform for FromTransform {
         wave
     }
 }
+/// what a `Hyperlane` does with a wave that arrives once `HyperlaneConfig::limit`
+/// waves are already queued
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Overflow {
+    /// the sender waits up to `HyperlaneConfig::send_timeout` for room
+    Block,
+    /// the oldest queued wave is dropped to make room
+    DropOldest,
+    /// the arriving wave is dropped
+    RejectNewest,
+}
+
+#[derive(Debug, Clone)]
+pub struct HyperlaneConfig {
+    pub limit: usize,
+    pub overflow: Overflow,
+    pub send_timeout: Duration,
+}
+
+impl Default for HyperlaneConfig {
+    fn default() -> Self {
+        Self {
+            limit: 1024,
+            overflow: Overflow::DropOldest,
+            send_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// what a `Hyperlane` holds & has dropped since it was created
+#[derive(Clone)]
+pub struct HyperlaneStats {
+    queued: Arc<AtomicUsize>,
+    dropped: Arc<AtomicU64>,
+    bounced: Arc<AtomicU64>,
+    created: Instant,
+    /// millis after `created` that an endpoint last took a wave (or the queue last
+    /// stopped being empty)
+    taken: Arc<AtomicU64>,
+    queued_gauge: Gauge,
+    dropped_counter: Counter,
+}
+
+impl HyperlaneStats {
+    fn new() -> Self {
+        Self {
+            queued: Arc::new(AtomicUsize::new(0)),
+            dropped: Arc::new(AtomicU64::new(0)),
+            bounced: Arc::new(AtomicU64::new(0)),
+            created: Instant::now(),
+            taken: Arc::new(AtomicU64::new(0)),
+            queued_gauge: queued_gauge(),
+            dropped_counter: dropped_counter(),
+        }
+    }
+
+    fn took(&self) {
+        self.taken.store(
+            self.created.elapsed().as_millis() as u64,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// how long ago an endpoint last took a wave from the lane
+    pub fn since_taken(&self) -> Duration {
+        self.created.elapsed().saturating_sub(Duration::from_millis(
+            self.taken.load(Ordering::Relaxed),
+        ))
+    }
+
+    /// waves are queued but no endpoint has taken one for `timeout`
+    pub fn stalled(&self, timeout: Duration) -> bool {
+        self.queued() > 0 && self.since_taken() > timeout
+    }
+
+    /// waves sent into the lane that an endpoint has not taken yet
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// waves dropped because the lane was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 503 reflections of dropped directed waves sent back toward their senders
+    pub fn bounced(&self) -> u64 {
+        self.bounced.load(Ordering::Relaxed)
+    }
+
+    pub fn report(&self) -> HyperlaneReport {
+        HyperlaneReport {
+            queued: self.queued(),
+            dropped: self.dropped(),
+            bounced: self.bounced(),
+            idle: self.since_taken().as_secs(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Hyperlane {
     tx: mpsc::Sender<HyperlaneCall>,
+    config: Arc<std::sync::RwLock<HyperlaneConfig>>,
+    stats: HyperlaneStats,
+    room: Arc<Notify>,
+    bounce: Arc<std::sync::RwLock<Option<Hyperlane>>>,
+    /// the negotiated protocol revision, waves that need a later one are refused
+    protocol: Arc<AtomicU16>,
+    capture: Arc<std::sync::RwLock<Option<Capture>>>,
     #[cfg(test)]
     eavesdrop_tx: broadcast::Sender<UltraWave>,
     label: String,
@@ -440,72 +723,92 @@ impl Hyperlane {
             HYPERLANE_INDEX.fetch_add(1, Ordering::Relaxed)
         );
 
-        let (tx, mut rx) = mpsc::channel(1024);
-        {
-            let label = label.clone();
-            let tx = tx.clone();
+        let (tx, rx) = mpsc::channel(1024);
+        let lane = Self {
+            tx,
+            config: Arc::new(std::sync::RwLock::new(HyperlaneConfig::default())),
+            stats: HyperlaneStats::new(),
+            room: Arc::new(Notify::new()),
+            bounce: Arc::new(std::sync::RwLock::new(None)),
+            protocol: Arc::new(AtomicU16::new(u16::MAX)),
+            capture: Arc::new(std::sync::RwLock::new(None)),
+            label,
             #[cfg(test)]
-            let eavesdrop_tx = eavesdrop_tx.clone();
+            eavesdrop_tx,
+        };
+        lane.start(rx);
+        lane
+    }
 
-            tokio::spawn(async move {
-                let mut ext = None;
-                let mut queue = vec![];
-                let mut transforms = vec![];
-                while let Some(call) = rx.recv().await {
-                    match call {
-                        HyperlaneCall::Ext(ext_tx) => {
-                            ext.replace(ext_tx);
-                        }
-                        HyperlaneCall::Transform(filter) => {
-                            transforms.push(filter);
-                        }
-                        HyperlaneCall::Wave(mut wave) => {
-                            while queue.len() > 1024 {
+    fn start(&self, mut rx: mpsc::Receiver<HyperlaneCall>) {
+        let lane = self.clone();
+        tokio::spawn(async move {
+            let mut ext: Option<mpsc::Sender<UltraWave>> = None;
+            let mut queue = vec![];
+            let mut transforms = vec![];
+            while let Some(call) = rx.recv().await {
+                match call {
+                    HyperlaneCall::Ext(ext_tx) => {
+                        ext.replace(ext_tx);
+                    }
+                    HyperlaneCall::Transform(filter) => {
+                        transforms.push(filter);
+                    }
+                    HyperlaneCall::Wave(mut wave) => {
+                        let config = lane.config();
+                        if config.overflow == Overflow::DropOldest {
+                            while !queue.is_empty() && queue.len() >= config.limit {
                                 // start dropping the oldest messages
-                                queue.remove(0);
-                            }
-                            for transform in transforms.iter() {
-                                wave = transform.filter(wave);
+                                lane.departed();
+                                lane.drop_wave(queue.remove(0));
                             }
-                            queue.push(wave);
                         }
-                        HyperlaneCall::Drain => {
-                            // just drains the queue later if there is a listener
-                        }
-                        HyperlaneCall::ResetExt => {
-                            ext = None;
+                        for transform in transforms.iter() {
+                            wave = transform.filter(wave);
                         }
+                        queue.push(wave);
                     }
-                    if !queue.is_empty() {
-                        if let Some(ext_tx) = ext.as_mut() {
-                            for wave in queue.drain(..) {
-                                #[cfg(test)]
-                                let wave_cp = wave.clone();
-
-                                match ext_tx.send(wave).await {
-                                    Ok(_) => {
-                                        #[cfg(test)]
-                                        eavesdrop_tx.send(wave_cp);
-                                    }
-                                    Err(err) => {
-                                        tx.send(HyperlaneCall::ResetExt).await;
-                                        tx.try_send(HyperlaneCall::Wave(err.0));
-                                    }
-                                }
+                    HyperlaneCall::Drain => {
+                        // just drains the queue later if there is a listener
+                    }
+                    HyperlaneCall::ResetExt => {
+                        ext = None;
+                    }
+                }
+                while !queue.is_empty() {
+                    let ext_tx = match ext.as_ref() {
+                        Some(ext_tx) => ext_tx,
+                        None => break,
+                    };
+                    let wave = queue.remove(0);
+                    // recorded once taken so a wave kept for the next endpoint is not recorded twice
+                    let captured = lane.capturing().map(|capture| (capture, wave.clone()));
+                    #[cfg(test)]
+                    let wave_cp = wave.clone();
+
+                    match ext_tx.send(wave).await {
+                        Ok(_) => {
+                            if let Some((capture, wave)) = captured {
+                                capture.record(lane.label.as_str(), &wave);
                             }
-                        } else {
+                            lane.stats.took();
+                            lane.departed();
+                            #[cfg(test)]
+                            lane.eavesdrop_tx.send(wave_cp);
+                        }
+                        Err(err) => {
+                            // keep the wave for the next endpoint
+                            ext = None;
+                            queue.insert(0, err.0);
                         }
                     }
                 }
-            });
-        }
-
-        Self {
-            tx,
-            label,
-            #[cfg(test)]
-            eavesdrop_tx,
-        }
+            }
+            // the lane is gone along with whatever it still queued
+            for _ in queue.drain(..) {
+                lane.departed();
+            }
+        });
     }
 
     #[cfg(test)]
@@ -513,19 +816,173 @@ impl Hyperlane {
         self.eavesdrop_tx.subscribe()
     }
 
+    pub fn config(&self) -> HyperlaneConfig {
+        match self.config.read() {
+            Ok(config) => config.clone(),
+            Err(_) => HyperlaneConfig::default(),
+        }
+    }
+
+    pub fn configure(&self, config: HyperlaneConfig) {
+        if let Ok(mut current) = self.config.write() {
+            *current = config;
+        }
+        // blocked senders recheck against the new limit
+        self.room.notify_waiters();
+    }
+
+    pub fn stats(&self) -> HyperlaneStats {
+        self.stats.clone()
+    }
+
+    /// the lane that 503 reflections of directed waves dropped by this lane are sent into
+    pub fn bounce_to(&self, lane: &Hyperlane) {
+        if let Ok(mut bounce) = self.bounce.write() {
+            bounce.replace(lane.clone());
+        }
+    }
+
+    /// record the waves this lane delivers
+    pub fn capture(&self, capture: Capture) {
+        if let Ok(mut current) = self.capture.write() {
+            current.replace(capture);
+        }
+    }
+
+    fn capturing(&self) -> Option<Capture> {
+        self.capture.read().ok()?.clone()
+    }
+
+    /// refuse waves that need a later protocol revision than `protocol`
+    pub fn protocol(&self, protocol: u16) {
+        self.protocol.store(protocol, Ordering::Relaxed);
+    }
+
     pub async fn send(&self, wave: UltraWave) -> Result<(), SpaceErr> {
-        Ok(self
+        let protocol = self.protocol.load(Ordering::Relaxed);
+        if wave.protocol() > protocol {
+            let err = SpaceErr::new(
+                426,
+                format!(
+                    "wave needs protocol revision {} but revision {} was negotiated",
+                    wave.protocol(),
+                    protocol
+                ),
+            );
+            self.bounce(&wave, ReflectedCore::err(err.clone()));
+            return Err(err);
+        }
+        let config = self.config();
+        if self.stats.queued() >= config.limit {
+            match config.overflow {
+                Overflow::DropOldest => {}
+                Overflow::RejectNewest => {
+                    self.drop_wave(wave);
+                    return Err(self.full());
+                }
+                Overflow::Block => {
+                    if tokio::time::timeout(config.send_timeout, self.room())
+                        .await
+                        .is_err()
+                    {
+                        self.drop_wave(wave);
+                        return Err(self.full());
+                    }
+                }
+            }
+        }
+        self.arrived();
+        match self
             .tx
-            .send_timeout(HyperlaneCall::Wave(wave), Duration::from_secs(5))
-            .await?)
+            .send_timeout(HyperlaneCall::Wave(wave), config.send_timeout)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(SendTimeoutError::Timeout(call)) | Err(SendTimeoutError::Closed(call)) => {
+                self.departed();
+                if let HyperlaneCall::Wave(wave) = call {
+                    self.drop_wave(wave);
+                }
+                Err(self.full())
+            }
+        }
+    }
+
+    /// like `send` but never waits: a wave that does not fit right away is dropped
+    fn try_send(&self, wave: UltraWave) -> bool {
+        let config = self.config();
+        if config.overflow != Overflow::DropOldest && self.stats.queued() >= config.limit {
+            self.drop_wave(wave);
+            return false;
+        }
+        self.arrived();
+        match self.tx.try_send(HyperlaneCall::Wave(wave)) {
+            Ok(_) => true,
+            Err(TrySendError::Full(call)) | Err(TrySendError::Closed(call)) => {
+                self.departed();
+                if let HyperlaneCall::Wave(wave) = call {
+                    self.drop_wave(wave);
+                }
+                false
+            }
+        }
+    }
+
+    async fn room(&self) {
+        while self.stats.queued() >= self.config().limit {
+            self.room.notified().await;
+        }
+    }
+
+    fn arrived(&self) {
+        if self.stats.queued.fetch_add(1, Ordering::Relaxed) == 0 {
+            // an empty lane is not stalled however long ago it was last drained
+            self.stats.took();
+        }
+        self.stats.queued_gauge.inc();
+    }
+
+    fn departed(&self) {
+        self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+        self.stats.queued_gauge.dec();
+        self.room.notify_one();
+    }
+
+    /// a dropped directed wave is reflected to its sender as a 503
+    fn drop_wave(&self, wave: UltraWave) {
+        self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+        self.stats.dropped_counter.inc();
+        let core = ReflectedCore::fail(503, format!("hyperlane {} is full", self.label));
+        self.bounce(&wave, core);
+    }
+
+    /// reflect `core` to the sender of `wave` if it is directed
+    fn bounce(&self, wave: &UltraWave, core: ReflectedCore) {
+        let reflection = match reflect(wave, core) {
+            Some(reflection) => reflection,
+            None => return,
+        };
+        let bounce = match self.bounce.read() {
+            Ok(bounce) => bounce.clone(),
+            Err(_) => None,
+        };
+        if let Some(bounce) = bounce {
+            if bounce.try_send(reflection) {
+                self.stats.bounced.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn full(&self) -> SpaceErr {
+        SpaceErr::new(503, format!("hyperlane {} is full", self.label))
     }
 
     pub fn tx(&self) -> mpsc::Sender<UltraWave> {
         let (tx, mut rx) = mpsc::channel(1024);
-        let call_tx = self.tx.clone();
+        let lane = self.clone();
         tokio::spawn(async move {
             while let Some(wave) = rx.recv().await {
-                call_tx.send(HyperlaneCall::Wave(wave)).await;
+                lane.send(wave).await;
             }
         });
         tx
@@ -541,30 +998,177 @@ impl Hyperlane {
     }
 }
 
+fn reflect(wave: &UltraWave, core: ReflectedCore) -> Option<UltraWave> {
+    let directed = wave.clone().to_directed().ok()?;
+    let reflection = directed.reflection().ok()?;
+    let from = directed
+        .to()
+        .single_or()
+        .unwrap_or_else(|_| wave.from().clone());
+    Some(reflection.make(core, from).to_ultra())
+}
+
+fn queued_gauge() -> Gauge {
+    METRICS.gauge(
+        "cosmic_hyperlane_queued",
+        "waves queued in hyperlanes waiting for an endpoint to take them",
+        &[],
+    )
+}
+
+fn dropped_counter() -> Counter {
+    METRICS.counter(
+        "cosmic_hyperlane_dropped_total",
+        "waves dropped because a hyperlane's queue was full",
+        &[],
+    )
+}
+
+/// the records a `Capture` queues for its writer thread before it starts dropping them
+pub const CAPTURE_QUEUE: usize = 1024;
+
+/// records the waves that match its filter to a capture file (see
+/// `cosmic_space::capture`).  Writing happens on a dedicated thread so a slow disk
+/// never holds up a lane: once `CAPTURE_QUEUE` records are waiting to be written
+/// further records are dropped and counted
+#[derive(Clone)]
+pub struct Capture {
+    tx: std::sync::mpsc::SyncSender<CaptureRecord>,
+    filter: Arc<CaptureFilter>,
+    dropped: Arc<AtomicU64>,
+    dropped_counter: Counter,
+}
+
+impl Capture {
+    /// a write that fails stops the capture & is logged to `logger`
+    pub fn file(
+        path: PathBuf,
+        filter: CaptureFilter,
+        logger: PointLogger,
+    ) -> Result<Self, SpaceErr> {
+        use std::io::Write;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        write_capture_header(&mut file)?;
+        file.flush()?;
+        let (tx, rx) = std::sync::mpsc::sync_channel::<CaptureRecord>(CAPTURE_QUEUE);
+        std::thread::spawn(move || {
+            while let Ok(record) = rx.recv() {
+                let mut result = write_capture_record(&mut file, &record);
+                // flush once the records that queued up meanwhile are written
+                while result.is_ok() {
+                    match rx.try_recv() {
+                        Ok(record) => result = write_capture_record(&mut file, &record),
+                        Err(_) => break,
+                    }
+                }
+                if let Err(err) = result.and_then(|_| Ok(file.flush()?)) {
+                    logger.error(format!(
+                        "capture {} stopped: {}",
+                        path.display(),
+                        err.to_string()
+                    ));
+                    return;
+                }
+            }
+        });
+        Ok(Self {
+            tx,
+            filter: Arc::new(filter),
+            dropped: Arc::new(AtomicU64::new(0)),
+            dropped_counter: METRICS.counter(
+                "cosmic_capture_dropped_total",
+                "captured waves dropped because the capture file could not keep up",
+                &[],
+            ),
+        })
+    }
+
+    /// records dropped because the writer thread fell behind (or stopped)
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// record `wave` as it crosses `site` if it matches the filter
+    pub fn record(&self, site: &str, wave: &UltraWave) {
+        if !self.filter.matches(wave) {
+            return;
+        }
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as i64);
+        let record = CaptureRecord {
+            timestamp: Timestamp::new(millis),
+            site: site.to_string(),
+            wave: wave.clone(),
+        };
+        if self.tx.try_send(record).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            self.dropped_counter.inc();
+        }
+    }
+}
+
 pub struct HyperwayInterchange {
     call_tx: mpsc::Sender<HyperwayInterchangeCall>,
     logger: PointLogger,
     singular_to: Option<Surface>,
+    captures: Vec<(Selector, Capture)>,
+    /// bans live in memory & are lifted when the interchange is dropped (i.e. when
+    /// the machine restarts)
+    banned: Arc<std::sync::RwLock<Bans>>,
+    removed_tx: broadcast::Sender<Surface>,
+}
+
+/// the points & transport peers refused entry to an interchange
+#[derive(Default)]
+struct Bans {
+    points: HashSet<Point>,
+    /// each peer & the point it was banned with
+    peers: HashMap<String, Point>,
 }
 
 impl HyperwayInterchange {
     pub fn new(logger: PointLogger) -> Self {
         let (call_tx, mut call_rx) = mpsc::channel(1024);
+        let (removed_tx, _) = broadcast::channel(1024);
 
         {
             let call_tx = call_tx.clone();
             let logger = logger.clone();
+            let removed_tx = removed_tx.clone();
             tokio::spawn(async move {
                 let mut hyperways = HashMap::new();
                 while let Some(call) = call_rx.recv().await {
                     match call {
                         HyperwayInterchangeCall::Internal(hyperway) => {
                             let mut rx = hyperway.inbound.rx(None).await;
+                            let limiter = hyperway.limiter.clone();
+                            let kind = hyperway.kind.clone();
+                            let peer = hyperway.peer.clone();
+                            let outbound = hyperway.outbound.clone();
                             hyperways.insert(hyperway.remote.clone(), hyperway);
                             let call_tx = call_tx.clone();
                             let logger = logger.clone();
                             tokio::spawn(async move {
                                 while let Some(wave) = rx.recv().await {
+                                    if let Some(limiter) = limiter.as_ref() {
+                                        if let Err(throttled) = limiter.admit_wave(
+                                            wave.agent(),
+                                            peer.as_ref(),
+                                            kind.as_ref(),
+                                            &wave,
+                                        ) {
+                                            if let Some(reflection) =
+                                                reflect(&wave, throttled.core())
+                                            {
+                                                outbound.send(reflection).await.unwrap_or_default();
+                                            }
+                                            continue;
+                                        }
+                                    }
                                     call_tx
                                         .send_timeout(
                                             HyperwayInterchangeCall::Wave(wave),
@@ -575,7 +1179,34 @@ impl HyperwayInterchange {
                             });
                         }
                         HyperwayInterchangeCall::Remove(point) => {
-                            hyperways.remove(&point);
+                            if hyperways.remove(&point).is_some() {
+                                removed_tx.send(point).unwrap_or_default();
+                            }
+                        }
+                        HyperwayInterchangeCall::Hyperways(rtn) => {
+                            rtn.send(hyperways.values().map(|h| h.report()).collect())
+                                .unwrap_or_default();
+                        }
+                        HyperwayInterchangeCall::Kick { point, rtn } => {
+                            let remotes: Vec<Surface> = hyperways
+                                .values()
+                                // star links & driver endpoints are not for kicking
+                                .filter(|h| h.agent != Agent::HyperUser && h.is(&point))
+                                .map(|h| h.remote.clone())
+                                .collect();
+                            let mut peers = vec![];
+                            for remote in remotes.iter() {
+                                if let Some(hyperway) = hyperways.remove(remote) {
+                                    logger.warn(format!("kicked hyperway {}", remote.to_string()));
+                                    peers.push(hyperway.peer.clone());
+                                    removed_tx.send(remote.clone()).unwrap_or_default();
+                                    // a full lane must not hold up the interchange
+                                    tokio::spawn(async move {
+                                        hyperway.disconnect().await;
+                                    });
+                                }
+                            }
+                            rtn.send(peers).unwrap_or_default();
                         }
                         HyperwayInterchangeCall::Wave(wave) => match wave.to().single_or() {
                             Ok(to) => match hyperways.get(&to) {
@@ -588,6 +1219,9 @@ impl HyperwayInterchange {
                                         );
                                 }
                                 Some(hyperway) => {
+                                    if let Some(limiter) = hyperway.limiter.as_ref() {
+                                        limiter.release_wave(&wave);
+                                    }
                                     hyperway.outbound.send(wave).await;
                                 }
                             },
@@ -625,9 +1259,18 @@ impl HyperwayInterchange {
             call_tx,
             logger,
             singular_to: None,
+            captures: vec![],
+            banned: Arc::new(std::sync::RwLock::new(Bans::default())),
+            removed_tx,
         }
     }
 
+    /// the remote of every hyperway removed from this interchange, whether it closed
+    /// or was kicked
+    pub fn removed(&self) -> broadcast::Receiver<Surface> {
+        self.removed_tx.subscribe()
+    }
+
     pub fn router(&self) -> Box<dyn Router> {
         Box::new(OutboundRouter::new(
             self.call_tx.clone(),
@@ -660,16 +1303,112 @@ impl HyperwayInterchange {
         self.singular_to.replace(to);
     }
 
+    /// record the waves of the hyperways added from now on whose remote is selected
+    /// by `hyperways`
+    pub fn capture(&mut self, hyperways: Selector, capture: Capture) {
+        self.captures.push((hyperways, capture));
+    }
+
     pub async fn add(&self, mut hyperway: Hyperway) {
         if let Some(to) = self.singular_to.as_ref() {
             hyperway.transform_to(to.clone());
         }
 
+        for (selector, capture) in self.captures.iter() {
+            if selector.matches_point(&hyperway.remote.point) {
+                hyperway.capture(capture.clone());
+            }
+        }
+
         self.call_tx
             .send(HyperwayInterchangeCall::Internal(hyperway))
             .await;
     }
 
+    pub async fn hyperways(&self) -> Result<Vec<HyperwayReport>, SpaceErr> {
+        let (rtn, rtn_rx) = oneshot::channel();
+        self.call_tx
+            .send(HyperwayInterchangeCall::Hyperways(rtn))
+            .await?;
+        Ok(rtn_rx.await?)
+    }
+
+    /// disconnect the hyperways whose remote or agent is `point` (unless their agent is
+    /// the HyperUser), returning how many there were.  Nothing stops them from coming
+    /// back, see `ban`
+    pub async fn kick(&self, point: Point) -> Result<usize, SpaceErr> {
+        Ok(self.evict(point).await?.len())
+    }
+
+    /// the peers of the hyperways kicked
+    async fn evict(&self, point: Point) -> Result<Vec<Option<String>>, SpaceErr> {
+        let (rtn, rtn_rx) = oneshot::channel();
+        self.call_tx
+            .send(HyperwayInterchangeCall::Kick { point, rtn })
+            .await?;
+        Ok(rtn_rx.await?)
+    }
+
+    /// kick `point` & refuse it, along with the transport peers of the hyperways
+    /// kicked, entry through the gates of this interchange until it is unbanned.  A
+    /// control gets a new remote each time it connects so only its peer keeps it out
+    pub async fn ban(&self, point: Point) -> Result<usize, SpaceErr> {
+        self.bans().points.insert(point.clone());
+        let peers = self.evict(point.clone()).await?;
+        let kicked = peers.len();
+        let mut bans = self.bans();
+        for peer in peers.into_iter().flatten() {
+            bans.peers.insert(peer, point.clone());
+        }
+        Ok(kicked)
+    }
+
+    /// lift the ban of `point` & of the peers banned with it.  Returns `false` if
+    /// `point` was not banned
+    pub fn unban(&self, point: &Point) -> bool {
+        let mut bans = self.bans();
+        bans.peers.retain(|_, banned| banned != point);
+        bans.points.remove(point)
+    }
+
+    pub fn banned(&self) -> Vec<Point> {
+        self.bans().points.iter().cloned().collect()
+    }
+
+    pub fn banned_peers(&self) -> Vec<String> {
+        self.bans().peers.keys().cloned().collect()
+    }
+
+    /// a panic while the lock was held cannot leave `Bans` half updated so a poisoned
+    /// lock is as good as any
+    fn bans(&self) -> std::sync::RwLockWriteGuard<'_, Bans> {
+        self.banned
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// refuse a hyperway stub whose remote or agent is banned or, unless it is the
+    /// HyperUser, whose transport `peer` is
+    fn admit(&self, stub: &HyperwayStub, peer: Option<&String>) -> Result<(), SpaceErr> {
+        let banned = self.bans();
+        let agent = match &stub.agent {
+            Agent::Point(point) => banned.points.contains(point),
+            _ => false,
+        };
+        let peer = match peer {
+            Some(peer) if stub.agent != Agent::HyperUser => banned.peers.contains_key(peer),
+            _ => false,
+        };
+        if agent || peer || banned.points.contains(&stub.remote.point) {
+            Err(SpaceErr::new(
+                403,
+                format!("{} is banned", stub.remote.point.to_string()),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn remove(&self, hyperway: Surface) {
         let call_tx = self.call_tx.clone();
         tokio::spawn(async move {
@@ -730,12 +1469,12 @@ impl SimpleGreeter {
 #[async_trait]
 impl HyperGreeter for SimpleGreeter {
     async fn greet(&self, stub: HyperwayStub) -> Result<Greet, SpaceErr> {
-        Ok(Greet {
-            surface: stub.remote,
-            agent: stub.agent,
-            hop: self.hop.clone(),
-            transport: self.transport.clone(),
-        })
+        Ok(Greet::new(
+            stub.agent,
+            stub.remote,
+            self.hop.clone(),
+            self.transport.clone(),
+        ))
     }
 }
 
@@ -969,12 +1708,13 @@ impl VersionGate {
     pub fn new(selector: HyperGateSelector) -> Self {
         Self { selector }
     }
-    pub async fn unlock(&self, version: semver::Version) -> Result<HyperGateSelector, String> {
-        if version == *VERSION {
-            Ok(self.selector.clone())
-        } else {
-            Err("version mismatch".to_string())
-        }
+    /// the selector & the highest protocol revision both sides support
+    pub async fn unlock(
+        &self,
+        offered: &ProtocolRange,
+    ) -> Result<(HyperGateSelector, u16), ProtocolMismatch> {
+        let protocol = PROTOCOL.negotiate(offered)?;
+        Ok((self.selector.clone(), protocol))
     }
 }
 
@@ -1132,6 +1872,8 @@ where
     greeter: G,
     interchange: Arc<HyperwayInterchange>,
     configurator: C,
+    limiter: Option<RateLimiter>,
+    config: Option<HyperlaneConfig>,
 }
 impl<A, G, C> InterchangeGate<A, G, C>
 where
@@ -1152,8 +1894,23 @@ where
             configurator,
             interchange,
             logger,
+            limiter: None,
+            config: None,
         }
     }
+
+    /// the queue limit & overflow policy of the lanes of every hyperway that enters
+    /// through this gate
+    pub fn with_config(mut self, config: HyperlaneConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// limit the waves of every hyperway that enters through this gate
+    pub fn with_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
 }
 
 impl<A, G, C> InterchangeGate<A, G, C>
@@ -1162,12 +1919,29 @@ where
     G: HyperGreeter,
     C: HyperwayConfigurator,
 {
-    async fn enter(&self, greet: Greet) -> Result<HyperwayEndpoint, SpaceErr> {
+    async fn enter(
+        &self,
+        greet: Greet,
+        kind: InterchangeKind,
+        peer: Option<String>,
+    ) -> Result<HyperwayEndpoint, SpaceErr> {
+        self.interchange.admit(
+            &HyperwayStub::new(greet.surface.clone(), greet.agent.clone()),
+            peer.as_ref(),
+        )?;
         let mut hyperway = Hyperway::new(
             greet.surface.clone(),
             greet.agent.clone(),
             self.logger.clone(),
         );
+        hyperway.protocol(greet.protocol);
+        hyperway.peer(peer);
+        if let Some(config) = self.config.as_ref() {
+            hyperway.configure(config.clone());
+        }
+        if let Some(limiter) = self.limiter.as_ref() {
+            hyperway.limit(limiter.clone(), Some(kind));
+        }
         self.configurator.config(&greet, &mut hyperway);
 
         self.interchange.add(hyperway).await;
@@ -1204,18 +1978,22 @@ where
     C: HyperwayConfigurator,
 {
     async fn knock(&self, knock: Knock) -> Result<HyperwayEndpoint, SpaceErr> {
+        let protocol = PROTOCOL.negotiate(&knock.protocol)?;
+        let kind = knock.kind.clone();
+        let peer = knock.peer.clone();
         let stub = self.auth.auth(knock).await?;
-        let greet = self.greeter.greet(stub).await?;
-        self.enter(greet).await
+        let mut greet = self.greeter.greet(stub).await?;
+        greet.protocol = protocol;
+        self.enter(greet, kind, peer).await
     }
 
     async fn jump(
         &self,
-        _kind: InterchangeKind,
+        kind: InterchangeKind,
         stub: HyperwayStub,
     ) -> Result<HyperwayEndpoint, SpaceErr> {
         let greet = self.greeter.greet(stub).await?;
-        self.enter(greet).await
+        self.enter(greet, kind, None).await
     }
 }
 
@@ -1250,8 +2028,13 @@ where
         }
     }
 
-    async fn enter(&self, greet: Greet) -> Result<HyperwayEndpoint, SpaceErr> {
+    async fn enter(
+        &self,
+        greet: Greet,
+        peer: Option<String>,
+    ) -> Result<HyperwayEndpoint, SpaceErr> {
         let stub = HyperwayStub::new(greet.surface.clone(), greet.agent.clone());
+        self.interchange.admit(&stub, peer.as_ref())?;
         let ext = self
             .interchange
             .mount(stub.clone(), Some(greet.into()))
@@ -1267,9 +2050,12 @@ where
     G: HyperGreeter,
 {
     async fn knock(&self, knock: Knock) -> Result<HyperwayEndpoint, SpaceErr> {
+        let protocol = PROTOCOL.negotiate(&knock.protocol)?;
+        let peer = knock.peer.clone();
         let stub = self.auth.auth(knock).await?;
-        let greet = self.greeter.greet(stub).await?;
-        let ext = self.enter(greet).await?;
+        let mut greet = self.greeter.greet(stub).await?;
+        greet.protocol = protocol;
+        let ext = self.enter(greet, peer).await?;
         Ok(ext)
     }
 
@@ -1279,7 +2065,7 @@ where
         stub: HyperwayStub,
     ) -> Result<HyperwayEndpoint, SpaceErr> {
         let greet = self.greeter.greet(stub).await?;
-        let ext = self.enter(greet).await?;
+        let ext = self.enter(greet, None).await?;
         Ok(ext)
     }
 }
@@ -1305,6 +2091,15 @@ impl HyperClient {
         factory: Box<dyn HyperwayEndpointFactory>,
         exchanger: Option<Exchanger>,
         logger: PointLogger,
+    ) -> Result<HyperClient, SpaceErr> {
+        Self::new_with_backoff(factory, exchanger, BackoffConfig::default(), logger)
+    }
+
+    pub fn new_with_backoff(
+        factory: Box<dyn HyperwayEndpointFactory>,
+        exchanger: Option<Exchanger>,
+        backoff: BackoffConfig,
+        logger: PointLogger,
     ) -> Result<HyperClient, SpaceErr> {
         let (to_client_listener_tx, _) = broadcast::channel(1024);
         let (to_hyperway_tx, from_client_rx) = mpsc::channel(1024);
@@ -1334,6 +2129,7 @@ impl HyperClient {
             factory,
             from_client_rx,
             status_mpsc_tx.clone(),
+            backoff,
             logger.clone(),
         );
 
@@ -1401,6 +2197,17 @@ impl HyperClient {
                                             let err = "403: Forbidden: FATAL: authentication succeeded however the authenticated agent does not have permission to connect to this service";
                                             return Err(err.into());
                                         }
+                                        426 => {
+                                            status_tx
+                                                .send(HyperConnectionStatus::Fatal)
+                                                .await
+                                                .unwrap_or_default();
+                                            let err = format!(
+                                                "426: Upgrade Required: FATAL: {}",
+                                                reflected.core().to_err().to_string()
+                                            );
+                                            return Err(err.into());
+                                        }
                                         408 => {
                                             status_tx
                                                 .send(HyperConnectionStatus::Panic)
@@ -1500,6 +2307,15 @@ impl HyperClient {
         self.exchanger.clone()
     }
 
+    pub fn status(&self) -> HyperConnectionStatus {
+        self.status_rx.borrow().clone()
+    }
+
+    /// changes every time the connection moves to another `HyperConnectionStatus`
+    pub fn status_rx(&self) -> watch::Receiver<HyperConnectionStatus> {
+        self.status_rx.clone()
+    }
+
     pub async fn transmitter_builder(&self) -> Result<ProtoTransmitterBuilder, SpaceErr> {
         self.wait_for_ready(Duration::from_secs(30)).await?;
         let mut builder = ProtoTransmitterBuilder::new(
@@ -1618,6 +2434,10 @@ pub enum HyperConnectionStatus {
     Handshake,
     Auth,
     Ready,
+    /// the other side stopped answering heartbeats, a reconnect follows
+    Stale,
+    /// disconnected for carrying no waves, reconnects once there is a wave to send
+    Idle,
     Panic,
     Fatal,
     Closed,
@@ -1647,12 +2467,89 @@ impl From<SpaceErr> for HyperConnectionErr {
     }
 }
 
+/// delays between the reconnect attempts of a `HyperClient`
+#[derive(Debug, Clone)]
+pub struct BackoffConfig {
+    pub initial: Duration,
+    pub max: Duration,
+    /// each failed attempt multiplies the delay by this much
+    pub multiplier: u32,
+    /// up to this fraction of each delay is added at random so clients that lost the
+    /// same server do not all come back at once
+    pub jitter: f64,
+    /// the client goes `Fatal` after this many failed attempts in a row. `None`
+    /// retries forever
+    pub max_retries: Option<u32>,
+}
+
+impl BackoffConfig {
+    /// the delay after the `attempt`th failure in a row (counting from 1)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let mut delay = self.initial;
+        for _ in 1..attempt {
+            if delay >= self.max {
+                break;
+            }
+            delay = delay * self.multiplier;
+        }
+        let delay = delay.min(self.max);
+        let random = (::uuid::Uuid::new_v4().as_u128() % 1000) as f64 / 1000.0;
+        delay + delay.mul_f64(self.jitter * random)
+    }
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+            multiplier: 2,
+            jitter: 0.25,
+            max_retries: None,
+        }
+    }
+}
+
+/// what a wave the client addresses to the `LOCAL_CLIENT_RUNNER` asks of it
+enum RunnerCommand {
+    Reset,
+    Close,
+    Unknown,
+}
+
+impl RunnerCommand {
+    /// `None` if `wave` is not for the runner but to be relayed
+    fn of(wave: &UltraWave) -> Option<Self> {
+        if !wave.is_directed()
+            || !wave.to().is_single()
+            || wave.to().unwrap_single().point != *LOCAL_CLIENT_RUNNER
+        {
+            return None;
+        }
+        match wave.method() {
+            Some(Method::Ext(method)) => match method.as_str() {
+                "Reset" => Some(RunnerCommand::Reset),
+                "Close" => Some(RunnerCommand::Close),
+                _ => Some(RunnerCommand::Unknown),
+            },
+            _ => Some(RunnerCommand::Unknown),
+        }
+    }
+}
+
 pub struct HyperClientRunner {
     ext: Option<HyperwayEndpoint>,
     factory: Box<dyn HyperwayEndpointFactory>,
     status_tx: mpsc::Sender<HyperConnectionStatus>,
     to_client_tx: mpsc::Sender<UltraWave>,
     from_client_rx: mpsc::Receiver<UltraWave>,
+    backoff: BackoffConfig,
+    /// disconnected for being idle, don't reconnect until the client sends a wave
+    idle: bool,
+    /// the wave that ended the idle state, sent once reconnected
+    pending: Option<UltraWave>,
+    /// the revision reported by the latest `Greet`
+    protocol: u16,
     logger: PointLogger,
 }
 
@@ -1661,6 +2558,7 @@ impl HyperClientRunner {
         factory: Box<dyn HyperwayEndpointFactory>,
         from_client_rx: mpsc::Receiver<UltraWave>,
         status_tx: mpsc::Sender<HyperConnectionStatus>,
+        backoff: BackoffConfig,
         logger: PointLogger,
     ) -> mpsc::Receiver<UltraWave> {
         let (to_client_tx, from_runner_rx) = mpsc::channel(1024);
@@ -1671,6 +2569,10 @@ impl HyperClientRunner {
             to_client_tx,
             from_client_rx,
             status_tx,
+            backoff,
+            idle: false,
+            pending: None,
+            protocol: PROTOCOL.max,
             logger,
         };
 
@@ -1708,6 +2610,7 @@ impl HyperClientRunner {
                         }
                     });
                 }
+                let mut attempt = 0;
                 loop {
                     match runner.logger.result_ctx(
                         "connect",
@@ -1735,9 +2638,58 @@ impl HyperClientRunner {
                             runner.logger.error(format!("{}", err.to_string()));
                         }
                     }
+                    attempt = attempt + 1;
+                    if let Some(max_retries) = runner.backoff.max_retries {
+                        if attempt > max_retries {
+                            return Err(HyperConnectionErr::Fatal(format!(
+                                "could not connect after {} attempts",
+                                attempt
+                            )));
+                        }
+                    }
                     // wait a little while before attempting to reconnect
-                    // maybe add exponential backoff later
-                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    tokio::time::sleep(runner.backoff.delay(attempt)).await;
+                }
+            }
+
+            /// resolves with each change of the endpoint's liveness and never if it
+            /// has none
+            async fn liveness(
+                liveness: &mut Option<watch::Receiver<HyperConnectionStatus>>,
+            ) -> HyperConnectionStatus {
+                if let Some(rx) = liveness.as_mut() {
+                    if rx.changed().await.is_ok() {
+                        return rx.borrow().clone();
+                    }
+                }
+                // the heartbeat is gone with its connection which relay notices anyway
+                liveness.take();
+                futures::future::pending().await
+            }
+
+            /// wait for the client to have something to send before reconnecting
+            async fn wake(runner: &mut HyperClientRunner) -> bool {
+                runner
+                    .status_tx
+                    .send(HyperConnectionStatus::Idle)
+                    .await
+                    .unwrap_or_default();
+                loop {
+                    match runner.from_client_rx.recv().await {
+                        None => return false,
+                        Some(wave) => match RunnerCommand::of(&wave) {
+                            Some(RunnerCommand::Close) => {
+                                runner.status_tx.send(HyperConnectionStatus::Closed).await;
+                                return false;
+                            }
+                            Some(RunnerCommand::Reset) => return true,
+                            Some(RunnerCommand::Unknown) => {}
+                            None => {
+                                runner.pending.replace(wave);
+                                return true;
+                            }
+                        },
+                    }
                 }
             }
 
@@ -1747,20 +2699,33 @@ impl HyperClientRunner {
                     .as_mut()
                     .ok_or::<SpaceErr>("must reconnect".into())?;
 
+                if let Some(wave) = runner.pending.take() {
+                    if let Err(_) = ext.tx.send(wave).await {
+                        return Err(SpaceErr::server_error("ext failure"));
+                    }
+                }
+
+                let mut live = ext.liveness();
                 loop {
                     tokio::select!(
                         wave = runner.from_client_rx.recv() => {
                                 // message comes from client, therefore it should go towards ext (unless it's pointed to the runner)
                                 match wave {
                                   Some(wave) => {
-                                    if wave.is_directed() && wave.to().is_single() && wave.to().unwrap_single().point == *LOCAL_CLIENT_RUNNER
-                                    {
-                                        let method: ExtMethod = wave.to_directed().unwrap().core().method.clone().try_into().unwrap();
-                                        if method.to_string() == "Reset".to_string() {
-                                           return Err(SpaceErr::server_error("reset"));
-                                        } else if method.to_string() == "Close".to_string(){
-                                            runner.status_tx.send(HyperConnectionStatus::Closed).await;
-                                            return Ok(());
+                                    if let Some(command) = RunnerCommand::of(&wave) {
+                                        match command {
+                                            RunnerCommand::Reset => return Err(SpaceErr::server_error("reset")),
+                                            RunnerCommand::Close => {
+                                                runner.status_tx.send(HyperConnectionStatus::Closed).await;
+                                                return Ok(());
+                                            }
+                                            RunnerCommand::Unknown => {}
+                                        }
+                                    } else if wave.protocol() > runner.protocol {
+                                        // the server would refuse it anyway
+                                        let err = SpaceErr::new(426, format!("wave needs protocol revision {} but revision {} was negotiated", wave.protocol(), runner.protocol));
+                                        if let Some(reflection) = reflect(&wave, ReflectedCore::err(err)) {
+                                            runner.to_client_tx.send(reflection).await.unwrap_or_default();
                                         }
                                     } else {
                                         match ext.tx.send(wave).await {
@@ -1783,12 +2748,32 @@ impl HyperClientRunner {
                         wave = ext.rx.recv() => {
                             match wave {
                                 Some( wave ) => {
+                                   if let UltraWave::Pong(pong) = &wave {
+                                       if let Substance::Greet(greet) = &pong.core.body {
+                                           runner.protocol = greet.protocol;
+                                       }
+                                   }
                                    runner.to_client_tx.send(wave).await;
                                 }
                                 None => {
                                    runner.logger.warn("client hyperway_endpoint has been closed.  This can happen if the client sender (tx) has been dropped.");
-                                    break;
+                                   return Err(SpaceErr::server_error("hyperway endpoint closed"));
+                                }
+                            }
+                        }
+
+                        status = liveness(&mut live) => {
+                            match status {
+                                HyperConnectionStatus::Stale => {
+                                    runner.logger.warn("hyperway endpoint is stale");
+                                    runner.status_tx.send(HyperConnectionStatus::Stale).await;
+                                    return Err(SpaceErr::server_error("stale"));
+                                }
+                                HyperConnectionStatus::Idle => {
+                                    runner.idle = true;
+                                    return Err(SpaceErr::server_error("idle"));
                                 }
+                                _ => {}
                             }
                         }
                     );
@@ -1800,6 +2785,13 @@ impl HyperClientRunner {
             }
 
             loop {
+                if self.idle {
+                    self.idle = false;
+                    if !wake(&mut self).await {
+                        return;
+                    }
+                }
+
                 match connect(&mut self).await {
                     Ok(_) => {}
                     Err(HyperConnectionErr::Fatal(message)) => {
@@ -2345,14 +3337,14 @@ pub mod test_util {
     #[async_trait]
     impl HyperGreeter for TestGreeter {
         async fn greet(&self, stub: HyperwayStub) -> Result<Greet, SpaceErr> {
-            Ok(Greet {
-                surface: stub.remote.clone(),
-                agent: stub.agent.clone(),
-                hop: Point::remote_endpoint()
+            Ok(Greet::new(
+                stub.agent.clone(),
+                stub.remote.clone(),
+                Point::remote_endpoint()
                     .to_surface()
                     .with_layer(Layer::Core),
-                transport: stub.remote.clone(),
-            })
+                stub.remote.clone(),
+            ))
         }
     }
 }
@@ -2369,7 +3361,8 @@ pub mod test {
     use tokio::sync::{broadcast, mpsc, oneshot};
 
     use cosmic_space::command::direct::create::PointFactoryU64;
-    use cosmic_space::err::SpaceErr;
+    use cosmic_space::err::{SpaceErr, StatusErr};
+    use cosmic_space::limit::{Limit, LimitRule, LimitScope, Rate, RateLimiter};
     use cosmic_space::hyper::{Greet, InterchangeKind, Knock};
     use cosmic_space::loc::{Layer, Point, Surface, ToPoint, ToSurface};
     use cosmic_space::log::RootLogger;
@@ -2396,6 +3389,11 @@ pub mod test {
         LocalHyperwayGateUnlocker, MountInterchangeGate, TokenAuthenticatorWithRemoteWhitelist,
     };
 
+    use crate::{
+        BackoffConfig, HeartbeatConfig, HyperConnectionStatus, HyperlaneConfig, Overflow,
+        RunnerCommand, LOCAL_CLIENT_RUNNER,
+    };
+
     pub struct TestRouter {}
 
     #[async_trait]
@@ -2462,6 +3460,271 @@ pub mod test {
         assert_eq!(wave.id(), wave_id);
     }
 
+    #[tokio::test]
+    pub async fn test_hyperway_overflow() {
+        let hyperway = Hyperway::new(
+            LESS.clone().to_surface(),
+            LESS.to_agent(),
+            Default::default(),
+        );
+        hyperway.configure(HyperlaneConfig {
+            limit: 1,
+            overflow: Overflow::RejectNewest,
+            send_timeout: Duration::from_secs(5u64),
+        });
+        hyperway.outbound.send(hello_wave()).await.unwrap();
+        let rejected = hello_wave();
+        let rejected_id = rejected.id().clone();
+        assert!(hyperway.outbound.send(rejected).await.is_err());
+
+        let bounced = tokio::time::timeout(
+            Duration::from_secs(5u64),
+            hyperway.inbound.rx(None).await.recv(),
+        )
+        .await
+        .unwrap()
+        .unwrap()
+        .to_reflected()
+        .unwrap();
+        assert_eq!(bounced.reflection_of(), &rejected_id);
+        assert_eq!(bounced.core().status.as_u16(), 503u16);
+        assert_eq!(hyperway.diagnostic.outbound.dropped(), 1);
+        assert_eq!(hyperway.diagnostic.outbound.bounced(), 1);
+        assert_eq!(hyperway.diagnostic.outbound.queued(), 1);
+    }
+
+    #[tokio::test]
+    pub async fn test_hyperway_protocol() {
+        let hyperway = Hyperway::new(
+            LESS.clone().to_surface(),
+            LESS.to_agent(),
+            Default::default(),
+        );
+        hyperway.protocol(1);
+        let mut track = DirectedProto::ping();
+        track.to(FAE.clone().to_surface());
+        track.from(LESS.clone().to_surface());
+        track.method(CmdMethod::Track);
+        track.body(Substance::Empty);
+        let track = track.build().unwrap().to_ultra();
+        let track_id = track.id().clone();
+
+        let err = hyperway.outbound.send(track).await.unwrap_err();
+        assert_eq!(err.status(), 426u16);
+        let refused = tokio::time::timeout(
+            Duration::from_secs(5u64),
+            hyperway.inbound.rx(None).await.recv(),
+        )
+        .await
+        .unwrap()
+        .unwrap()
+        .to_reflected()
+        .unwrap();
+        assert_eq!(refused.reflection_of(), &track_id);
+        assert_eq!(refused.core().status.as_u16(), 426u16);
+
+        // waves of the negotiated revision still pass
+        hyperway.outbound.send(hello_wave()).await.unwrap();
+    }
+
+    #[tokio::test]
+    pub async fn test_interchange_limit() {
+        let interchange = HyperwayInterchange::new(Default::default());
+        let limiter = RateLimiter::new(vec![LimitRule::new(
+            LimitScope::default(),
+            Limit {
+                waves: Some(Rate::new(0, 1)),
+                bytes: None,
+                in_flight: None,
+            },
+        )]);
+        let mut less = Hyperway::new(
+            LESS.clone().to_surface(),
+            LESS.to_agent(),
+            Default::default(),
+        );
+        less.limit(limiter.clone(), None);
+        interchange.add(less).await;
+        interchange
+            .add(Hyperway::new(
+                FAE.clone().to_surface(),
+                FAE.to_agent(),
+                Default::default(),
+            ))
+            .await;
+        let mut less = interchange
+            .mount(HyperwayStub::new(LESS.clone().to_surface(), LESS.to_agent()), None)
+            .await
+            .unwrap();
+        let mut fae = interchange
+            .mount(HyperwayStub::new(FAE.clone().to_surface(), FAE.to_agent()), None)
+            .await
+            .unwrap();
+
+        let admitted = hello_wave();
+        let admitted_id = admitted.id();
+        less.tx.send(admitted).await.unwrap();
+        let throttled = hello_wave();
+        let throttled_id = throttled.id();
+        less.tx.send(throttled).await.unwrap();
+
+        let wave = tokio::time::timeout(Duration::from_secs(5u64), fae.rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(wave.id(), admitted_id);
+        let reflected = tokio::time::timeout(Duration::from_secs(5u64), less.rx.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .to_reflected()
+            .unwrap();
+        assert_eq!(reflected.reflection_of(), &throttled_id);
+        assert_eq!(reflected.core().status.as_u16(), 429u16);
+        assert!(reflected.core().headers.contains_key("Retry-After"));
+        assert_eq!(limiter.states()[0].throttled, 1);
+    }
+
+    #[tokio::test]
+    pub async fn test_interchange_ban() {
+        let interchange = HyperwayInterchange::new(Default::default());
+        for point in vec![LESS.clone(), FAE.clone()] {
+            interchange
+                .add(Hyperway::new(
+                    point.clone().to_surface(),
+                    point.to_agent(),
+                    Default::default(),
+                ))
+                .await;
+        }
+        let stub = HyperwayStub::new(LESS.clone().to_surface(), LESS.to_agent());
+        let mut less = interchange.mount(stub.clone(), None).await.unwrap();
+        let hyperways = interchange.hyperways().await.unwrap();
+        assert_eq!(hyperways.len(), 2);
+        assert!(hyperways.iter().any(|h| h.agent == LESS.to_agent()));
+
+        assert_eq!(interchange.ban(LESS.clone()).await.unwrap(), 1);
+        // the far side sees the connection close
+        let closed = tokio::time::timeout(Duration::from_secs(5u64), less.rx.recv())
+            .await
+            .unwrap();
+        assert!(closed.is_none());
+        assert_eq!(interchange.hyperways().await.unwrap().len(), 1);
+        assert_eq!(interchange.admit(&stub, None).unwrap_err().status(), 403u16);
+
+        assert!(interchange.unban(&LESS));
+        assert!(interchange.admit(&stub, None).is_ok());
+        assert_eq!(interchange.kick(LESS.clone()).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    pub async fn test_interchange_ban_peer() {
+        let interchange = HyperwayInterchange::new(Default::default());
+        let mut hyperway = Hyperway::new(
+            LESS.clone().to_surface(),
+            Agent::Anonymous,
+            Default::default(),
+        );
+        let peer = "10.0.0.7".to_string();
+        hyperway.peer(Some(peer.clone()));
+        interchange.add(hyperway).await;
+
+        assert_eq!(interchange.ban(LESS.clone()).await.unwrap(), 1);
+        assert_eq!(interchange.banned_peers(), vec![peer.clone()]);
+
+        // the same peer is refused whatever remote it comes back as
+        let stub = HyperwayStub::new(FAE.clone().to_surface(), Agent::Anonymous);
+        assert_eq!(
+            interchange.admit(&stub, Some(&peer)).unwrap_err().status(),
+            403u16
+        );
+        assert!(interchange.admit(&stub, None).is_ok());
+        // but not as the HyperUser
+        let admin = HyperwayStub::new(FAE.clone().to_surface(), Agent::HyperUser);
+        assert!(interchange.admit(&admin, Some(&peer)).is_ok());
+
+        assert!(interchange.unban(&LESS));
+        assert!(interchange.banned_peers().is_empty());
+        assert!(interchange.admit(&stub, Some(&peer)).is_ok());
+    }
+
+    #[tokio::test]
+    pub async fn test_hyperway_stale() {
+        let mut hyperway = Hyperway::new(
+            LESS.clone().to_surface(),
+            LESS.to_agent(),
+            Default::default(),
+        );
+        hyperway.heartbeat(Some(HeartbeatConfig {
+            interval: Duration::from_millis(10u64),
+            timeout: Duration::from_millis(50u64),
+            idle: None,
+        }));
+        // nothing takes the waves sent into the outbound lane
+        let endpoint = hyperway.hyperway_endpoint_near(None).await;
+        let mut liveness = endpoint.liveness().unwrap();
+        endpoint.tx.send(hello_wave()).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5u64), async {
+            while *liveness.borrow() != HyperConnectionStatus::Stale {
+                liveness.changed().await.unwrap();
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    pub fn test_runner_command() {
+        let wave = |to: &Point, method: Method| {
+            let mut wave = DirectedProto::signal();
+            wave.from(LESS.clone().to_surface());
+            wave.to(to.clone().to_surface());
+            wave.method(method);
+            wave.build().unwrap().to_ultra()
+        };
+        let ext = |method: &str| Method::Ext(ExtMethod::new(method).unwrap());
+        let runner = LOCAL_CLIENT_RUNNER.clone();
+        assert!(matches!(
+            RunnerCommand::of(&wave(&runner, ext("Reset"))),
+            Some(RunnerCommand::Reset)
+        ));
+        assert!(matches!(
+            RunnerCommand::of(&wave(&runner, ext("Close"))),
+            Some(RunnerCommand::Close)
+        ));
+        // the runner ignores what it does not know rather than panic
+        assert!(matches!(
+            RunnerCommand::of(&wave(&runner, ext("Other"))),
+            Some(RunnerCommand::Unknown)
+        ));
+        assert!(matches!(
+            RunnerCommand::of(&wave(&runner, Method::Cmd(CmdMethod::Bounce))),
+            Some(RunnerCommand::Unknown)
+        ));
+        // a Close for anyone else is relayed
+        assert!(RunnerCommand::of(&wave(&FAE, ext("Close"))).is_none());
+    }
+
+    #[test]
+    pub fn test_backoff() {
+        let backoff = BackoffConfig {
+            initial: Duration::from_millis(100u64),
+            max: Duration::from_secs(1u64),
+            multiplier: 2,
+            jitter: 0.5,
+            max_retries: None,
+        };
+        let first = backoff.delay(1);
+        assert!(first >= Duration::from_millis(100u64));
+        assert!(first <= Duration::from_millis(150u64));
+        let third = backoff.delay(3);
+        assert!(third >= Duration::from_millis(400u64));
+        assert!(third <= Duration::from_millis(600u64));
+        let capped = backoff.delay(30);
+        assert!(capped >= Duration::from_secs(1u64));
+        assert!(capped <= Duration::from_millis(1500u64));
+    }
+
     /*
     #[tokio::mem]
     pub async fn test_hyperway_ext() {
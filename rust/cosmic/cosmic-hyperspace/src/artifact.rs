@@ -0,0 +1,327 @@
+use cosmic_space::artifact::asynch::{ArtifactApi, ArtifactFetcher};
+use cosmic_space::err::SpaceErr;
+use cosmic_space::loc::{Point, PointSeg};
+use cosmic_space::particle::Stub;
+use cosmic_space::substance::Bin;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+/// how often a `DirectoryArtifactFetcher` scans its mounted directories for changes
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// serves the artifacts of mounted bundles straight from local directories so a developer
+/// can edit a bind file or rebuild a wasm without zipping & publishing the bundle again.
+/// Artifacts of bundles that are not mounted are fetched from the `fallback` fetcher
+pub struct DirectoryArtifactFetcher {
+    mounts: HashMap<Point, PathBuf>,
+    fallback: Arc<dyn ArtifactFetcher>,
+}
+
+impl DirectoryArtifactFetcher {
+    pub fn new(fallback: Arc<dyn ArtifactFetcher>) -> Self {
+        Self {
+            mounts: HashMap::new(),
+            fallback,
+        }
+    }
+
+    /// serve the artifacts of `bundle` (i.e. `repo:my-app:1.0.0`) from `dir`
+    pub fn mount(&mut self, bundle: Point, dir: PathBuf) -> Result<(), SpaceErr> {
+        if bundle.clone().to_bundle()? != bundle {
+            return Err(SpaceErr::bad_request(format!(
+                "'{}' is not a bundle (expected something like repo:my-app:1.0.0)",
+                bundle.to_string()
+            )));
+        }
+        if !dir.is_dir() {
+            return Err(SpaceErr::not_found(format!(
+                "cannot mount bundle {}: '{}' is not a directory",
+                bundle.to_string(),
+                dir.display()
+            )));
+        }
+        self.mounts.insert(bundle, dir);
+        Ok(())
+    }
+
+    pub fn mounts(&self) -> &HashMap<Point, PathBuf> {
+        &self.mounts
+    }
+
+    /// the local file of `point` or `None` if its bundle is not mounted
+    fn path(&self, point: &Point) -> Option<Result<PathBuf, SpaceErr>> {
+        let bundle = point.clone().to_bundle().ok()?;
+        let dir = self.mounts.get(&bundle)?;
+        let filepath = match point.filepath() {
+            Some(filepath) => filepath,
+            None => {
+                return Some(Err(SpaceErr::bad_request(format!(
+                    "{} is not a file artifact",
+                    point.to_string()
+                ))))
+            }
+        };
+        let relative = PathBuf::from(filepath.trim_start_matches('/'));
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Some(Err(SpaceErr::bad_request(format!(
+                "{} escapes its mounted bundle directory",
+                point.to_string()
+            ))));
+        }
+        Some(Ok(dir.join(relative)))
+    }
+
+    /// poll the mounted directories every `interval` and invalidate the cached artifacts of
+    /// a bundle when any of its files change. Every subscriber of `ArtifactApi::invalidations()`
+    /// is notified so particles bound to the bundle pick up the change (i.e. Mechtron Hosts
+    /// reload their wasm)
+    pub fn watch(&self, artifacts: ArtifactApi, interval: Duration) -> JoinHandle<()> {
+        let mounts = self.mounts.clone();
+        tokio::spawn(async move {
+            let mut snapshots: HashMap<Point, Snapshot> = HashMap::new();
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let scan = mounts.clone();
+                let scanned = match tokio::task::spawn_blocking(move || {
+                    scan.into_iter()
+                        .map(|(bundle, dir)| (bundle, snapshot(&dir)))
+                        .collect::<Vec<(Point, Snapshot)>>()
+                })
+                .await
+                {
+                    Ok(scanned) => scanned,
+                    Err(_) => break,
+                };
+                for (bundle, snapshot) in scanned {
+                    match snapshots.insert(bundle.clone(), snapshot.clone()) {
+                        // the first scan only records the initial state
+                        None => {}
+                        Some(previous) if previous == snapshot => {}
+                        Some(_) => artifacts.invalidate(&bundle),
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// modified time & length of every file under a mounted directory
+type Snapshot = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+fn snapshot(dir: &Path) -> Snapshot {
+    let mut rtn = BTreeMap::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() => dirs.push(path),
+                Ok(metadata) => {
+                    rtn.insert(path, (metadata.modified().ok(), metadata.len()));
+                }
+                Err(_) => {}
+            }
+        }
+    }
+    rtn
+}
+
+#[async_trait]
+impl ArtifactFetcher for DirectoryArtifactFetcher {
+    async fn stub(&self, point: &Point) -> Result<Stub, SpaceErr> {
+        self.fallback.stub(point).await
+    }
+
+    async fn fetch(&self, point: &Point) -> Result<Bin, SpaceErr> {
+        match self.path(point) {
+            None => self.fallback.fetch(point).await,
+            Some(path) => {
+                let path = path?;
+                let bin = tokio::fs::read(&path).await.map_err(|e| {
+                    SpaceErr::not_found(format!(
+                        "could not read {} from '{}': {}",
+                        point.to_string(),
+                        path.display(),
+                        e.to_string()
+                    ))
+                })?;
+                Ok(Arc::new(bin))
+            }
+        }
+    }
+
+    async fn versions(&self, series: &Point) -> Result<Vec<semver::Version>, SpaceErr> {
+        let mut versions = HashSet::new();
+        for bundle in self.mounts.keys() {
+            if bundle.parent().as_ref() == Some(series) {
+                if let Some(PointSeg::Version(version)) = bundle.last_segment() {
+                    versions.insert(version.version);
+                }
+            }
+        }
+        match self.fallback.versions(series).await {
+            Ok(published) => versions.extend(published),
+            Err(err) if versions.is_empty() => return Err(err),
+            // mounted bundles are enough to resolve a range on a dev machine
+            Err(_) => {}
+        }
+        Ok(versions.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use cosmic_space::artifact::asynch::{ArtifactApi, ArtifactFetcher, MapFetcher};
+    use cosmic_space::loc::{Point, Uuid};
+
+    use crate::artifact::DirectoryArtifactFetcher;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    /// a fresh bundle directory holding `bind/app.bind`
+    fn bundle_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cosmic-bundle-{}", Uuid::rnd().to_string()));
+        fs::create_dir_all(dir.join("bind")).unwrap();
+        fs::write(dir.join("bind").join("app.bind"), "local").unwrap();
+        dir
+    }
+
+    fn fetcher(dir: &PathBuf) -> DirectoryArtifactFetcher {
+        let mut published = MapFetcher::new();
+        published.str(
+            &Point::from_str("repo:other:1.0.0:/bind/app.bind").unwrap(),
+            "published",
+        );
+        published.str(
+            &Point::from_str("repo:app:0.9.0:/bind/app.bind").unwrap(),
+            "published",
+        );
+        let mut fetcher = DirectoryArtifactFetcher::new(Arc::new(published));
+        fetcher
+            .mount(Point::from_str("repo:app:1.0.0").unwrap(), dir.clone())
+            .unwrap();
+        fetcher
+    }
+
+    #[test]
+    pub fn test_mount() {
+        let dir = bundle_dir();
+        let mut fetcher = DirectoryArtifactFetcher::new(Arc::new(MapFetcher::new()));
+        // only whole bundles can be mounted
+        assert!(fetcher
+            .mount(Point::from_str("repo:app:1.0.0:/bind").unwrap(), dir.clone())
+            .is_err());
+        assert!(fetcher
+            .mount(Point::from_str("repo:app:1.0.0").unwrap(), dir.join("missing"))
+            .is_err());
+        assert!(fetcher
+            .mount(Point::from_str("repo:app:1.0.0").unwrap(), dir.clone())
+            .is_ok());
+        assert_eq!(fetcher.mounts().len(), 1);
+        fs::remove_dir_all(dir).unwrap_or_default();
+    }
+
+    #[test]
+    pub fn test_fetch() {
+        let dir = bundle_dir();
+        let fetcher = fetcher(&dir);
+        runtime().block_on(async move {
+            // mounted bundles are served from the directory
+            let bin = fetcher
+                .fetch(&Point::from_str("repo:app:1.0.0:/bind/app.bind").unwrap())
+                .await
+                .unwrap();
+            assert_eq!(bin.as_slice(), b"local");
+
+            // edits are served without publishing again
+            fs::write(dir.join("bind").join("app.bind"), "edited").unwrap();
+            let bin = fetcher
+                .fetch(&Point::from_str("repo:app:1.0.0:/bind/app.bind").unwrap())
+                .await
+                .unwrap();
+            assert_eq!(bin.as_slice(), b"edited");
+
+            assert!(fetcher
+                .fetch(&Point::from_str("repo:app:1.0.0:/bind/missing.bind").unwrap())
+                .await
+                .is_err());
+
+            // other bundles come from the fallback
+            let bin = fetcher
+                .fetch(&Point::from_str("repo:other:1.0.0:/bind/app.bind").unwrap())
+                .await
+                .unwrap();
+            assert_eq!(bin.as_slice(), b"published");
+
+            fs::remove_dir_all(dir).unwrap_or_default();
+        });
+    }
+
+    #[test]
+    pub fn test_versions() {
+        let dir = bundle_dir();
+        let fetcher = fetcher(&dir);
+        runtime().block_on(async move {
+            let mut versions = fetcher
+                .versions(&Point::from_str("repo:app").unwrap())
+                .await
+                .unwrap();
+            versions.sort();
+            assert_eq!(
+                versions,
+                vec![
+                    semver::Version::from_str("0.9.0").unwrap(),
+                    semver::Version::from_str("1.0.0").unwrap()
+                ]
+            );
+            fs::remove_dir_all(dir).unwrap_or_default();
+        });
+    }
+
+    #[test]
+    pub fn test_watch() {
+        let dir = bundle_dir();
+        let fetcher = fetcher(&dir);
+        runtime().block_on(async move {
+            let artifacts = ArtifactApi::no_fetcher();
+            let mut invalidations = artifacts.invalidations();
+            let watch = fetcher.watch(artifacts, Duration::from_millis(10));
+
+            // let the first scan record the initial state
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            fs::write(dir.join("bind").join("new.bind"), "new").unwrap();
+
+            let bundle = tokio::time::timeout(Duration::from_secs(5), invalidations.recv())
+                .await
+                .expect("change was not noticed")
+                .unwrap();
+            assert_eq!(bundle, Point::from_str("repo:app:1.0.0").unwrap());
+
+            watch.abort();
+            fs::remove_dir_all(dir).unwrap_or_default();
+        });
+    }
+}
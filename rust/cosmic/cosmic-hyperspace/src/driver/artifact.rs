@@ -10,17 +10,21 @@ use acid_store::repo::value::ValueRepo;
 use acid_store::repo::Commit;
 use acid_store::repo::{OpenMode, OpenOptions};
 use acid_store::store::MemoryConfig;
+use cosmic_space::artifact::manifest::{
+    from_hex, BundleManifest, MANIFEST_PATH, MANIFEST_SIGNATURE_PATH,
+};
 use cosmic_space::artifact::ArtRef;
-use cosmic_space::command::common::{SetProperties, StateSrc};
+use cosmic_space::command::common::{PropertyMod, SetProperties, StateSrc};
 use cosmic_space::command::direct::create::{
     Create, KindTemplate, PointSegTemplate, PointTemplate, Strategy, Template,
 };
 use cosmic_space::config::bind::BindConfig;
-use cosmic_space::err::SpaceErr;
+use cosmic_space::err::report::{Report, ReportKind};
+use cosmic_space::err::{ParseErrs, SpaceErr};
 use cosmic_space::hyper::{Assign, HyperSubstance, ParticleLocation};
 use cosmic_space::kind::{ArtifactSubKind, BaseKind, Kind};
 use cosmic_space::loc::{Point, ToBaseKind};
-use cosmic_space::parse::bind_config;
+use cosmic_space::parse::{bind_config, mechtron_config};
 use cosmic_space::particle::PointKind;
 use cosmic_space::selector::KindSelector;
 use cosmic_space::substance::{Bin, Substance};
@@ -29,14 +33,18 @@ use cosmic_space::wave::core::{CoreBounce, DirectedCore, ReflectedCore};
 use cosmic_space::wave::exchange::asynch::{DirectedHandler, InCtx, RootInCtx};
 use cosmic_space::wave::{DirectedProto, Pong, Wave};
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs::File;
 use std::io::Read;
 use std::io::Write;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use std::convert::TryFrom;
+use mechtron_host::REQUIRED_GUEST_EXPORTS;
 use tempdir::TempDir;
+use wasmer::wasmparser::{Parser, Payload};
 
 lazy_static! {
     static ref REPO_BIND_CONFIG: ArtRef<BindConfig> = ArtRef::new(
@@ -362,6 +370,55 @@ where
             Err(err) => return Err(SpaceErr::new(500u16, err.to_string())),
         }
     }
+
+    /// verify the manifest digests of a bundle and (if the Repo has trusted `publisher-keys`)
+    /// the signature of the manifest. Returns the verified manifest if the bundle has one
+    async fn verify(
+        &self,
+        bundle: &Point,
+        files: &BTreeMap<String, Vec<u8>>,
+    ) -> Result<Option<BundleManifest>, SpaceErr> {
+        let repo = bundle
+            .parent()
+            .and_then(|series| series.parent())
+            .ok_or(SpaceErr::bad_request("bundle must be in a Repo BundleSeries"))?;
+        let keys = self
+            .skel
+            .star
+            .registry
+            .get_properties(&repo)
+            .await
+            .map_err(|e| e.to_space_err())?
+            .get("publisher-keys")
+            .map(|p| p.value.clone());
+        let keys: Vec<PublicKey> = match keys {
+            None => vec![],
+            Some(keys) => keys
+                .split(',')
+                .filter(|key| !key.trim().is_empty())
+                .map(|key| {
+                    PublicKey::from_bytes(from_hex(key.trim())?.as_slice()).map_err(|e| {
+                        SpaceErr::server_error(format!(
+                            "Repo {} has an invalid publisher key: {}",
+                            repo.to_string(),
+                            e.to_string()
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<PublicKey>, SpaceErr>>()?,
+        };
+
+        let manifest = verify_bundle(bundle, &repo, &keys, files)?;
+        if manifest.is_none() {
+            self.skel.driver.logger.warn(format!(
+                "bundle {} has no {} so its contents cannot be verified",
+                bundle.to_string(),
+                MANIFEST_PATH
+            ));
+        }
+        Ok(manifest)
+    }
+
     #[route("Hyp<Assign>")]
     async fn assign(&self, ctx: InCtx<'_, HyperSubstance>) -> Result<(), P::Err> {
         if let HyperSubstance::Assign(assign) = ctx.input {
@@ -385,13 +442,31 @@ where
                 let file = File::open(file_path.as_path())?;
                 let mut archive = zip::ZipArchive::new(file)?;
                 let mut artifacts = vec![];
+                let mut files = BTreeMap::new();
                 for i in 0..archive.len() {
-                    let file = archive.by_index(i).unwrap();
+                    let mut file = archive.by_index(i).unwrap();
                     if !file.name().ends_with("/") {
-                        artifacts.push(file.name().to_string())
+                        artifacts.push(file.name().to_string());
+                        let mut buf = vec![];
+                        file.read_to_end(&mut buf)?;
+                        files.insert(file.name().to_string(), buf);
                     }
                 }
 
+                let manifest = self
+                    .skel
+                    .driver
+                    .logger
+                    .result(self.verify(&assign.details.stub.point, &files).await)?
+                    .unwrap_or_default();
+
+                // typed artifacts are validated now rather than when a wave first uses them
+                let kinds = self.skel.driver.logger.result(classify(
+                    &assign.details.stub.point,
+                    &manifest,
+                    &files,
+                ))?;
+
                 {
                     let mut store = self.store()?;
                     let state = *state;
@@ -399,6 +474,13 @@ where
                     store.commit()?;
                 }
 
+                // a republished bundle must not be served from stale caches
+                self.skel
+                    .star
+                    .machine
+                    .artifacts
+                    .invalidate(&assign.details.stub.point);
+
                 self.skel
                     .star
                     .registry
@@ -429,7 +511,12 @@ where
                         let kind = if index < segments.len() - 1 {
                             Kind::Artifact(ArtifactSubKind::Dir)
                         } else {
-                            Kind::Artifact(ArtifactSubKind::Raw)
+                            Kind::Artifact(
+                                kinds
+                                    .get(&artifact)
+                                    .cloned()
+                                    .unwrap_or(ArtifactSubKind::Raw),
+                            )
                         };
                         let point_and_kind = PointKind { point, kind };
                         point_and_kind_set.insert(point_and_kind);
@@ -490,6 +577,17 @@ where
                             }
                         };
 
+                        let mut properties = SetProperties::new();
+                        if let Some(path) = point_and_kind.point.filepath() {
+                            if let Some(bin) = files.get(path.trim_start_matches('/')) {
+                                properties.push(PropertyMod::Set {
+                                    key: "digest".to_string(),
+                                    value: BundleManifest::digest(bin),
+                                    lock: true,
+                                });
+                            }
+                        }
+
                         let create = Create {
                             template: Template {
                                 point: PointTemplate {
@@ -509,7 +607,7 @@ where
                                 },
                             },
                             state,
-                            properties: SetProperties::new(),
+                            properties,
                             strategy: Strategy::Commit,
                         };
 
@@ -775,3 +873,320 @@ where
         Ok(ARTIFACT_BIND_CONFIG.clone())
     }
 }
+
+/// the first bytes of every wasm module
+const WASM_MAGIC: &[u8] = b"\0asm";
+
+/// verify the manifest digests of `bundle` and, if its `repo` has trusted publisher `keys`,
+/// the signature of the manifest.  A bundle without a manifest is only accepted if its
+/// repo has no keys, in which case there is no manifest to return
+fn verify_bundle(
+    bundle: &Point,
+    repo: &Point,
+    keys: &[PublicKey],
+    files: &BTreeMap<String, Vec<u8>>,
+) -> Result<Option<BundleManifest>, SpaceErr> {
+    let rejected = |reason: String| {
+        SpaceErr::new(
+            400,
+            format!("bundle {} rejected: {}", bundle.to_string(), reason),
+        )
+    };
+
+    let manifest = match files.get(MANIFEST_PATH) {
+        Some(manifest) => manifest,
+        None if keys.is_empty() => return Ok(None),
+        None => {
+            return Err(rejected(format!(
+                "Repo {} requires a signed {}",
+                repo.to_string(),
+                MANIFEST_PATH
+            )))
+        }
+    };
+
+    let text = String::from_utf8(manifest.clone())
+        .map_err(|_| rejected(format!("{} must be utf8", MANIFEST_PATH)))?;
+    let parsed = BundleManifest::parse(text.as_str()).map_err(|e| rejected(e.to_string()))?;
+    parsed.verify(files).map_err(|e| rejected(e.to_string()))?;
+
+    if !keys.is_empty() {
+        let signature = files
+            .get(MANIFEST_SIGNATURE_PATH)
+            .ok_or(rejected(format!("missing {}", MANIFEST_SIGNATURE_PATH)))?;
+        let signature = String::from_utf8(signature.clone())
+            .map_err(|_| rejected(format!("{} must be hex", MANIFEST_SIGNATURE_PATH)))?;
+        let signature = from_hex(signature.as_str()).map_err(|e| rejected(e.to_string()))?;
+        let signature = Signature::try_from(signature.as_slice())
+            .map_err(|_| rejected(format!("malformed {}", MANIFEST_SIGNATURE_PATH)))?;
+        if !keys
+            .iter()
+            .any(|key| key.verify(manifest.as_slice(), &signature).is_ok())
+        {
+            return Err(rejected(format!(
+                "manifest signature does not match any publisher key of Repo {}",
+                repo.to_string()
+            )));
+        }
+    }
+
+    Ok(Some(parsed))
+}
+
+/// the `ArtifactSubKind` of every file of `bundle`.  The publish is rejected with the
+/// reports of every typed artifact that does not parse
+fn classify(
+    bundle: &Point,
+    manifest: &BundleManifest,
+    files: &BTreeMap<String, Vec<u8>>,
+) -> Result<BTreeMap<String, ArtifactSubKind>, SpaceErr> {
+    let mut kinds = BTreeMap::new();
+    let mut errs = vec![];
+    for (path, bin) in files.iter() {
+        if BundleManifest::is_reserved(path) {
+            continue;
+        }
+        let kind = manifest.kind(path);
+        if let Err(err) = validate_artifact(path, &kind, bin) {
+            errs.push(err);
+        }
+        kinds.insert(path.clone(), kind);
+    }
+    if !errs.is_empty() {
+        let errs = ParseErrs::fold(errs).ctx(format!(
+            "bundle {} rejected: invalid artifacts",
+            bundle.to_string()
+        ));
+        return Err(errs.into());
+    }
+    Ok(kinds)
+}
+
+/// parse or inspect a typed artifact so a bundle with a broken artifact is rejected at publish
+fn validate_artifact(path: &str, kind: &ArtifactSubKind, bin: &[u8]) -> Result<(), ParseErrs> {
+    let result = match kind {
+        ArtifactSubKind::Bind => {
+            artifact_text(bin).and_then(|src| bind_config(src.as_str()).map(|_| ()))
+        }
+        ArtifactSubKind::ParticleConfig => {
+            artifact_text(bin).and_then(|src| mechtron_config(src.as_str()).map(|_| ()))
+        }
+        ArtifactSubKind::Wasm => validate_wasm(bin),
+        _ => Ok(()),
+    };
+
+    result.map_err(|err| match err {
+        SpaceErr::ParseErrs(errs) => errs.ctx(path),
+        SpaceErr::Status { message, .. } => {
+            let report = Report::build(ReportKind::Error, (), 0)
+                .with_message(format!("{} artifact '{}': {}", kind.to_string(), path, message))
+                .finish();
+            ParseErrs {
+                report: vec![report],
+                source: None,
+                ctx: path.to_string(),
+            }
+        }
+    })
+}
+
+fn artifact_text(bin: &[u8]) -> Result<String, SpaceErr> {
+    String::from_utf8(bin.to_vec()).map_err(|_| SpaceErr::bad_request("expected utf8 text"))
+}
+
+fn validate_wasm(bin: &[u8]) -> Result<(), SpaceErr> {
+    if !bin.starts_with(WASM_MAGIC) {
+        return Err(SpaceErr::bad_request("not a wasm module (bad magic number)"));
+    }
+    let mut exports = HashSet::new();
+    for payload in Parser::new(0).parse_all(bin) {
+        let payload =
+            payload.map_err(|e| SpaceErr::bad_request(format!("invalid wasm: {}", e)))?;
+        if let Payload::ExportSection(reader) = payload {
+            for export in reader {
+                let export =
+                    export.map_err(|e| SpaceErr::bad_request(format!("invalid wasm: {}", e)))?;
+                exports.insert(export.field.to_string());
+            }
+        }
+    }
+    let missing: Vec<&str> = REQUIRED_GUEST_EXPORTS
+        .iter()
+        .filter(|export| !exports.contains(**export))
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        return Err(SpaceErr::bad_request(format!(
+            "not a Mechtron guest, missing exports: {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    use cosmic_space::artifact::manifest::{
+        BundleManifest, MANIFEST_PATH, MANIFEST_SIGNATURE_PATH,
+    };
+    use cosmic_space::err::{SpaceErr, StatusErr};
+    use cosmic_space::kind::ArtifactSubKind;
+    use cosmic_space::loc::Point;
+    use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+
+    use crate::driver::artifact::{classify, verify_bundle};
+
+    const BIND: &str = "Bind(version=1.0.0) { Route -> { Ext<*> -> (()) => &; } }";
+
+    fn bundle() -> Point {
+        Point::from_str("localhost:repo:my:1.0.0").unwrap()
+    }
+
+    fn files(entries: &[(&str, &[u8])]) -> BTreeMap<String, Vec<u8>> {
+        entries
+            .iter()
+            .map(|(path, bin)| (path.to_string(), bin.to_vec()))
+            .collect()
+    }
+
+    fn repo() -> Point {
+        Point::from_str("localhost:repo").unwrap()
+    }
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    /// `files` with a manifest of their digests signed by `keypair`
+    fn signed(
+        mut files: BTreeMap<String, Vec<u8>>,
+        keypair: &Keypair,
+    ) -> BTreeMap<String, Vec<u8>> {
+        let manifest = BundleManifest::create(&files).to_string().into_bytes();
+        let signature: String = keypair
+            .sign(manifest.as_slice())
+            .to_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        files.insert(MANIFEST_PATH.to_string(), manifest);
+        files.insert(MANIFEST_SIGNATURE_PATH.to_string(), signature.into_bytes());
+        files
+    }
+
+    /// the message of the rejection of `files`
+    fn rejected(keys: &[PublicKey], files: &BTreeMap<String, Vec<u8>>) -> String {
+        match verify_bundle(&bundle(), &repo(), keys, files) {
+            Ok(_) => panic!("expected the bundle to be rejected"),
+            Err(err) => {
+                assert_eq!(err.status(), 400);
+                err.message()
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_signature() {
+        let publisher = keypair(1);
+        let keys = vec![publisher.public];
+        let files = signed(files(&[("bind/app.bind", BIND.as_bytes())]), &publisher);
+        let manifest = verify_bundle(&bundle(), &repo(), &keys, &files).unwrap();
+        assert!(manifest.unwrap().digests.contains_key("bind/app.bind"));
+
+        // a key that is not the publisher's does not verify the signature
+        let stranger = vec![keypair(2).public];
+        assert!(rejected(&stranger, &files).contains("does not match any publisher key"));
+
+        // any of the trusted keys may have signed the manifest
+        let keys = vec![keypair(2).public, publisher.public];
+        assert!(verify_bundle(&bundle(), &repo(), &keys, &files).is_ok());
+
+        let mut malformed = files.clone();
+        malformed.insert(MANIFEST_SIGNATURE_PATH.to_string(), b"00ff".to_vec());
+        assert!(rejected(&keys, &malformed).contains("malformed"));
+    }
+
+    #[test]
+    pub fn test_unsigned_rejected() {
+        let publisher = keypair(1);
+        let keys = vec![publisher.public];
+        let unsigned = files(&[("bind/app.bind", BIND.as_bytes())]);
+
+        // a repo without keys accepts a bundle without a manifest
+        assert_eq!(verify_bundle(&bundle(), &repo(), &[], &unsigned).unwrap(), None);
+        assert!(rejected(&keys, &unsigned).contains("requires a signed"));
+
+        let mut files = signed(unsigned, &publisher);
+        files.remove(MANIFEST_SIGNATURE_PATH);
+        assert!(rejected(&keys, &files).contains("missing"));
+    }
+
+    #[test]
+    pub fn test_tampered_rejected() {
+        let publisher = keypair(1);
+        let keys = vec![publisher.public];
+        let files = signed(files(&[("bind/app.bind", BIND.as_bytes())]), &publisher);
+
+        let mut tampered = files.clone();
+        tampered.insert("bind/app.bind".to_string(), b"tampered".to_vec());
+        assert!(rejected(&keys, &tampered).contains("digest mismatch"));
+        // the digests are checked even when the repo trusts no keys
+        assert!(rejected(&[], &tampered).contains("digest mismatch"));
+
+        let mut smuggled = files.clone();
+        smuggled.insert("bind/other.bind".to_string(), BIND.as_bytes().to_vec());
+        assert!(rejected(&keys, &smuggled).contains("not listed"));
+
+        // a manifest altered after it was signed
+        let mut altered = files;
+        let mut manifest =
+            String::from_utf8(altered.get(MANIFEST_PATH).unwrap().clone()).unwrap();
+        manifest.push_str("#kind Raw bind/app.bind\n");
+        altered.insert(MANIFEST_PATH.to_string(), manifest.into_bytes());
+        assert!(rejected(&keys, &altered).contains("does not match any publisher key"));
+    }
+
+    #[test]
+    pub fn test_classify() {
+        let files = files(&[
+            ("bind/app.bind", BIND.as_bytes()),
+            ("data/app.bind.txt", b"not a bind"),
+            ("config/app.cfg", BIND.as_bytes()),
+        ]);
+        let mut manifest = BundleManifest::create(&files);
+        manifest
+            .kinds
+            .insert("config/app.cfg".to_string(), ArtifactSubKind::Bind);
+        let kinds = classify(&bundle(), &manifest, &files).unwrap();
+        assert_eq!(kinds.get("bind/app.bind"), Some(&ArtifactSubKind::Bind));
+        assert_eq!(kinds.get("data/app.bind.txt"), Some(&ArtifactSubKind::Raw));
+        assert_eq!(kinds.get("config/app.cfg"), Some(&ArtifactSubKind::Bind));
+    }
+
+    #[test]
+    pub fn test_invalid_artifacts_rejected() {
+        let files = files(&[
+            ("bind/app.bind", b"Bind(version=1.0.0) { Route -> { Ext<*> -> "),
+            ("wasm/app.wasm", b"not wasm"),
+            ("bind/other.bind", BIND.as_bytes()),
+        ]);
+        let manifest = BundleManifest::create(&files);
+        match classify(&bundle(), &manifest, &files) {
+            Err(SpaceErr::ParseErrs(errs)) => {
+                // every invalid artifact is reported, not just the first
+                assert!(errs.report.len() >= 2);
+                assert_eq!(
+                    errs.ctx,
+                    "bundle localhost:repo:my:1.0.0 rejected: invalid artifacts"
+                );
+            }
+            Err(err) => panic!("expected ParseErrs got: {}", err.to_string()),
+            Ok(_) => panic!("expected the bundle to be rejected"),
+        }
+    }
+}
@@ -1,35 +1,38 @@
 use crate::driver::{
-    Driver, DriverAvail, DriverCtx, DriverSkel, DriverStatus, HyperDriverFactory, HyperSkel, Item,
-    ItemRouter, ItemSphere,
+    Driver, DriverAvail, DriverCtx, DriverHandler, DriverSkel, DriverStatus, HyperDriverFactory,
+    HyperSkel, Item, ItemRouter, ItemSphere,
 };
 use crate::err::HyperErr;
 use crate::star::{HyperStarSkel, LayerInjectionRouter};
 use crate::Cosmos;
 use cosmic_hyperlane::{
-    AnonHyperAuthenticatorAssignEndPoint, FromTransform, HopTransform, HyperClient, HyperGreeter,
-    Hyperway, HyperwayConfigurator, HyperwayEndpointFactory, HyperwayInterchange, HyperwayStub,
-    InterchangeGate, TransportTransform,
+    AnonHyperAuthenticatorAssignEndPoint, FromTransform, HopTransform, HyperAuthenticator,
+    HyperClient, HyperGreeter, Hyperway, HyperwayConfigurator, HyperwayEndpointFactory,
+    HyperwayInterchange, HyperwayStub, InterchangeGate, TransportTransform,
 };
 use cosmic_space::artifact::ArtRef;
 use cosmic_space::command::common::StateSrc;
 use cosmic_space::command::direct::create::{
     Create, KindTemplate, PointSegTemplate, PointTemplate, Strategy, Template,
 };
-use cosmic_space::command::RawCommand;
+use cosmic_space::command::{RawCommand, UploadChunk};
 use cosmic_space::config::bind::BindConfig;
 use cosmic_space::err::SpaceErr;
-use cosmic_space::hyper::{ControlPattern, Greet, InterchangeKind};
+use cosmic_space::hyper::{
+    ControlPattern, Greet, HyperSubstance, InterchangeKind, Interchanges, Knock,
+};
 use cosmic_space::kind::{BaseKind, Kind, StarSub};
 use cosmic_space::loc::{Layer, Point, PointFactory, Surface, ToSurface};
 use cosmic_space::log::{RootLogger, Tracker};
 use cosmic_space::particle::traversal::Traversal;
+use cosmic_space::particle::Progress;
 use cosmic_space::selector::KindSelector;
 use cosmic_space::settings::Timeouts;
 use cosmic_space::substance::Substance;
 use cosmic_space::wave::core::ext::ExtMethod;
 use cosmic_space::wave::core::ReflectedCore;
 use cosmic_space::wave::exchange::asynch::{
-    Exchanger, ProtoTransmitter, ProtoTransmitterBuilder, Router, TraversalRouter,
+    Exchanger, InCtx, ProtoTransmitter, ProtoTransmitterBuilder, Router, TraversalRouter,
 };
 use cosmic_space::wave::exchange::SetStrategy;
 use cosmic_space::wave::{Agent, DirectedProto, DirectedWave, Pong, ToRecipients, UltraWave, Wave};
@@ -180,20 +183,29 @@ where
             self.fabric_routers.clone(),
             ctx,
         ));
-        let auth = AnonHyperAuthenticatorAssignEndPoint::new(
-            remote_point_factory,
-            self.skel.driver.logger.clone(),
+        let auth = ControlAuthenticator::new(
+            self.skel.star.machine.cosmos.clone(),
+            AnonHyperAuthenticatorAssignEndPoint::new(
+                remote_point_factory,
+                self.skel.driver.logger.clone(),
+            ),
         );
         let mut interchange = HyperwayInterchange::new(self.skel.driver.logger.clone());
+        self.skel.star.machine.capture_hyperways(&mut interchange);
         let hyperway = Hyperway::new(
             Point::remote_endpoint().to_surface(),
             Agent::HyperUser,
             self.skel.driver.logger.clone(),
         );
+        hyperway.configure(self.skel.star.machine.hyperlane.clone());
         let mut hyperway_endpoint = hyperway.hyperway_endpoint_far(None).await;
         interchange.add(hyperway).await;
         interchange.singular_to(Point::remote_endpoint().to_surface());
         let interchange = Arc::new(interchange);
+        self.skel.star.machine.interchanges.insert(
+            InterchangeKind::Control(ControlPattern::Star(self.skel.star.point.clone())),
+            interchange.clone(),
+        );
         let greeter = ControlGreeter::new(
             self.skel.clone(),
             self.skel.driver.point.push("controls".to_string()).unwrap(),
@@ -211,13 +223,17 @@ where
             }
         }
         let configurator = ControlHyperwayConfigurator;
-        let gate = Arc::new(InterchangeGate::new(
-            auth,
-            greeter,
-            configurator,
-            interchange,
-            self.skel.driver.logger.clone(),
-        ));
+        let gate = Arc::new(
+            InterchangeGate::new(
+                auth,
+                greeter,
+                configurator,
+                interchange,
+                self.skel.driver.logger.clone(),
+            )
+            .with_limiter(self.skel.star.machine.limiter.clone())
+            .with_config(self.skel.star.machine.hyperlane.clone()),
+        );
         {
             let logger = self.skel.driver.logger.clone();
             let fabric_routers = self.fabric_routers.clone();
@@ -301,6 +317,129 @@ where
             (),
         ))))
     }
+
+    async fn handler(&self) -> Box<dyn DriverHandler<P>> {
+        Box::new(ControlDriverHandler::restore(self.skel.clone()))
+    }
+}
+
+/// administers the interchanges of the machine for HyperUser controls
+pub struct ControlDriverHandler<P>
+where
+    P: Cosmos,
+{
+    skel: HyperSkel<P>,
+}
+
+impl<P> ControlDriverHandler<P>
+where
+    P: Cosmos,
+{
+    fn restore(skel: HyperSkel<P>) -> Self {
+        ControlDriverHandler { skel }
+    }
+
+    fn hyper_user(&self, agent: &Agent) -> Result<(), SpaceErr> {
+        match agent {
+            Agent::HyperUser => Ok(()),
+            _ => Err(SpaceErr::forbidden(
+                "only the HyperUser may administer interchanges",
+            )),
+        }
+    }
+}
+
+impl<P> DriverHandler<P> for ControlDriverHandler<P> where P: Cosmos {}
+
+#[handler]
+impl<P> ControlDriverHandler<P>
+where
+    P: Cosmos,
+{
+    /// reply with the hyperways & bans of every interchange of the machine
+    #[route("Ext<Interchanges>")]
+    pub async fn interchanges(&self, ctx: InCtx<'_, ()>) -> Result<Substance, P::Err> {
+        self.hyper_user(ctx.wave().agent())?;
+        let interchanges = self.skel.star.machine.interchange_reports().await?;
+        Ok(Substance::Hyper(HyperSubstance::Interchanges(interchanges)))
+    }
+
+    /// reply with how many hyperways of the point were disconnected
+    #[route("Ext<Kick>")]
+    pub async fn kick(&self, ctx: InCtx<'_, Point>) -> Result<Substance, P::Err> {
+        self.hyper_user(ctx.wave().agent())?;
+        let kicked = self.skel.star.machine.kick(ctx.input).await?;
+        Ok(Substance::Int(kicked as i64))
+    }
+
+    #[route("Ext<Ban>")]
+    pub async fn ban(&self, ctx: InCtx<'_, Point>) -> Result<Substance, P::Err> {
+        self.hyper_user(ctx.wave().agent())?;
+        let kicked = self.skel.star.machine.ban(ctx.input).await?;
+        Ok(Substance::Int(kicked as i64))
+    }
+
+    #[route("Ext<Unban>")]
+    pub async fn unban(&self, ctx: InCtx<'_, Point>) -> Result<(), P::Err> {
+        self.hyper_user(ctx.wave().agent())?;
+        match self.skel.star.machine.unban(ctx.input) {
+            true => Ok(()),
+            false => Err(SpaceErr::not_found(format!(
+                "{} is not banned",
+                ctx.input.to_string()
+            ))
+            .into()),
+        }
+    }
+}
+
+/// assigns every control a new remote point & the HyperUser agent if its `Knock::auth`
+/// is the `Cosmos::control_token`, else the agent `Cosmos::control_agent` grants it
+#[derive(Clone)]
+pub struct ControlAuthenticator<P>
+where
+    P: Cosmos,
+{
+    cosmos: P,
+    anon: AnonHyperAuthenticatorAssignEndPoint,
+}
+
+impl<P> ControlAuthenticator<P>
+where
+    P: Cosmos,
+{
+    pub fn new(cosmos: P, anon: AnonHyperAuthenticatorAssignEndPoint) -> Self {
+        Self { cosmos, anon }
+    }
+}
+
+#[async_trait]
+impl<P> HyperAuthenticator for ControlAuthenticator<P>
+where
+    P: Cosmos,
+{
+    async fn auth(&self, knock: Knock) -> Result<HyperwayStub, SpaceErr> {
+        let agent = match (self.cosmos.control_token(), knock.auth.as_ref()) {
+            (Some(token), Substance::Text(presented)) if same_token(&token, presented) => {
+                Agent::HyperUser
+            }
+            _ => self.cosmos.control_agent(&knock),
+        };
+        let mut stub = self.anon.auth(knock).await?;
+        stub.agent = agent;
+        Ok(stub)
+    }
+}
+
+/// compares every byte so the time taken says nothing about how much of a guess
+/// was right
+fn same_token(token: &String, presented: &String) -> bool {
+    token.len() == presented.len()
+        && token
+            .bytes()
+            .zip(presented.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
 }
 
 pub struct ControlCreator<P>
@@ -397,12 +536,12 @@ where
     P: Cosmos,
 {
     async fn greet(&self, stub: HyperwayStub) -> Result<Greet, SpaceErr> {
-        Ok(Greet {
-            surface: stub.remote.clone().with_layer(Layer::Core),
-            agent: stub.agent.clone(),
-            hop: self.skel.driver.point.clone().to_surface(),
-            transport: stub.remote.clone().with_layer(Layer::Portal),
-        })
+        Ok(Greet::new(
+            stub.agent.clone(),
+            stub.remote.clone().with_layer(Layer::Core),
+            self.skel.driver.point.clone().to_surface(),
+            stub.remote.clone().with_layer(Layer::Portal),
+        ))
     }
 }
 
@@ -513,6 +652,49 @@ impl ControlClient {
         self.client.transmitter_builder().await
     }
 
+    /// the hyperways & bans of every interchange of the machine.  Like the other
+    /// administration methods this needs a control whose agent is the HyperUser
+    pub async fn interchanges(&self) -> Result<Interchanges, SpaceErr> {
+        match self.admin("Interchanges", Substance::Empty).await? {
+            Substance::Hyper(HyperSubstance::Interchanges(interchanges)) => Ok(interchanges),
+            _ => Err("Interchanges expected: Hyper<Interchanges>".into()),
+        }
+    }
+
+    /// disconnect the hyperways whose remote or agent is `point`, returning how many
+    /// there were
+    pub async fn kick(&self, point: Point) -> Result<i64, SpaceErr> {
+        match self.admin("Kick", Substance::Point(point)).await? {
+            Substance::Int(kicked) => Ok(kicked),
+            _ => Err("Kick expected: Int".into()),
+        }
+    }
+
+    /// kick `point` & refuse it at every gate until it is unbanned
+    pub async fn ban(&self, point: Point) -> Result<i64, SpaceErr> {
+        match self.admin("Ban", Substance::Point(point)).await? {
+            Substance::Int(kicked) => Ok(kicked),
+            _ => Err("Ban expected: Int".into()),
+        }
+    }
+
+    pub async fn unban(&self, point: Point) -> Result<(), SpaceErr> {
+        self.admin("Unban", Substance::Point(point)).await?;
+        Ok(())
+    }
+
+    async fn admin(&self, method: &str, body: Substance) -> Result<Substance, SpaceErr> {
+        let greet = self.wait_for_greet().await?;
+        let transmitter = self.transmitter_builder().await?.build();
+        let mut proto = DirectedProto::ping();
+        proto.to(greet.hop.with_layer(Layer::Core));
+        proto.method(ExtMethod::new(method.to_string())?);
+        proto.body(body);
+        let pong: Wave<Pong> = transmitter.direct(proto).await?;
+        pong.ok_or()?;
+        Ok(pong.variant.core.body)
+    }
+
     pub async fn new_cli_session(&self) -> Result<ControlCliSession, SpaceErr> {
         let transmitter = self.transmitter_builder().await?.build();
         let mut proto = DirectedProto::ping();
@@ -554,4 +736,25 @@ impl ControlCliSession {
         let pong: Wave<Pong> = self.transmitter.direct(proto).await?;
         Ok(pong.variant.core)
     }
+
+    /// upload `content` to the session in `UploadChunk`s, calling `progress` as each chunk
+    /// is acknowledged. A later `RawCommand` refers to the upload with a `CmdTransfer` of
+    /// the same `id` and empty content
+    pub async fn upload<F>(&self, id: &str, content: &[u8], mut progress: F) -> Result<(), SpaceErr>
+    where
+        F: FnMut(Progress),
+    {
+        for chunk in UploadChunk::split(id, content)? {
+            let mut proto = DirectedProto::ping();
+            proto.method(ExtMethod::new("UploadChunk".to_string())?);
+            proto.body(Substance::Bin(chunk.to_bin()?));
+            let pong: Wave<Pong> = self.transmitter.direct(proto).await?;
+            pong.ok_or()?;
+            match &pong.variant.core.body {
+                Substance::Text(text) => progress(Progress::from_str(text.as_str())?),
+                _ => return Err("UploadChunk expected: Text".into()),
+            }
+        }
+        Ok(())
+    }
 }
@@ -5,7 +5,7 @@ use crate::driver::{
 use crate::err::HyperErr;
 use crate::star::{HyperStarSkel, LayerInjectionRouter};
 use crate::Cosmos;
-use cosmic_space::artifact::ArtRef;
+use cosmic_space::artifact::{ArtRef, ArtifactReq};
 use cosmic_space::command::common::{PropertyMod, SetProperties, StateSrc};
 use cosmic_space::command::direct::create::{
     Create, PointSegTemplate, PointTemplate, Strategy, Template, TemplateDef,
@@ -18,23 +18,30 @@ use cosmic_space::loc::{Layer, Point, ToSurface};
 use cosmic_space::log::RootLogger;
 use cosmic_space::parse::bind_config;
 use cosmic_space::particle::traversal::{Traversal, TraversalDirection};
+use cosmic_space::particle::Status;
 use cosmic_space::selector::KindSelector;
 use cosmic_space::substance::Substance;
 use cosmic_space::util::log;
 use cosmic_space::wave::core::hyp::HypMethod;
 use cosmic_space::wave::core::DirectedCore;
 use cosmic_space::wave::exchange::asynch::{InCtx, TraversalRouter};
-use cosmic_space::wave::{DirectedProto, DirectedWave, Pong, UltraWave, Wave};
+use cosmic_space::wave::{Agent, DirectedProto, DirectedWave, Pong, UltraWave, Wave};
+use cosmic_space::artifact::asynch::ArtifactApi;
+use cosmic_space::wave::core::ext::ExtMethod;
 use dashmap::DashMap;
 use std::marker::PhantomData;
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use cosmic_space::wave::exchange::asynch::ProtoTransmitterBuilder;
 use cosmic_space::wave::exchange::SetStrategy;
 use mechtron_host::{HostsCall, WasmHostApi, HostsRunner, HostsApi};
+use mechtron_host::cache::ModuleCache;
+use mechtron_host::err::HostErr;
+use mechtron_host::state::{FileStateStore, MechtronStateStore, MemStateStore};
 
 lazy_static! {
     static ref HOST_DRIVER_BIND_CONFIG: ArtRef<BindConfig> = ArtRef::new(
@@ -73,6 +80,7 @@ fn host_bind() -> BindConfig {
     {
        Route -> {
           Hyp<Transport> -> (());
+          Ext<Retract> -> (());
        }
     }
     "#,
@@ -230,7 +238,39 @@ where
         let router = Arc::new(router);
         let transmitter = ProtoTransmitterBuilder::new( router, skel.skel.exchanger.clone() );
 
-        let hosts= HostsRunner::new(skel.skel.machine.artifacts.clone(), transmitter, skel.logger.logger.clone() );
+        // mechtron states are persisted in the star's data dir
+        let states: Arc<dyn MechtronStateStore> =
+            match FileStateStore::new(format!("{}states", skel.skel.data_dir())) {
+                Ok(store) => Arc::new(store),
+                Err(err) => {
+                    skel.logger.error(format!(
+                        "could not create durable mechtron state store: {}",
+                        err.to_string()
+                    ));
+                    Arc::new(MemStateStore::new())
+                }
+            };
+
+        // mechtrons that breach their wasm limits are marked as panicked
+        let (panic_tx, mut panic_rx) = mpsc::channel(1024);
+        {
+            let skel = skel.clone();
+            tokio::spawn(async move {
+                while let Some(point) = panic_rx.recv().await {
+                    skel.logger
+                        .result(skel.registry().set_status(&point, &Status::Panic).await)
+                        .unwrap_or_default();
+                }
+            });
+        }
+
+        // compiled guest modules are shared by all hosts & serialized to the star's data dir
+        let modules = ModuleCache::new(Some(PathBuf::from(format!(
+            "{}modules",
+            skel.skel.data_dir()
+        ))));
+
+        let hosts= HostsRunner::new(skel.skel.machine.artifacts.clone(), transmitter, skel.logger.logger.clone(), states, panic_tx, modules );
         let hosts_base = skel.point.push("hosts").unwrap();
         Self {
             skel,
@@ -274,7 +314,8 @@ where
                 .get("config")
                 .ok_or("expected config property")
                 .map_err(|e| SpaceErr::server_error(e))?;
-            let config = Point::from_str(config.value.as_str())?;
+            let config = ArtifactReq::from_str(config.value.as_str())?;
+            let config = self.skel.skel.artifacts().resolve(&config).await?;
             let config = self
                 .skel
                 .skel
@@ -293,6 +334,13 @@ where
                     value: config.wasm.to_string(),
                     lock: false
                 });
+                for (key, value) in config.limits.to_pairs() {
+                    properties.push(PropertyMod::Set {
+                        key,
+                        value,
+                        lock: false,
+                    });
+                }
                 let create = Create {
                     template: Template {
                         point: PointTemplate {
@@ -399,6 +447,21 @@ where
             }
         }
     }
+
+    /// stop hosting a Mechtron that was deleted so it is not created again
+    /// when the guest reloads
+    #[route("Ext<Retract>")]
+    async fn retract(&self, ctx: InCtx<'_, Point>) -> Result<(), P::Err> {
+        if *ctx.wave().agent() != Agent::HyperUser {
+            return Err(SpaceErr::forbidden("only the HyperUser may retract a Mechtron").into());
+        }
+        self.skel
+            .host
+            .unhost(ctx.input)
+            .await
+            .map_err(|e| e.to_space_err())?;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -438,12 +501,82 @@ where
     }
 }
 
+/// the Mechtrons assigned with a config version range (i.e. `repo:app:^1.0:/config/app.mechtron`)
+/// and the config each range currently resolves to. When a bundle of a range's series is
+/// published the range is resolved again so its Mechtrons pick up a patch release
+#[derive(Clone, Default)]
+pub struct ConfigRanges {
+    assigned: Arc<DashMap<Point, RangedAssign>>,
+}
+
+#[derive(Clone)]
+struct RangedAssign {
+    req: ArtifactReq,
+    config: Point,
+    assign: Assign,
+}
+
+impl ConfigRanges {
+    /// remember that `assign` resolved `req` to `config`. Exact configs are never resolved again
+    pub fn insert(&self, assign: Assign, req: ArtifactReq, config: Point) {
+        if req.series().is_some() {
+            self.assigned.insert(
+                assign.details.stub.point.clone(),
+                RangedAssign {
+                    req,
+                    config,
+                    assign,
+                },
+            );
+        }
+    }
+
+    pub fn remove(&self, point: &Point) {
+        self.assigned.remove(point);
+    }
+
+    /// resolve the ranges of the series `bundle` belongs to again. Returns every
+    /// assignment that now resolves to another config along with that config
+    pub async fn resolve(
+        &self,
+        artifacts: &ArtifactApi,
+        bundle: &Point,
+    ) -> Vec<Result<(Assign, Point), SpaceErr>> {
+        let series = match bundle.parent() {
+            Some(series) => series,
+            None => return vec![],
+        };
+        let ranged: Vec<RangedAssign> = self
+            .assigned
+            .iter()
+            .filter(|ranged| ranged.req.series() == Some(&series))
+            .map(|ranged| ranged.value().clone())
+            .collect();
+        let mut rtn = vec![];
+        for ranged in ranged {
+            match artifacts.resolve(&ranged.req).await {
+                Ok(config) if config == ranged.config => {}
+                Ok(config) => {
+                    let point = ranged.assign.details.stub.point.clone();
+                    if let Some(mut current) = self.assigned.get_mut(&point) {
+                        current.config = config.clone();
+                    }
+                    rtn.push(Ok((ranged.assign, config)));
+                }
+                Err(err) => rtn.push(Err(err)),
+            }
+        }
+        rtn
+    }
+}
+
 pub struct MechtronDriver<P>
 where
     P: Cosmos,
 {
     pub ctx: DriverCtx,
     pub skel: DriverSkel<P>,
+    pub ranges: ConfigRanges,
 }
 
 #[async_trait]
@@ -455,6 +588,54 @@ where
         Kind::Mechtron
     }
 
+    async fn init(&mut self, skel: DriverSkel<P>, ctx: DriverCtx) -> Result<(), P::Err> {
+        // host the Mechtrons again whenever their config range resolves to a newer bundle
+        let mut invalidations = skel.artifacts().invalidations();
+        let ranges = self.ranges.clone();
+        {
+            let skel = skel.clone();
+            tokio::spawn(async move {
+                loop {
+                    let bundle = match invalidations.recv().await {
+                        Ok(bundle) => bundle,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    for resolved in ranges.resolve(skel.artifacts(), &bundle).await {
+                        let rehosted = match resolved {
+                            Ok((assign, config)) => {
+                                let point = assign.details.stub.point.clone();
+                                match rehost(&skel, &ctx, &assign, &config).await {
+                                    Ok(true) => Ok(format!(
+                                        "rehosted mechtron {} with config {}",
+                                        point.to_string(),
+                                        config.to_string()
+                                    )),
+                                    Ok(false) => {
+                                        ranges.remove(&point);
+                                        continue;
+                                    }
+                                    Err(err) => Err(err.to_string()),
+                                }
+                            }
+                            Err(err) => Err(err.to_string()),
+                        };
+                        match rehosted {
+                            Ok(msg) => skel.logger.info(msg),
+                            Err(err) => skel
+                                .logger
+                                .error(format!("could not resolve mechtron config: {}", err)),
+                        }
+                    }
+                }
+            });
+        }
+        skel.logger
+            .result(skel.status_tx.send(DriverStatus::Ready).await)
+            .unwrap_or_default();
+        Ok(())
+    }
+
     async fn item(&self, point: &Point) -> Result<ItemSphere<P>, P::Err> {
         let ctx = self.skel.item_ctx(point, Layer::Core)?;
         let skel = ItemSkel::new(point.clone(), Kind::Mechtron, self.skel.clone());
@@ -465,6 +646,7 @@ where
         Box::new(MechtronDriverHandler::restore(
             self.skel.clone(),
             self.ctx.clone(),
+            self.ranges.clone(),
         ))
     }
 }
@@ -474,8 +656,71 @@ where
     P: Cosmos,
 {
     pub fn new(skel: DriverSkel<P>, ctx: DriverCtx) -> Self {
-        Self { skel, ctx }
+        Self {
+            skel,
+            ctx,
+            ranges: Default::default(),
+        }
+    }
+}
+
+/// ask the Host Driver of this Star to host the Mechtron of `assign` with `config`
+async fn host<P>(
+    skel: &DriverSkel<P>,
+    ctx: &DriverCtx,
+    assign: &Assign,
+    config: &Point,
+) -> Result<(), P::Err>
+where
+    P: Cosmos,
+{
+    let config = skel.logger.result(skel.artifacts().mechtron(config).await)?;
+    let config = config.contents();
+
+    let host = skel.drivers().local_driver_lookup(Kind::Host).await?.ok_or(P::Err::new("missing Host Driver which must be on the same Star as the Mechtron Driver in order for it to work"))?;
+    let mut wave = DirectedProto::ping();
+    wave.method(HypMethod::Host);
+    wave.to(host.to_surface().with_layer(Layer::Core));
+    wave.body(HyperSubstance::Host(assign.clone().to_host_cmd(config)).into());
+    let pong = ctx.transmitter.ping(wave).await?;
+    pong.ok_or()?;
+    Ok(())
+}
+
+/// host an already assigned Mechtron with a newly resolved `config` and retract it
+/// from its previous Host if the new config's wasm is hosted elsewhere.
+/// Returns `false` if the Mechtron no longer exists
+async fn rehost<P>(
+    skel: &DriverSkel<P>,
+    ctx: &DriverCtx,
+    assign: &Assign,
+    config: &Point,
+) -> Result<bool, P::Err>
+where
+    P: Cosmos,
+{
+    let point = &assign.details.stub.point;
+    let previous = match skel.registry().record(point).await {
+        Ok(record) => record.location.host,
+        Err(_) => return Ok(false),
+    };
+    let mut assign = assign.clone();
+    if let Some(property) = assign.details.properties.get_mut("config") {
+        property.value = config.to_string();
+    }
+    host(skel, ctx, &assign, config).await?;
+    let current = skel.registry().record(point).await?.location.host;
+    if let Some(previous) = previous {
+        if Some(&previous) != current.as_ref() {
+            let mut proto = DirectedProto::signal();
+            proto.method(ExtMethod::new("Retract")?);
+            proto.agent(Agent::HyperUser);
+            proto.to(previous.to_surface().with_layer(Layer::Core));
+            proto.body(Substance::Point(point.clone()));
+            ctx.transmitter.signal(proto).await?;
+        }
     }
+    Ok(true)
 }
 
 pub struct MechtronDriverHandler<P>
@@ -484,14 +729,15 @@ where
 {
     skel: DriverSkel<P>,
     ctx: DriverCtx,
+    ranges: ConfigRanges,
 }
 
 impl<P> MechtronDriverHandler<P>
 where
     P: Cosmos,
 {
-    fn restore(skel: DriverSkel<P>, ctx: DriverCtx) -> Self {
-        MechtronDriverHandler { skel, ctx }
+    fn restore(skel: DriverSkel<P>, ctx: DriverCtx, ranges: ConfigRanges) -> Self {
+        MechtronDriverHandler { skel, ctx, ranges }
     }
 }
 
@@ -513,22 +759,19 @@ where
                 .get(&"config".to_string())
                 .ok_or("config property must be set for a Mechtron")?;
 
-            let config = Point::from_str(config.value.as_str())?;
+            // the config may be a version range which is resolved again whenever
+            // a bundle of its series is published
+            let req = ArtifactReq::from_str(config.value.as_str())?;
             let config = self
                 .skel
                 .logger
-                .result(self.skel.artifacts().mechtron(&config).await)?;
-
-            let config = config.contents();
-
-            let host = self.skel.drivers().local_driver_lookup(Kind::Host).await?.ok_or(P::Err::new("missing Host Driver which must be on the same Star as the Mechtron Driver in order for it to work"))?;
-            let mut wave = DirectedProto::ping();
-            wave.method(HypMethod::Host);
-            wave.to(host.to_surface().with_layer(Layer::Core));
-            wave.body(HyperSubstance::Host(assign.clone().to_host_cmd(config)).into());
-            let pong = self.ctx.transmitter.ping(wave).await?;
-            pong.ok_or()?;
-            Ok(())
+                .result(self.skel.artifacts().resolve(&req).await)?;
+            self.ranges.insert(assign.clone(), req, config.clone());
+            let mut assign = assign.clone();
+            if let Some(property) = assign.details.properties.get_mut("config") {
+                property.value = config.to_string();
+            }
+            host(&self.skel, &self.ctx, &assign, &config).await
         } else {
             Err(P::Err::new("MechtronDriverHandler expecting Assign"))
         }
@@ -594,3 +837,81 @@ where
         Ok(MECHTRON_BIND_CONFIG.clone())
     }
 }
+
+#[cfg(test)]
+pub mod test {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use cosmic_space::artifact::asynch::{ArtifactApi, MapFetcher};
+    use cosmic_space::artifact::ArtifactReq;
+    use cosmic_space::command::common::StateSrc;
+    use cosmic_space::hyper::{Assign, AssignmentKind};
+    use cosmic_space::loc::Point;
+    use cosmic_space::particle::Details;
+
+    use crate::driver::mechtron::ConfigRanges;
+
+    fn published(versions: &[&str]) -> Arc<MapFetcher> {
+        let mut fetcher = MapFetcher::new();
+        for version in versions {
+            let config = format!("repo:app:{}:/config/app.mechtron", version);
+            fetcher.str(&Point::from_str(config.as_str()).unwrap(), "");
+        }
+        Arc::new(fetcher)
+    }
+
+    #[test]
+    pub fn test_patch_publish() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            let artifacts = ArtifactApi::new(published(&["1.0.0"]));
+            let req = ArtifactReq::from_str("repo:app:^1.0:/config/app.mechtron").unwrap();
+            let config = artifacts.resolve(&req).await.unwrap();
+            assert_eq!(
+                config,
+                Point::from_str("repo:app:1.0.0:/config/app.mechtron").unwrap()
+            );
+
+            let mut details = Details::default();
+            details.stub.point = Point::from_str("localhost:app").unwrap();
+            let assign = Assign {
+                kind: AssignmentKind::Create,
+                details,
+                state: StateSrc::None,
+            };
+            let ranges = ConfigRanges::default();
+            ranges.insert(assign.clone(), req, config);
+
+            // bundles of another series do not concern the range
+            let other = Point::from_str("repo:other:1.0.0").unwrap();
+            artifacts.invalidate(&other);
+            assert!(ranges.resolve(&artifacts, &other).await.is_empty());
+
+            // a patch release is picked up
+            artifacts.set_fetcher(published(&["1.0.0", "1.0.1"])).await;
+            let patch = Point::from_str("repo:app:1.0.1").unwrap();
+            artifacts.invalidate(&patch);
+            let resolved = ranges.resolve(&artifacts, &patch).await;
+            assert_eq!(resolved.len(), 1);
+            let (resolved_assign, config) = resolved.into_iter().next().unwrap().unwrap();
+            assert_eq!(resolved_assign, assign);
+            assert_eq!(
+                config,
+                Point::from_str("repo:app:1.0.1:/config/app.mechtron").unwrap()
+            );
+
+            // and only once
+            assert!(ranges.resolve(&artifacts, &patch).await.is_empty());
+
+            // a major release is not
+            artifacts.set_fetcher(published(&["1.0.0", "1.0.1", "2.0.0"])).await;
+            let major = Point::from_str("repo:app:2.0.0").unwrap();
+            artifacts.invalidate(&major);
+            assert!(ranges.resolve(&artifacts, &major).await.is_empty());
+        });
+    }
+}
@@ -2,6 +2,7 @@ pub mod artifact;
 pub mod base;
 pub mod control;
 pub mod mechtron;
+pub mod portal;
 pub mod root;
 pub mod space;
 pub mod star;
@@ -24,6 +25,7 @@ use cosmic_space::hyper::{Assign, HyperSubstance, ParticleLocation, ParticleReco
 use cosmic_space::kind::{BaseKind, Kind, KindParts, StarSub};
 use cosmic_space::loc::{Layer, Point, Surface, ToPoint, ToSurface};
 use cosmic_space::log::{PointLogger, Tracker};
+use cosmic_space::metrics::METRICS;
 use cosmic_space::parse::bind_config;
 use cosmic_space::particle::traversal::{
     Traversal, TraversalDirection, TraversalInjection, TraversalLayer,
@@ -670,9 +672,21 @@ where
                 let logger = logger.point(point.clone());
                 let kind = kind.clone();
                 let mut status_rx = status_rx.clone();
+                let star = skel.point.to_string();
                 tokio::spawn(async move {
                     loop {
                         let status = status_rx.borrow().clone();
+                        METRICS
+                            .counter(
+                                "cosmic_driver_status_total",
+                                "status transitions of the drivers of each star",
+                                &[
+                                    ("star", star.as_str()),
+                                    ("driver", kind.to_string().as_str()),
+                                    ("status", status.to_string().as_str()),
+                                ],
+                            )
+                            .inc();
                         match status {
                             DriverStatus::Unknown => {
                                 //                                logger.info(format!("{} {}", kind.to_string(), status.to_string()));
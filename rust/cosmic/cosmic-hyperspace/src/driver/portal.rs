@@ -0,0 +1,800 @@
+use crate::driver::{
+    Driver, DriverAvail, DriverCtx, DriverSkel, DriverStatus, HyperDriverFactory, HyperSkel, Item,
+    ItemRouter, ItemSphere,
+};
+use crate::err::HyperErr;
+use crate::star::{HyperStarSkel, LayerInjectionRouter};
+use crate::Cosmos;
+use cosmic_hyperlane::{
+    FromTransform, HopTransform, HyperAuthenticator, HyperGate, HyperGreeter, Hyperway,
+    HyperwayConfigurator, HyperwayEndpoint, HyperwayInterchange, HyperwayStub, InterchangeGate,
+    TransportTransform,
+};
+use cosmic_space::artifact::ArtRef;
+use cosmic_space::command::common::{PropertyMod, SetProperties, StateSrc};
+use cosmic_space::command::direct::create::{
+    Create, KindTemplate, PointSegTemplate, PointTemplate, Strategy, Template,
+};
+use cosmic_space::command::direct::delete::Delete;
+use cosmic_space::config::bind::BindConfig;
+use cosmic_space::config::PortalConfig;
+use cosmic_space::err::SpaceErr;
+use cosmic_space::hyper::{ControlPattern, Greet, InterchangeKind, Knock};
+use cosmic_space::kind::{BaseKind, Kind};
+use cosmic_space::loc::{Layer, Point, Surface, ToSurface};
+use cosmic_space::log::{PointLogger, Tracker};
+use cosmic_space::parse::bind_config;
+use cosmic_space::particle::traversal::Traversal;
+use cosmic_space::selector::KindSelector;
+use cosmic_space::substance::Substance;
+use cosmic_space::util::log;
+use cosmic_space::wave::core::ReflectedCore;
+use cosmic_space::wave::exchange::asynch::{Router, TraversalRouter};
+use cosmic_space::wave::{Agent, UltraWave};
+use dashmap::DashMap;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::error::SendTimeoutError;
+use tokio::sync::{broadcast, mpsc};
+
+lazy_static! {
+    static ref PORTAL_BIND_CONFIG: ArtRef<BindConfig> = ArtRef::new(
+        Arc::new(portal_bind()),
+        Point::from_str("GLOBAL::repo:1.0.0:/bind/portal.bind").unwrap()
+    );
+}
+
+/// everything addressed to a portal is passed on to its external process. A portal
+/// created with a `bind` property is bound by that config instead
+fn portal_bind() -> BindConfig {
+    log(bind_config(
+        r#"
+    Bind(version=1.0.0)
+    {
+       Route -> {
+          Ext<*> -> (()) => &;
+          Http<*> -> (()) => &;
+       }
+    }
+    "#,
+    ))
+    .unwrap()
+}
+
+pub struct PortalDriverFactory<P>
+where
+    P: Cosmos,
+{
+    config: PortalConfig,
+    phantom: PhantomData<P>,
+}
+
+impl<P> PortalDriverFactory<P>
+where
+    P: Cosmos,
+{
+    pub fn new() -> Self {
+        Self::with_config(PortalConfig::default())
+    }
+
+    pub fn with_config(config: PortalConfig) -> Self {
+        Self {
+            config,
+            phantom: Default::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl<P> HyperDriverFactory<P> for PortalDriverFactory<P>
+where
+    P: Cosmos,
+{
+    fn kind(&self) -> KindSelector {
+        KindSelector::from_base(BaseKind::Portal)
+    }
+
+    fn avail(&self) -> DriverAvail {
+        DriverAvail::Internal
+    }
+
+    async fn create(
+        &self,
+        star: HyperStarSkel<P>,
+        driver: DriverSkel<P>,
+        ctx: DriverCtx,
+    ) -> Result<Box<dyn Driver<P>>, P::Err> {
+        let skel = HyperSkel::new(star, driver);
+        Ok(Box::new(PortalDriver {
+            skel,
+            config: self.config.clone(),
+            external_router: None,
+            fabric_routers: Arc::new(Default::default()),
+            ctx,
+        }))
+    }
+}
+
+pub struct PortalDriver<P>
+where
+    P: Cosmos,
+{
+    pub ctx: DriverCtx,
+    pub skel: HyperSkel<P>,
+    pub config: PortalConfig,
+    pub external_router: Option<Arc<dyn Router>>,
+    pub fabric_routers: Arc<DashMap<Point, LayerInjectionRouter>>,
+}
+
+impl<P> PortalDriver<P>
+where
+    P: Cosmos,
+{
+    /// a portal lives as long as its hyperway: once the hyperway leaves the interchange
+    /// its fabric router is dropped and its particle deleted from the registry
+    fn reap(&self, mut removed: broadcast::Receiver<Surface>) {
+        let skel = self.skel.clone();
+        let fabric_routers = self.fabric_routers.clone();
+        tokio::spawn(async move {
+            loop {
+                let remote = match removed.recv().await {
+                    Ok(remote) => remote,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        skel.driver.logger.warn(format!(
+                            "portal reaper missed {} removed hyperways",
+                            skipped
+                        ));
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if fabric_routers.remove(&remote.point).is_none() {
+                    continue;
+                }
+                let delete = Delete {
+                    selector: remote.point.clone().into(),
+                };
+                if let Err(err) = skel.star.registry.delete(&delete).await {
+                    skel.driver.logger.warn(format!(
+                        "could not delete portal {}: {}",
+                        remote.point.to_string(),
+                        err.to_string()
+                    ));
+                }
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl<P> Driver<P> for PortalDriver<P>
+where
+    P: Cosmos,
+{
+    fn kind(&self) -> Kind {
+        Kind::Portal
+    }
+
+    fn layer(&self) -> Layer {
+        Layer::Portal
+    }
+
+    async fn init(&mut self, skel: DriverSkel<P>, ctx: DriverCtx) -> Result<(), P::Err> {
+        self.skel.driver.status_tx.send(DriverStatus::Init).await;
+
+        skel.create_in_driver(
+            PointSegTemplate::Exact("portals".to_string()),
+            Kind::Base.to_template(),
+        )
+        .await?;
+
+        let auth = PortalAuthenticator::new(self.skel.clone(), self.fabric_routers.clone());
+        let mut interchange = HyperwayInterchange::new(self.skel.driver.logger.clone());
+        self.skel.star.machine.capture_hyperways(&mut interchange);
+        let removed = interchange.removed();
+        let hyperway = Hyperway::new(
+            Point::remote_endpoint().to_surface(),
+            Agent::HyperUser,
+            self.skel.driver.logger.clone(),
+        );
+        hyperway.configure(self.skel.star.machine.hyperlane.clone());
+        let mut hyperway_endpoint = hyperway.hyperway_endpoint_far(None).await;
+        interchange.add(hyperway).await;
+        interchange.singular_to(Point::remote_endpoint().to_surface());
+        let interchange = Arc::new(interchange);
+        let greeter = PortalGreeter::new(self.skel.clone());
+        self.external_router = Some(interchange.router().into());
+
+        pub struct PortalHyperwayConfigurator;
+
+        impl HyperwayConfigurator for PortalHyperwayConfigurator {
+            fn config(&self, greet: &Greet, hyperway: &mut Hyperway) {
+                hyperway.transform_inbound(Box::new(FromTransform::new(greet.surface.clone())));
+                hyperway
+                    .transform_inbound(Box::new(TransportTransform::new(greet.transport.clone())));
+                hyperway.transform_inbound(Box::new(HopTransform::new(greet.hop.clone())));
+            }
+        }
+
+        self.skel.star.machine.interchanges.insert(
+            InterchangeKind::Portal(ControlPattern::Star(self.skel.star.point.clone())),
+            interchange.clone(),
+        );
+        let gate = InterchangeGate::new(
+            auth,
+            greeter,
+            PortalHyperwayConfigurator,
+            interchange,
+            self.skel.driver.logger.clone(),
+        )
+        .with_limiter(self.skel.star.machine.limiter.clone())
+        .with_config(self.skel.star.machine.hyperlane.clone());
+        let gate = Arc::new(PortalGate::new(
+            gate,
+            self.config.clone(),
+            self.skel.driver.logger.clone(),
+        ));
+
+        {
+            let logger = self.skel.driver.logger.clone();
+            let fabric_routers = self.fabric_routers.clone();
+            tokio::spawn(async move {
+                while let Some(hop) = hyperway_endpoint.rx.recv().await {
+                    let remote = hop.from().clone().with_layer(Layer::Portal);
+                    let router = match fabric_routers.get(&remote.point) {
+                        None => {
+                            logger.warn(format!("portal not found: {}", remote.to_string()));
+                            continue;
+                        }
+                        Some(router) => router.value().clone(),
+                    };
+
+                    match hop.unwrap_from_hop() {
+                        Ok(transport) => {
+                            if transport.to.point == remote.point {
+                                match transport.unwrap_from_transport() {
+                                    Ok(wave) => {
+                                        router.route(wave).await;
+                                    }
+                                    Err(err) => {
+                                        logger.warn(format!(
+                                            "could not unwrap from Transport: {}",
+                                            err.to_string()
+                                        ));
+                                    }
+                                }
+                            } else {
+                                logger.warn("portal cannot transport to any other point than its remote self".to_string());
+                            }
+                        }
+                        Err(err) => {
+                            logger.warn(format!("could not unwrap from Hop: {}", err.to_string()));
+                        }
+                    }
+                }
+            });
+        }
+
+        self.reap(removed);
+
+        self.skel
+            .star
+            .machine
+            .api
+            .add_interchange(
+                InterchangeKind::Portal(ControlPattern::Star(self.skel.star.point.clone())),
+                gate,
+            )
+            .await?;
+
+        self.skel.driver.status_tx.send(DriverStatus::Ready).await;
+
+        Ok(())
+    }
+
+    async fn item(&self, point: &Point) -> Result<ItemSphere<P>, P::Err> {
+        let router = self
+            .external_router
+            .as_ref()
+            .ok_or(P::Err::new("FATAL: router is not set"))?
+            .clone();
+        let ctx = PortalCtx::new(router);
+        Ok(ItemSphere::Router(Box::new(Portal::restore(
+            self.skel.clone(),
+            ctx,
+            (),
+        ))))
+    }
+}
+
+/// creates a new `Portal` particle for every knock. If the knock's auth is a `Point`
+/// it is set as the `bind` property of the new portal so the external process can
+/// supply its own `BindConfig`
+#[derive(Clone)]
+pub struct PortalAuthenticator<P>
+where
+    P: Cosmos,
+{
+    pub skel: HyperSkel<P>,
+    pub fabric_routers: Arc<DashMap<Point, LayerInjectionRouter>>,
+    pub portals: Point,
+}
+
+impl<P> PortalAuthenticator<P>
+where
+    P: Cosmos,
+{
+    pub fn new(skel: HyperSkel<P>, fabric_routers: Arc<DashMap<Point, LayerInjectionRouter>>) -> Self {
+        let portals = skel.driver.point.push("portals").unwrap();
+        Self {
+            skel,
+            fabric_routers,
+            portals,
+        }
+    }
+}
+
+#[async_trait]
+impl<P> HyperAuthenticator for PortalAuthenticator<P>
+where
+    P: Cosmos,
+{
+    async fn auth(&self, knock: Knock) -> Result<HyperwayStub, SpaceErr> {
+        let mut properties = SetProperties::new();
+        match &*knock.auth {
+            Substance::Empty => {}
+            Substance::Point(bind) => {
+                properties.push(PropertyMod::Set {
+                    key: "bind".to_string(),
+                    value: bind.to_string(),
+                    lock: true,
+                });
+            }
+            _ => {
+                return Err(SpaceErr::bad_request(
+                    "Portal knock expected auth Substance: Empty or Point (bind)",
+                ));
+            }
+        }
+
+        let create = Create {
+            template: Template::new(
+                PointTemplate {
+                    parent: self.portals.clone(),
+                    child_segment_template: PointSegTemplate::Pattern("portal-%".to_string()),
+                },
+                KindTemplate {
+                    base: BaseKind::Portal,
+                    sub: None,
+                    specific: None,
+                },
+            ),
+            properties,
+            strategy: Strategy::Commit,
+            state: StateSrc::None,
+        };
+
+        let details = self
+            .skel
+            .driver
+            .logger
+            .result_ctx("create-portal", self.skel.star.create_in_star(create).await)
+            .map_err(|e| e.to_space_err())?;
+
+        let point = details.stub.point;
+        let fabric_router = LayerInjectionRouter::new(
+            self.skel.star.clone(),
+            point.clone().to_surface().with_layer(Layer::Shell),
+        );
+        self.fabric_routers.insert(point.clone(), fabric_router);
+
+        Ok(HyperwayStub {
+            agent: Agent::Anonymous,
+            remote: point.to_surface(),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct PortalGreeter<P>
+where
+    P: Cosmos,
+{
+    pub skel: HyperSkel<P>,
+}
+
+impl<P> PortalGreeter<P>
+where
+    P: Cosmos,
+{
+    pub fn new(skel: HyperSkel<P>) -> Self {
+        Self { skel }
+    }
+}
+
+#[async_trait]
+impl<P> HyperGreeter for PortalGreeter<P>
+where
+    P: Cosmos,
+{
+    async fn greet(&self, stub: HyperwayStub) -> Result<Greet, SpaceErr> {
+        Ok(Greet::new(
+            stub.agent.clone(),
+            stub.remote.clone().with_layer(Layer::Core),
+            self.skel.driver.point.clone().to_surface(),
+            stub.remote.clone().with_layer(Layer::Portal),
+        ))
+    }
+}
+
+/// wraps a gate and enforces the limits of a `PortalConfig`:
+/// `init_timeout` bounds the knock, `max_payload_size` rejects oversized inbound waves
+/// and `frame_timeout` bounds the time an inbound wave may wait to enter the hyperway.
+/// A rejected directed wave is reflected to its sender with the status of the rejection
+pub struct PortalGate<G>
+where
+    G: HyperGate,
+{
+    gate: G,
+    config: PortalConfig,
+    logger: PointLogger,
+}
+
+impl<G> PortalGate<G>
+where
+    G: HyperGate,
+{
+    pub fn new(gate: G, config: PortalConfig, logger: PointLogger) -> Self {
+        Self {
+            gate,
+            config,
+            logger,
+        }
+    }
+
+    async fn init<F>(&self, enter: F) -> Result<HyperwayEndpoint, SpaceErr>
+    where
+        F: std::future::Future<Output = Result<HyperwayEndpoint, SpaceErr>> + Send,
+    {
+        let endpoint =
+            tokio::time::timeout(Duration::from_secs(self.config.init_timeout), enter)
+                .await
+                .map_err(|_| SpaceErr::new(408, "Portal init timeout"))??;
+        Ok(self.limit(endpoint))
+    }
+
+    fn limit(&self, mut endpoint: HyperwayEndpoint) -> HyperwayEndpoint {
+        let (inbound_tx, mut inbound_rx) = mpsc::channel(1024);
+        let (outbound_tx, outbound_rx) = mpsc::channel(1024);
+        let logger = endpoint.logger.clone();
+        let max_payload_size = self.config.max_payload_size as u64;
+        let frame_timeout = Duration::from_secs(self.config.frame_timeout);
+        {
+            let logger = logger.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        wave = inbound_rx.recv() => {
+                            match wave {
+                                Some(wave) => {
+                                    let rejected = match bincode::serialized_size(&wave) {
+                                        Ok(size) if size <= max_payload_size => None,
+                                        Ok(size) => Some(SpaceErr::new(413, format!("Portal wave rejected: payload size {} exceeds max_payload_size {}", size, max_payload_size))),
+                                        Err(err) => Some(SpaceErr::new(400, format!("Portal wave rejected: {}", err.to_string()))),
+                                    };
+                                    if let Some(err) = rejected {
+                                        logger.warn(err.to_string());
+                                        if let Some(reflection) = reject(&wave, err) {
+                                            if outbound_tx.send(reflection).await.is_err() {
+                                                break;
+                                            }
+                                        }
+                                        continue;
+                                    }
+                                    match endpoint.tx.send_timeout(wave, frame_timeout).await {
+                                        Ok(_) => {}
+                                        Err(SendTimeoutError::Timeout(wave)) => {
+                                            let err = SpaceErr::new(408, "Portal wave rejected: frame timeout");
+                                            logger.warn(err.to_string());
+                                            if let Some(reflection) = reject(&wave, err) {
+                                                if outbound_tx.send(reflection).await.is_err() {
+                                                    break;
+                                                }
+                                            }
+                                        }
+                                        Err(SendTimeoutError::Closed(_)) => break,
+                                    }
+                                }
+                                None => break,
+                            }
+                        }
+                        wave = endpoint.rx.recv() => {
+                            match wave {
+                                Some(wave) => {
+                                    if outbound_tx.send(wave).await.is_err() {
+                                        break;
+                                    }
+                                }
+                                None => break,
+                            }
+                        }
+                    }
+                }
+                // endpoint is dropped here which in turn removes the hyperway from the interchange
+            });
+        }
+        HyperwayEndpoint::new(inbound_tx, outbound_rx, logger)
+    }
+}
+
+/// reflect `err` back to the sender of a rejected directed wave
+fn reject(wave: &UltraWave, err: SpaceErr) -> Option<UltraWave> {
+    let directed = wave.clone().to_directed().ok()?;
+    let reflection = directed.reflection().ok()?;
+    let from = directed
+        .to()
+        .single_or()
+        .unwrap_or_else(|_| wave.from().clone());
+    Some(
+        reflection
+            .make(ReflectedCore::result(Err(err)), from)
+            .to_ultra(),
+    )
+}
+
+#[async_trait]
+impl<G> HyperGate for PortalGate<G>
+where
+    G: HyperGate,
+{
+    async fn knock(&self, knock: Knock) -> Result<HyperwayEndpoint, SpaceErr> {
+        self.init(self.gate.knock(knock)).await
+    }
+
+    async fn jump(
+        &self,
+        kind: InterchangeKind,
+        stub: HyperwayStub,
+    ) -> Result<HyperwayEndpoint, SpaceErr> {
+        self.init(self.gate.jump(kind, stub)).await
+    }
+}
+
+pub struct Portal<P>
+where
+    P: Cosmos,
+{
+    pub skel: HyperSkel<P>,
+    pub ctx: PortalCtx<P>,
+}
+
+impl<P> Item<P> for Portal<P>
+where
+    P: Cosmos,
+{
+    type Skel = HyperSkel<P>;
+    type Ctx = PortalCtx<P>;
+    type State = ();
+
+    fn restore(skel: Self::Skel, ctx: Self::Ctx, _: Self::State) -> Self {
+        Self { skel, ctx }
+    }
+}
+
+#[async_trait]
+impl<P> TraversalRouter for Portal<P>
+where
+    P: Cosmos,
+{
+    async fn traverse(&self, traversal: Traversal<UltraWave>) -> Result<(), SpaceErr> {
+        self.skel.driver.logger.track(&traversal, || {
+            Tracker::new(
+                format!("portal -> {}", traversal.dir.to_string()),
+                "Traverse",
+            )
+        });
+
+        self.ctx.router.route(traversal.payload).await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<P> ItemRouter<P> for Portal<P>
+where
+    P: Cosmos,
+{
+    async fn bind(&self) -> Result<ArtRef<BindConfig>, P::Err> {
+        Ok(PORTAL_BIND_CONFIG.clone())
+    }
+}
+
+#[derive(Clone)]
+pub struct PortalCtx<P>
+where
+    P: Cosmos,
+{
+    pub phantom: PhantomData<P>,
+    pub router: Arc<dyn Router>,
+}
+
+impl<P> PortalCtx<P>
+where
+    P: Cosmos,
+{
+    pub fn new(router: Arc<dyn Router>) -> Self {
+        Self {
+            phantom: Default::default(),
+            router,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::str::FromStr;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use cosmic_hyperlane::{HyperGate, HyperwayEndpoint, HyperwayStub};
+    use cosmic_space::config::PortalConfig;
+    use cosmic_space::err::{SpaceErr, StatusErr};
+    use cosmic_space::hyper::{InterchangeKind, Knock};
+    use cosmic_space::loc::{Point, ToSurface};
+    use cosmic_space::log::PointLogger;
+    use cosmic_space::substance::Substance;
+    use cosmic_space::wave::core::ext::ExtMethod;
+    use cosmic_space::wave::{DirectedProto, UltraWave};
+    use tokio::sync::mpsc;
+
+    use crate::driver::portal::PortalGate;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    /// hands out the endpoint it was made with once, or never answers if it has none
+    struct StubGate {
+        endpoint: Mutex<Option<HyperwayEndpoint>>,
+    }
+
+    impl StubGate {
+        /// the gate & the far side of the endpoint it hands out, whose channel into
+        /// the hyperway holds `capacity` waves
+        fn new(capacity: usize) -> (Self, HyperwayEndpoint) {
+            let (inbound_tx, inbound_rx) = mpsc::channel(capacity);
+            let (outbound_tx, outbound_rx) = mpsc::channel(capacity);
+            let endpoint = HyperwayEndpoint::new(inbound_tx, outbound_rx, PointLogger::default());
+            let far = HyperwayEndpoint::new(outbound_tx, inbound_rx, PointLogger::default());
+            let gate = Self {
+                endpoint: Mutex::new(Some(endpoint)),
+            };
+            (gate, far)
+        }
+
+        fn pending() -> Self {
+            Self {
+                endpoint: Mutex::new(None),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl HyperGate for StubGate {
+        async fn knock(&self, _knock: Knock) -> Result<HyperwayEndpoint, SpaceErr> {
+            let endpoint = self.endpoint.lock().unwrap().take();
+            match endpoint {
+                Some(endpoint) => Ok(endpoint),
+                None => futures::future::pending().await,
+            }
+        }
+
+        async fn jump(
+            &self,
+            _kind: InterchangeKind,
+            _stub: HyperwayStub,
+        ) -> Result<HyperwayEndpoint, SpaceErr> {
+            self.knock(Knock::default()).await
+        }
+    }
+
+    fn config() -> PortalConfig {
+        PortalConfig {
+            max_payload_size: 1024,
+            init_timeout: 1,
+            frame_timeout: 1,
+            response_timeout: 1,
+        }
+    }
+
+    fn ping(body: Substance) -> UltraWave {
+        let mut ping = DirectedProto::ping();
+        ping.to(Point::from_str("portal").unwrap().to_surface());
+        ping.from(Point::from_str("remote").unwrap().to_surface());
+        ping.method(ExtMethod::new("Hello").unwrap());
+        ping.body(body);
+        ping.build().unwrap().to_ultra()
+    }
+
+    /// the status of the reflection of `wave` the portal sent back
+    async fn rejected(endpoint: &mut HyperwayEndpoint, wave: &UltraWave) -> u16 {
+        let reflected = tokio::time::timeout(Duration::from_secs(5), endpoint.rx.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .to_reflected()
+            .unwrap();
+        assert_eq!(*reflected.reflection_of(), wave.id());
+        reflected.core().status.as_u16()
+    }
+
+    #[test]
+    pub fn test_framing() {
+        runtime().block_on(async move {
+            let (gate, mut far) = StubGate::new(16);
+            let gate = PortalGate::new(gate, config(), PointLogger::default());
+            let mut endpoint = gate.knock(Knock::default()).await.unwrap();
+
+            let hello = ping(Substance::Text("hello".to_string()));
+            endpoint.tx.send(hello.clone()).await.unwrap();
+            let passed = far.rx.recv().await.unwrap();
+            assert_eq!(passed.id(), hello.id());
+
+            let oversized = ping(Substance::Text("x".repeat(4096)));
+            endpoint.tx.send(oversized.clone()).await.unwrap();
+            assert_eq!(rejected(&mut endpoint, &oversized).await, 413);
+
+            // waves from the hyperway pass to the external process untouched
+            far.tx.send(hello.clone()).await.unwrap();
+            assert_eq!(endpoint.rx.recv().await.unwrap().id(), hello.id());
+        });
+    }
+
+    #[test]
+    pub fn test_init_timeout() {
+        runtime().block_on(async move {
+            let gate = PortalGate::new(StubGate::pending(), config(), PointLogger::default());
+            match gate.knock(Knock::default()).await {
+                Ok(_) => panic!("expected the knock to time out"),
+                Err(err) => assert_eq!(err.status(), 408),
+            }
+        });
+    }
+
+    #[test]
+    pub fn test_frame_timeout() {
+        runtime().block_on(async move {
+            // the hyperway takes one wave & never reads it
+            let (gate, _far) = StubGate::new(1);
+            let gate = PortalGate::new(gate, config(), PointLogger::default());
+            let mut endpoint = gate.knock(Knock::default()).await.unwrap();
+
+            let first = ping(Substance::Empty);
+            let second = ping(Substance::Empty);
+            endpoint.tx.send(first).await.unwrap();
+            endpoint.tx.send(second.clone()).await.unwrap();
+            assert_eq!(rejected(&mut endpoint, &second).await, 408);
+        });
+    }
+
+    #[test]
+    pub fn test_disconnect() {
+        runtime().block_on(async move {
+            let (gate, far) = StubGate::new(16);
+            let gate = PortalGate::new(gate, config(), PointLogger::default());
+            let mut endpoint = gate.knock(Knock::default()).await.unwrap();
+
+            // the hyperway going away closes the external process' endpoint
+            drop(far);
+            let closed = tokio::time::timeout(Duration::from_secs(5), endpoint.rx.recv())
+                .await
+                .unwrap();
+            assert!(closed.is_none());
+        });
+    }
+}
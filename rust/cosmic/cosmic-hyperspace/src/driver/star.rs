@@ -17,6 +17,7 @@ use cosmic_space::hyper::{
 use cosmic_space::kind::{BaseKind, Kind, StarSub};
 use cosmic_space::loc::{Layer, Point, StarKey, ToPoint, ToSurface, LOCAL_STAR};
 use cosmic_space::log::{Trackable, Tracker};
+use cosmic_space::metrics::METRICS;
 use cosmic_space::parse::bind_config;
 use cosmic_space::particle::traversal::TraversalInjection;
 use cosmic_space::particle::Status;
@@ -277,6 +278,20 @@ where
 
         Ok(())
     }
+
+    /// count one more `name` of `kind` for this star
+    fn count(&self, name: &str, help: &str, kind: &Kind) {
+        METRICS
+            .counter(
+                name,
+                help,
+                &[
+                    ("star", self.skel.point.to_string().as_str()),
+                    ("kind", kind.to_string().as_str()),
+                ],
+            )
+            .inc();
+    }
 }
 
 #[async_trait]
@@ -320,34 +335,37 @@ where
                     .await
                     .map_err(|e| e.to_space_err())?;
 
-                let registration = Registration {
-                    point: Point::global_executor(),
-                    kind: Kind::Global,
-                    registry: Default::default(),
-                    properties: Default::default(),
-                    owner: HYPERUSER.clone(),
-                    strategy: Strategy::Ensure,
-                    status: Status::Ready,
-                };
-                self.skel
-                    .registry
-                    .register(&registration)
-                    .await
-                    .map_err(|e| e.to_space_err())?;
-
-                let record = self
-                    .skel
-                    .registry
-                    .record(&Point::global_executor())
-                    .await
-                    .map_err(|e| e.to_space_err())?;
-                let assign = Assign::new(AssignmentKind::Create, record.details, StateSrc::None);
-                self.create(&assign).await.map_err(|e| e.to_space_err())?;
-                self.skel
-                    .registry
-                    .assign_star(&Point::global_executor(), &LOCAL_STAR)
-                    .await
-                    .map_err(|e| e.to_space_err())?;
+                for global in [Point::global_executor(), Point::global_logger()] {
+                    let registration = Registration {
+                        point: global.clone(),
+                        kind: Kind::Global,
+                        registry: Default::default(),
+                        properties: Default::default(),
+                        owner: HYPERUSER.clone(),
+                        strategy: Strategy::Ensure,
+                        status: Status::Ready,
+                    };
+                    self.skel
+                        .registry
+                        .register(&registration)
+                        .await
+                        .map_err(|e| e.to_space_err())?;
+
+                    let record = self
+                        .skel
+                        .registry
+                        .record(&global)
+                        .await
+                        .map_err(|e| e.to_space_err())?;
+                    let assign =
+                        Assign::new(AssignmentKind::Create, record.details, StateSrc::None);
+                    self.create(&assign).await.map_err(|e| e.to_space_err())?;
+                    self.skel
+                        .registry
+                        .assign_star(&global, &LOCAL_STAR)
+                        .await
+                        .map_err(|e| e.to_space_err())?;
+                }
 
                 Ok(Status::Ready)
             }
@@ -386,6 +404,11 @@ where
     ) -> Result<ParticleLocation, P::Err> {
         if let HyperSubstance::Provision(provision) = ctx.input {
             let record = self.skel.registry.record(&provision.point).await?;
+            self.count(
+                "cosmic_provisions_total",
+                "provisions requested of a star",
+                &record.details.stub.kind,
+            );
 
             match self.skel.wrangles.find(&record.details.stub.kind) {
                 None => {
@@ -450,6 +473,11 @@ where
                 .assignment
                 .send(assign.clone())
                 .unwrap_or_default();
+            self.count(
+                "cosmic_assigns_total",
+                "particles assigned to a star",
+                &assign.details.stub.kind,
+            );
 
             if self
                 .skel
@@ -12,18 +12,21 @@ use cosmic_space::command::common::StateSrc;
 use cosmic_space::command::direct::create::{
     Create, KindTemplate, PointSegTemplate, PointTemplate, Strategy, Template,
 };
-use cosmic_space::config::bind::BindConfig;
+use cosmic_space::config::bind::{BindConfig, PipelineStopVar};
 use cosmic_space::err::SpaceErr;
 use cosmic_space::fail::http;
 use cosmic_space::hyper::{HyperSubstance, ParticleLocation};
 use cosmic_space::kind::{BaseKind, Kind, NativeSub};
-use cosmic_space::loc::{Layer, Point, ToSurface};
-use cosmic_space::parse::{bind_config, CamelCase};
+use cosmic_space::limit::RateLimiter;
+use cosmic_space::loc::{Layer, Point, ToSurface, Uuid};
+use cosmic_space::log::{PointLogger, TraceCtx};
+use cosmic_space::metrics::METRICS;
+use cosmic_space::parse::{bind_config, CamelCase, Env};
 use cosmic_space::particle::traversal::{Traversal, TraversalDirection};
 use cosmic_space::particle::Status;
 use cosmic_space::selector::{KindSelector, Pattern, SubKindSelector};
 use cosmic_space::substance::{Bin, Substance};
-use cosmic_space::util::{log, ValuePattern};
+use cosmic_space::util::{log, ToResolved, ValuePattern};
 use cosmic_space::wave::core::http2::{HttpMethod, HttpRequest};
 use cosmic_space::wave::core::{DirectedCore, HeaderMap, ReflectedCore};
 use cosmic_space::wave::exchange::asynch::{
@@ -32,6 +35,7 @@ use cosmic_space::wave::exchange::asynch::{
 use cosmic_space::wave::exchange::SetStrategy;
 use cosmic_space::wave::{
     Agent, DirectedProto, Handling, HandlingKind, Ping, ToRecipients, UltraWave, WaitTime, Wave,
+    WaveId, WaveKind,
 };
 use cosmic_space::HYPERUSER;
 use inflector::Inflector;
@@ -296,12 +300,32 @@ where
         let runtime = tokio::runtime::Handle::current();
         thread::spawn(move || {
             let port = self.skel.skel.skel.machine.cosmos.web_port().unwrap();
+            let metrics_path = self.skel.skel.skel.machine.cosmos.metrics_path();
             let server = Server::http(format!("0.0.0.0:{}", port)).unwrap();
             for req in server.incoming_requests() {
+                if is_metrics(&req, &metrics_path) {
+                    let metrics = format!(
+                        "{}{}",
+                        METRICS.to_prometheus(),
+                        self.skel.skel.skel.machine.limiter.to_prometheus()
+                    );
+                    let response = tiny_http::Response::from_string(metrics)
+                        .with_header(
+                            tiny_http::Header::from_bytes(
+                                &b"Content-Type"[..],
+                                &b"text/plain; version=0.0.4"[..],
+                            )
+                            .unwrap(),
+                        );
+                    req.respond(response).unwrap_or_default();
+                    continue;
+                }
                 let runtime = runtime.clone();
                 let transmitter = self.transmitter.clone();
+                let limiter = self.skel.skel.skel.machine.limiter.clone();
+                let logger = self.skel.skel.skel.logger.point(self.skel.point.clone());
                 runtime.spawn(async move {
-                    match Self::handle::<P>(transmitter, req).await {
+                    match Self::handle::<P>(transmitter, limiter, logger, req).await {
                         Ok(_) => {}
                         Err(err) => {
                             println!("http handle ERR: {}", err.to_string());
@@ -314,6 +338,8 @@ where
 
     async fn handle<C>(
         transmitter: ProtoTransmitter,
+        limiter: RateLimiter,
+        logger: PointLogger,
         mut req: tiny_http::Request,
     ) -> Result<(), C::Err>
     where
@@ -351,12 +377,47 @@ where
             body,
         };
 
+        let trace = traceparent(&request.headers);
         let core: DirectedCore = request.into();
 
+        // every Web client is `Agent::Anonymous` so each address has an allowance of its own
+        let peer = req.remote_addr().map(|addr| addr.ip().to_string());
+        let to = routed_to(&logger.point, &core);
+        let exchange = WaveId::new(WaveKind::Ping);
+        let bytes = req.body_length().unwrap_or_default() as u64;
+        if let Err(throttled) = limiter.admit(
+            &Agent::Anonymous,
+            peer.as_ref(),
+            None,
+            to.as_ref(),
+            bytes,
+            Some(&exchange),
+        ) {
+            let retry_after = throttled.retry_after.as_secs_f64().ceil().to_string();
+            rayon::spawn(move || {
+                let response = tiny_http::Response::from_string(throttled.reason)
+                    .with_status_code(429)
+                    .with_header(
+                        tiny_http::Header::from_bytes(&b"Retry-After"[..], retry_after.as_bytes())
+                            .unwrap(),
+                    );
+                req.respond(response).unwrap_or_default();
+            });
+            return Ok(());
+        }
+
+        // the request is the root of its trace unless the client sent a `traceparent`
+        let logger = match trace {
+            Some(ctx) => logger.trace_span(&ctx),
+            None => logger.span(),
+        };
         let mut wave = DirectedProto::ping();
+        wave.trace(logger.trace_ctx());
         wave.core(core);
         //        wave.track = true;
-        let pong = transmitter.ping(wave).await?;
+        let pong = transmitter.ping(wave).await;
+        limiter.release(&exchange);
+        let pong = pong?;
 
         let body = pong.core.body.clone().to_bin()?;
         let mut headers = vec![];
@@ -384,3 +445,117 @@ where
         Ok(())
     }
 }
+
+/// the point `WEB_BIND_CONFIG` routes a request of `core` to (i.e. `localhost`) so
+/// that rate limits scoped by `to` see where the request goes rather than the Web driver
+fn routed_to(web: &Point, core: &DirectedCore) -> Option<Point> {
+    let mut probe = DirectedProto::ping();
+    probe.from(web.clone().to_surface());
+    probe.to(web.clone().to_surface());
+    probe.core(core.clone()).ok()?;
+    let probe = probe.build().ok()?;
+    let route = WEB_BIND_CONFIG.select(&probe).ok()?;
+    route
+        .block
+        .segments
+        .iter()
+        .find_map(|segment| match &segment.stop {
+            PipelineStopVar::Point(point) => point.clone().to_resolved(&Env::new(web.clone())).ok(),
+            _ => None,
+        })
+}
+
+/// a `GET` of the metrics path (ignoring any query)
+fn is_metrics(req: &tiny_http::Request, metrics_path: &Option<String>) -> bool {
+    match metrics_path {
+        Some(path) => {
+            *req.method() == tiny_http::Method::Get
+                && req.url().split('?').next() == Some(path.as_str())
+        }
+        None => false,
+    }
+}
+
+/// the trace context of a W3C `traceparent` header: `00-<trace id>-<parent id>-<flags>`.
+/// A malformed header starts a new trace rather than joining one it can't name
+fn traceparent(headers: &HeaderMap) -> Option<TraceCtx> {
+    let (_, header) = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("traceparent"))?;
+    // lowercase hex of `len` digits that are not all zero
+    let id = |id: &str, len: usize| {
+        id.len() == len
+            && id.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+            && id.chars().any(|c| c != '0')
+    };
+    let hex = |field: &str| {
+        field.len() == 2 && field.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+    };
+    let parts: Vec<&str> = header.trim().split('-').collect();
+    match parts.as_slice() {
+        [version, trace, span, flags, ..]
+            if hex(version)
+                && *version != "ff"
+                && id(trace, 32)
+                && id(span, 16)
+                && hex(flags)
+                && (*version != "00" || parts.len() == 4) =>
+        {
+            Some(TraceCtx {
+                trace: Uuid::from_unwrap(*trace),
+                span: Uuid::from_unwrap(*span),
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use cosmic_space::loc::Uuid;
+    use cosmic_space::log::TraceCtx;
+    use cosmic_space::wave::core::HeaderMap;
+
+    use crate::driver::web::traceparent;
+
+    fn parse(header: &str) -> Option<TraceCtx> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Traceparent".to_string(), header.to_string());
+        traceparent(&headers)
+    }
+
+    #[test]
+    pub fn test_traceparent() {
+        let trace = "4bf92f3577b34da6a3ce929d0e0e4736";
+        let span = "00f067aa0ba902b7";
+        assert_eq!(
+            parse(format!("00-{}-{}-01", trace, span).as_str()),
+            Some(TraceCtx {
+                trace: Uuid::from_unwrap(trace),
+                span: Uuid::from_unwrap(span),
+            })
+        );
+        // later versions may append fields
+        assert!(parse(format!("01-{}-{}-01-extra", trace, span).as_str()).is_some());
+        assert!(traceparent(&HeaderMap::new()).is_none());
+    }
+
+    #[test]
+    pub fn test_malformed_traceparent() {
+        let trace = "4bf92f3577b34da6a3ce929d0e0e4736";
+        let span = "00f067aa0ba902b7";
+        for header in [
+            format!("00-{}-{}", trace, span),
+            format!("00-{}-{}-01-extra", trace, span),
+            format!("ff-{}-{}-01", trace, span),
+            format!("00-{}-{}-01", trace.to_uppercase(), span),
+            format!("00-{}-{}-01", "zz".repeat(16), span),
+            format!("00-{}-{}-01", trace, "0".repeat(16)),
+            format!("00-{}-{}-01", "0".repeat(32), span),
+            format!("00-{}-{}-01", &trace[1..], span),
+            format!("00-{}-{}-1", trace, span),
+        ] {
+            assert!(parse(header.as_str()).is_none(), "accepted '{}'", header);
+        }
+    }
+}
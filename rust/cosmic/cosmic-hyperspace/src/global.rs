@@ -6,7 +6,7 @@ use cosmic_nom::new_span;
 use cosmic_space::artifact::ArtRef;
 use cosmic_space::command::common::StateSrc;
 use cosmic_space::command::direct::create::{Create, PointSegTemplate, Strategy};
-use cosmic_space::command::direct::select::Select;
+use cosmic_space::command::direct::select::{Select, SelectIntoSubstance};
 use cosmic_space::command::Command;
 use cosmic_space::command::RawCommand;
 use cosmic_space::config::bind::{BindConfig, RouteSelector};
@@ -14,14 +14,15 @@ use cosmic_space::err::SpaceErr;
 use cosmic_space::hyper::{Assign, AssignmentKind, HyperSubstance};
 use cosmic_space::kind::Kind;
 use cosmic_space::loc::{Layer, Point, Surface, ToPoint, ToSurface};
-use cosmic_space::log::{PointLogger, RootLogger};
+use cosmic_space::log::{PointLogger, RootLogger, TrackRule};
 use cosmic_space::parse::error::result;
 use cosmic_space::parse::route_attribute;
 use cosmic_space::parse::{bind_config, command_line};
 use cosmic_space::particle::{Details, PointKind, Status};
-use cosmic_space::substance::Substance;
+use cosmic_space::substance::{Bin, Substance, SubstanceList, SubstanceMap};
 use cosmic_space::util::{log, ToResolved};
 use cosmic_space::wave::core::cmd::CmdMethod;
+use cosmic_space::wave::core::ext::ExtMethod;
 use cosmic_space::wave::core::hyp::HypMethod;
 use cosmic_space::wave::core::CoreBounce;
 use cosmic_space::wave::core::ReflectedCore;
@@ -105,6 +106,18 @@ where
         self.command(ctx).await
     }
 
+    /// install a `TrackRule` on this machine: every star logs the stops of matching waves
+    /// until the rule's ttl runs out.  Only the HyperUser may install rules
+    #[route("Cmd<Track>")]
+    pub async fn track(&self, ctx: InCtx<'_, Bin>) -> Result<(), P::Err> {
+        if *ctx.wave().agent() != Agent::HyperUser {
+            return Err(SpaceErr::forbidden("only the HyperUser may install a TrackRule").into());
+        }
+        let rule = TrackRule::from_bin(ctx.input.as_slice())?;
+        self.skel.logger.logger.tracks().add(&rule)?;
+        Ok(())
+    }
+
     #[route("Cmd<Command>")]
     pub async fn command(&self, ctx: InCtx<'_, Command>) -> Result<ReflectedCore, P::Err> {
         let global = GlobalExecutionChamber::new(self.skel.clone());
@@ -118,11 +131,59 @@ where
             }
             Command::Select(select) => {
                 let mut select = select.clone();
-                let substance: Substance = self.skel.registry.select(&mut select).await?.into();
+                let mut list = self.skel.registry.select(&mut select).await?;
+                if select.into_substance == SelectIntoSubstance::Records {
+                    let mut records = vec![];
+                    for stub in list.list {
+                        if let Substance::Stub(stub) = *stub {
+                            let location = self.skel.registry.record(&stub.point).await?.location;
+                            let mut record = SubstanceMap::new();
+                            record.map.insert("stub".to_string(), Substance::Stub(stub));
+                            record
+                                .map
+                                .insert("location".to_string(), Substance::Location(location));
+                            records.push(Box::new(Substance::Map(record)));
+                        }
+                    }
+                    list = SubstanceList { list: records };
+                }
+                let substance: Substance = list.into();
                 Ok(ReflectedCore::ok_body(substance))
             }
             Command::Delete(delete) => {
-                let substance: Substance = self.skel.registry.delete(delete).await?.into();
+                // the Hosts of deleted Mechtrons are told to stop hosting them
+                let mut hosted = vec![];
+                let mut select: Select = delete.clone().into();
+                for point in self.skel.registry.select(&mut select).await?.list {
+                    if let Substance::Point(point) = *point {
+                        if let Ok(record) = self.skel.registry.record(&point).await {
+                            if let Some(host) = record.location.host {
+                                hosted.push((point, host));
+                            }
+                        }
+                    }
+                }
+                let deleted = self.skel.registry.delete(delete).await?;
+                for (point, host) in hosted {
+                    let mut proto = DirectedProto::signal();
+                    proto.method(ExtMethod::new("Retract")?);
+                    proto.agent(Agent::HyperUser);
+                    proto.to(host.to_surface().with_layer(Layer::Core));
+                    proto.body(Substance::Point(point));
+                    self.skel
+                        .logger
+                        .result(ctx.transmitter.signal(proto).await)
+                        .unwrap_or_default();
+                }
+                // cached artifacts of a deleted bundle must not be served anymore
+                for point in deleted.list.iter() {
+                    if let Ok(point) = TryInto::<Point>::try_into((**point).clone()) {
+                        if let Ok(bundle) = point.to_bundle() {
+                            self.skel.machine.artifacts.invalidate(&bundle);
+                        }
+                    }
+                }
+                let substance: Substance = deleted.into();
                 Ok(ReflectedCore::ok_body(substance))
             }
             Command::Set(set) => {
@@ -3,7 +3,7 @@ use std::sync::Arc;
 
 use url::Url;
 
-use cosmic_space::artifact::ArtRef;
+use cosmic_space::artifact::{ArtRef, ArtifactReq};
 use cosmic_space::config::bind::{BindConfig, PipelineStepVar, PipelineStopVar};
 use cosmic_space::err::{CoreReflector, SpaceErr, StatusErr};
 use cosmic_space::loc::{Layer, Point, Surface, ToSurface};
@@ -81,7 +81,8 @@ where
                     .map_err(|e| e.to_space_err())?
             }
             Some(bind) => {
-                let bind = Point::from_str(bind.value.as_str())?;
+                let bind = ArtifactReq::from_str(bind.value.as_str())?;
+                let bind = self.skel.machine.artifacts.resolve(&bind).await?;
                 log(self.skel.machine.artifacts.bind(&bind).await)?
             }
         };
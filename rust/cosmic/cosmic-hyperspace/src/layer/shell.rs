@@ -1,18 +1,20 @@
 use std::sync::atomic::{AtomicU16, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use dashmap::{DashMap, DashSet};
 
 use cosmic_nom::new_span;
 use cosmic_space::command::common::StateSrc;
-use cosmic_space::command::{Command, RawCommand};
+use cosmic_space::command::{CmdTransfer, Command, RawCommand, UploadChunk, UPLOAD_CHUNK_SIZE};
 use cosmic_space::err::SpaceErr;
 use cosmic_space::loc::{Layer, Point, Surface, SurfaceSelector, ToPoint, ToSurface, Topic};
 use cosmic_space::log::PointLogger;
 use cosmic_space::parse::error::result;
 use cosmic_space::parse::{command_line, Env};
 use cosmic_space::particle::traversal::{Traversal, TraversalInjection, TraversalLayer};
-use cosmic_space::substance::Substance;
+use cosmic_space::particle::Progress;
+use cosmic_space::substance::{Bin, Substance};
 use cosmic_space::util::{log, ToResolved};
 use cosmic_space::wave::core::{CoreBounce, DirectedCore, ReflectedCore};
 use cosmic_space::wave::exchange::asynch::{
@@ -234,6 +236,7 @@ where
             source_selector: ctx.from().clone().into(),
             env,
             port: session_port.clone(),
+            uploads: Uploads::new(),
         };
 
         self.skel
@@ -252,7 +255,176 @@ impl CliSession {
         let exec_port = self.port.clone().with_topic(exec_topic.clone());
         let mut exec = CommandExecutor::new(exec_port, ctx.from().clone(), self.env.clone());
 
-        Ok(exec.execute(ctx).await?)
+        let mut transfers = vec![];
+        for transfer in ctx.transfers.iter() {
+            transfers.push(self.resolve(transfer)?);
+        }
+
+        Ok(exec.execute(ctx, transfers).await?)
+    }
+
+    /// append a chunk of a large transfer & reply with the upload's `Progress`
+    #[route("Ext<UploadChunk>")]
+    pub async fn upload(&self, ctx: InCtx<'_, Bin>) -> Result<Substance, SpaceErr> {
+        let chunk = UploadChunk::from_bin(ctx.input.as_slice())?;
+        let progress = self.uploads.append(chunk)?;
+        Ok(Substance::Text(progress.to_string()))
+    }
+}
+
+impl CliSession {
+    /// a transfer with empty content refers to a completed upload of the same id
+    fn resolve(&self, transfer: &CmdTransfer) -> Result<CmdTransfer, SpaceErr> {
+        if !transfer.content.is_empty() {
+            return Ok(transfer.clone());
+        }
+        match self.uploads.take(&transfer.id)? {
+            None => Ok(transfer.clone()),
+            Some(content) => Ok(CmdTransfer::new(transfer.id.clone(), Arc::new(content))),
+        }
+    }
+}
+
+/// the largest single transfer a `CliSession` will assemble from chunks
+pub const MAX_UPLOAD_BYTES: usize = 256 * 1024 * 1024;
+
+/// the most bytes of unfinished or unclaimed uploads one `CliSession` may hold
+pub const MAX_SESSION_UPLOAD_BYTES: usize = 512 * 1024 * 1024;
+
+/// how long an upload may go without receiving a chunk or being claimed before it is dropped
+pub const UPLOAD_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// a transfer being assembled from `UploadChunk`s
+struct Upload {
+    received: u16,
+    total: u16,
+    content: Vec<u8>,
+    touched: Instant,
+}
+
+impl Upload {
+    fn new(total: u16) -> Self {
+        Self {
+            received: 0,
+            total,
+            content: vec![],
+            touched: Instant::now(),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.received == self.total
+    }
+}
+
+/// the uploads of one `CliSession`, bounded per upload, per session & in time
+pub struct Uploads {
+    uploads: DashMap<String, Upload>,
+    max_upload: usize,
+    max_session: usize,
+    ttl: Duration,
+}
+
+impl Uploads {
+    pub fn new() -> Self {
+        Self::with_limits(MAX_UPLOAD_BYTES, MAX_SESSION_UPLOAD_BYTES, UPLOAD_TTL)
+    }
+
+    pub fn with_limits(max_upload: usize, max_session: usize, ttl: Duration) -> Self {
+        Self {
+            uploads: Uploads::new(),
+            max_upload,
+            max_session,
+            ttl,
+        }
+    }
+
+    /// bytes held by every upload of this session
+    pub fn bytes(&self) -> usize {
+        self.uploads.iter().map(|upload| upload.content.len()).sum()
+    }
+
+    fn evict_expired(&self) {
+        let ttl = self.ttl;
+        self.uploads.retain(|_, upload| upload.touched.elapsed() < ttl);
+    }
+
+    pub fn append(&self, chunk: UploadChunk) -> Result<Progress, SpaceErr> {
+        self.evict_expired();
+        if chunk.total == 0 || chunk.index >= chunk.total {
+            self.uploads.remove(&chunk.id);
+            return Err(SpaceErr::bad_request(format!(
+                "upload '{}' received chunk {} of {}",
+                chunk.id, chunk.index, chunk.total
+            )));
+        }
+        if chunk.content.len() > UPLOAD_CHUNK_SIZE {
+            self.uploads.remove(&chunk.id);
+            return Err(SpaceErr::bad_request(format!(
+                "upload '{}' chunk {} is larger than {} bytes",
+                chunk.id, chunk.index, UPLOAD_CHUNK_SIZE
+            )));
+        }
+        if chunk.index == 0 {
+            self.uploads.insert(chunk.id.clone(), Upload::new(chunk.total));
+        }
+
+        // sum before taking the entry's lock so the DashMap shard is not locked twice
+        let session = self.bytes();
+        let mut upload = self.uploads.get_mut(&chunk.id).ok_or(SpaceErr::bad_request(format!(
+            "upload '{}' must start with chunk 0",
+            chunk.id
+        )))?;
+        let err = if chunk.index != upload.received || chunk.total != upload.total {
+            Some(SpaceErr::bad_request(format!(
+                "upload '{}' expected chunk {}/{} but received {}/{}",
+                chunk.id, upload.received, upload.total, chunk.index, chunk.total
+            )))
+        } else if upload.content.len() + chunk.content.len() > self.max_upload {
+            Some(SpaceErr::new(
+                413,
+                format!(
+                    "upload '{}' exceeds the limit of {} bytes",
+                    chunk.id, self.max_upload
+                ),
+            ))
+        } else if session + chunk.content.len() > self.max_session {
+            Some(SpaceErr::new(
+                413,
+                format!(
+                    "upload '{}' exceeds the session limit of {} bytes",
+                    chunk.id, self.max_session
+                ),
+            ))
+        } else {
+            None
+        };
+        if let Some(err) = err {
+            drop(upload);
+            self.uploads.remove(&chunk.id);
+            return Err(err);
+        }
+
+        upload.content.extend_from_slice(chunk.content.as_slice());
+        upload.received = upload.received + 1;
+        upload.touched = Instant::now();
+        Ok(Progress {
+            step: upload.received,
+            total: upload.total,
+        })
+    }
+
+    /// remove & return the content of a completed upload, `None` if there is no such upload
+    pub fn take(&self, id: &String) -> Result<Option<Vec<u8>>, SpaceErr> {
+        self.evict_expired();
+        match self.uploads.remove(id) {
+            None => Ok(None),
+            Some((_, upload)) if upload.is_complete() => Ok(Some(upload.content)),
+            Some((id, upload)) => Err(SpaceErr::bad_request(format!(
+                "upload '{}' is incomplete: received {} of {} chunks",
+                id, upload.received, upload.total
+            ))),
+        }
     }
 }
 
@@ -261,6 +433,7 @@ pub struct CliSession {
     pub source_selector: SurfaceSelector,
     pub env: Env,
     pub port: Surface,
+    uploads: Uploads,
 }
 
 impl TopicHandler for CliSession {
@@ -282,23 +455,30 @@ impl CommandExecutor {
         Self { port, source, env }
     }
 
-    pub async fn execute(&self, ctx: InCtx<'_, RawCommand>) -> Result<ReflectedCore, SpaceErr> {
+    pub async fn execute(
+        &self,
+        ctx: InCtx<'_, RawCommand>,
+        transfers: Vec<CmdTransfer>,
+    ) -> Result<ReflectedCore, SpaceErr> {
         // make sure everything is coming from this command executor topic
         let ctx = ctx.push_from(self.port.clone());
 
         let command = log(result(command_line(new_span(ctx.line.as_str()))))?;
 
-        let mut env = self.env.clone();
-        for transfer in &ctx.transfers {
+        let mut env = match &ctx.working {
+            Some(working) => Env::new(working.clone()),
+            None => self.env.clone(),
+        };
+        for transfer in &transfers {
             env.set_file(transfer.id.clone(), transfer.content.clone())
         }
-        let mut command: Command = command.to_resolved(&self.env)?;
+        let mut command: Command = command.to_resolved(&env)?;
 
         if let Command::Create(create) = &mut command {
-            if ctx.transfers.len() == 1 {
-                let transfer = ctx.transfers.get(0).unwrap().clone();
+            if transfers.len() == 1 {
+                let transfer = transfers.get(0).unwrap().clone();
                 create.state = StateSrc::Substance(Box::new(Substance::Bin(transfer.content)));
-            } else if ctx.transfers.len() > 1 {
+            } else if transfers.len() > 1 {
                 return Err("create cannot handle more than one state transfer".into());
             }
         }
@@ -18,6 +18,7 @@ use inflector::Inflector;
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -28,7 +29,9 @@ use tokio::sync::{mpsc, oneshot};
 use tracing::error;
 use uuid::Uuid;
 
-use cosmic_hyperlane::{HyperAuthenticator, HyperGate, HyperGateSelector, HyperwayEndpointFactory};
+use cosmic_hyperlane::{
+    HyperAuthenticator, HyperGate, HyperGateSelector, HyperlaneConfig, HyperwayEndpointFactory,
+};
 use cosmic_space::artifact::asynch::ArtifactApi;
 use cosmic_space::command::common::{SetProperties, SetRegistry};
 use cosmic_space::command::direct::create::{KindTemplate, Strategy};
@@ -37,13 +40,14 @@ use cosmic_space::command::direct::query::{Query, QueryResult};
 use cosmic_space::command::direct::select::{Select, SubSelect};
 use cosmic_space::err::SpaceErr;
 use cosmic_space::fail::Timeout;
-use cosmic_space::hyper::{ParticleLocation, ParticleRecord};
+use cosmic_space::hyper::{Knock, ParticleLocation, ParticleRecord};
 use cosmic_space::kind::{
     ArtifactSubKind, BaseKind, FileSubKind, Kind, NativeSub, Specific, StarSub, UserBaseSubKind,
 };
 use cosmic_space::loc::{
     Layer, MachineName, Point, RouteSeg, StarKey, Surface, ToBaseKind, ToSurface,
 };
+use cosmic_space::limit::LimitRule;
 use cosmic_space::log::RootLogger;
 use cosmic_space::particle::property::{PropertiesConfig, PropertiesConfigBuilder};
 use cosmic_space::particle::{Details, Properties, Status, Stub};
@@ -54,20 +58,25 @@ use cosmic_space::settings::Timeouts;
 use cosmic_space::substance::{Substance, SubstanceList, Token};
 use cosmic_space::wave::core::http2::StatusCode;
 use cosmic_space::wave::core::ReflectedCore;
-use cosmic_space::wave::UltraWave;
+use cosmic_space::wave::{Agent, UltraWave};
 use err::HyperErr;
 use mechtron_host::err::HostErr;
 use reg::Registry;
 
 use crate::driver::{DriverFactory, DriversBuilder};
-use crate::machine::{Machine, MachineApi, MachineTemplate};
+use crate::logger::LogRetention;
+use crate::otlp::OtlpTarget;
+use crate::machine::{CaptureConfig, Machine, MachineApi, MachineTemplate};
 
+pub mod artifact;
 pub mod driver;
 pub mod err;
 pub mod global;
 pub mod layer;
+pub mod logger;
 pub mod machine;
 pub mod mem;
+pub mod otlp;
 pub mod reg;
 pub mod star;
 
@@ -117,11 +126,23 @@ where
         builder.kind(kind.clone());
         match kind.to_base() {
             BaseKind::Mechtron => {
-                builder.add_point("config", true, true).unwrap();
+                builder.add_artifact_req("config", true, true).unwrap();
                 builder.build().unwrap()
             }
             BaseKind::Host => {
                 builder.add_point("bin", true, true).unwrap();
+                builder.add_u64("fuel", false, true).unwrap();
+                builder.add_u64("max-memory-pages", false, true).unwrap();
+                builder.add_u64("call-timeout", false, true).unwrap();
+                builder.build().unwrap()
+            }
+            BaseKind::Repo => {
+                // comma separated hex ed25519 keys trusted to sign bundle manifests
+                builder.add_string("publisher-keys").unwrap();
+                builder.build().unwrap()
+            }
+            BaseKind::Artifact => {
+                builder.add_string("digest").unwrap();
                 builder.build().unwrap()
             }
             _ => builder.build().unwrap(),
@@ -141,10 +162,67 @@ where
         Ok(8080u16)
     }
 
+    /// the path the Web driver serves `METRICS` & the state of the `RateLimiter` on in
+    /// the Prometheus text format (i.e. `/metrics`), or `None` to not serve them.
+    /// Metrics are not served unless a `Cosmos` opts in since the Web driver does not
+    /// authenticate the request
+    fn metrics_path(&self) -> Option<String> {
+        None
+    }
+
     fn data_dir(&self) -> String {
         "./data/".to_string()
     }
 
+    /// limits on the logs stored by `GLOBAL::logger`
+    fn log_retention(&self) -> LogRetention {
+        LogRetention::default()
+    }
+
+    /// where to export completed spans, including those logged by guests
+    fn trace_export(&self) -> Option<OtlpTarget> {
+        None
+    }
+
+    /// waves to record to capture files (see `cosmic_space::capture`) so traffic can
+    /// be replayed later
+    fn captures(&self) -> Vec<CaptureConfig> {
+        vec![]
+    }
+
+    /// the secret a control presents as the `Substance::Text` `auth` of its `Knock`
+    /// (i.e. `cosmic-cli --token`) to be the HyperUser, the only agent that may list,
+    /// kick & ban hyperways.  `None` makes no control the HyperUser
+    fn control_token(&self) -> Option<String> {
+        None
+    }
+
+    /// the agent of a control that knocked with `knock` without the `control_token`.
+    /// Implementors should grant an agent on the strength of the credential in
+    /// `knock.auth` alone: the rest of a knock is whatever the control claims
+    fn control_agent(&self, _knock: &Knock) -> Agent {
+        Agent::Anonymous
+    }
+
+    /// the rate limits of the agents of controls, portals & Web clients.  A wave must
+    /// be within every rule that matches it
+    fn rate_limits(&self) -> Vec<LimitRule> {
+        vec![]
+    }
+
+    /// the queue limit & overflow policy of the lanes of star, control & portal
+    /// hyperways
+    fn hyperlane_config(&self) -> HyperlaneConfig {
+        HyperlaneConfig::default()
+    }
+
+    /// bundles to serve from local directories instead of their published zips.
+    /// Intended for development: changes to a mounted directory take effect immediately
+    /// (see `DirectoryArtifactFetcher`)
+    fn artifact_dirs(&self) -> HashMap<Point, PathBuf> {
+        HashMap::new()
+    }
+
     fn select_kind(&self, template: &KindTemplate) -> Result<Kind, SpaceErr> {
         let base: BaseKind = BaseKind::from_str(template.base.to_string().as_str())?;
         Ok(match base {
@@ -0,0 +1,499 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use tokio::sync::{mpsc, oneshot};
+
+use cosmic_space::err::SpaceErr;
+use cosmic_space::loc::{Point, Surface, ToPoint};
+use cosmic_space::log::{LogQuery, PointLogger};
+use cosmic_space::particle::{Aspect, Watch};
+use cosmic_space::selector::{PointHierarchy, PointKindSeg, Selector};
+use cosmic_space::substance::{Bin, LogSubstance, Substance, SubstanceList};
+use cosmic_space::wave::core::cmd::CmdMethod;
+use cosmic_space::wave::exchange::asynch::InCtx;
+use cosmic_space::wave::{Agent, DirectedProto};
+
+use crate::err::HyperErr;
+use crate::star::HyperStarSkel;
+use crate::Cosmos;
+
+/// how long a watch of `GLOBAL::logger` lasts unless the watcher sends `Ext<Watch>` again
+pub const LOG_WATCH_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// how many matches `GLOBAL::logger` takes from its store at a time while it looks for
+/// the logs of a query that the agent may see
+pub const LOG_QUERY_BATCH: usize = 256;
+
+/// how many logs `GLOBAL::logger` keeps & for how long
+#[derive(Debug, Clone)]
+pub struct LogRetention {
+    pub max_records: usize,
+    pub max_age: Duration,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        Self {
+            max_records: 100_000,
+            max_age: Duration::from_secs(60 * 60 * 24 * 7),
+        }
+    }
+}
+
+pub enum LogStoreCall {
+    Append {
+        log: LogSubstance,
+        rtn: oneshot::Sender<Vec<Surface>>,
+    },
+    Query {
+        query: LogQuery,
+        before: Option<u64>,
+        batch: usize,
+        rtn: oneshot::Sender<Vec<(u64, LogSubstance)>>,
+    },
+    Watch {
+        watcher: Surface,
+        point: Point,
+    },
+    Unwatch {
+        watcher: Surface,
+        point: Point,
+    },
+}
+
+/// the machine wide store behind `GLOBAL::logger`
+#[derive(Clone)]
+pub struct LogStoreApi {
+    tx: mpsc::Sender<LogStoreCall>,
+}
+
+impl LogStoreApi {
+    pub fn new(dir: PathBuf, retention: LogRetention, logger: PointLogger) -> Self {
+        let (tx, rx) = mpsc::channel(1024);
+        LogStore::new(dir, retention, logger, rx).start();
+        Self { tx }
+    }
+
+    /// store `log` & return the watchers that should receive it once it has been written
+    pub async fn append(&self, log: LogSubstance) -> Result<Vec<Surface>, SpaceErr> {
+        let (rtn, rtn_rx) = oneshot::channel();
+        self.tx.send(LogStoreCall::Append { log, rtn }).await?;
+        Ok(rtn_rx.await?)
+    }
+
+    /// at most `batch` of the stored logs matching `query` (ignoring its `selector` &
+    /// `limit`) newest first, with their sequence.  Only logs with a sequence lower than
+    /// `before` are scanned so the sequence of the last log returned fetches the next batch
+    pub async fn query(
+        &self,
+        query: LogQuery,
+        before: Option<u64>,
+        batch: usize,
+    ) -> Result<Vec<(u64, LogSubstance)>, SpaceErr> {
+        let (rtn, rtn_rx) = oneshot::channel();
+        self.tx
+            .send(LogStoreCall::Query {
+                query,
+                before,
+                batch,
+                rtn,
+            })
+            .await?;
+        Ok(rtn_rx.await?)
+    }
+
+    /// `watcher` receives every log of `point` and of its children for `LOG_WATCH_TTL`.
+    /// Watching the same point again renews the watch
+    pub async fn watch(&self, watcher: Surface, point: Point) -> Result<(), SpaceErr> {
+        self.tx.send(LogStoreCall::Watch { watcher, point }).await?;
+        Ok(())
+    }
+
+    pub async fn unwatch(&self, watcher: Surface, point: Point) -> Result<(), SpaceErr> {
+        self.tx
+            .send(LogStoreCall::Unwatch { watcher, point })
+            .await?;
+        Ok(())
+    }
+}
+
+/// logs are kept in memory and appended to `global.log` as json lines.  The file is
+/// rewritten once more records have been dropped by retention than are still kept
+struct LogStore {
+    path: PathBuf,
+    retention: LogRetention,
+    logger: PointLogger,
+    rx: mpsc::Receiver<LogStoreCall>,
+    logs: VecDeque<LogSubstance>,
+    /// the sequence of the oldest log kept, the sequence of every other log follows it
+    first: u64,
+    /// the watched point, the watcher & when the watch expires
+    watchers: Vec<(Point, Surface, Instant)>,
+    file: Option<File>,
+    stale: usize,
+}
+
+impl LogStore {
+    fn new(
+        dir: PathBuf,
+        retention: LogRetention,
+        logger: PointLogger,
+        rx: mpsc::Receiver<LogStoreCall>,
+    ) -> Self {
+        Self {
+            path: dir.join("global.log"),
+            retention,
+            logger,
+            rx,
+            logs: VecDeque::new(),
+            first: 0,
+            watchers: vec![],
+            file: None,
+            stale: 0,
+        }
+    }
+
+    /// the store writes to its file synchronously so it runs on a blocking thread of its own
+    fn start(mut self) {
+        tokio::task::spawn_blocking(move || {
+            let result = self.load();
+            self.logger.eat(result);
+            while let Some(call) = self.rx.blocking_recv() {
+                match call {
+                    LogStoreCall::Append { log, rtn } => {
+                        let watchers = self.watchers(&log);
+                        let result = self.append(log);
+                        self.logger.eat(result);
+                        rtn.send(watchers).unwrap_or_default();
+                    }
+                    LogStoreCall::Query {
+                        query,
+                        before,
+                        batch,
+                        rtn,
+                    } => {
+                        self.prune();
+                        rtn.send(self.query(&query, before, batch))
+                            .unwrap_or_default();
+                    }
+                    LogStoreCall::Watch { watcher, point } => {
+                        self.watchers
+                            .retain(|(p, w, _)| !(*p == point && *w == watcher));
+                        self.watchers
+                            .push((point, watcher, Instant::now() + LOG_WATCH_TTL));
+                    }
+                    LogStoreCall::Unwatch { watcher, point } => {
+                        self.watchers
+                            .retain(|(p, w, _)| !(*p == point && *w == watcher));
+                    }
+                }
+            }
+        });
+    }
+
+    fn watchers(&mut self, log: &LogSubstance) -> Vec<Surface> {
+        let now = Instant::now();
+        self.watchers.retain(|(_, _, expires)| *expires > now);
+        match log.point() {
+            None => vec![],
+            Some(point) => self
+                .watchers
+                .iter()
+                .filter(|(watched, _, _)| watched.is_parent_of(point))
+                .map(|(_, watcher, _)| watcher.clone())
+                .collect(),
+        }
+    }
+
+    fn query(
+        &self,
+        query: &LogQuery,
+        before: Option<u64>,
+        batch: usize,
+    ) -> Vec<(u64, LogSubstance)> {
+        let end = match before {
+            Some(before) => (before.saturating_sub(self.first) as usize).min(self.logs.len()),
+            None => self.logs.len(),
+        };
+        self.logs
+            .range(..end)
+            .enumerate()
+            .rev()
+            .filter(|(_, log)| query.matches(log))
+            .take(batch)
+            .map(|(index, log)| (self.first + index as u64, log.clone()))
+            .collect()
+    }
+
+    fn load(&mut self) -> Result<(), SpaceErr> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        if self.path.exists() {
+            let reader = BufReader::new(File::open(&self.path)?);
+            for line in reader.lines() {
+                // a record cut short by a crash is skipped
+                if let Ok(log) = serde_json::from_str(line?.as_str()) {
+                    self.logs.push_back(log);
+                }
+            }
+        }
+        self.prune();
+        self.compact()
+    }
+
+    fn append(&mut self, log: LogSubstance) -> Result<(), SpaceErr> {
+        let line = serde_json::to_string(&log)
+            .map_err(|e| SpaceErr::server_error(format!("could not store log: {}", e)))?;
+        self.logs.push_back(log);
+        self.prune();
+        if self.stale > self.logs.len() {
+            return self.compact();
+        }
+        if self.file.is_none() {
+            self.file = Some(OpenOptions::new().create(true).append(true).open(&self.path)?);
+        }
+        writeln!(self.file.as_mut().unwrap(), "{}", line)?;
+        Ok(())
+    }
+
+    fn prune(&mut self) {
+        let oldest = Utc::now().timestamp_millis() - self.retention.max_age.as_millis() as i64;
+        while let Some(log) = self.logs.front() {
+            if self.logs.len() > self.retention.max_records || log.timestamp() < oldest {
+                self.logs.pop_front();
+                self.first = self.first + 1;
+                self.stale = self.stale + 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// rewrite the file with only the logs that are still retained
+    fn compact(&mut self) -> Result<(), SpaceErr> {
+        self.file = None;
+        let tmp = self.path.with_extension("log.tmp");
+        let mut file = File::create(&tmp)?;
+        for log in self.logs.iter() {
+            let line = serde_json::to_string(log)
+                .map_err(|e| SpaceErr::server_error(format!("could not store log: {}", e)))?;
+            writeln!(file, "{}", line)?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        self.stale = 0;
+        Ok(())
+    }
+}
+
+/// handles the waves addressed to `GLOBAL::logger`: `Cmd<Log>` signals from
+/// `SynchTransmittingLogAppender`s, `Ext<LogQuery>` and `Ext<Watch>`/`Ext<Unwatch>`
+/// for `Aspect::Log`.  Watchers receive each matching log as a `Cmd<Log>` signal
+#[derive(Clone, DirectedHandler)]
+pub struct GlobalLoggerHandler<P>
+where
+    P: Cosmos,
+{
+    skel: HyperStarSkel<P>,
+}
+
+impl<P> GlobalLoggerHandler<P>
+where
+    P: Cosmos,
+{
+    pub fn new(skel: HyperStarSkel<P>) -> Self {
+        Self { skel }
+    }
+}
+
+#[handler]
+impl<P> GlobalLoggerHandler<P>
+where
+    P: Cosmos,
+{
+    /// a log is accepted from the point it was logged for, from the point hosting it (a
+    /// wasm host transmits the logs of its mechtrons) or from the HyperUser
+    #[route("Cmd<Log>")]
+    pub async fn log(&self, ctx: InCtx<'_, LogSubstance>) -> Result<(), P::Err> {
+        let agent = ctx.wave().agent();
+        let accepted = match ctx.input.point() {
+            _ if *agent == Agent::HyperUser => true,
+            Some(point) => self.logs_for(agent, &ctx.wave().from().point, point).await,
+            None => false,
+        };
+        if !accepted {
+            return Err(SpaceErr::forbidden(format!(
+                "{} may not log for {}",
+                agent.to_point().to_string(),
+                ctx.input
+                    .point()
+                    .map_or("a pointless log".to_string(), |point| point.to_string())
+            ))
+            .into());
+        }
+        // spans of guests join the host's in the exported traces
+        if let (LogSubstance::Event(event), Some(traces)) = (ctx.input, &self.skel.machine.traces) {
+            traces.export(event.clone());
+        }
+        let watchers = self.skel.machine.logs.append(ctx.input.clone()).await?;
+        for watcher in watchers {
+            let mut proto = DirectedProto::signal();
+            proto.method(CmdMethod::Log);
+            proto.to(watcher);
+            proto.body(Substance::Log(ctx.input.clone()));
+            self.skel.logger.eat(ctx.transmitter.signal(proto).await);
+        }
+        Ok(())
+    }
+
+    /// reply with the logs matching a `LogQuery`, oldest first.  Agents other than the
+    /// HyperUser only receive the logs of points they own
+    #[route("Ext<LogQuery>")]
+    pub async fn query(&self, ctx: InCtx<'_, Bin>) -> Result<Substance, P::Err> {
+        let query = LogQuery::from_bin(ctx.input.as_slice())?;
+        let agent = ctx.wave().agent();
+        // the store is scanned newest first a batch at a time until the limit is met
+        let batch = query.limit.map_or(LOG_QUERY_BATCH, |limit| limit.min(LOG_QUERY_BATCH));
+        // many logs share a point so whether a point is visible is resolved once
+        let mut visible: HashMap<Point, bool> = HashMap::new();
+        let mut matches = vec![];
+        let mut before = None;
+        'scan: loop {
+            if batch == 0 {
+                break;
+            }
+            let logs = self
+                .skel
+                .machine
+                .logs
+                .query(query.clone(), before, batch)
+                .await?;
+            let scanned = logs.len();
+            for (sequence, log) in logs {
+                before = Some(sequence);
+                match log.point() {
+                    Some(point) => {
+                        if !visible.contains_key(point) {
+                            let selected = match &query.selector {
+                                Some(selector) => self.selects(selector, point).await,
+                                None => true,
+                            };
+                            let selected = selected && self.authorized(agent, point).await;
+                            visible.insert(point.clone(), selected);
+                        }
+                        if !visible.get(point).cloned().unwrap_or_default() {
+                            continue;
+                        }
+                    }
+                    None if query.selector.is_some() || *agent != Agent::HyperUser => continue,
+                    None => {}
+                }
+                matches.push(Box::new(Substance::Log(log)));
+                if query.limit.map_or(false, |limit| matches.len() >= limit) {
+                    break 'scan;
+                }
+            }
+            if scanned < batch {
+                break;
+            }
+        }
+        matches.reverse();
+        Ok(Substance::List(SubstanceList { list: matches }))
+    }
+
+    /// only the HyperUser & the owner of a point may watch its logs
+    #[route("Ext<Watch>")]
+    pub async fn watch(&self, ctx: InCtx<'_, Bin>) -> Result<(), P::Err> {
+        let watch = self.log_watch(ctx.input.as_slice())?;
+        if !self.authorized(ctx.wave().agent(), &watch.point).await {
+            return Err(SpaceErr::forbidden(format!(
+                "{} may not watch the logs of {}",
+                ctx.wave().agent().to_point().to_string(),
+                watch.point.to_string()
+            ))
+            .into());
+        }
+        let watcher = ctx.wave().from().clone();
+        Ok(self.skel.machine.logs.watch(watcher, watch.point).await?)
+    }
+
+    #[route("Ext<Unwatch>")]
+    pub async fn unwatch(&self, ctx: InCtx<'_, Bin>) -> Result<(), P::Err> {
+        let watch = self.log_watch(ctx.input.as_slice())?;
+        let watcher = ctx.wave().from().clone();
+        Ok(self.skel.machine.logs.unwatch(watcher, watch.point).await?)
+    }
+}
+
+impl<P> GlobalLoggerHandler<P>
+where
+    P: Cosmos,
+{
+    fn log_watch(&self, bin: &[u8]) -> Result<Watch, SpaceErr> {
+        let watch = Watch::from_bin(bin)?;
+        match watch.aspect {
+            Aspect::Log => Ok(watch),
+            aspect => Err(SpaceErr::bad_request(format!(
+                "{} cannot watch Aspect::{}",
+                Point::global_logger().to_string(),
+                aspect.to_string()
+            ))),
+        }
+    }
+
+    /// true if `agent` sending from `from` is `point` or the point hosting it
+    async fn logs_for(&self, agent: &Agent, from: &Point, point: &Point) -> bool {
+        let sender = agent.to_point();
+        if sender != *from {
+            return false;
+        }
+        if sender == *point {
+            return true;
+        }
+        match self.skel.registry.record(point).await {
+            Ok(record) => record.location.host == Some(sender),
+            Err(_) => false,
+        }
+    }
+
+    /// the HyperUser may see every log, other agents only those of points they own
+    async fn authorized(&self, agent: &Agent, point: &Point) -> bool {
+        match agent {
+            Agent::HyperUser => true,
+            agent => match self.skel.registry.access(&agent.to_point(), point).await {
+                Ok(access) => access.has_full(),
+                Err(_) => false,
+            },
+        }
+    }
+
+    /// points that are not in the registry are never selected
+    async fn selects(&self, selector: &Selector, point: &Point) -> bool {
+        match self.hierarchy(point).await {
+            Ok(hierarchy) => selector.matches(&hierarchy),
+            Err(_) => false,
+        }
+    }
+
+    async fn hierarchy(&self, point: &Point) -> Result<PointHierarchy, P::Err> {
+        let mut segments = vec![];
+        for (index, segment) in point.segments.iter().enumerate() {
+            let parent = Point {
+                route: point.route.clone(),
+                segments: point.segments[..=index].to_vec(),
+            };
+            let record = self.skel.registry.record(&parent).await?;
+            segments.push(PointKindSeg {
+                segment: segment.clone(),
+                kind: record.details.stub.kind,
+            });
+        }
+        Ok(PointHierarchy::new(point.route.clone(), segments))
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::marker::PhantomData;
+use std::path::PathBuf;
 use std::process::Output;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -16,19 +17,24 @@ use tokio::sync::{broadcast, mpsc, oneshot, watch};
 use tracing::info;
 
 use cosmic_hyperlane::{
-    HyperClient, HyperConnectionDetails, HyperConnectionErr, HyperGate, HyperGateSelector,
-    HyperRouter, Hyperway, HyperwayEndpoint, HyperwayEndpointFactory, HyperwayInterchange,
+    Capture, HyperClient, HyperConnectionDetails, HyperConnectionErr, HyperGate, HyperGateSelector,
+    HyperRouter, HyperlaneConfig, Hyperway, HyperwayEndpoint, HyperwayEndpointFactory, HyperwayInterchange,
     HyperwayStub, InterchangeGate, LayerTransform, LocalHyperwayGateJumper,
     LocalHyperwayGateUnlocker, MountInterchangeGate, SimpleGreeter,
     TokenAuthenticatorWithRemoteWhitelist,
 };
 use cosmic_space::artifact::asynch::{ArtifactApi, ArtifactFetcher, ReadArtifactFetcher};
+use cosmic_space::capture::CaptureFilter;
 use cosmic_space::err::SpaceErr;
-use cosmic_space::hyper::{InterchangeKind, Knock};
+use cosmic_space::hyper::{InterchangeKind, InterchangeReport, Interchanges, Knock};
 use cosmic_space::kind::StarSub;
+use cosmic_space::command::direct::select::{Select, SelectIntoSubstance, SelectKind};
 use cosmic_space::loc::{
-    ConstellationName, Layer, MachineName, Point, StarHandle, StarKey, Surface, ToPoint, ToSurface,
+    ConstellationName, Layer, MachineName, Point, PointSeg, StarHandle, StarKey, Surface, ToPoint,
+    ToSurface,
 };
+use cosmic_space::selector::Selector;
+use cosmic_space::limit::RateLimiter;
 use cosmic_space::log::{PointLogger, RootLogger};
 use cosmic_space::particle::{Status, Stub};
 use cosmic_space::settings::Timeouts;
@@ -38,7 +44,10 @@ use cosmic_space::wave::exchange::asynch::Exchanger;
 use cosmic_space::wave::exchange::SetStrategy;
 use cosmic_space::wave::{Agent, DirectedProto, HyperWave, Pong, UltraWave, Wave};
 
+use crate::artifact::{DirectoryArtifactFetcher, DEFAULT_WATCH_INTERVAL};
 use crate::err::HyperErr;
+use crate::logger::LogStoreApi;
+use crate::otlp::{OtlpAppender, TraceExporter};
 use crate::reg::{Registry, RegistryApi};
 use crate::star::{HyperStar, HyperStarApi, HyperStarSkel, HyperStarTx, StarCon, StarTemplate};
 use crate::{Cosmos, DriversBuilder};
@@ -140,6 +149,15 @@ where
     pub cosmos: P,
     pub registry: Registry<P>,
     pub artifacts: ArtifactApi,
+    pub logs: LogStoreApi,
+    pub traces: Option<TraceExporter>,
+    /// limits the waves of controls, portals & Web clients
+    pub limiter: RateLimiter,
+    /// the queue limit & overflow policy of the lanes of every hyperway
+    pub hyperlane: HyperlaneConfig,
+    pub captures: Vec<(CaptureSite, Capture)>,
+    /// the interchanges of stars, controls & portals so they can be administered
+    pub interchanges: Arc<DashMap<InterchangeKind, Arc<HyperwayInterchange>>>,
     pub logger: RootLogger,
     pub timeouts: Timeouts,
     pub api: MachineApi<P>,
@@ -149,6 +167,98 @@ where
     pub global: Surface,
 }
 
+impl<P> MachineSkel<P>
+where
+    P: Cosmos,
+{
+    /// record the hyperways of `interchange` selected by a `CaptureSite::Hyperways`
+    pub fn capture_hyperways(&self, interchange: &mut HyperwayInterchange) {
+        for (site, capture) in self.captures.iter() {
+            if let CaptureSite::Hyperways(selector) = site {
+                interchange.capture(selector.clone(), capture.clone());
+            }
+        }
+    }
+
+    pub async fn interchange_reports(&self) -> Result<Interchanges, SpaceErr> {
+        let interchanges: Vec<(InterchangeKind, Arc<HyperwayInterchange>)> = self
+            .interchanges
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        let mut reports = Interchanges::new();
+        for (kind, interchange) in interchanges {
+            reports.push(InterchangeReport {
+                kind,
+                hyperways: interchange.hyperways().await?,
+                banned: interchange.banned(),
+                banned_peers: interchange.banned_peers(),
+            });
+        }
+        Ok(reports)
+    }
+
+    /// disconnect the hyperways of `point` from every interchange
+    pub async fn kick(&self, point: &Point) -> Result<usize, SpaceErr> {
+        let mut kicked = 0;
+        for interchange in self.interchange_list() {
+            kicked = kicked + interchange.kick(point.clone()).await?;
+        }
+        Ok(kicked)
+    }
+
+    /// kick `point` & refuse it at the gates of every interchange
+    pub async fn ban(&self, point: &Point) -> Result<usize, SpaceErr> {
+        let mut kicked = 0;
+        for interchange in self.interchange_list() {
+            kicked = kicked + interchange.ban(point.clone()).await?;
+        }
+        Ok(kicked)
+    }
+
+    /// returns `false` if no interchange had banned `point`
+    pub fn unban(&self, point: &Point) -> bool {
+        let mut unbanned = false;
+        for interchange in self.interchange_list() {
+            unbanned = interchange.unban(point) || unbanned;
+        }
+        unbanned
+    }
+
+    fn interchange_list(&self) -> Vec<Arc<HyperwayInterchange>> {
+        self.interchanges
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    /// the captures of the waves that start traversing at `layer`
+    pub fn layer_captures(&self, layer: &Layer) -> Vec<&Capture> {
+        self.captures
+            .iter()
+            .filter(|(site, _)| *site == CaptureSite::Layer(layer.clone()))
+            .map(|(_, capture)| capture)
+            .collect()
+    }
+}
+
+/// where a `CaptureConfig` records waves
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CaptureSite {
+    /// the waves every star starts to traverse at this layer
+    Layer(Layer),
+    /// the hyperways of star, control & portal interchanges whose remote is selected
+    Hyperways(Selector),
+}
+
+/// record the waves crossing `site` that match `filter` to the capture file at `path`
+#[derive(Debug, Clone)]
+pub struct CaptureConfig {
+    pub site: CaptureSite,
+    pub path: PathBuf,
+    pub filter: CaptureFilter,
+}
+
 pub struct Machine<P>
 where
     P: Cosmos + 'static,
@@ -195,19 +305,52 @@ where
             .to_point()
             .to_surface()
             .with_layer(Layer::Gravity);
-        let logger = platform.logger().point(machine_star.point.clone());
+        let traces = platform
+            .trace_export()
+            .map(|target| {
+                let logger = platform.logger().point(machine_star.point.push("traces").unwrap());
+                TraceExporter::new(target, machine_name.clone(), logger)
+            });
+        let root_logger = match &traces {
+            None => platform.logger(),
+            Some(traces) => platform
+                .logger()
+                .wrap(|inner| Arc::new(OtlpAppender::new(traces.clone(), inner))),
+        };
+        let logger = root_logger.point(machine_star.point.clone());
         let global = machine_star
             .point
             .push("global")
             .unwrap()
             .to_surface()
             .with_layer(Layer::Core);
+        let mut captures = vec![];
+        for config in platform.captures() {
+            captures.push((
+                config.site,
+                Capture::file(
+                    config.path,
+                    config.filter,
+                    logger.push_point("captures").unwrap(),
+                )?,
+            ));
+        }
         let skel = MachineSkel {
             name: machine_name.clone(),
             machine_star,
             registry: platform.global_registry().await?,
             artifacts: platform.artifact_hub(),
-            logger: platform.logger(),
+            logs: LogStoreApi::new(
+                PathBuf::from(format!("{}logs", platform.data_dir())),
+                platform.log_retention(),
+                logger.push_point("logs").unwrap(),
+            ),
+            logger: root_logger,
+            traces,
+            limiter: RateLimiter::new(platform.rate_limits()),
+            hyperlane: platform.hyperlane_config(),
+            captures,
+            interchanges: Arc::new(DashMap::new()),
             timeouts: Timeouts::default(),
             cosmos: platform.clone(),
             api: machine_api.clone(),
@@ -235,10 +378,12 @@ where
 
             let mut interchange =
                 HyperwayInterchange::new(logger.push_point("interchange").unwrap());
+            skel.capture_hyperways(&mut interchange);
 
             let star_hop = star_point.clone().to_surface().with_layer(Layer::Gravity);
 
             let mut hyperway = Hyperway::new(star_hop.clone(), Agent::HyperUser, logger.clone());
+            hyperway.configure(skel.hyperlane.clone());
             hyperway.transform_inbound(Box::new(LayerTransform::new(Layer::Gravity)));
 
             let hyperway_endpoint = hyperway.hyperway_endpoint_far(None).await;
@@ -265,6 +410,7 @@ where
                             .to_surface()
                             .with_layer(Layer::Gravity);
                         let hyperway = Hyperway::new(star, Agent::HyperUser, logger.clone());
+                        hyperway.configure(skel.hyperlane.clone());
                         interchange.add(hyperway).await;
                     }
                     StarCon::Connector(remote) => {
@@ -275,11 +421,16 @@ where
                             .to_surface()
                             .with_layer(Layer::Gravity);
                         let hyperway = Hyperway::new(star, Agent::HyperUser, logger.clone());
+                        hyperway.configure(skel.hyperlane.clone());
                         interchange.add(hyperway).await;
                     }
                 }
             }
 
+            skel.interchanges.insert(
+                InterchangeKind::Star(star_template.key.clone()),
+                interchange.clone(),
+            );
             gates.insert(InterchangeKind::Star(star_template.key.clone()), gate);
             let star_api = HyperStar::new(
                 star_skel.clone(),
@@ -391,7 +542,24 @@ where
             HyperClient::new_with_exchanger(Box::new(factory), Some(exchanger), logger.clone())
                 .unwrap();
 
-        let fetcher = Arc::new(ClientArtifactFetcher::new(client, skel.registry.clone()));
+        let fetcher: Arc<dyn ArtifactFetcher> =
+            Arc::new(ClientArtifactFetcher::new(client, skel.registry.clone()));
+        let dirs = platform.artifact_dirs();
+        let fetcher = if dirs.is_empty() {
+            fetcher
+        } else {
+            let mut fetcher = DirectoryArtifactFetcher::new(fetcher);
+            for (bundle, dir) in dirs {
+                logger.warn(format!(
+                    "serving bundle {} from local directory '{}'",
+                    bundle.to_string(),
+                    dir.display()
+                ));
+                logger.result(fetcher.mount(bundle, dir))?;
+            }
+            fetcher.watch(skel.artifacts.clone(), DEFAULT_WATCH_INTERVAL);
+            Arc::new(fetcher)
+        };
         skel.artifacts.set_fetcher(fetcher).await;
 
         machine.start().await;
@@ -678,11 +846,7 @@ where
         &self,
         status_tx: mpsc::Sender<HyperConnectionDetails>,
     ) -> Result<HyperwayEndpoint, SpaceErr> {
-        let knock = Knock {
-            kind: InterchangeKind::DefaultControl,
-            auth: Box::new(Substance::Empty),
-            remote: None,
-        };
+        let knock = Knock::default();
         self.logger
             .result_ctx("machine_api.knock()", self.machine_api.knock(knock).await)
     }
@@ -735,4 +899,26 @@ where
             Err("expecting Bin encountered some other substance when fetching artifact".into())
         }
     }
+
+    async fn versions(&self, series: &Point) -> Result<Vec<semver::Version>, SpaceErr> {
+        let mut select = Select {
+            pattern: Selector::from_str(format!("{}:*", series.to_string()).as_str())?,
+            properties: Default::default(),
+            into_substance: SelectIntoSubstance::Points,
+            kind: SelectKind::Initial,
+        };
+        let bundles = self
+            .registry
+            .select(&mut select)
+            .await
+            .map_err(|e| e.to_space_err())?;
+        let mut versions = vec![];
+        for bundle in bundles.list {
+            let bundle: Point = (*bundle).try_into()?;
+            if let Some(PointSeg::Version(version)) = bundle.last_segment() {
+                versions.push(version.version);
+            }
+        }
+        Ok(versions)
+    }
 }
@@ -4,6 +4,7 @@ use crate::driver::artifact::{
 use crate::driver::base::BaseDriverFactory;
 use crate::driver::control::ControlDriverFactory;
 use crate::driver::mechtron::{HostDriverFactory, MechtronDriverFactory};
+use crate::driver::portal::PortalDriverFactory;
 use crate::driver::root::RootDriverFactory;
 use crate::driver::space::SpaceDriverFactory;
 use crate::driver::web::WebDriverFactory;
@@ -33,6 +34,8 @@ impl MemCosmos {
     pub fn new() -> Self {
         Self {
             ctx: MemRegCtx::new(),
+            data_dir: "./data/".to_string(),
+            control_token: None,
         }
     }
 }
@@ -40,6 +43,10 @@ impl MemCosmos {
 #[derive(Clone)]
 pub struct MemCosmos {
     pub ctx: MemRegCtx,
+    /// where files such as the logs of `GLOBAL::logger` are kept (ends with a `/`)
+    pub data_dir: String,
+    /// the `Cosmos::control_token` of the machine
+    pub control_token: Option<String>,
 }
 
 #[async_trait]
@@ -60,6 +67,14 @@ impl Cosmos for MemCosmos {
         todo!()
     }
 
+    fn data_dir(&self) -> String {
+        self.data_dir.clone()
+    }
+
+    fn control_token(&self) -> Option<String> {
+        self.control_token.clone()
+    }
+
     fn machine_template(&self) -> MachineTemplate {
         MachineTemplate::default()
     }
@@ -98,6 +113,7 @@ impl Cosmos for MemCosmos {
             }
             StarSub::Jump => {
                 builder.add_post(Arc::new(WebDriverFactory::new()));
+                builder.add_post(Arc::new(PortalDriverFactory::new()));
             }
             StarSub::Fold => {}
             StarSub::Machine => {
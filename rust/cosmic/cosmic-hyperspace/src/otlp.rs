@@ -0,0 +1,408 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fs::OpenOptions;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use url::Url;
+
+use cosmic_space::err::SpaceErr;
+use cosmic_space::loc::Uuid;
+use cosmic_space::metrics::{Counter, METRICS};
+use cosmic_space::log::{
+    AuditLog, Log, LogAppender, LogSpanEvent, LogSpanEventKind, PointLogger, PointlessLog,
+};
+
+/// span events waiting for the export thread beyond this many are dropped
+const QUEUE_SIZE: usize = BATCH_SIZE * 64;
+/// spans are exported in batches of at most this many...
+const BATCH_SIZE: usize = 256;
+/// ...or at least this often
+const BATCH_INTERVAL: Duration = Duration::from_secs(1);
+/// spans that are entered but never exited are forgotten, oldest first, beyond this many...
+const MAX_OPEN_SPANS: usize = 10_000;
+/// ...or once they have been open this long
+const OPEN_SPAN_TTL: Duration = Duration::from_secs(10 * 60);
+/// how long connecting to, writing to or reading from the collector may take
+const COLLECTOR_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// where completed spans are exported as OTLP/JSON `ExportTraceServiceRequest`s
+#[derive(Debug, Clone)]
+pub enum OtlpTarget {
+    /// one request per line, the format of the OpenTelemetry Collector's file exporter
+    File(PathBuf),
+    /// an OTLP/HTTP collector endpoint such as `http://localhost:4318/v1/traces`
+    Collector(Url),
+}
+
+impl OtlpTarget {
+    pub fn collector(url: &str) -> Result<Self, SpaceErr> {
+        let url = Url::parse(url)
+            .map_err(|e| SpaceErr::bad_request(format!("invalid collector url '{}': {}", url, e)))?;
+        if url.scheme() != "http" {
+            return Err(SpaceErr::bad_request(format!(
+                "collector url '{}' must be http",
+                url
+            )));
+        }
+        Ok(OtlpTarget::Collector(url))
+    }
+}
+
+/// turns pairs of `Entry`/`Exit` span events into OTLP spans.  Export happens on a
+/// dedicated thread so logging never waits on the file or the collector.  When the
+/// thread falls behind by `QUEUE_SIZE` events the rest are dropped & counted
+#[derive(Clone)]
+pub struct TraceExporter {
+    tx: mpsc::SyncSender<LogSpanEvent>,
+    dropped: Counter,
+}
+
+impl TraceExporter {
+    /// export failures are logged to `logger`, which must not itself export to this
+    pub fn new<S: ToString>(target: OtlpTarget, service: S, logger: PointLogger) -> Self {
+        let service = service.to_string();
+        let (tx, rx) = mpsc::sync_channel(QUEUE_SIZE);
+        let dropped = METRICS.counter(
+            "cosmic_otlp_span_events_dropped_total",
+            "span events dropped because the OTLP export fell behind",
+            &[("service", service.as_str())],
+        );
+        let mut export = Export::new(target, service, logger);
+        thread::spawn(move || export.run(rx));
+        Self { tx, dropped }
+    }
+
+    pub fn export(&self, event: LogSpanEvent) {
+        if let Err(mpsc::TrySendError::Full(_)) = self.tx.try_send(event) {
+            self.dropped.inc();
+        }
+    }
+}
+
+struct Export {
+    target: OtlpTarget,
+    service: String,
+    logger: PointLogger,
+    open: HashMap<Uuid, LogSpanEvent>,
+    /// when each open span was entered, oldest first.  Spans that have since exited are
+    /// skipped when they reach the front
+    entered: VecDeque<(Instant, Uuid)>,
+    batch: Vec<Value>,
+}
+
+impl Export {
+    fn new(target: OtlpTarget, service: String, logger: PointLogger) -> Self {
+        Self {
+            target,
+            service,
+            logger,
+            open: HashMap::new(),
+            entered: VecDeque::new(),
+            batch: vec![],
+        }
+    }
+
+    fn run(&mut self, rx: mpsc::Receiver<LogSpanEvent>) {
+        let mut flushed = Instant::now();
+        loop {
+            match rx.recv_timeout(BATCH_INTERVAL) {
+                Ok(event) => self.event(event),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    self.flush();
+                    return;
+                }
+            }
+            if self.batch.len() >= BATCH_SIZE || flushed.elapsed() >= BATCH_INTERVAL {
+                self.expire();
+                self.flush();
+                flushed = Instant::now();
+            }
+        }
+    }
+
+    fn event(&mut self, event: LogSpanEvent) {
+        match event.kind {
+            LogSpanEventKind::Entry => {
+                self.entered.push_back((Instant::now(), event.span.clone()));
+                self.open.insert(event.span.clone(), event);
+                while self.open.len() > MAX_OPEN_SPANS {
+                    self.forget_oldest();
+                }
+                // exited spans pile up in `entered` when nothing is left open for long
+                if self.entered.len() > MAX_OPEN_SPANS * 2 {
+                    let open = &self.open;
+                    self.entered.retain(|(_, span)| open.contains_key(span));
+                }
+            }
+            LogSpanEventKind::Exit => {
+                let entry = self.open.remove(&event.span);
+                self.batch.push(span(entry.as_ref(), &event));
+            }
+        }
+    }
+
+    /// forget the span entered longest ago that is still open
+    fn forget_oldest(&mut self) {
+        while let Some((_, span)) = self.entered.pop_front() {
+            if self.open.remove(&span).is_some() {
+                return;
+            }
+        }
+    }
+
+    /// forget open spans that were entered more than `OPEN_SPAN_TTL` ago
+    fn expire(&mut self) {
+        while let Some((entered, span)) = self.entered.front() {
+            if entered.elapsed() < OPEN_SPAN_TTL {
+                return;
+            }
+            self.open.remove(span);
+            self.entered.pop_front();
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+        let spans: Vec<Value> = self.batch.drain(..).collect();
+        let count = spans.len();
+        let request = json!({
+            "resourceSpans": [{
+                "resource": { "attributes": [attribute("service.name", &self.service)] },
+                "scopeSpans": [{ "scope": { "name": "cosmic-hyperspace" }, "spans": spans }]
+            }]
+        });
+        let result = match &self.target {
+            OtlpTarget::File(path) => append(path, &request),
+            OtlpTarget::Collector(url) => post(url, &request),
+        };
+        if let Err(err) = result {
+            self.logger
+                .warn(format!("could not export {} spans: {}", count, err.to_string()));
+        }
+    }
+}
+
+/// an OTLP span from the `Exit` event of a span (and its `Entry` if it was seen)
+fn span(entry: Option<&LogSpanEvent>, exit: &LogSpanEvent) -> Value {
+    let start = entry.map_or(exit.timestamp.millis, |entry| entry.timestamp.millis);
+    let name = match exit.attributes.get("type") {
+        Some(kind) => format!("{} {}", kind, exit.point.to_string()),
+        None => exit.point.to_string(),
+    };
+    let mut attributes = vec![attribute("cosmic.point", &exit.point.to_string())];
+    let mut keys: Vec<&String> = exit.attributes.keys().collect();
+    keys.sort();
+    for key in keys {
+        attributes.push(attribute(key, &exit.attributes[key]));
+    }
+    let mut span = json!({
+        "traceId": hex_id(&exit.trace, 32),
+        "spanId": hex_id(&exit.span, 16),
+        "name": name,
+        "kind": 1,
+        "startTimeUnixNano": nanos(start),
+        "endTimeUnixNano": nanos(exit.timestamp.millis),
+        "attributes": attributes
+    });
+    if let Some(parent) = &exit.parent {
+        span["parentSpanId"] = Value::String(hex_id(parent, 16));
+    }
+    span
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+fn nanos(millis: i64) -> String {
+    (millis * 1_000_000).to_string()
+}
+
+/// OTLP ids are hex: a uuid's own hex digits are used when it has enough of them,
+/// otherwise (uuids are not guaranteed to be hex) a hash of it
+fn hex_id(id: &Uuid, len: usize) -> String {
+    let hex: String = id
+        .to_string()
+        .chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if hex.len() >= len {
+        return hex[..len].to_string();
+    }
+    let mut rtn = String::new();
+    let mut seed = 0u64;
+    while rtn.len() < len {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        id.hash(&mut hasher);
+        rtn.push_str(format!("{:016x}", hasher.finish()).as_str());
+        seed = seed + 1;
+    }
+    rtn[..len].to_string()
+}
+
+fn append(path: &PathBuf, request: &Value) -> Result<(), SpaceErr> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", request.to_string())?;
+    Ok(())
+}
+
+fn post(url: &Url, request: &Value) -> Result<(), SpaceErr> {
+    let host = url
+        .host_str()
+        .ok_or(SpaceErr::bad_request(format!("collector url '{}' has no host", url)))?;
+    let port = url.port_or_known_default().unwrap_or(4318);
+    let body = request.to_string();
+    let mut stream = connect(host, port)?;
+    stream.set_read_timeout(Some(COLLECTOR_TIMEOUT))?;
+    stream.set_write_timeout(Some(COLLECTOR_TIMEOUT))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        url.path(),
+        host,
+        port,
+        body.len()
+    )?;
+    stream.write_all(body.as_bytes())?;
+    let mut status = String::new();
+    BufReader::new(stream).read_line(&mut status)?;
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => Err(SpaceErr::server_error(format!(
+            "collector replied '{}'",
+            status.trim()
+        ))),
+    }
+}
+
+/// the first address of `host` that accepts a connection within `COLLECTOR_TIMEOUT`
+fn connect(host: &str, port: u16) -> Result<TcpStream, SpaceErr> {
+    let mut err = SpaceErr::server_error(format!("collector host '{}' has no address", host));
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, COLLECTOR_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => err = e.into(),
+        }
+    }
+    Err(err)
+}
+
+/// passes every log on to `inner` and exports span events to a `TraceExporter`
+pub struct OtlpAppender {
+    exporter: TraceExporter,
+    inner: Arc<dyn LogAppender>,
+}
+
+impl OtlpAppender {
+    pub fn new(exporter: TraceExporter, inner: Arc<dyn LogAppender>) -> Self {
+        Self { exporter, inner }
+    }
+}
+
+impl LogAppender for OtlpAppender {
+    fn log(&self, log: Log) {
+        self.inner.log(log)
+    }
+
+    fn audit(&self, log: AuditLog) {
+        self.inner.audit(log)
+    }
+
+    fn span_event(&self, log: LogSpanEvent) {
+        self.exporter.export(log.clone());
+        self.inner.span_event(log)
+    }
+
+    fn pointless(&self, log: PointlessLog) {
+        self.inner.pointless(log)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use cosmic_space::loc::{Point, Uuid};
+    use std::sync::mpsc;
+
+    use cosmic_space::log::{LogSpan, LogSpanEvent, LogSpanEventKind, RootLogger};
+    use cosmic_space::metrics::METRICS;
+
+    use crate::otlp::{hex_id, span, Export, OtlpTarget, TraceExporter, MAX_OPEN_SPANS};
+
+    fn span_event(span: &LogSpan, kind: LogSpanEventKind) -> LogSpanEvent {
+        let mut attributes = HashMap::new();
+        attributes.insert("type".to_string(), "Wave".to_string());
+        LogSpanEvent::new(span, &span.point, kind, attributes)
+    }
+
+    #[test]
+    pub fn test_otlp_span() {
+        let root = LogSpan::new(Point::from_str("localhost").unwrap());
+        let child = LogSpan::child(Point::from_str("localhost:app").unwrap(), &root.trace_ctx());
+        let entry = span_event(&child, LogSpanEventKind::Entry);
+        let exit = span_event(&child, LogSpanEventKind::Exit);
+
+        let span = span(Some(&entry), &exit);
+        assert_eq!(span["traceId"], hex_id(&root.id, 32));
+        assert_eq!(span["spanId"], hex_id(&child.id, 16));
+        assert_eq!(span["parentSpanId"], hex_id(&root.id, 16));
+        assert_eq!(span["name"], "Wave localhost:app");
+        assert_eq!(hex_id(&Uuid::from_unwrap("not-a-uuid"), 16).len(), 16);
+    }
+
+    #[test]
+    pub fn test_orphan_spans() {
+        let logger = RootLogger::default().point(Point::from_str("localhost").unwrap());
+        let target = OtlpTarget::File(std::env::temp_dir().join("cosmic-otlp-test.json"));
+        let mut export = Export::new(target, "test".to_string(), logger);
+        let point = Point::from_str("localhost:app").unwrap();
+        let first = LogSpan::new(point.clone());
+        export.event(span_event(&first, LogSpanEventKind::Entry));
+        for _ in 0..MAX_OPEN_SPANS {
+            let orphan = LogSpan::new(point.clone());
+            export.event(span_event(&orphan, LogSpanEventKind::Entry));
+        }
+        // the oldest orphan makes room for the newest
+        assert_eq!(export.open.len(), MAX_OPEN_SPANS);
+        assert!(!export.open.contains_key(&first.id));
+        assert!(export.entered.len() <= MAX_OPEN_SPANS * 2);
+    }
+
+    #[test]
+    pub fn test_queue_overflow() {
+        // nothing takes from the queue, so all but the first event overflow it
+        let (tx, rx) = mpsc::sync_channel(1);
+        let dropped = METRICS.counter(
+            "cosmic_otlp_span_events_dropped_total",
+            "span events dropped because the OTLP export fell behind",
+            &[("service", "test_queue_overflow")],
+        );
+        let exporter = TraceExporter {
+            tx,
+            dropped: dropped.clone(),
+        };
+        let span = LogSpan::new(Point::from_str("localhost:app").unwrap());
+        for _ in 0..3 {
+            exporter.export(span_event(&span, LogSpanEventKind::Entry));
+        }
+        assert_eq!(dropped.get(), 2);
+        assert_eq!(rx.try_iter().count(), 1);
+    }
+}
@@ -38,9 +38,10 @@ use cosmic_space::hyper::{MountKind, ParticleLocation};
 use cosmic_space::kind::{BaseKind, Kind, StarStub, StarSub, Sub};
 use cosmic_space::loc::{
     Layer, Point, RouteSeg, StarKey, Surface, SurfaceSelector, ToBaseKind, ToPoint, ToSurface,
-    Topic, Uuid, GLOBAL_EXEC, LOCAL_STAR,
+    Topic, Uuid, GLOBAL_EXEC, GLOBAL_LOGGER, LOCAL_STAR,
 };
 use cosmic_space::log::{PointLogger, RootLogger, Trackable, Tracker};
+use cosmic_space::metrics::{Counter, METRICS};
 use cosmic_space::parse::{bind_config, route_attribute, Env};
 use cosmic_space::particle::traversal::{
     Traversal, TraversalDirection, TraversalInjection, TraversalLayer,
@@ -72,6 +73,7 @@ use crate::driver::{
 use crate::err::HyperErr;
 use crate::global::{GlobalCommandExecutionHandler, GlobalExecutionChamber};
 use crate::layer::field::Field;
+use crate::logger::GlobalLoggerHandler;
 use crate::layer::shell::Shell;
 use crate::layer::shell::ShellState;
 use crate::machine::MachineSkel;
@@ -670,6 +672,7 @@ where
     hyper_router: Arc<dyn Router>,
     layer_traversal_engine: LayerTraversalEngine<P>,
     global_handler: DirectedHandlerShell<GlobalCommandExecutionHandler<P>>,
+    logger_handler: DirectedHandlerShell<GlobalLoggerHandler<P>>,
 }
 
 impl<P> HyperStar<P>
@@ -711,6 +714,21 @@ where
             skel.logger.logger.clone(),
         );
 
+        let logger_port = Point::global_logger().to_surface().with_layer(Layer::Core);
+        let mut transmitter = ProtoTransmitterBuilder::new(
+            Arc::new(skel.gravity_router.clone()),
+            skel.exchanger.clone(),
+        );
+        transmitter.from = SetStrategy::Override(logger_port.clone());
+        transmitter.agent = SetStrategy::Fill(Agent::HyperUser);
+
+        let logger_handler = DirectedHandlerShell::new(
+            GlobalLoggerHandler::new(skel.clone()),
+            transmitter,
+            logger_port,
+            skel.logger.logger.clone(),
+        );
+
         let mut forwarders = vec![];
         for (point, stub) in skel.adjacents.iter() {
             if stub.kind.is_forwarder() {
@@ -890,6 +908,7 @@ where
                 hyper_router,
                 layer_traversal_engine,
                 global_handler,
+                logger_handler,
             };
             star.start();
         }
@@ -1049,7 +1068,7 @@ where
     }
     // sending a wave that is from and to a particle into the fabric...
     // here it will be wrapped into a transport for star to star delivery or
-    // sent to GLOBAL::registry if addressed in such a way
+    // sent to GLOBAL::executor or GLOBAL::logger if addressed in such a way
     #[track_caller]
     async fn to_gravity(&self, mut wave: UltraWave) -> Result<(), P::Err> {
         wave.add_to_history(self.skel.point.clone());
@@ -1072,6 +1091,16 @@ where
                 handler.handle(wave).await;
             });
             return Ok(());
+        } else if wave.is_directed()
+            && wave.to().is_single()
+            && wave.to().to_single().unwrap().point == *GLOBAL_LOGGER
+        {
+            let wave = wave.to_directed().unwrap();
+            let handler = self.logger_handler.clone();
+            tokio::spawn(async move {
+                handler.handle(wave).await;
+            });
+            return Ok(());
         } else {
             logger
                 .result(self.star_tx.send(HyperStarCall::Shard(wave)).await)
@@ -1329,6 +1358,8 @@ where
     pub exit_up: mpsc::Sender<Traversal<UltraWave>>,
     pub exit_down: mpsc::Sender<Traversal<UltraWave>>,
     pub layers: HashSet<Layer>,
+    /// `cosmic_waves_routed_total` of this star for each layer
+    routed: Arc<HashMap<Layer, Counter>>,
 }
 
 impl<P> LayerTraversalEngine<P>
@@ -1344,12 +1375,36 @@ where
         let mut layers = HashSet::new();
         layers.insert(Layer::Field);
         layers.insert(Layer::Shell);
+        let star = skel.point.to_string();
+        let routed = [
+            Layer::Gravity,
+            Layer::Field,
+            Layer::Shell,
+            Layer::Portal,
+            Layer::Host,
+            Layer::Guest,
+            Layer::Core,
+        ]
+        .into_iter()
+        .map(|layer| {
+            let counter = METRICS.counter(
+                "cosmic_waves_routed_total",
+                "waves that visited a layer of a star",
+                &[
+                    ("star", star.as_str()),
+                    ("layer", layer.to_string().as_str()),
+                ],
+            );
+            (layer, counter)
+        })
+        .collect();
         Self {
             skel,
             injector,
             exit_down,
             exit_up,
             layers,
+            routed: Arc::new(routed),
         }
     }
 
@@ -1572,6 +1627,15 @@ println!("ERR: {}",err.to_string());
                 .send(traversal.clone())
                 .unwrap_or_default();
 
+            for capture in self.skel.machine.layer_captures(&traversal.layer) {
+                let site = format!(
+                    "{}<{}>",
+                    self.skel.point.to_string(),
+                    traversal.layer.to_string()
+                );
+                capture.record(site.as_str(), &traversal.payload);
+            }
+
             // alright, let's visit the injection layer first...
             self.visit_layer(traversal).await?;
         }
@@ -1609,6 +1673,9 @@ println!("ERR: {}",err.to_string());
                 "Visit",
             )
         });
+        if let Some(routed) = self.routed.get(&traversal.layer) {
+            routed.inc();
+        }
 
         match traversal.layer {
             Layer::Field => {
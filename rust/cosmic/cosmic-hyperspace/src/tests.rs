@@ -2,6 +2,7 @@
 
 use std::fs;
 use std::io::Error;
+use std::path::PathBuf;
 use std::sync::atomic;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
@@ -16,7 +17,7 @@ use tokio::time::error::Elapsed;
 
 use cosmic_hyperlane::{
     AnonHyperAuthenticator, HyperClient, HyperConnectionDetails, HyperConnectionErr, HyperGate,
-    HyperwayEndpoint, HyperwayStub, LocalHyperwayGateJumper,
+    HyperwayEndpoint, HyperwayEndpointFactory, HyperwayStub, LocalHyperwayGateJumper,
 };
 use cosmic_space::artifact::asynch::ReadArtifactFetcher;
 use cosmic_space::command::common::StateSrc;
@@ -27,10 +28,16 @@ use cosmic_space::command::{CmdTransfer, RawCommand};
 use cosmic_space::hyper::MountKind;
 use cosmic_space::hyper::{Assign, AssignmentKind, HyperSubstance, InterchangeKind, Knock};
 use cosmic_space::loc::{Layer, StarHandle, ToPoint, ToSurface, Uuid};
-use cosmic_space::log::{LogSource, PointLogger, RootLogger, StdOutAppender};
+use cosmic_space::log::{
+    Level, Log, LogPayload, LogQuery, LogSource, PointLogger, RootLogger, StdOutAppender,
+    Track, TrackRule, Tracker,
+};
+use cosmic_space::particle::{Aspect, Watch};
+use cosmic_space::substance::LogSubstance;
 use cosmic_space::particle::traversal::TraversalDirection;
 use cosmic_space::wave::core::cmd::CmdMethod;
 use cosmic_space::wave::core::ext::ExtMethod;
+use cosmic_space::wave::core::http2::HttpMethod;
 use cosmic_space::wave::core::hyp::HypMethod;
 use cosmic_space::wave::core::Method;
 use cosmic_space::wave::exchange::asynch::Exchanger;
@@ -45,7 +52,8 @@ use crate::driver::root::RootDriverFactory;
 use crate::driver::space::SpaceDriverFactory;
 use crate::driver::{DriverAvail, DriverFactory};
 use crate::err::CosmicErr;
-use crate::machine::MachineApiExtFactory;
+use crate::logger::{LogRetention, LogStoreApi};
+use crate::machine::{MachineApi, MachineApiExtFactory};
 use crate::mem::cosmos::MemCosmos;
 use crate::mem::registry::MemRegCtx;
 use crate::star::HyperStarApi;
@@ -753,6 +761,261 @@ fn test_control_cli() -> Result<(), CosmicErr> {
     })
 }
 
+/// knocks on the machine with `auth` as the credential of the control
+struct AuthExtFactory {
+    machine_api: MachineApi<MemCosmos>,
+    auth: Substance,
+}
+
+#[async_trait]
+impl HyperwayEndpointFactory for AuthExtFactory {
+    async fn create(
+        &self,
+        status_tx: tokio::sync::mpsc::Sender<HyperConnectionDetails>,
+    ) -> Result<HyperwayEndpoint, SpaceErr> {
+        let mut knock = Knock::default();
+        knock.auth = Box::new(self.auth.clone());
+        self.machine_api.knock(knock).await
+    }
+}
+
+#[test]
+fn test_control_token() -> Result<(), CosmicErr> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async move {
+        let mut platform = MemCosmos::new();
+        platform.control_token = Some("secret".to_string());
+        let machine_api = platform.machine();
+
+        tokio::time::timeout(Duration::from_secs(5), machine_api.wait_ready())
+            .await
+            .unwrap();
+
+        let factory = AuthExtFactory {
+            machine_api: machine_api.clone(),
+            auth: Substance::Text("secret".to_string()),
+        };
+        let admin = ControlClient::new(Box::new(factory))?;
+        admin.wait_for_ready(Duration::from_secs(5)).await?;
+        assert!(admin.interchanges().await.is_ok());
+
+        // neither a wrong token nor no token at all makes a control the HyperUser
+        for auth in vec![Substance::Text("guess".to_string()), Substance::Empty] {
+            let factory = AuthExtFactory {
+                machine_api: machine_api.clone(),
+                auth,
+            };
+            let client = ControlClient::new(Box::new(factory))?;
+            client.wait_for_ready(Duration::from_secs(5)).await?;
+            assert!(client.interchanges().await.is_err());
+        }
+
+        Ok(())
+    })
+}
+
+/// a fresh data dir so files kept by earlier runs are never read back
+fn temp_data_dir(name: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("{}-{}", name, Uuid::rnd().to_string()));
+    format!("{}/", dir.display())
+}
+
+#[test]
+fn test_global_logger() -> Result<(), CosmicErr> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async move {
+        let mut platform = MemCosmos::new();
+        platform.data_dir = temp_data_dir("cosmic-global-logger");
+        let data_dir = platform.data_dir.clone();
+        let machine_api = platform.machine();
+        let logger = RootLogger::new(LogSource::Core, Arc::new(StdOutAppender()));
+        let logger = logger.point(Point::from_str("mem-client").unwrap());
+
+        tokio::time::timeout(Duration::from_secs(5), machine_api.wait_ready())
+            .await
+            .unwrap();
+
+        let factory = MachineApiExtFactory {
+            machine_api,
+            logger: logger.clone(),
+        };
+
+        let client = ControlClient::new(Box::new(factory))?;
+        client.wait_for_ready(Duration::from_secs(5)).await?;
+
+        let transmitter = client.transmitter_builder().await?;
+        let transmitter = transmitter.build();
+
+        let log = Log {
+            point: Point::root(),
+            mark: Point::root(),
+            action: None,
+            source: LogSource::Core,
+            span: Some(Uuid::rnd()),
+            timestamp: Utc::now().timestamp_millis(),
+            payload: LogPayload::Message("global logger test".to_string()),
+            level: Level::Error,
+        };
+        // the control is neither the root nor its host so it may not log for it
+        let mut proto = DirectedProto::ping();
+        proto.method(CmdMethod::Log);
+        proto.to(Point::global_logger().to_surface().with_layer(Layer::Core));
+        proto.body(Substance::Log(LogSubstance::Log(log.clone())));
+        let pong: Wave<Pong> = transmitter.direct(proto).await?;
+        assert_eq!(pong.core.status.as_u16(), 403);
+
+        let mut query = LogQuery::new();
+        query.span = log.span.clone();
+        query.level = Some(Level::Warn);
+
+        // the control is not the HyperUser & does not own the root so none of its logs
+        // are returned & it may not watch them
+        let mut proto = DirectedProto::ping();
+        proto.method(ExtMethod::new("LogQuery".to_string())?);
+        proto.to(Point::global_logger().to_surface().with_layer(Layer::Core));
+        proto.body(Substance::Bin(query.to_bin()?));
+        let pong: Wave<Pong> = transmitter.direct(proto).await?;
+        assert!(pong.core.is_ok());
+        match pong.variant.core.body {
+            Substance::List(list) => assert!(list.list.is_empty()),
+            _ => panic!("expected a list of logs"),
+        }
+
+        let mut proto = DirectedProto::ping();
+        proto.method(ExtMethod::new("Watch".to_string())?);
+        proto.to(Point::global_logger().to_surface().with_layer(Layer::Core));
+        proto.body(Substance::Bin(
+            Watch::new(Point::root(), Aspect::Log).to_bin()?,
+        ));
+        let pong: Wave<Pong> = transmitter.direct(proto).await?;
+        assert_eq!(pong.core.status.as_u16(), 403);
+
+        // nor was the log written to the machine's data dir
+        let stored =
+            fs::read_to_string(format!("{}logs/global.log", data_dir)).unwrap_or_default();
+        let log = serde_json::to_string(&LogSubstance::Log(log)).unwrap();
+        assert!(!stored.contains(log.as_str()));
+
+        fs::remove_dir_all(data_dir.as_str()).unwrap_or_default();
+        Ok(())
+    })
+}
+
+#[test]
+fn test_log_store() -> Result<(), CosmicErr> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async move {
+        let dir = PathBuf::from(format!("{}logs", temp_data_dir("cosmic-log-store")));
+        let logger = RootLogger::default().point(Point::from_str("log-store").unwrap());
+        let retention = LogRetention {
+            max_records: 2,
+            max_age: Duration::from_secs(60),
+        };
+        let store = LogStoreApi::new(dir.clone(), retention.clone(), logger.clone());
+
+        let watcher = Point::from_str("localhost:watcher").unwrap().to_surface();
+        store.watch(watcher.clone(), Point::root()).await?;
+
+        let log = |message: &str| {
+            LogSubstance::Log(Log {
+                point: Point::from_str("localhost:app").unwrap(),
+                mark: Point::root(),
+                action: None,
+                source: LogSource::Core,
+                span: None,
+                timestamp: Utc::now().timestamp_millis(),
+                payload: LogPayload::Message(message.to_string()),
+                level: Level::Info,
+            })
+        };
+        let logs: Vec<LogSubstance> = ["one", "two", "three", "four"]
+            .into_iter()
+            .map(log)
+            .collect();
+        for log in logs[..3].iter() {
+            // append returns once the log is written
+            assert_eq!(store.append(log.clone()).await?, vec![watcher.clone()]);
+        }
+        store.unwatch(watcher, Point::root()).await?;
+        assert!(store.append(logs[3].clone()).await?.is_empty());
+
+        // a query scans from the newest log & a batch continues before the last returned
+        let newest = store.query(LogQuery::new(), None, 1).await?;
+        assert_eq!(newest, vec![(3, logs[3].clone())]);
+        let next = store.query(LogQuery::new(), Some(3), 1).await?;
+        assert_eq!(next, vec![(2, logs[2].clone())]);
+        assert!(store.query(LogQuery::new(), Some(2), 1).await?.is_empty());
+
+        // only the newest logs are retained, also by a store that reloads the file
+        for store in [store, LogStoreApi::new(dir.clone(), retention, logger)] {
+            let retained: Vec<LogSubstance> = store
+                .query(LogQuery::new(), None, 10)
+                .await?
+                .into_iter()
+                .rev()
+                .map(|(_, log)| log)
+                .collect();
+            assert_eq!(retained, logs[2..].to_vec());
+        }
+
+        fs::remove_dir_all(&dir).unwrap_or_default();
+        Ok(())
+    })
+}
+
+#[test]
+fn test_track_rule() {
+    let tracks = RootLogger::default().tracks().clone();
+    let track = Track::with_method("localhost:app:**", "Http<Post>", "to_gravity", ".*").unwrap();
+    tracks.add(&TrackRule::new(track, 60)).unwrap();
+
+    let wave = |method: HttpMethod| {
+        let mut wave = DirectedProto::ping();
+        wave.to(Point::from_str("localhost:app:users").unwrap().to_surface());
+        wave.from(HYPERUSER.clone());
+        wave.agent(Agent::HyperUser);
+        wave.method(method);
+        wave.build().unwrap().to_ultra()
+    };
+    let tracker = || Tracker::new("to_gravity", "Receive");
+
+    assert!(tracks.matches(&wave(HttpMethod::Post), &tracker()));
+    assert!(!tracks.matches(&wave(HttpMethod::Get), &tracker()));
+    assert!(!tracks.matches(
+        &wave(HttpMethod::Post),
+        &Tracker::new("from_hyperway", "Receive")
+    ));
+
+    // the wave is still tracked while a transport carries it between stars
+    let transport = wave(HttpMethod::Post)
+        .wrap_in_transport(
+            HYPERUSER.clone(),
+            Point::from_str("localhost:star").unwrap().to_surface(),
+        )
+        .build()
+        .unwrap()
+        .to_ultra();
+    assert!(tracks.matches(&transport, &tracker()));
+
+    tracks.clear();
+    assert!(tracks.is_empty());
+    let track = Track::new("localhost:app:**", ".*", ".*").unwrap();
+    tracks.add(&TrackRule::new(track, 0)).unwrap();
+    assert!(tracks.is_empty());
+    assert!(!tracks.matches(&wave(HttpMethod::Post), &tracker()));
+
+    // a ttl past the end of time does not overflow, the rule just never expires
+    let track = Track::new("localhost:app:**", ".*", ".*").unwrap();
+    tracks.add(&TrackRule::new(track, u64::MAX)).unwrap();
+    assert!(tracks.matches(&wave(HttpMethod::Post), &tracker()));
+}
+
 //#[test]
 fn test_publish() -> Result<(), CosmicErr> {
     let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -926,3 +1189,44 @@ fn test_mechtron() -> Result<(), CosmicErr> {
         Ok(())
     })
 }
+
+#[test]
+fn test_upload_limits() {
+    use crate::layer::shell::Uploads;
+    use cosmic_space::command::UploadChunk;
+    use cosmic_space::err::StatusErr;
+
+    let chunk = |id: &str, index: u16, total: u16, len: usize| UploadChunk {
+        id: id.to_string(),
+        index,
+        total,
+        content: Arc::new(vec![0u8; len]),
+    };
+
+    let uploads = Uploads::with_limits(8, 12, Duration::from_secs(60));
+    uploads.append(chunk("a", 0, 2, 4)).unwrap();
+    uploads.append(chunk("a", 1, 2, 4)).unwrap();
+    assert_eq!(uploads.take(&"a".to_string()).unwrap().unwrap().len(), 8);
+
+    // a chunk index past the total is refused
+    assert!(uploads.append(chunk("b", 2, 2, 1)).is_err());
+    assert!(uploads.append(chunk("b", 0, 0, 1)).is_err());
+
+    // one upload may not grow past its limit
+    uploads.append(chunk("c", 0, 3, 4)).unwrap();
+    uploads.append(chunk("c", 1, 3, 4)).unwrap();
+    assert_eq!(uploads.append(chunk("c", 2, 3, 1)).unwrap_err().status(), 413);
+    assert_eq!(uploads.bytes(), 0);
+
+    // nor may the uploads of one session together
+    uploads.append(chunk("d", 0, 2, 8)).unwrap();
+    assert_eq!(uploads.append(chunk("e", 0, 2, 5)).unwrap_err().status(), 413);
+    assert_eq!(uploads.bytes(), 8);
+
+    // uploads nobody touches expire
+    let uploads = Uploads::with_limits(8, 12, Duration::from_millis(10));
+    uploads.append(chunk("f", 0, 2, 4)).unwrap();
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(uploads.append(chunk("f", 1, 2, 4)).is_err());
+    assert_eq!(uploads.take(&"f".to_string()).unwrap(), None);
+}
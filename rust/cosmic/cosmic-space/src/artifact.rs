@@ -1,14 +1,19 @@
 use core::borrow::Borrow;
 use dashmap::DashMap;
 use std::cell::Cell;
+use std::any::Any;
+use std::collections::HashMap;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use serde::{Deserialize, Serialize};
 use tokio::sync::{RwLock, watch};
 
 use crate::config::mechtron::MechtronConfig;
 use crate::loc::{Point, ToSurface};
+use crate::selector::VersionReq;
 use crate::particle::Stub;
 use crate::substance::Bin;
 use crate::wave::core::cmd::CmdMethod;
@@ -19,7 +24,10 @@ use crate::{BindConfig, SpaceErr, Substance};
 
 pub mod synch;
 pub mod asynch;
+pub mod manifest;
 
+/// a handle to a cached artifact. While any ArtRef to an artifact is alive the artifact
+/// is pinned in the `ArtifactCache` and will not be evicted
 #[derive(Clone)]
 pub struct ArtRef<A> {
     artifact: Arc<A>,
@@ -58,9 +66,206 @@ impl<A> Deref for ArtRef<A> {
     }
 }
 
-impl<A> Drop for ArtRef<A> {
-    fn drop(&mut self) {
-        //
+/// the default max bytes held by an `ArtifactCache`
+pub const DEFAULT_ARTIFACT_CACHE_CAPACITY: usize = 256 * 1024 * 1024;
+
+struct CachedArtifact {
+    artifact: Arc<dyn Any + Send + Sync>,
+    size: usize,
+    last_access: u64,
+}
+
+impl CachedArtifact {
+    /// the cache holds one reference, any other reference is an ArtRef
+    fn pinned(&self) -> bool {
+        Arc::strong_count(&self.artifact) > 1
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ArtifactCacheStats {
+    pub entries: usize,
+    pub pinned: usize,
+    pub size: usize,
+    pub capacity: usize,
+}
+
+/// artifacts of any type bounded by their total byte size (the size of the Bin they
+/// were parsed from).  When the capacity is exceeded the least recently used
+/// artifacts that are not pinned by an `ArtRef` are evicted
+pub struct ArtifactCache {
+    entries: Mutex<HashMap<Point, CachedArtifact>>,
+    capacity: usize,
+    clock: AtomicU64,
+}
+
+impl ArtifactCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            capacity,
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get<A>(&self, point: &Point) -> Option<Arc<A>>
+    where
+        A: Send + Sync + 'static,
+    {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(point)?;
+        entry.last_access = self.clock.fetch_add(1, Ordering::Relaxed);
+        entry.artifact.clone().downcast::<A>().ok()
+    }
+
+    pub fn insert<A>(&self, point: &Point, artifact: Arc<A>, size: usize)
+    where
+        A: Send + Sync + 'static,
+    {
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(
+            point.clone(),
+            CachedArtifact {
+                artifact,
+                size,
+                last_access: self.clock.fetch_add(1, Ordering::Relaxed),
+            },
+        );
+        Self::evict(&mut entries, self.capacity);
+    }
+
+    fn evict(entries: &mut HashMap<Point, CachedArtifact>, capacity: usize) {
+        let mut size: usize = entries.values().map(|e| e.size).sum();
+        while size > capacity {
+            let lru = entries
+                .iter()
+                .filter(|(_, e)| !e.pinned())
+                .min_by_key(|(_, e)| e.last_access)
+                .map(|(point, _)| point.clone());
+            match lru {
+                Some(point) => {
+                    size -= entries.remove(&point).unwrap().size;
+                }
+                // everything left is in use
+                None => break,
+            }
+        }
+    }
+
+    /// drop every artifact of `bundle`
+    pub fn invalidate(&self, bundle: &Point) {
+        self.entries.lock().unwrap().retain(|point, _| {
+            point
+                .clone()
+                .to_bundle()
+                .map(|b| b != *bundle)
+                .unwrap_or(true)
+        });
+    }
+
+    pub fn stats(&self) -> ArtifactCacheStats {
+        let entries = self.entries.lock().unwrap();
+        ArtifactCacheStats {
+            entries: entries.len(),
+            pinned: entries.values().filter(|e| e.pinned()).count(),
+            size: entries.values().map(|e| e.size).sum(),
+            capacity: self.capacity,
+        }
+    }
+}
+
+/// a reference to an artifact whose bundle version may be a semver range
+/// i.e. `repo:hello-goodbye:^1.0:/config/x.mechtron` which resolves against the
+/// bundles published in the `repo:hello-goodbye` BundleSeries
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ArtifactReq {
+    Exact(Point),
+    Range {
+        series: Point,
+        version: VersionReq,
+        path: Option<String>,
+    },
+}
+
+impl ArtifactReq {
+    /// pick the highest of the published `versions` that satisfies this requirement
+    pub fn resolve(&self, versions: &Vec<semver::Version>) -> Result<Point, SpaceErr> {
+        match self {
+            ArtifactReq::Exact(point) => Ok(point.clone()),
+            ArtifactReq::Range {
+                series,
+                version,
+                path,
+            } => {
+                let resolved = versions
+                    .iter()
+                    .filter(|v| version.matches(v))
+                    .max()
+                    .ok_or(SpaceErr::not_found(format!(
+                        "no bundle in series {} matches {}",
+                        series.to_string(),
+                        version.to_string()
+                    )))?;
+                let mut point = format!("{}:{}", series.to_string(), resolved.to_string());
+                if let Some(path) = path {
+                    point.push_str(":/");
+                    point.push_str(path.as_str());
+                }
+                Point::from_str(point.as_str())
+            }
+        }
+    }
+
+    /// the BundleSeries this requirement resolves against (if it is a range)
+    pub fn series(&self) -> Option<&Point> {
+        match self {
+            ArtifactReq::Exact(_) => None,
+            ArtifactReq::Range { series, .. } => Some(series),
+        }
+    }
+}
+
+impl FromStr for ArtifactReq {
+    type Err = SpaceErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(point) = Point::from_str(s) {
+            return Ok(ArtifactReq::Exact(point));
+        }
+        let (bundle, path) = match s.split_once(":/") {
+            None => (s, None),
+            Some((bundle, path)) => (bundle, Some(path.to_string())),
+        };
+        let (series, version) = bundle.rsplit_once(':').ok_or(SpaceErr::bad_request(format!(
+            "expected a Point or a bundle version requirement: {}",
+            s
+        )))?;
+        Ok(ArtifactReq::Range {
+            series: Point::from_str(series)?,
+            version: VersionReq::from_str(version)?,
+            path,
+        })
+    }
+}
+
+impl ToString for ArtifactReq {
+    fn to_string(&self) -> String {
+        match self {
+            ArtifactReq::Exact(point) => point.to_string(),
+            ArtifactReq::Range {
+                series,
+                version,
+                path,
+            } => match path {
+                None => format!("{}:{}", series.to_string(), version.to_string()),
+                Some(path) => format!(
+                    "{}:{}:/{}",
+                    series.to_string(),
+                    version.to_string(),
+                    path
+                ),
+            },
+        }
     }
 }
 
@@ -82,3 +287,53 @@ pub struct ArtifactResponse {
     pub to: Point,
     pub payload: Bin,
 }
+
+#[cfg(test)]
+pub mod test {
+    use crate::artifact::{ArtifactCache, ArtifactReq};
+    use crate::loc::Point;
+    use core::str::FromStr;
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_artifact_req() {
+        let versions = vec![
+            semver::Version::from_str("1.0.0").unwrap(),
+            semver::Version::from_str("1.2.3").unwrap(),
+            semver::Version::from_str("2.0.0").unwrap(),
+        ];
+
+        let req = ArtifactReq::from_str("repo:hello-goodbye:^1.0:/config/x.mechtron").unwrap();
+        assert_eq!(
+            req.resolve(&versions).unwrap().to_string(),
+            "repo:hello-goodbye:1.2.3:/config/x.mechtron".to_string()
+        );
+
+        let req = ArtifactReq::from_str("repo:hello-goodbye:1.0.0:/config/x.mechtron").unwrap();
+        assert!(matches!(req, ArtifactReq::Exact(_)));
+
+        let req = ArtifactReq::from_str("repo:hello-goodbye:^3").unwrap();
+        assert!(req.resolve(&versions).is_err());
+    }
+
+    #[test]
+    pub fn test_artifact_cache_eviction() {
+        let cache = ArtifactCache::new(10);
+        let a = Point::from_str("repo:app:1.0.0:/a").unwrap();
+        let b = Point::from_str("repo:app:1.0.0:/b").unwrap();
+        let c = Point::from_str("repo:app:1.0.0:/c").unwrap();
+        cache.insert(&a, Arc::new(vec![0u8; 4]), 4);
+        cache.insert(&b, Arc::new(vec![0u8; 4]), 4);
+
+        // pin a & touch it so b is the least recently used
+        let pinned = cache.get::<Vec<u8>>(&a).unwrap();
+        cache.insert(&c, Arc::new(vec![0u8; 4]), 4);
+        assert!(cache.get::<Vec<u8>>(&a).is_some());
+        assert!(cache.get::<Vec<u8>>(&b).is_none());
+        assert!(cache.get::<Vec<u8>>(&c).is_some());
+
+        cache.invalidate(&Point::from_str("repo:app:1.0.0").unwrap());
+        assert_eq!(cache.stats().entries, 0);
+        drop(pinned);
+    }
+}
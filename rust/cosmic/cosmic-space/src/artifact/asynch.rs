@@ -1,21 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use dashmap::DashMap;
 use serde::Serialize;
-use tokio::sync::watch;
+use tokio::sync::{broadcast, watch};
 use crate::{Bin, BindConfig, Point, SpaceErr, Stub, Substance};
-use crate::artifact::ArtRef;
+use crate::artifact::{
+    ArtRef, ArtifactCache, ArtifactCacheStats, ArtifactReq, DEFAULT_ARTIFACT_CACHE_CAPACITY,
+};
 use crate::config::mechtron::MechtronConfig;
-use crate::loc::ToSurface;
+use crate::loc::{PointSeg, ToSurface};
 use crate::wave::core::cmd::CmdMethod;
 use crate::wave::DirectedProto;
 use crate::wave::exchange::asynch::ProtoTransmitter;
 
 #[derive(Clone)]
 pub struct ArtifactApi {
-    binds: Arc<DashMap<Point, Arc<BindConfig>>>,
-    mechtrons: Arc<DashMap<Point, Arc<MechtronConfig>>>,
-    wasm: Arc<DashMap<Point, Bin>>,
+    cache: Arc<ArtifactCache>,
+    resolutions: Arc<DashMap<ArtifactReq, Point>>,
+    invalidations: broadcast::Sender<Point>,
     fetcher_tx: Arc<watch::Sender<Arc<dyn ArtifactFetcher>>>,
     fetcher_rx: watch::Receiver<Arc<dyn ArtifactFetcher>>,
 }
@@ -27,12 +29,18 @@ impl ArtifactApi {
     }
 
     pub fn new(fetcher: Arc<dyn ArtifactFetcher>) -> Self {
+        Self::with_capacity(fetcher, DEFAULT_ARTIFACT_CACHE_CAPACITY)
+    }
+
+    /// `capacity` is the max bytes of artifacts held in the cache
+    pub fn with_capacity(fetcher: Arc<dyn ArtifactFetcher>, capacity: usize) -> Self {
         let (fetcher_tx, fetcher_rx) = watch::channel(fetcher);
         let fetcher_tx = Arc::new(fetcher_tx);
+        let (invalidations, _) = broadcast::channel(64);
         Self {
-            binds: Arc::new(DashMap::new()),
-            mechtrons: Arc::new(DashMap::new()),
-            wasm: Arc::new(DashMap::new()),
+            cache: Arc::new(ArtifactCache::new(capacity)),
+            resolutions: Arc::new(DashMap::new()),
+            invalidations,
             fetcher_tx,
             fetcher_rx,
         }
@@ -46,58 +54,82 @@ impl ArtifactApi {
         self.fetcher_rx.borrow().clone()
     }
 
-    pub async fn mechtron(&self, point: &Point) -> Result<ArtRef<MechtronConfig>, SpaceErr> {
-        {
-            if self.mechtrons.contains_key(point) {
-                let mechtron = self.mechtrons.get(point).unwrap().clone();
-                return Ok(ArtRef::new(mechtron, point.clone()));
-            }
-        }
+    pub fn cache_stats(&self) -> ArtifactCacheStats {
+        self.cache.stats()
+    }
 
-        let mechtron: Arc<MechtronConfig> = Arc::new(self.fetch(point).await?);
-        self.mechtrons.insert(point.clone(), mechtron.clone());
-        return Ok(ArtRef::new(mechtron, point.clone()));
+    pub async fn mechtron(&self, point: &Point) -> Result<ArtRef<MechtronConfig>, SpaceErr> {
+        self.get(point).await
     }
 
     pub async fn bind(&self, point: &Point) -> Result<ArtRef<BindConfig>, SpaceErr> {
-        {
-            if self.binds.contains_key(point) {
-                let bind = self.binds.get(point).unwrap().clone();
-                return Ok(ArtRef::new(bind, point.clone()));
-            }
-        }
-
-        let bind: Arc<BindConfig> = Arc::new(self.fetch(point).await?);
-        {
-            self.binds.insert(point.clone(), bind.clone());
-        }
-        return Ok(ArtRef::new(bind, point.clone()));
+        self.get(point).await
     }
 
     pub async fn wasm(&self, point: &Point) -> Result<ArtRef<Bin>, SpaceErr> {
-        {
-            if self.wasm.contains_key(point) {
-                let wasm = self.wasm.get(point).unwrap().clone();
-                return Ok(ArtRef::new(Arc::new(wasm), point.clone()));
-            }
+        if let Some(wasm) = self.cache.get(point) {
+            return Ok(ArtRef::new(wasm, point.clone()));
         }
 
         let wasm = self.get_fetcher().fetch(point).await?;
-        {
-            self.wasm.insert(point.clone(), wasm.clone());
-        }
-        return Ok(ArtRef::new(Arc::new(wasm), point.clone()));
+        let size = wasm.len();
+        let wasm = Arc::new(wasm);
+        self.cache.insert(point, wasm.clone(), size);
+        Ok(ArtRef::new(wasm, point.clone()))
     }
 
-    async fn fetch<A>(&self, point: &Point) -> Result<A, SpaceErr>
+    async fn get<A>(&self, point: &Point) -> Result<ArtRef<A>, SpaceErr>
     where
-        A: TryFrom<Bin, Error = SpaceErr>,
+        A: TryFrom<Bin, Error = SpaceErr> + Send + Sync + 'static,
     {
+        if let Some(artifact) = self.cache.get(point) {
+            return Ok(ArtRef::new(artifact, point.clone()));
+        }
+
         if !point.has_bundle() {
             return Err("point is not from a bundle".into());
         }
         let bin = self.get_fetcher().fetch(point).await?;
-        Ok(A::try_from(bin)?)
+        let size = bin.len();
+        let artifact = Arc::new(A::try_from(bin)?);
+        self.cache.insert(point, artifact.clone(), size);
+        Ok(ArtRef::new(artifact, point.clone()))
+    }
+
+    /// resolve `req` to an exact Point. Version ranges resolve to the highest matching
+    /// bundle published in the series and are cached until a bundle of that series is published
+    pub async fn resolve(&self, req: &ArtifactReq) -> Result<Point, SpaceErr> {
+        let series = match req.series() {
+            None => return req.resolve(&vec![]),
+            Some(series) => series,
+        };
+        if let Some(point) = self.resolutions.get(req) {
+            return Ok(point.clone());
+        }
+        let versions = self.get_fetcher().versions(series).await?;
+        let point = req.resolve(&versions)?;
+        self.resolutions.insert(req.clone(), point.clone());
+        Ok(point)
+    }
+
+    /// forget every cached artifact of `bundle` so the next request fetches it again
+    /// and notify every subscriber of `invalidations()`.
+    /// called when a bundle is (re)published or deleted. ArtRefs already handed
+    /// out remain valid but are no longer shared with new requests
+    pub fn invalidate(&self, bundle: &Point) {
+        self.cache.invalidate(bundle);
+        // a newly published bundle may be a better match for a version range
+        if let Some(series) = bundle.parent() {
+            self.resolutions
+                .retain(|req, _| req.series() != Some(&series));
+        }
+        self.invalidations.send(bundle.clone()).unwrap_or_default();
+    }
+
+    /// bundles that have been invalidated, for components that keep
+    /// their own caches derived from artifacts
+    pub fn invalidations(&self) -> broadcast::Receiver<Point> {
+        self.invalidations.subscribe()
     }
 }
 
@@ -115,6 +147,14 @@ impl FetchChamber {
 pub trait ArtifactFetcher: Send + Sync {
     async fn stub(&self, point: &Point) -> Result<Stub, SpaceErr>;
     async fn fetch(&self, point: &Point) -> Result<Bin, SpaceErr>;
+
+    /// versions of the bundles published in BundleSeries `series`
+    async fn versions(&self, series: &Point) -> Result<Vec<semver::Version>, SpaceErr> {
+        Err(SpaceErr::not_found(format!(
+            "cannot list the bundles of series {}",
+            series.to_string()
+        )))
+    }
 }
 
 pub struct NoDiceArtifactFetcher;
@@ -176,6 +216,20 @@ impl ArtifactFetcher for MapFetcher {
         let rtn = self.map.get(point).ok_or(SpaceErr::not_found(format!("could not find {}",point.to_string())))?;
         Ok(rtn.clone())
     }
+
+    async fn versions(&self, series: &Point) -> Result<Vec<semver::Version>, SpaceErr> {
+        let mut versions = HashSet::new();
+        for point in self.map.keys() {
+            if let Ok(bundle) = point.clone().to_bundle() {
+                if bundle.parent().as_ref() == Some(series) {
+                    if let Some(PointSeg::Version(version)) = bundle.last_segment() {
+                        versions.insert(version.version);
+                    }
+                }
+            }
+        }
+        Ok(versions.into_iter().collect())
+    }
 }
 
 
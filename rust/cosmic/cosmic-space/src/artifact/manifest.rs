@@ -0,0 +1,219 @@
+use crate::kind::ArtifactSubKind;
+use crate::SpaceErr;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// path of the manifest within a bundle zip
+pub const MANIFEST_PATH: &str = "bundle.manifest";
+
+/// path of the detached ed25519 signature (hex) of the manifest within a bundle zip
+pub const MANIFEST_SIGNATURE_PATH: &str = "bundle.manifest.sig";
+
+/// prefix of a manifest line that declares the `ArtifactSubKind` of a file:
+/// `#kind <SubKind> <path>`
+pub const MANIFEST_KIND_DIRECTIVE: &str = "#kind";
+
+/// lists the SHA-256 digest of every file in a bundle, one `<digest>  <path>` per line
+/// (the same format `sha256sum` produces).  Files whose extension does not imply their
+/// kind may be typed with a `#kind` line, any other line starting with `#` is a comment
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct BundleManifest {
+    pub digests: BTreeMap<String, String>,
+    pub kinds: BTreeMap<String, ArtifactSubKind>,
+}
+
+impl BundleManifest {
+    pub fn digest(bin: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bin))
+    }
+
+    /// create a manifest for `files` (the manifest & signature themselves are skipped)
+    pub fn create(files: &BTreeMap<String, Vec<u8>>) -> Self {
+        let digests = files
+            .iter()
+            .filter(|(path, _)| !Self::is_reserved(path))
+            .map(|(path, bin)| (path.clone(), Self::digest(bin)))
+            .collect();
+        Self {
+            digests,
+            kinds: BTreeMap::new(),
+        }
+    }
+
+    /// the declared kind of `path` or else the kind implied by its extension
+    pub fn kind(&self, path: &str) -> ArtifactSubKind {
+        self.kinds
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| ArtifactSubKind::from_path(path))
+    }
+
+    pub fn is_reserved(path: &str) -> bool {
+        path == MANIFEST_PATH || path == MANIFEST_SIGNATURE_PATH
+    }
+
+    pub fn parse(manifest: &str) -> Result<Self, SpaceErr> {
+        let mut digests = BTreeMap::new();
+        let mut kinds = BTreeMap::new();
+        for (index, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(directive) = line.strip_prefix(MANIFEST_KIND_DIRECTIVE) {
+                let (kind, path) = directive.trim().split_once(char::is_whitespace).ok_or(
+                    SpaceErr::bad_request(format!(
+                        "malformed manifest line {}: expected '{} <SubKind> <path>'",
+                        index + 1,
+                        MANIFEST_KIND_DIRECTIVE
+                    )),
+                )?;
+                let kind = ArtifactSubKind::from_str(kind).map_err(|_| {
+                    SpaceErr::bad_request(format!(
+                        "unknown ArtifactSubKind '{}' in manifest line {}",
+                        kind,
+                        index + 1
+                    ))
+                })?;
+                if kind == ArtifactSubKind::Dir {
+                    return Err(SpaceErr::bad_request(format!(
+                        "manifest line {} cannot declare a file as a Dir",
+                        index + 1
+                    )));
+                }
+                kinds.insert(path.trim().to_string(), kind);
+                continue;
+            }
+            if line.starts_with('#') {
+                continue;
+            }
+            let (digest, path) = line.split_once(char::is_whitespace).ok_or(
+                SpaceErr::bad_request(format!("malformed manifest line {}: '{}'", index + 1, line)),
+            )?;
+            let path = path.trim().trim_start_matches('*');
+            if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(SpaceErr::bad_request(format!(
+                    "malformed SHA-256 digest for '{}' in manifest",
+                    path
+                )));
+            }
+            digests.insert(path.to_string(), digest.to_lowercase());
+        }
+        Ok(Self { digests, kinds })
+    }
+
+    /// every file must be listed with a matching digest, every listed file must be present
+    /// and a `#kind` may only type a listed file
+    pub fn verify(&self, files: &BTreeMap<String, Vec<u8>>) -> Result<(), SpaceErr> {
+        for path in self.kinds.keys() {
+            if !self.digests.contains_key(path) {
+                return Err(SpaceErr::bad_request(format!(
+                    "'{}' has a {} in the bundle manifest but no digest",
+                    path, MANIFEST_KIND_DIRECTIVE
+                )));
+            }
+        }
+        for (path, bin) in files.iter().filter(|(path, _)| !Self::is_reserved(path)) {
+            match self.digests.get(path) {
+                None => {
+                    return Err(SpaceErr::bad_request(format!(
+                        "'{}' is not listed in the bundle manifest",
+                        path
+                    )))
+                }
+                Some(digest) if *digest != Self::digest(bin) => {
+                    return Err(SpaceErr::bad_request(format!(
+                        "digest mismatch for '{}' (the bundle has been tampered with or corrupted)",
+                        path
+                    )))
+                }
+                Some(_) => {}
+            }
+        }
+        for path in self.digests.keys() {
+            if !files.contains_key(path) {
+                return Err(SpaceErr::bad_request(format!(
+                    "'{}' is listed in the bundle manifest but missing from the bundle",
+                    path
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToString for BundleManifest {
+    fn to_string(&self) -> String {
+        let mut rtn = String::new();
+        for (path, digest) in &self.digests {
+            rtn.push_str(format!("{}  {}\n", digest, path).as_str());
+        }
+        for (path, kind) in &self.kinds {
+            rtn.push_str(
+                format!("{} {} {}\n", MANIFEST_KIND_DIRECTIVE, kind.to_string(), path).as_str(),
+            );
+        }
+        rtn
+    }
+}
+
+pub fn from_hex(hex: &str) -> Result<Vec<u8>, SpaceErr> {
+    let hex = hex.trim();
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return Err(SpaceErr::bad_request(
+            "hex string must be ascii with an even length",
+        ));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| SpaceErr::bad_request(format!("invalid hex: '{}'", hex)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::artifact::manifest::{BundleManifest, MANIFEST_PATH};
+    use crate::kind::ArtifactSubKind;
+    use std::collections::BTreeMap;
+
+    #[test]
+    pub fn test_manifest() {
+        let mut files = BTreeMap::new();
+        files.insert("bind/app.bind".to_string(), b"Bind(version=1.0.0){}".to_vec());
+        files.insert("wasm/app.wasm".to_string(), vec![0, 97, 115, 109]);
+        let manifest = BundleManifest::create(&files);
+        files.insert(MANIFEST_PATH.to_string(), manifest.to_string().into_bytes());
+
+        let parsed = BundleManifest::parse(manifest.to_string().as_str()).unwrap();
+        assert_eq!(parsed, manifest);
+        parsed.verify(&files).unwrap();
+
+        files.insert("wasm/app.wasm".to_string(), vec![0, 97, 115, 110]);
+        assert!(parsed.verify(&files).is_err());
+
+        let mut manifest = manifest;
+        manifest
+            .kinds
+            .insert("config/app".to_string(), ArtifactSubKind::ParticleConfig);
+        let parsed = BundleManifest::parse(manifest.to_string().as_str()).unwrap();
+        assert_eq!(parsed, manifest);
+        assert_eq!(parsed.kind("config/app"), ArtifactSubKind::ParticleConfig);
+        assert_eq!(parsed.kind("bind/app.bind"), ArtifactSubKind::Bind);
+        assert_eq!(parsed.kind("html/index.html"), ArtifactSubKind::Raw);
+        assert!(BundleManifest::parse("#kind Dir config/app").is_err());
+
+        // 'config/app' is typed but has no digest
+        files.insert("wasm/app.wasm".to_string(), vec![0, 97, 115, 109]);
+        assert!(parsed.verify(&files).is_err());
+        files.insert("config/app".to_string(), b"config".to_vec());
+        let mut manifest = BundleManifest::create(&files);
+        manifest
+            .kinds
+            .insert("config/app".to_string(), ArtifactSubKind::ParticleConfig);
+        manifest.verify(&files).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+use std::io::{ErrorKind, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::err::SpaceErr;
+use crate::hyper::{at_revision, PROTOCOL};
+use crate::selector::Selector;
+use crate::wasm::Timestamp;
+use crate::wave::core::Method;
+use crate::wave::UltraWave;
+
+/// the first bytes of every capture, followed by the big endian `u16` `CAPTURE_VERSION`
+/// & the big endian `u16` protocol revision whose layout the records are written in
+pub const CAPTURE_MAGIC: &[u8; 14] = b"COSMIC-CAPTURE";
+pub const CAPTURE_VERSION: u16 = 2;
+
+/// which waves a capture records, `None` records any
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CaptureFilter {
+    /// matches when the sender or the single recipient is selected
+    pub surfaces: Option<Selector>,
+    /// reflected waves have no method and never match
+    pub methods: Option<Vec<Method>>,
+}
+
+impl CaptureFilter {
+    pub fn matches(&self, wave: &UltraWave) -> bool {
+        let surfaces = match &self.surfaces {
+            None => true,
+            Some(selector) => {
+                selector.matches_point(&wave.from().point)
+                    || wave
+                        .to()
+                        .single_or()
+                        .map_or(false, |to| selector.matches_point(&to.point))
+            }
+        };
+        let methods = match &self.methods {
+            None => true,
+            Some(methods) => wave
+                .method()
+                .map_or(false, |method| methods.contains(method)),
+        };
+        surfaces && methods
+    }
+}
+
+/// a wave as it crossed `site` (a hyperlane or a star layer)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRecord {
+    pub timestamp: Timestamp,
+    pub site: String,
+    pub wave: UltraWave,
+}
+
+pub fn write_capture_header<W: Write>(write: &mut W) -> Result<(), SpaceErr> {
+    write.write_all(CAPTURE_MAGIC)?;
+    write.write_all(&CAPTURE_VERSION.to_be_bytes())?;
+    write.write_all(&PROTOCOL.max.to_be_bytes())?;
+    Ok(())
+}
+
+/// a big endian `u32` length followed by that many bytes of bincode in the layout of
+/// the latest protocol revision
+pub fn write_capture_record<W: Write>(
+    write: &mut W,
+    record: &CaptureRecord,
+) -> Result<(), SpaceErr> {
+    let data = at_revision(PROTOCOL.max, || bincode::serialize(record))?;
+    write.write_all(&(data.len() as u32).to_be_bytes())?;
+    write.write_all(data.as_slice())?;
+    Ok(())
+}
+
+/// the records of a capture in the order they were written.  A record cut short at
+/// the end (a capture that is still being written) ends the capture.  A capture
+/// recorded at a protocol revision this build does not speak is refused
+pub struct CaptureReader<R: Read> {
+    read: R,
+    revision: u16,
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub fn new(mut read: R) -> Result<Self, SpaceErr> {
+        let mut magic = [0u8; 14];
+        read.read_exact(&mut magic)?;
+        if &magic != CAPTURE_MAGIC {
+            return Err(SpaceErr::bad_request("not a cosmic capture"));
+        }
+        let mut version = [0u8; 2];
+        read.read_exact(&mut version)?;
+        let version = u16::from_be_bytes(version);
+        if version != CAPTURE_VERSION {
+            return Err(SpaceErr::bad_request(format!(
+                "capture version {} is not supported (expected {})",
+                version, CAPTURE_VERSION
+            )));
+        }
+        let mut revision = [0u8; 2];
+        read.read_exact(&mut revision)?;
+        let revision = u16::from_be_bytes(revision);
+        if !PROTOCOL.contains(revision) {
+            return Err(SpaceErr::bad_request(format!(
+                "capture was recorded at protocol revision {} (expected {} to {})",
+                revision, PROTOCOL.min, PROTOCOL.max
+            )));
+        }
+        Ok(Self { read, revision })
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord, SpaceErr>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut len = [0u8; 4];
+        match self.read.read_exact(&mut len) {
+            Ok(_) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return None,
+            Err(err) => return Some(Err(err.into())),
+        }
+        let mut data = vec![0u8; u32::from_be_bytes(len) as usize];
+        match self.read.read_exact(&mut data) {
+            Ok(_) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return None,
+            Err(err) => return Some(Err(err.into())),
+        }
+        Some(
+            at_revision(self.revision, || bincode::deserialize(data.as_slice()))
+                .map_err(|e| e.into()),
+        )
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::str::FromStr;
+
+    use crate::capture::{
+        write_capture_header, write_capture_record, CaptureFilter, CaptureReader, CaptureRecord,
+    };
+    use crate::loc::{Point, ToSurface};
+    use crate::selector::Selector;
+    use crate::substance::Substance;
+    use crate::wasm::Timestamp;
+    use crate::wave::core::ext::ExtMethod;
+    use crate::wave::core::Method;
+    use crate::wave::DirectedProto;
+
+    #[test]
+    pub fn test_capture() {
+        let mut hello = DirectedProto::ping();
+        hello.to(Point::from_str("fae").unwrap().to_surface());
+        hello.from(Point::from_str("less").unwrap().to_surface());
+        hello.method(ExtMethod::new("Hello").unwrap());
+        hello.body(Substance::Empty);
+        let wave = hello.build().unwrap().to_ultra();
+
+        let filter = CaptureFilter {
+            surfaces: Some(Selector::from_str("fae").unwrap()),
+            methods: Some(vec![Method::Ext(ExtMethod::new("Hello").unwrap())]),
+        };
+        assert!(filter.matches(&wave));
+        let filter = CaptureFilter {
+            surfaces: None,
+            methods: Some(vec![Method::Ext(ExtMethod::new("Bye").unwrap())]),
+        };
+        assert!(!filter.matches(&wave));
+
+        let mut capture = vec![];
+        write_capture_header(&mut capture).unwrap();
+        for millis in 0..2 {
+            let record = CaptureRecord {
+                timestamp: Timestamp::new(millis),
+                site: "less<Inbound>".to_string(),
+                wave: wave.clone(),
+            };
+            write_capture_record(&mut capture, &record).unwrap();
+        }
+        // a record that was still being written when the capture was read
+        capture.extend_from_slice(&[0, 0, 1]);
+
+        let records: Vec<CaptureRecord> = CaptureReader::new(capture.as_slice())
+            .unwrap()
+            .map(|record| record.unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].timestamp.millis, 1);
+        assert_eq!(records[1].wave.id(), wave.id());
+
+        assert!(CaptureReader::new(&b"COSMIC-CAPTURE\0\x01\0\x02"[..]).is_err());
+        // a revision this build does not speak
+        assert!(CaptureReader::new(&b"COSMIC-CAPTURE\0\x02\0\x09"[..]).is_err());
+        assert!(CaptureReader::new(&b"COSMIC-CAPTURE\0\x02\0\x02"[..]).is_ok());
+    }
+}
@@ -2,6 +2,7 @@ use core::str::FromStr;
 
 use nom::combinator::all_consuming;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 use cosmic_macros_primitive::Autobox;
 use cosmic_nom::{new_span, Trace};
@@ -13,6 +14,7 @@ use direct::select::{SelectCtx, SelectVar};
 use direct::set::{Set, SetCtx, SetVar};
 use direct::write::{Write, WriteCtx, WriteVar};
 
+use crate::loc::Point;
 use crate::parse::error::result;
 use crate::parse::{command_line, Env};
 use crate::substance::{Bin, ChildSubstance};
@@ -712,12 +714,15 @@ pub mod direct {
         pub enum SelectIntoSubstance {
             Stubs,
             Points,
+            /// a Map of each `stub` & its `location` (the location is attached by the executor
+            /// since only the registry knows it)
+            Records,
         }
 
         impl SelectIntoSubstance {
             pub fn to_primitive(&self, stubs: Vec<Stub>) -> Result<SubstanceList, SpaceErr> {
                 match self {
-                    SelectIntoSubstance::Stubs => {
+                    SelectIntoSubstance::Stubs | SelectIntoSubstance::Records => {
                         let stubs: Vec<Box<Substance>> = stubs
                             .into_iter()
                             .map(|stub| Box::new(Substance::Stub(stub)))
@@ -1116,6 +1121,14 @@ pub struct CommandTemplate {
 pub struct RawCommand {
     pub line: String,
     pub transfers: Vec<CmdTransfer>,
+    /// the point relative points (`.:child`, `..`) in `line` are resolved against.
+    /// `None` means the working point of the session
+    #[serde(
+        default,
+        serialize_with = "crate::hyper::since2::serialize",
+        deserialize_with = "crate::hyper::since2::deserialize"
+    )]
+    pub working: Option<Point>,
 }
 
 impl RawCommand {
@@ -1126,8 +1139,14 @@ impl RawCommand {
         Self {
             line: line.to_string(),
             transfers: vec![],
+            working: None,
         }
     }
+
+    pub fn with_working(mut self, working: Point) -> Self {
+        self.working = Some(working);
+        self
+    }
 }
 
 impl ToString for RawCommand {
@@ -1150,3 +1169,87 @@ impl CmdTransfer {
         }
     }
 }
+
+/// the largest piece of a transfer sent in one wave. Larger transfers are uploaded to the
+/// cli session as a series of `UploadChunk`s before the command referencing them is sent
+pub const UPLOAD_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// one piece of a transfer too large to travel inside a single `RawCommand`.
+/// Chunks are sent to a cli session in order; a `CmdTransfer` with the same `id` and
+/// empty content then refers to the assembled upload
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct UploadChunk {
+    pub id: String,
+    pub index: u16,
+    pub total: u16,
+    pub content: Bin,
+}
+
+impl UploadChunk {
+    /// split `content` into chunks of at most `UPLOAD_CHUNK_SIZE` bytes
+    pub fn split<N: ToString>(id: N, content: &[u8]) -> Result<Vec<Self>, SpaceErr> {
+        let id = id.to_string();
+        let pieces: Vec<&[u8]> = content.chunks(UPLOAD_CHUNK_SIZE).collect();
+        let total: u16 = pieces.len().try_into().map_err(|_| {
+            SpaceErr::bad_request(format!(
+                "transfer '{}' is too large ({} bytes)",
+                id,
+                content.len()
+            ))
+        })?;
+        Ok(pieces
+            .into_iter()
+            .enumerate()
+            .map(|(index, piece)| Self {
+                id: id.clone(),
+                index: index as u16,
+                total,
+                content: Arc::new(piece.to_vec()),
+            })
+            .collect())
+    }
+
+    pub fn to_bin(&self) -> Result<Bin, SpaceErr> {
+        Ok(Arc::new(bincode::serialize(self)?))
+    }
+
+    pub fn from_bin(bin: &[u8]) -> Result<Self, SpaceErr> {
+        Ok(bincode::deserialize(bin)?)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::command::{UploadChunk, UPLOAD_CHUNK_SIZE};
+
+    #[test]
+    pub fn test_upload_chunk_split() {
+        let content: Vec<u8> = (0..UPLOAD_CHUNK_SIZE * 2 + 7).map(|i| i as u8).collect();
+        let chunks = UploadChunk::split("upload", content.as_slice()).unwrap();
+        assert_eq!(chunks.len(), 3);
+        for (index, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.id, "upload".to_string());
+            assert_eq!(chunk.index as usize, index);
+            assert_eq!(chunk.total, 3);
+        }
+        assert_eq!(chunks[0].content.len(), UPLOAD_CHUNK_SIZE);
+        assert_eq!(chunks[2].content.len(), 7);
+
+        let assembled: Vec<u8> = chunks
+            .iter()
+            .flat_map(|chunk| chunk.content.iter().cloned())
+            .collect();
+        assert_eq!(assembled, content);
+
+        assert!(UploadChunk::split("empty", &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_upload_chunk_bin() {
+        let chunks = UploadChunk::split("upload", b"hello").unwrap();
+        let chunk = chunks.first().unwrap();
+        let bin = chunk.to_bin().unwrap();
+        assert_eq!(&UploadChunk::from_bin(bin.as_slice()).unwrap(), chunk);
+        assert!(UploadChunk::from_bin(&bin[..bin.len() - 1]).is_err());
+    }
+}
@@ -8,6 +8,7 @@ use crate::particle::{Details, Stub};
 use crate::BindConfig;
 
 pub mod bind;
+pub mod check;
 pub mod mechtron;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
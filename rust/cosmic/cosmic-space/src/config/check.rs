@@ -0,0 +1,563 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use nom::bytes::complete::tag;
+use nom::character::complete::multispace0;
+use nom::combinator::opt;
+use nom::multi::many0;
+use nom::sequence::tuple;
+
+use cosmic_nom::{new_span, span_with_extra, Res, Span};
+
+use crate::command::direct::create::{CreateVar, PointSegTemplate};
+use crate::command::CommandVar;
+use crate::config::mechtron::WasmLimits;
+use crate::err::{ParseErrs, SpaceErr};
+use crate::kind::BaseKind;
+use crate::loc::{Point, Version};
+use crate::parse::error::result;
+use crate::parse::model::{MethodScope, RouteScope, WaveScope};
+use crate::parse::{
+    bind_config, lex_child_scopes, lex_root_scope, lex_scopes, mechtron_config, nospace1,
+    script_lines, skewer, strip_comments,
+};
+use crate::util::ValuePattern;
+
+/// how deep each `{` block of a config is indented by `format`
+const INDENT: &str = "    ";
+
+/// the documents `check` & `format` understand
+#[derive(Debug, Clone, Copy, Eq, PartialEq, strum_macros::Display)]
+pub enum DocKind {
+    Bind,
+    Mechtron,
+    Script,
+}
+
+impl DocKind {
+    /// the kind of `src` implied by the extension of `path` or else by its root scope
+    pub fn detect(path: &str, src: &str) -> Self {
+        match path.rsplit('.').next() {
+            Some("bind") => DocKind::Bind,
+            Some("mechtron") => DocKind::Mechtron,
+            _ => {
+                let head = src
+                    .lines()
+                    .map(|line| line.trim())
+                    .find(|line| !line.is_empty() && !line.starts_with('#'))
+                    .unwrap_or_default();
+                if head.starts_with("Bind(") {
+                    DocKind::Bind
+                } else if head.starts_with("Mechtron(") {
+                    DocKind::Mechtron
+                } else {
+                    DocKind::Script
+                }
+            }
+        }
+    }
+}
+
+/// parse `src` and validate what the parser alone lets through: route scopes that can
+/// never be selected, unknown or missing `Wasm` keys and point templates that cannot be
+/// created.  Every problem found is reported in the returned `ParseErrs`
+pub fn check(kind: DocKind, src: &str) -> Result<(), SpaceErr> {
+    let source = Arc::new(src.to_string());
+    let errs = match kind {
+        DocKind::Bind => check_bind(src),
+        DocKind::Mechtron => check_mechtron(src),
+        DocKind::Script => check_script(src),
+    };
+    if errs.is_empty() {
+        return Ok(());
+    }
+    let errs: Vec<ParseErrs> = errs
+        .into_iter()
+        .map(|err| match err {
+            SpaceErr::ParseErrs(errs) => errs,
+            // a Status carries no location so it is reported at the top of the document
+            SpaceErr::Status { message, .. } => {
+                ParseErrs::from_range(message.as_str(), "here", 0..0, source.clone()).into()
+            }
+        })
+        .collect();
+    let mut errs = ParseErrs::fold(errs);
+    errs.source = Some(source);
+    Err(errs.into())
+}
+
+/// re-emit a valid document in canonical form: configs are indented by block depth with
+/// blank lines collapsed and scripts get one statement per line.  Comments are kept
+pub fn format(kind: DocKind, src: &str) -> Result<String, SpaceErr> {
+    check(kind, src)?;
+    let formatted = match kind {
+        DocKind::Bind | DocKind::Mechtron => format_config(src),
+        DocKind::Script => format_script(src)?,
+    };
+    // formatting must never turn a valid document into an invalid one
+    check(kind, formatted.as_str()).map_err(|_| {
+        SpaceErr::server_error(format!("formatting changed the meaning of this {}", kind))
+    })?;
+    Ok(formatted)
+}
+
+fn check_bind(src: &str) -> Vec<SpaceErr> {
+    let config = match bind_config(src) {
+        Ok(config) => config,
+        Err(err) => return vec![err],
+    };
+    match unreachable_routes(src, config.route_scopes()) {
+        Ok(errs) => errs,
+        Err(err) => vec![err],
+    }
+}
+
+/// the first matching method scope of a BindConfig wins, so a scope following one that
+/// matches every wave of its parent can never be selected
+fn unreachable_routes(src: &str, routes: Vec<&RouteScope>) -> Result<Vec<SpaceErr>, SpaceErr> {
+    let (_, stripped) = strip_comments(new_span(src))?;
+    let span = span_with_extra(stripped.as_str(), Arc::new(src.to_string()));
+    let root = lex_root_scope(span)?;
+    let lex_routes = lex_scopes(root.block.content)?;
+
+    let mut errs = vec![];
+    let mut shadowed = false;
+    for (lex_route, route) in lex_routes.into_iter().zip(routes) {
+        if shadowed {
+            errs.push(ParseErrs::from_loc_span(
+                "unreachable route: an earlier route matches every wave",
+                "never selected",
+                lex_route.selector.name,
+            ));
+            continue;
+        }
+        let lex_route = lex_child_scopes(lex_route)?;
+        let mut route_catches_all = false;
+        for (lex_message, message) in lex_route.block.into_iter().zip(route.block.iter()) {
+            if route_catches_all {
+                errs.push(ParseErrs::from_loc_span(
+                    "unreachable scope: an earlier scope of this route matches every wave",
+                    "never selected",
+                    lex_message.selector.name,
+                ));
+                continue;
+            }
+            let lex_message = lex_child_scopes(lex_message)?;
+            let mut message_catches_all = false;
+            for (lex_method, method) in lex_message.block.into_iter().zip(message.block.iter()) {
+                if message_catches_all {
+                    errs.push(ParseErrs::from_loc_span(
+                        "unreachable scope: an earlier method scope matches every method",
+                        "never selected",
+                        lex_method.selector.name,
+                    ));
+                    continue;
+                }
+                message_catches_all = method_catches_all(method);
+            }
+            route_catches_all = message_catches_all && message_catches_all_kinds(message);
+        }
+        shadowed = route_catches_all
+            && route.selector.filters.is_empty()
+            && route.selector.selector.path.as_str() == ".*";
+    }
+    Ok(errs)
+}
+
+fn method_catches_all(method: &MethodScope) -> bool {
+    matches!(method.selector.selector.name, ValuePattern::Any)
+        && method.selector.filters.is_empty()
+        && method.selector.selector.path.as_str() == ".*"
+}
+
+fn message_catches_all_kinds(message: &WaveScope) -> bool {
+    matches!(message.selector.selector.name, ValuePattern::Any)
+        && message.selector.filters.is_empty()
+        && message.selector.selector.path.as_str() == ".*"
+}
+
+/// the `Wasm` keyword followed by the key & value spans of its assignments
+fn wasm_scope<I: Span>(input: I) -> Res<I, (I, Vec<(I, I)>)> {
+    tuple((
+        multispace0,
+        tag("Wasm"),
+        multispace0,
+        tag("{"),
+        many0(tuple((
+            multispace0,
+            skewer,
+            multispace0,
+            tag("="),
+            multispace0,
+            nospace1,
+            multispace0,
+            opt(tag(";")),
+        ))),
+        multispace0,
+        tag("}"),
+    ))(input)
+    .map(|(next, (_, wasm, _, _, assignments, _, _))| {
+        let assignments = assignments
+            .into_iter()
+            .map(|(_, key, _, _, _, value, _, _)| (key, value))
+            .collect();
+        (next, (wasm, assignments))
+    })
+}
+
+fn check_mechtron(src: &str) -> Vec<SpaceErr> {
+    let stripped = match strip_comments(new_span(src)) {
+        Ok((_, stripped)) => stripped,
+        Err(err) => return vec![err.into()],
+    };
+    let span = span_with_extra(stripped.as_str(), Arc::new(src.to_string()));
+    let scope = lex_root_scope(span)
+        .ok()
+        .and_then(|root| result(wasm_scope(root.block.content)).ok());
+    let (wasm, assignments) = match scope {
+        Some(scope) => scope,
+        // the parser reports what is wrong with the structure of the document
+        None => {
+            return match mechtron_config(src) {
+                Ok(_) => vec![],
+                Err(err) => vec![err],
+            }
+        }
+    };
+
+    let mut errs = vec![];
+    let mut keys = HashSet::new();
+    let mut limits = WasmLimits::default();
+    for (key, value) in assignments {
+        let name = key.to_string();
+        if !keys.insert(name.clone()) {
+            errs.push(ParseErrs::from_loc_span(
+                format!("'{}' is assigned more than once", name).as_str(),
+                "duplicate key",
+                key.clone(),
+            ));
+        }
+        match name.as_str() {
+            "bin" => {
+                if let Err(err) = Point::from_str(value.to_string().as_str()) {
+                    errs.push(ParseErrs::from_loc_span(
+                        format!("invalid wasm point: {}", err.to_string()).as_str(),
+                        "expected a point i.e. repo:my-app:1.0.0:/wasm/my-app.wasm",
+                        value,
+                    ));
+                }
+            }
+            "name" => {}
+            limit if WasmLimits::KEYS.contains(&limit) => {
+                if let Err(err) = limits.set(limit, value.to_string().as_str()) {
+                    errs.push(ParseErrs::from_loc_span(
+                        err.to_string().as_str(),
+                        "invalid limit",
+                        value,
+                    ));
+                }
+            }
+            unknown => errs.push(ParseErrs::from_loc_span(
+                format!("unknown Wasm key '{}'", unknown).as_str(),
+                format!("expected one of: bin, name, {}", WasmLimits::KEYS.join(", ")).as_str(),
+                key,
+            )),
+        }
+    }
+    for required in ["bin", "name"] {
+        if !keys.contains(required) {
+            errs.push(ParseErrs::from_loc_span(
+                format!("missing required key '{}'", required).as_str(),
+                "in this Wasm scope",
+                wasm.clone(),
+            ));
+        }
+    }
+
+    if errs.is_empty() {
+        if let Err(err) = mechtron_config(src) {
+            errs.push(err);
+        }
+    }
+    errs
+}
+
+fn check_script(src: &str) -> Vec<SpaceErr> {
+    let lines = match script_lines(src) {
+        Ok(lines) => lines,
+        Err(err) => return vec![err],
+    };
+    let source = Arc::new(src.to_string());
+    let mut errs = vec![];
+    for line in lines {
+        let range = line.offset..line.offset + line.text.len();
+        match CommandVar::from_str(line.text.as_str()) {
+            Ok(CommandVar::Create(create)) => {
+                if let Err((message, label)) = check_template(&create) {
+                    errs.push(ParseErrs::from_range(
+                        message.as_str(),
+                        label,
+                        range,
+                        source.clone(),
+                    ));
+                }
+            }
+            Ok(_) => {}
+            Err(err) => errs.push(ParseErrs::from_range(
+                err.to_string().as_str(),
+                "invalid command",
+                range,
+                source.clone(),
+            )),
+        }
+    }
+    errs
+}
+
+/// the segment a particle is created at must suit its kind
+fn check_template(create: &CreateVar) -> Result<(), (String, &'static str)> {
+    let point = &create.template.point;
+    let kind = &create.template.kind.base;
+    let child = match &point.child_segment_template {
+        PointSegTemplate::Exact(child) => child.trim_end_matches('%'),
+        PointSegTemplate::Pattern(pattern) => pattern.as_str(),
+        PointSegTemplate::Root => {
+            return Err((
+                format!("a {} cannot be created at the root", kind.to_string()),
+                "invalid point template",
+            ))
+        }
+    };
+    let at_root = point.parent.is_root();
+    match kind {
+        BaseKind::Root => Err((
+            "the Root particle cannot be created".to_string(),
+            "invalid point template",
+        )),
+        BaseKind::Space if !at_root => Err((
+            format!(
+                "a Space must be created at the root, not under '{}'",
+                point.parent.to_string()
+            ),
+            "invalid point template",
+        )),
+        BaseKind::Space => Ok(()),
+        _ if at_root => Err((
+            format!(
+                "only a Space can be created at the root (i.e. create localhost<Space>), not a {}",
+                kind.to_string()
+            ),
+            "invalid point template",
+        )),
+        BaseKind::Bundle => match Version::from_str(child) {
+            Ok(_) => Ok(()),
+            Err(_) => Err((
+                format!(
+                    "a Bundle is created at a version i.e. repo:my-app:1.0.0, not '{}'",
+                    child
+                ),
+                "expected a version",
+            )),
+        },
+        BaseKind::File => Ok(()),
+        _ if Version::from_str(child).is_ok() => Err((
+            format!(
+                "a {} cannot be created at version segment '{}' (only a Bundle can)",
+                kind.to_string(),
+                child
+            ),
+            "invalid point template",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// `s` with runs of whitespace outside of double quotes collapsed to one space
+fn collapse(s: &str) -> String {
+    let mut rtn = String::with_capacity(s.len());
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut space = false;
+    for c in s.trim().chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            _ => {}
+        }
+        if c.is_whitespace() && !quoted {
+            space = true;
+            continue;
+        }
+        if space {
+            rtn.push(' ');
+            space = false;
+        }
+        rtn.push(c);
+    }
+    rtn
+}
+
+/// split a config line into its code & its `#` comment. A `#` inside a string literal
+/// does not start a comment
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return (&line[..index], Some(line[index..].trim_end())),
+            _ => {}
+        }
+    }
+    (line, None)
+}
+
+/// how many blocks `code` opens (negative if it closes more than it opens). Braces inside
+/// string literals are not counted
+fn block_depth(code: &str) -> isize {
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in code.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '{' if !quoted => depth += 1,
+            '}' if !quoted => depth -= 1,
+            _ => {}
+        }
+    }
+    depth
+}
+
+fn format_config(src: &str) -> String {
+    let mut rtn = String::new();
+    let mut depth: usize = 0;
+    let mut blank = false;
+    for line in src.lines() {
+        let (code, comment) = split_comment(line);
+        let code = collapse(code);
+        if code.is_empty() && comment.is_none() {
+            blank = true;
+            continue;
+        }
+        // a blank line may separate statements but never opens or closes a block
+        if blank && !rtn.is_empty() && !rtn.ends_with("{\n") && !code.starts_with('}') {
+            rtn.push('\n');
+        }
+        blank = false;
+
+        let indent = if code.starts_with('}') {
+            depth.saturating_sub(1)
+        } else {
+            depth
+        };
+        rtn.push_str(INDENT.repeat(indent).as_str());
+        rtn.push_str(code.as_str());
+        if let Some(comment) = comment {
+            if !code.is_empty() {
+                rtn.push(' ');
+            }
+            rtn.push_str(comment);
+        }
+        rtn.push('\n');
+        depth = (depth as isize + block_depth(code.as_str())).max(0) as usize;
+    }
+    rtn
+}
+
+fn format_script(src: &str) -> Result<String, SpaceErr> {
+    let mut rtn = String::new();
+    let mut end = 0;
+    for line in script_lines(src)? {
+        rtn.push_str(collapse(line.text.as_str()).as_str());
+        rtn.push_str(";\n");
+        end = line.offset + line.text.len();
+    }
+    // `exit;` and whatever follows it are kept as written
+    let rest = src[end..].trim_start_matches(|c: char| c.is_whitespace() || c == ';');
+    if !rest.trim().is_empty() {
+        rtn.push_str(rest.trim_end());
+        rtn.push('\n');
+    }
+    Ok(rtn)
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::config::check::{check, format, format_config, DocKind};
+    use crate::err::SpaceErr;
+
+    #[test]
+    pub fn test_unreachable_route() {
+        let src = r#"Bind(version=1.0.0) {
+    Route<*<*>> -> (()) => &;
+    Route<Ext<Hello>> -> (()) => &;
+}"#;
+        match check(DocKind::Bind, src) {
+            Err(SpaceErr::ParseErrs(errs)) => assert_eq!(1, errs.report.len()),
+            _ => panic!("expected the second route to be reported unreachable"),
+        }
+
+        let src = r#"Bind(version=1.0.0) {
+    Route<Ext<Hello>> -> (()) => &;
+    Route<*<*>> -> (()) => &;
+}"#;
+        check(DocKind::Bind, src).unwrap();
+    }
+
+    #[test]
+    pub fn test_wasm_keys() {
+        let src = r#"Mechtron(version=1.0.0) {
+  Wasm {
+    bin=repo:my-app:1.0.0:/wasm/my-app.wasm
+    nmae=my-app
+  }
+}"#;
+        match check(DocKind::Mechtron, src) {
+            // the unknown `nmae` and the missing `name`
+            Err(SpaceErr::ParseErrs(errs)) => assert_eq!(2, errs.report.len()),
+            _ => panic!("expected ParseErrs"),
+        }
+    }
+
+    #[test]
+    pub fn test_script_templates() {
+        let src = "create? localhost<Space>;\ncreate localhost:repo:my:app<Bundle>;\n";
+        match check(DocKind::Script, src) {
+            Err(SpaceErr::ParseErrs(errs)) => assert_eq!(1, errs.report.len()),
+            _ => panic!("expected a Bundle at a non version segment to be reported"),
+        }
+    }
+
+    #[test]
+    pub fn test_format() -> Result<(), SpaceErr> {
+        let src = "Bind(version=1.0.0)   {\n\n  Route<Ext<Hello>>   ->   (()) => &;  # hi\n\n\n}\n";
+        let formatted = format(DocKind::Bind, src)?;
+        assert_eq!(
+            "Bind(version=1.0.0) {\n    Route<Ext<Hello>> -> (()) => &; # hi\n}\n",
+            formatted
+        );
+        // canonical formatting is stable
+        assert_eq!(formatted, format(DocKind::Bind, formatted.as_str())?);
+
+        // a `#` or a brace inside a string literal is neither a comment nor a block
+        let src = "Bind(version=1.0.0) {\n  Route<Ext<Hello>> -> (()) => {{ \"#{ \\\" }\" }}; # hi\nRoute<Ext<Bye>> -> (()) => &;\n}\n";
+        assert_eq!(
+            "Bind(version=1.0.0) {\n    Route<Ext<Hello>> -> (()) => {{ \"#{ \\\" }\" }}; # hi\n    Route<Ext<Bye>> -> (()) => &;\n}\n",
+            format_config(src)
+        );
+
+        let src = "create?   localhost<Space>;  create localhost:repo<Repo>;\nexit;\nanything";
+        assert_eq!(
+            "create? localhost<Space>;\ncreate localhost:repo<Repo>;\nexit;\nanything\n",
+            format(DocKind::Script, src)?
+        );
+        Ok(())
+    }
+}
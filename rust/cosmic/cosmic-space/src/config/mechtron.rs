@@ -1,6 +1,7 @@
 use crate::loc::Point;
 use crate::parse::mechtron_config;
 use crate::parse::model::MechtronScope;
+use crate::particle::Properties;
 use crate::{Bin, SpaceErr};
 use core::str::FromStr;
 use serde::de::Unexpected::Option;
@@ -10,12 +11,14 @@ use serde::{Deserialize, Serialize};
 pub struct MechtronConfig {
     pub wasm: Point,
     pub name: String,
+    pub limits: WasmLimits,
 }
 
 impl MechtronConfig {
     pub fn new(scopes: Vec<MechtronScope>) -> Result<Self, SpaceErr> {
         let mut wasm = None;
         let mut name = None;
+        let mut limits = WasmLimits::default();
         for scope in scopes {
             match scope {
                 MechtronScope::WasmScope(assigns) => {
@@ -24,6 +27,8 @@ impl MechtronConfig {
                             wasm.replace(Point::from_str(assign.value.as_str())?);
                         } else if assign.key.as_str() == "name" {
                             name.replace(assign.value);
+                        } else if WasmLimits::KEYS.contains(&assign.key.as_str()) {
+                            limits.set(assign.key.as_str(), assign.value.as_str())?;
                         }
                     }
                 }
@@ -33,6 +38,7 @@ impl MechtronConfig {
             Ok(Self {
                 wasm: wasm.unwrap(),
                 name: name.unwrap(),
+                limits,
             })
         } else {
             Err("required `bin` and `name` in Wasm scope".into())
@@ -57,3 +63,64 @@ impl TryFrom<Bin> for MechtronConfig {
         mechtron_config(doc.as_str())
     }
 }
+
+/// resource limits applied to a Wasm guest. They can be set in the `Wasm` scope of a
+/// MechtronConfig (`fuel`, `max-memory-pages` & `call-timeout`) or directly as
+/// properties of the Host particle. `None` means unlimited
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash, Default)]
+pub struct WasmLimits {
+    /// instructions a guest may execute per call
+    pub fuel: Option<u64>,
+    /// max linear memory of the guest in 64KiB pages
+    pub max_memory_pages: Option<u32>,
+    /// max wall clock seconds a guest call may take
+    pub call_timeout: Option<u64>,
+}
+
+impl WasmLimits {
+    pub const KEYS: [&'static str; 3] = ["fuel", "max-memory-pages", "call-timeout"];
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), SpaceErr> {
+        fn num<N: FromStr>(key: &str, value: &str) -> Result<N, SpaceErr> {
+            N::from_str(value).map_err(|_| {
+                SpaceErr::bad_request(format!("expected a number for '{}' found '{}'", key, value))
+            })
+        }
+        match key {
+            "fuel" => self.fuel = Some(num(key, value)?),
+            "max-memory-pages" => self.max_memory_pages = Some(num(key, value)?),
+            "call-timeout" => self.call_timeout = Some(num(key, value)?),
+            what => {
+                return Err(SpaceErr::bad_request(format!(
+                    "unrecognized Wasm assignment '{}'",
+                    what
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    pub fn from_properties(properties: &Properties) -> Result<Self, SpaceErr> {
+        let mut limits = Self::default();
+        for key in Self::KEYS {
+            if let Some(property) = properties.get(key) {
+                limits.set(key, property.value.as_str())?;
+            }
+        }
+        Ok(limits)
+    }
+
+    pub fn to_pairs(&self) -> Vec<(String, String)> {
+        let mut rtn = vec![];
+        if let Some(fuel) = &self.fuel {
+            rtn.push(("fuel".to_string(), fuel.to_string()));
+        }
+        if let Some(pages) = &self.max_memory_pages {
+            rtn.push(("max-memory-pages".to_string(), pages.to_string()));
+        }
+        if let Some(timeout) = &self.call_timeout {
+            rtn.push(("call-timeout".to_string(), timeout.to_string()));
+        }
+        rtn
+    }
+}
@@ -489,6 +489,23 @@ impl ParseErrs {
         }
     }
 
+    /// errors found in a text derived from `source` with the offsets of every report
+    /// mapped back into `source` by `original`
+    pub fn map_offsets<F>(self, original: F, source: Arc<String>) -> Self
+    where
+        F: Fn(usize) -> usize,
+    {
+        Self {
+            report: self
+                .report
+                .into_iter()
+                .map(|report| report.map_offsets(&original))
+                .collect(),
+            source: Some(source),
+            ctx: self.ctx,
+        }
+    }
+
     pub fn from_report(report: Report, source: Arc<String>) -> Self {
         Self {
             report: vec![report],
@@ -667,6 +684,18 @@ pub mod report {
     }
 
     impl Report {
+        /// the report with the location & label spans mapped by `f`
+        pub fn map_offsets<F>(mut self, f: &F) -> Self
+        where
+            F: Fn(usize) -> usize,
+        {
+            self.location = self.location.map_offsets(f);
+            for label in self.labels.iter_mut() {
+                label.span = label.span.map_offsets(f);
+            }
+            self
+        }
+
         pub(crate) fn build(kind: ReportKind, p1: (), p2: i32) -> ReportBuilder {
             ReportBuilder {
                 kind,
@@ -703,6 +732,21 @@ pub mod report {
         pub end: u32,
     }
 
+    impl Range {
+        /// the end is exclusive so the last offset within the range is mapped instead
+        fn map_offsets<F>(&self, f: &F) -> Self
+        where
+            F: Fn(usize) -> usize,
+        {
+            let start = f(self.start as usize) as u32;
+            let end = match self.end > self.start {
+                true => (f(self.end as usize - 1) + 1) as u32,
+                false => start,
+            };
+            Self { start, end }
+        }
+    }
+
     impl Into<std::ops::Range<usize>> for Range {
         fn into(self) -> std::ops::Range<usize> {
             std::ops::Range {
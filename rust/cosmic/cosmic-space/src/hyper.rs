@@ -1,5 +1,7 @@
+use std::cell::Cell;
 use std::collections::HashSet;
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
@@ -234,6 +236,7 @@ pub enum HyperSubstance {
     Log(Log),
     Search(Search),
     Discoveries(Discoveries),
+    Interchanges(Interchanges),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -276,6 +279,65 @@ impl DerefMut for Discoveries {
     }
 }
 
+/// what the interchanges of a machine report of their hyperways
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Interchanges {
+    pub vec: Vec<InterchangeReport>,
+}
+
+impl Interchanges {
+    pub fn new() -> Self {
+        Self { vec: vec![] }
+    }
+}
+
+impl Deref for Interchanges {
+    type Target = Vec<InterchangeReport>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.vec
+    }
+}
+
+impl DerefMut for Interchanges {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.vec
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct InterchangeReport {
+    pub kind: InterchangeKind,
+    pub hyperways: Vec<HyperwayReport>,
+    /// points that may be neither the remote nor the agent of a hyperway
+    pub banned: Vec<Point>,
+    /// transport peers that may not connect unless as the HyperUser
+    pub banned_peers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct HyperwayReport {
+    pub remote: Surface,
+    pub agent: Agent,
+    /// seconds since the hyperway entered the interchange
+    pub age: u64,
+    /// the address of the transport peer of the remote, if it has one
+    pub peer: Option<String>,
+    /// the waves from the remote
+    pub inbound: HyperlaneReport,
+    /// the waves to the remote
+    pub outbound: HyperlaneReport,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct HyperlaneReport {
+    pub queued: usize,
+    pub dropped: u64,
+    pub bounced: u64,
+    /// seconds since an endpoint last took a wave
+    pub idle: u64,
+}
+
 impl TryFrom<Ping> for Assign {
     type Error = SpaceErr;
 
@@ -327,11 +389,193 @@ pub enum ControlPattern {
     Star(Point),
 }
 
+/// the hyperlane protocol revisions this build speaks.  A revision is added whenever
+/// a new `Substance` or `Method` variant or a new field would confuse a peer that
+/// predates it:
+///
+/// 1. the protocol of 0.3.4, whose handshake sends `LEGACY_VERSION` instead of a range
+/// 2. `Cmd<Track>`, the admin `Ext` methods & `HyperSubstance::Interchanges`, the
+///    `protocol` of `Knock` & `Greet`, `Wave::trace` and `RawCommand::working`
+pub const PROTOCOL: ProtocolRange = ProtocolRange { min: 1, max: 2 };
+
+/// the version a 0.3.4 peer sends in its handshake, it speaks revision 1
+pub const LEGACY_VERSION: &str = "0.3.4";
+
+thread_local! {
+    static WIRE_REVISION: Cell<u16> = Cell::new(PROTOCOL.max);
+}
+
+/// the protocol revision whose layout waves are (de)serialized in on this thread
+pub fn wire_revision() -> u16 {
+    WIRE_REVISION.with(|revision| revision.get())
+}
+
+/// (de)serialize in the layout of `revision` for the duration of `f`: the fields
+/// `since2` marks are left out below revision 2
+pub fn at_revision<F, R>(revision: u16, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let previous = WIRE_REVISION.with(|current| current.replace(revision));
+    let result = f();
+    WIRE_REVISION.with(|current| current.set(previous));
+    result
+}
+
+/// the value of a field that a revision 1 peer neither sends nor expects
+pub trait Revision1 {
+    fn revision1() -> Self;
+}
+
+impl<T> Revision1 for Option<T> {
+    fn revision1() -> Self {
+        None
+    }
+}
+
+impl Revision1 for ProtocolRange {
+    fn revision1() -> Self {
+        ProtocolRange::new(1, 1)
+    }
+}
+
+impl Revision1 for u16 {
+    fn revision1() -> Self {
+        1
+    }
+}
+
+/// `serialize_with` & `deserialize_with` of fields added in revision 2.  Below
+/// revision 2 the field takes no bytes and reads back as `Revision1::revision1`,
+/// which only holds for formats that are not self describing such as bincode
+pub mod since2 {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::hyper::{wire_revision, Revision1};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        if wire_revision() < 2 {
+            serializer.serialize_unit()
+        } else {
+            value.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de> + Revision1,
+        D: Deserializer<'de>,
+    {
+        if wire_revision() < 2 {
+            Ok(T::revision1())
+        } else {
+            T::deserialize(deserializer)
+        }
+    }
+}
+
+/// an inclusive range of hyperlane protocol revisions
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ProtocolRange {
+    pub min: u16,
+    pub max: u16,
+}
+
+impl ProtocolRange {
+    pub fn new(min: u16, max: u16) -> Self {
+        Self { min, max }
+    }
+
+    pub fn contains(&self, revision: u16) -> bool {
+        self.min <= revision && revision <= self.max
+    }
+
+    /// the highest revision both `self` and `offered` speak
+    pub fn negotiate(&self, offered: &ProtocolRange) -> Result<u16, ProtocolMismatch> {
+        let max = self.max.min(offered.max);
+        if max >= self.min.max(offered.min) {
+            Ok(max)
+        } else {
+            Err(ProtocolMismatch {
+                supported: self.clone(),
+                offered: offered.clone(),
+            })
+        }
+    }
+}
+
+impl ToString for ProtocolRange {
+    fn to_string(&self) -> String {
+        format!("{}..={}", self.min, self.max)
+    }
+}
+
+impl FromStr for ProtocolRange {
+    type Err = SpaceErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (min, max) = s.split_once("..=").ok_or(SpaceErr::bad_request(format!(
+            "expected a protocol range such as '1..=2' encountered '{}'",
+            s
+        )))?;
+        let revision = |r: &str| {
+            u16::from_str(r.trim()).map_err(|_| {
+                SpaceErr::bad_request(format!("'{}' is not a protocol revision", r))
+            })
+        };
+        Ok(Self::new(revision(min)?, revision(max)?))
+    }
+}
+
+/// a peer offered protocol revisions that have nothing in common with ours
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ProtocolMismatch {
+    pub supported: ProtocolRange,
+    pub offered: ProtocolRange,
+}
+
+impl ToString for ProtocolMismatch {
+    fn to_string(&self) -> String {
+        let upgrade = if self.offered.max < self.supported.min {
+            "the client is too old"
+        } else {
+            "the client is too new"
+        };
+        format!(
+            "protocol mismatch: offered revisions {} but supported revisions are {} ({})",
+            self.offered.to_string(),
+            self.supported.to_string(),
+            upgrade
+        )
+    }
+}
+
+impl From<ProtocolMismatch> for SpaceErr {
+    fn from(mismatch: ProtocolMismatch) -> Self {
+        // 426 Upgrade Required
+        SpaceErr::new(426, mismatch.to_string())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct Knock {
     pub kind: InterchangeKind,
     pub auth: Box<Substance>,
     pub remote: Option<Surface>,
+    /// the protocol revisions the knocking side speaks
+    #[serde(
+        serialize_with = "since2::serialize",
+        deserialize_with = "since2::deserialize"
+    )]
+    pub protocol: ProtocolRange,
+    /// the address of the transport peer the knock arrived from.  Set by the receiving
+    /// side of a transport (never sent) so bans outlast the remote a control is assigned
+    #[serde(skip)]
+    pub peer: Option<String>,
 }
 
 impl Knock {
@@ -340,6 +584,8 @@ impl Knock {
             kind,
             remote: Some(remote),
             auth: Box::new(auth),
+            protocol: PROTOCOL,
+            peer: None,
         }
     }
 }
@@ -350,6 +596,8 @@ impl Default for Knock {
             kind: InterchangeKind::DefaultControl,
             auth: Box::new(Substance::Empty),
             remote: None,
+            protocol: PROTOCOL,
+            peer: None,
         }
     }
 }
@@ -372,6 +620,13 @@ pub struct Greet {
     pub agent: Agent,
     pub hop: Surface,
     pub transport: Surface,
+    /// the protocol revision negotiated from the `Knock`, waves that need a later
+    /// revision are refused by both sides
+    #[serde(
+        serialize_with = "since2::serialize",
+        deserialize_with = "since2::deserialize"
+    )]
+    pub protocol: u16,
 }
 
 impl Greet {
@@ -381,6 +636,7 @@ impl Greet {
             surface,
             hop,
             transport,
+            protocol: PROTOCOL.max,
         }
     }
 }
@@ -414,3 +670,96 @@ impl MountKind {
         }
     }
 }
+
+#[cfg(test)]
+pub mod test {
+    use std::str::FromStr;
+
+    use serde::Serialize;
+
+    use crate::hyper::{
+        at_revision, wire_revision, InterchangeKind, Knock, ProtocolRange, PROTOCOL,
+    };
+    use crate::loc::{Point, Surface, ToSurface};
+    use crate::substance::Substance;
+    use crate::wave::core::ext::ExtMethod;
+    use crate::wave::core::Method;
+    use crate::wave::DirectedProto;
+
+    #[test]
+    pub fn test_protocol_negotiate() {
+        assert_eq!(PROTOCOL.negotiate(&ProtocolRange::new(1, 1)), Ok(1));
+        assert_eq!(
+            PROTOCOL.negotiate(&ProtocolRange::new(1, 100)),
+            Ok(PROTOCOL.max)
+        );
+
+        let mismatch = PROTOCOL
+            .negotiate(&ProtocolRange::new(PROTOCOL.max + 1, PROTOCOL.max + 2))
+            .unwrap_err();
+        assert_eq!(mismatch.supported, PROTOCOL);
+        assert!(mismatch.to_string().contains("too new"));
+
+        let range = ProtocolRange::from_str(PROTOCOL.to_string().as_str()).unwrap();
+        assert_eq!(range, PROTOCOL);
+        assert!(ProtocolRange::from_str("2").is_err());
+    }
+
+    #[test]
+    pub fn test_revision1_layout() {
+        // the layout of `Knock` in 0.3.4
+        #[derive(Serialize)]
+        struct LegacyKnock {
+            kind: InterchangeKind,
+            auth: Box<Substance>,
+            remote: Option<Surface>,
+        }
+
+        let knock = Knock::new(
+            InterchangeKind::DefaultControl,
+            Point::root().to_surface(),
+            Substance::Empty,
+        );
+        let legacy = LegacyKnock {
+            kind: knock.kind.clone(),
+            auth: knock.auth.clone(),
+            remote: knock.remote.clone(),
+        };
+
+        let bin = at_revision(1, || bincode::serialize(&knock)).unwrap();
+        assert_eq!(bin, bincode::serialize(&legacy).unwrap());
+        let read: Knock = at_revision(1, || bincode::deserialize(bin.as_slice())).unwrap();
+        assert_eq!(read.protocol, ProtocolRange::new(1, 1));
+        assert_eq!(wire_revision(), PROTOCOL.max);
+
+        let bin = bincode::serialize(&knock).unwrap();
+        let read: Knock = bincode::deserialize(bin.as_slice()).unwrap();
+        assert_eq!(read, knock);
+    }
+
+    #[test]
+    pub fn test_method_protocol() {
+        let control = Point::from_str("hyperspace:star:fae:drivers:control").unwrap();
+        let ext = |method: &str| Method::Ext(ExtMethod::new(method).unwrap());
+        assert_eq!(ext("Kick").protocol(&control), 2);
+        assert_eq!(ext("Interchanges").protocol(&control), 2);
+        assert_eq!(ext("Check").protocol(&control), 1);
+
+        // a particle's own Ext methods may share a name with the administration methods
+        let app = Point::from_str("localhost:app").unwrap();
+        assert_eq!(ext("Kick").protocol(&app), 1);
+        assert_eq!(ext("Ban").protocol(&app), 1);
+        let driver = Point::from_str("hyperspace:star:fae:drivers:mechtron").unwrap();
+        assert_eq!(ext("Kick").protocol(&driver), 1);
+
+        let wave = |to: &Point| {
+            let mut ping = DirectedProto::ping();
+            ping.from(app.clone().to_surface());
+            ping.to(to.clone().to_surface());
+            ping.method(ext("Kick"));
+            ping.build().unwrap().to_ultra()
+        };
+        assert_eq!(wave(&control).protocol(), 2);
+        assert_eq!(wave(&app).protocol(), 1);
+    }
+}
@@ -113,6 +113,7 @@ impl KindParts {
     Hash,
     strum_macros::Display,
     strum_macros::EnumString,
+    strum_macros::EnumIter,
 )]
 pub enum BaseKind {
     Root,
@@ -140,6 +141,11 @@ pub enum BaseKind {
 }
 
 impl BaseKind {
+    /// every BaseKind (i.e. for completion in a shell)
+    pub fn all() -> Vec<BaseKind> {
+        <BaseKind as strum::IntoEnumIterator>::iter().collect()
+    }
+
     pub fn to_skewer(&self) -> SkewerCase {
         SkewerCase::from_str(self.to_string().to_case(Case::Kebab).as_str()).unwrap()
     }
@@ -636,6 +642,18 @@ pub enum ArtifactSubKind {
     Dir,
 }
 
+impl ArtifactSubKind {
+    /// the subkind implied by the extension of `path` (`Raw` if it has no known extension)
+    pub fn from_path(path: &str) -> Self {
+        match path.rsplit_once('.').map(|(_, ext)| ext) {
+            Some("bind") => ArtifactSubKind::Bind,
+            Some("wasm") => ArtifactSubKind::Wasm,
+            Some("mechtron") => ArtifactSubKind::ParticleConfig,
+            _ => ArtifactSubKind::Raw,
+        }
+    }
+}
+
 impl Into<Sub> for ArtifactSubKind {
     fn into(self) -> Sub {
         Sub::Artifact(self)
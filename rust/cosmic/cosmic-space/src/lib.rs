@@ -42,6 +42,7 @@ use crate::security::{Access, AccessGrant};
 use crate::wave::Agent;
 
 pub mod artifact;
+pub mod capture;
 pub mod command;
 pub mod config;
 pub mod err;
@@ -49,8 +50,10 @@ pub mod fail;
 pub mod frame;
 pub mod hyper;
 pub mod kind;
+pub mod limit;
 pub mod loc;
 pub mod log;
+pub mod metrics;
 pub mod parse;
 pub mod particle;
 pub mod path;
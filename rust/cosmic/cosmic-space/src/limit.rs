@@ -0,0 +1,535 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::err::SpaceErr;
+use crate::hyper::InterchangeKind;
+use crate::loc::{Point, ToPoint};
+use crate::metrics::{render_labels, METRICS};
+use crate::selector::Selector;
+use crate::wave::core::ReflectedCore;
+use crate::wave::{Agent, UltraWave, WaveId};
+
+/// exchanges that are never reflected stop counting as in flight after this long
+pub const IN_FLIGHT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// the longest a throttled wave is told to wait, even by a bucket that is never
+/// refilled (`Rate::per_second` of `0`)
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(3600);
+
+/// beyond this many agents the state of agents that are back to their full allowance
+/// is forgotten
+const MAX_STATES: usize = 10_000;
+
+/// a token bucket: it holds at most `burst` tokens & is refilled at `per_second`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Rate {
+    pub per_second: u64,
+    pub burst: u64,
+}
+
+impl Rate {
+    pub fn new(per_second: u64, burst: u64) -> Self {
+        Self { per_second, burst }
+    }
+}
+
+/// the allowance of each agent that a `LimitRule` applies to
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Limit {
+    pub waves: Option<Rate>,
+    pub bytes: Option<Rate>,
+    /// directed waves awaiting their reflection
+    pub in_flight: Option<usize>,
+}
+
+/// the traffic a `LimitRule` applies to, `None` matches any
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct LimitScope {
+    pub agent: Option<Agent>,
+    pub kind: Option<InterchangeKind>,
+    pub to: Option<Selector>,
+}
+
+impl LimitScope {
+    fn matches(&self, agent: &Agent, kind: Option<&InterchangeKind>, to: Option<&Point>) -> bool {
+        self.agent.as_ref().map_or(true, |a| a == agent)
+            && self.kind.as_ref().map_or(true, |k| Some(k) == kind)
+            && self.to.as_ref().map_or(true, |selector| {
+                to.map_or(false, |to| selector.matches_point(to))
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct LimitRule {
+    pub scope: LimitScope,
+    pub limit: Limit,
+}
+
+impl LimitRule {
+    pub fn new(scope: LimitScope, limit: Limit) -> Self {
+        Self { scope, limit }
+    }
+}
+
+/// why a wave was not admitted
+#[derive(Debug, Clone)]
+pub struct Throttled {
+    pub retry_after: Duration,
+    pub reason: String,
+}
+
+impl Throttled {
+    /// a 429 with the whole seconds to wait in a `Retry-After` header
+    pub fn core(&self) -> ReflectedCore {
+        let mut core = ReflectedCore::err(self.clone().into());
+        core.headers.insert(
+            "Retry-After".to_string(),
+            self.retry_after.as_secs_f64().ceil().to_string(),
+        );
+        core
+    }
+}
+
+impl From<Throttled> for SpaceErr {
+    fn from(throttled: Throttled) -> Self {
+        SpaceErr::new(429, throttled.reason)
+    }
+}
+
+/// the state of one rule for one agent & peer, for diagnostics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimiterState {
+    pub rule: usize,
+    pub agent: Agent,
+    pub peer: Option<String>,
+    /// tokens left in the waves bucket, negative while in debt
+    pub waves: Option<f64>,
+    pub bytes: Option<f64>,
+    pub in_flight: usize,
+    pub throttled: u64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(rate: &Rate) -> Self {
+        Self {
+            tokens: rate.burst as f64,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, rate: &Rate, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate.per_second as f64).min(rate.burst as f64);
+        self.updated = now;
+    }
+
+    /// how long until `amount` can be taken.  An amount larger than the burst is taken
+    /// from a full bucket and paid back before anything else is
+    fn wait(&self, rate: &Rate, amount: u64) -> Duration {
+        let needed = (amount as f64).min(rate.burst as f64);
+        if self.tokens >= needed {
+            Duration::ZERO
+        } else if rate.per_second == 0 {
+            MAX_RETRY_AFTER
+        } else {
+            Duration::from_secs_f64((needed - self.tokens) / rate.per_second as f64)
+                .min(MAX_RETRY_AFTER)
+        }
+    }
+
+    fn full(&self, rate: &Rate) -> bool {
+        self.tokens >= rate.burst as f64
+    }
+}
+
+struct State {
+    waves: Option<Bucket>,
+    bytes: Option<Bucket>,
+    in_flight: HashMap<WaveId, Instant>,
+    throttled: u64,
+}
+
+impl State {
+    fn new(limit: &Limit) -> Self {
+        Self {
+            waves: limit.waves.as_ref().map(Bucket::new),
+            bytes: limit.bytes.as_ref().map(Bucket::new),
+            in_flight: HashMap::new(),
+            throttled: 0,
+        }
+    }
+
+    fn refill(&mut self, limit: &Limit, now: Instant) {
+        if let (Some(bucket), Some(rate)) = (self.waves.as_mut(), limit.waves.as_ref()) {
+            bucket.refill(rate, now);
+        }
+        if let (Some(bucket), Some(rate)) = (self.bytes.as_mut(), limit.bytes.as_ref()) {
+            bucket.refill(rate, now);
+        }
+        self.in_flight
+            .retain(|_, since| now.saturating_duration_since(*since) < IN_FLIGHT_TIMEOUT);
+    }
+
+    fn wait(&self, limit: &Limit, bytes: u64, exchange: bool) -> Option<(Duration, String)> {
+        let mut wait = None;
+        if let (Some(bucket), Some(rate)) = (self.waves.as_ref(), limit.waves.as_ref()) {
+            let w = bucket.wait(rate, 1);
+            if w > Duration::ZERO {
+                wait = Some((w, format!("more than {} waves per second", rate.per_second)));
+            }
+        }
+        if let (Some(bucket), Some(rate)) = (self.bytes.as_ref(), limit.bytes.as_ref()) {
+            let w = bucket.wait(rate, bytes);
+            if w > wait.as_ref().map_or(Duration::ZERO, |(w, _)| *w) {
+                wait = Some((w, format!("more than {} bytes per second", rate.per_second)));
+            }
+        }
+        if let Some(max) = limit.in_flight {
+            if exchange && self.in_flight.len() >= max && wait.is_none() {
+                wait = Some((
+                    Duration::from_secs(1),
+                    format!("more than {} exchanges in flight", max),
+                ));
+            }
+        }
+        wait
+    }
+
+    fn take(&mut self, bytes: u64, exchange: Option<&WaveId>, now: Instant) {
+        if let Some(bucket) = self.waves.as_mut() {
+            bucket.tokens = bucket.tokens - 1.0;
+        }
+        if let Some(bucket) = self.bytes.as_mut() {
+            bucket.tokens = bucket.tokens - bytes as f64;
+        }
+        if let Some(exchange) = exchange {
+            self.in_flight.insert(exchange.clone(), now);
+        }
+    }
+
+    /// indistinguishable from a new state
+    fn idle(&self, limit: &Limit) -> bool {
+        self.in_flight.is_empty()
+            && self
+                .waves
+                .as_ref()
+                .zip(limit.waves.as_ref())
+                .map_or(true, |(bucket, rate)| bucket.full(rate))
+            && self
+                .bytes
+                .as_ref()
+                .zip(limit.bytes.as_ref())
+                .map_or(true, |(bucket, rate)| bucket.full(rate))
+    }
+}
+
+/// applies `LimitRule`s to the waves of each agent.  A wave is admitted only if every
+/// rule that matches it allows it.  Waves of `Agent::HyperUser` are never limited.
+/// Each transport peer of an agent (i.e. the address of a Web client) has an allowance
+/// of its own so that every `Agent::Anonymous` client does not share one
+#[derive(Clone)]
+pub struct RateLimiter {
+    rules: Arc<Vec<LimitRule>>,
+    states: Arc<Mutex<HashMap<(usize, Agent, Option<String>), State>>>,
+}
+
+impl RateLimiter {
+    pub fn new(rules: Vec<LimitRule>) -> Self {
+        Self {
+            rules: Arc::new(rules),
+            states: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn rules(&self) -> &Vec<LimitRule> {
+        &self.rules
+    }
+
+    /// admit `wave` from `agent` at `peer`.  A `Ping` or `Ripple` stays in flight until
+    /// its reflection is passed to `release`
+    pub fn admit_wave(
+        &self,
+        agent: &Agent,
+        peer: Option<&String>,
+        kind: Option<&InterchangeKind>,
+        wave: &UltraWave,
+    ) -> Result<(), Throttled> {
+        if self.rules.is_empty() {
+            return Ok(());
+        }
+        let to = wave.to().single_or().ok().map(|to| to.point);
+        let bytes = bincode::serialized_size(wave).unwrap_or_default();
+        let exchange = match wave {
+            UltraWave::Ping(_) | UltraWave::Ripple(_) => Some(wave.id()),
+            _ => None,
+        };
+        self.admit(agent, peer, kind, to.as_ref(), bytes, exchange.as_ref())
+    }
+
+    /// admit `bytes` from `agent` at `peer` addressed to `to`.  An `exchange` stays in
+    /// flight until it is released
+    pub fn admit(
+        &self,
+        agent: &Agent,
+        peer: Option<&String>,
+        kind: Option<&InterchangeKind>,
+        to: Option<&Point>,
+        bytes: u64,
+        exchange: Option<&WaveId>,
+    ) -> Result<(), Throttled> {
+        if self.rules.is_empty() || *agent == Agent::HyperUser {
+            return Ok(());
+        }
+        let mut states = match self.states.lock() {
+            Ok(states) => states,
+            Err(_) => return Ok(()),
+        };
+        let now = Instant::now();
+        let peer = peer.cloned();
+        let mut matched = vec![];
+        let mut throttled: Option<(Duration, String)> = None;
+        for (index, rule) in self.rules.iter().enumerate() {
+            if !rule.scope.matches(agent, kind, to) {
+                continue;
+            }
+            let state = states
+                .entry((index, agent.clone(), peer.clone()))
+                .or_insert_with(|| State::new(&rule.limit));
+            state.refill(&rule.limit, now);
+            if let Some((wait, reason)) = state.wait(&rule.limit, bytes, exchange.is_some()) {
+                state.throttled = state.throttled + 1;
+                if throttled.as_ref().map_or(true, |(w, _)| wait > *w) {
+                    throttled = Some((wait, reason));
+                }
+            }
+            matched.push(index);
+        }
+
+        if let Some((retry_after, reason)) = throttled {
+            throttled_counter().inc();
+            return Err(Throttled {
+                retry_after,
+                reason: format!("{} is limited to {}", agent.to_point().to_string(), reason),
+            });
+        }
+
+        for index in matched {
+            if let Some(state) = states.get_mut(&(index, agent.clone(), peer.clone())) {
+                state.take(bytes, exchange, now);
+            }
+        }
+
+        if states.len() > MAX_STATES {
+            let rules = self.rules.clone();
+            states.retain(|(index, _, _), state| !state.idle(&rules[*index].limit));
+        }
+        Ok(())
+    }
+
+    /// `wave` no longer counts as in flight if it is the reflection of an admitted exchange
+    pub fn release_wave(&self, wave: &UltraWave) {
+        match wave {
+            UltraWave::Pong(pong) => self.release(&pong.reflection_of),
+            UltraWave::Echo(echo) => self.release(&echo.reflection_of),
+            _ => {}
+        }
+    }
+
+    pub fn release(&self, exchange: &WaveId) {
+        if self.rules.is_empty() {
+            return;
+        }
+        if let Ok(mut states) = self.states.lock() {
+            for state in states.values_mut() {
+                state.in_flight.remove(exchange);
+            }
+        }
+    }
+
+    pub fn states(&self) -> Vec<LimiterState> {
+        let now = Instant::now();
+        let mut states = match self.states.lock() {
+            Ok(states) => states,
+            Err(_) => return vec![],
+        };
+        let mut rtn: Vec<LimiterState> = states
+            .iter_mut()
+            .map(|((rule, agent, peer), state)| {
+                state.refill(&self.rules[*rule].limit, now);
+                LimiterState {
+                    rule: *rule,
+                    agent: agent.clone(),
+                    peer: peer.clone(),
+                    waves: state.waves.as_ref().map(|bucket| bucket.tokens),
+                    bytes: state.bytes.as_ref().map(|bucket| bucket.tokens),
+                    in_flight: state.in_flight.len(),
+                    throttled: state.throttled,
+                }
+            })
+            .collect();
+        rtn.sort_by(|a, b| a.rule.cmp(&b.rule));
+        rtn
+    }
+
+    /// the `states` in the Prometheus text exposition format, to be served along with
+    /// `METRICS`.  They are not kept in `METRICS` since a series would outlive the state
+    /// of an agent that is forgotten
+    pub fn to_prometheus(&self) -> String {
+        let states = self.states();
+        let mut rtn = String::new();
+        let families: [(&str, &str, &str, fn(&LimiterState) -> Option<String>); 4] = [
+            (
+                "cosmic_limiter_wave_tokens",
+                "gauge",
+                "waves an agent may send before it is throttled, negative while in debt",
+                |state| state.waves.map(|tokens| tokens.to_string()),
+            ),
+            (
+                "cosmic_limiter_byte_tokens",
+                "gauge",
+                "bytes an agent may send before it is throttled, negative while in debt",
+                |state| state.bytes.map(|tokens| tokens.to_string()),
+            ),
+            (
+                "cosmic_limiter_in_flight",
+                "gauge",
+                "exchanges of an agent awaiting their reflection",
+                |state| Some(state.in_flight.to_string()),
+            ),
+            (
+                "cosmic_limiter_throttled_total",
+                "counter",
+                "waves of an agent refused by a rule",
+                |state| Some(state.throttled.to_string()),
+            ),
+        ];
+        for (name, kind, help, value) in families.iter() {
+            rtn.push_str(format!("# HELP {} {}\n", name, help).as_str());
+            rtn.push_str(format!("# TYPE {} {}\n", name, kind).as_str());
+            for state in states.iter() {
+                if let Some(value) = value(state) {
+                    let rule = state.rule.to_string();
+                    let agent = state.agent.to_point().to_string();
+                    let peer = state.peer.clone().unwrap_or_default();
+                    let labels = render_labels(&[
+                        ("rule", rule.as_str()),
+                        ("agent", agent.as_str()),
+                        ("peer", peer.as_str()),
+                    ]);
+                    rtn.push_str(format!("{}{} {}\n", name, labels, value).as_str());
+                }
+            }
+        }
+        rtn
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+fn throttled_counter() -> crate::metrics::Counter {
+    METRICS.counter(
+        "cosmic_throttled_total",
+        "waves refused because their agent was over a rate limit",
+        &[],
+    )
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::str::FromStr;
+
+    use crate::limit::{Limit, LimitRule, LimitScope, Rate, RateLimiter};
+    use crate::loc::Point;
+    use crate::selector::Selector;
+    use crate::wave::{Agent, WaveId, WaveKind};
+
+    #[test]
+    pub fn test_rate_limiter() {
+        let fae = Agent::Point(Point::from_str("fae").unwrap());
+        let less = Agent::Point(Point::from_str("less").unwrap());
+        let to = Point::from_str("localhost:app").unwrap();
+        let limiter = RateLimiter::new(vec![
+            LimitRule::new(
+                LimitScope::default(),
+                Limit {
+                    waves: Some(Rate::new(0, 2)),
+                    bytes: None,
+                    in_flight: Some(1),
+                },
+            ),
+            LimitRule::new(
+                LimitScope {
+                    agent: Some(less.clone()),
+                    kind: None,
+                    to: Some(Selector::from_str("localhost:app").unwrap()),
+                },
+                Limit {
+                    waves: None,
+                    bytes: Some(Rate::new(1, 10)),
+                    in_flight: None,
+                },
+            ),
+        ]);
+
+        let exchange = WaveId::new(WaveKind::Ping);
+        limiter
+            .admit(&fae, None, None, Some(&to), 1, Some(&exchange))
+            .unwrap();
+        // one exchange is already in flight
+        let throttled = limiter
+            .admit(&fae, None, None, Some(&to), 1, Some(&WaveId::new(WaveKind::Ping)))
+            .unwrap_err();
+        assert_eq!(throttled.core().status.as_u16(), 429u16);
+        assert_eq!(throttled.core().headers.get("Retry-After").unwrap(), "1");
+
+        limiter.release(&exchange);
+        limiter.admit(&fae, None, None, Some(&to), 1, None).unwrap();
+        // the bucket is empty & never refilled yet the wait is bounded
+        let throttled = limiter.admit(&fae, None, None, Some(&to), 1, None).unwrap_err();
+        assert_eq!(throttled.core().headers.get("Retry-After").unwrap(), "3600");
+
+        // each peer of an agent has its own allowance
+        let peer = "10.0.0.7".to_string();
+        limiter
+            .admit(&fae, Some(&peer), None, Some(&to), 1, None)
+            .unwrap();
+
+        // each agent has its own allowance
+        limiter.admit(&less, None, None, Some(&to), 10, None).unwrap();
+        let throttled = limiter.admit(&less, None, None, Some(&to), 5, None).unwrap_err();
+        assert!(throttled.reason.contains("bytes per second"));
+        assert_eq!(throttled.core().headers.get("Retry-After").unwrap(), "5");
+
+        assert!(limiter.admit(&Agent::HyperUser, None, None, None, 100, None).is_ok());
+
+        let states = limiter.states();
+        assert_eq!(states.iter().filter(|s| s.rule == 0).count(), 3);
+        assert_eq!(
+            states
+                .iter()
+                .find(|s| s.rule == 0 && s.agent == fae && s.peer.is_none())
+                .unwrap()
+                .throttled,
+            2
+        );
+
+        let text = limiter.to_prometheus();
+        assert!(text.contains("# TYPE cosmic_limiter_throttled_total counter\n"));
+        assert!(text.contains(
+            "cosmic_limiter_wave_tokens{rule=\"0\",agent=\"fae\",peer=\"10.0.0.7\"} 1\n"
+        ));
+    }
+}
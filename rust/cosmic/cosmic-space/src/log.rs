@@ -2,7 +2,7 @@ use core::str::FromStr;
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::process::Output;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use crate::{Agent, ToSubstance, ANONYMOUS};
 use regex::Regex;
@@ -15,17 +15,28 @@ use crate::err::SpaceErr;
 use crate::loc::{Layer, Point, ToPoint, ToSurface, Uuid};
 use crate::parse::{to_string, CamelCase};
 use crate::selector::Selector;
-use crate::substance::LogSubstance;
+use crate::substance::{Bin, LogSubstance};
 use crate::util::{timestamp, uuid};
 use crate::wasm::Timestamp;
 use crate::wave::core::cmd::CmdMethod;
 use crate::wave::exchange::synch::{ProtoTransmitter, ProtoTransmitterBuilder};
 use crate::wave::exchange::SetStrategy;
 use crate::wave::{
-    DirectedProto, Handling, HandlingKind, Priority, Retries, ToRecipients, WaitTime,
+    DirectedProto, Handling, HandlingKind, Priority, Retries, ToRecipients, UltraWave, WaitTime,
 };
 
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, strum_macros::Display)]
+#[derive(
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Ord,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
 pub enum Level {
     Trace,
     Debug,
@@ -78,6 +89,8 @@ pub enum LogSpanEventKind {
 pub struct LogSpanEvent {
     pub point: Point,
     pub span: Uuid,
+    pub trace: Uuid,
+    pub parent: Option<Uuid>,
     pub kind: LogSpanEventKind,
     pub attributes: HashMap<String, String>,
     pub timestamp: Timestamp,
@@ -92,6 +105,8 @@ impl LogSpanEvent {
     ) -> LogSpanEvent {
         LogSpanEvent {
             span: span.id.clone(),
+            trace: span.trace.clone(),
+            parent: span.parent.clone(),
             point: point.clone(),
             kind,
             attributes,
@@ -102,9 +117,19 @@ impl LogSpanEvent {
 
 pub type TrailSpanId = Uuid;
 
+/// identifies a span within a trace.  Travels with a `DirectedWave` so the spans of the
+/// receiving star continue the trace of the sender
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct TraceCtx {
+    pub trace: Uuid,
+    pub span: TrailSpanId,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct LogSpan {
     pub id: TrailSpanId,
+    /// shared by every span of a trace; a root span's trace is its own id
+    pub trace: Uuid,
     pub point: Point,
     pub mark: Point,
     pub action: Option<CamelCase>,
@@ -115,8 +140,10 @@ pub struct LogSpan {
 
 impl LogSpan {
     pub fn new(point: Point) -> Self {
+        let id = uuid();
         Self {
-            id: uuid(),
+            trace: id.clone(),
+            id,
             point,
             mark: Point::root(),
             action: None,
@@ -129,6 +156,7 @@ impl LogSpan {
     pub fn parent(point: Point, parent: Uuid) -> Self {
         Self {
             id: uuid(),
+            trace: parent.clone(),
             point,
             mark: Point::root(),
             action: None,
@@ -138,19 +166,43 @@ impl LogSpan {
         }
     }
 
-    pub fn opt(point: Point, span: Option<Self>) -> Self {
-        let mut span = span.unwrap_or(Self {
+    /// a new span continuing the trace of `ctx`
+    pub fn child(point: Point, ctx: &TraceCtx) -> Self {
+        Self {
             id: uuid(),
-            point: point.clone(),
+            trace: ctx.trace.clone(),
+            point,
             mark: Point::root(),
             action: None,
-            parent: None,
+            parent: Some(ctx.span.clone()),
             attributes: Default::default(),
             entry_timestamp: timestamp(),
-        });
+        }
+    }
+
+    pub fn opt(point: Point, span: Option<Self>) -> Self {
+        let mut span = span.unwrap_or_else(|| Self::new(point.clone()));
         span.point = point;
         span
     }
+
+    pub fn trace_ctx(&self) -> TraceCtx {
+        TraceCtx {
+            trace: self.trace.clone(),
+            span: self.id.clone(),
+        }
+    }
+
+    fn spannable<S>(mut self, spannable: &S) -> Self
+    where
+        S: Spannable,
+    {
+        self.attributes
+            .insert("type".to_string(), spannable.span_type().to_string());
+        self.attributes
+            .insert("id".to_string(), spannable.span_id().to_string());
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -160,6 +212,105 @@ pub struct PointlessLog {
     level: Level,
 }
 
+impl LogSubstance {
+    /// the point that produced this log (`None` for a `PointlessLog`)
+    pub fn point(&self) -> Option<&Point> {
+        match self {
+            LogSubstance::Log(log) => Some(&log.point),
+            LogSubstance::Span(span) => Some(&span.point),
+            LogSubstance::Event(event) => Some(&event.point),
+            LogSubstance::Audit(audit) => Some(&audit.point),
+            LogSubstance::Pointless(_) => None,
+        }
+    }
+
+    /// millis since the epoch
+    pub fn timestamp(&self) -> i64 {
+        match self {
+            LogSubstance::Log(log) => log.timestamp,
+            LogSubstance::Span(span) => span.entry_timestamp.millis,
+            LogSubstance::Event(event) => event.timestamp.millis,
+            LogSubstance::Audit(audit) => audit.timestamp.millis,
+            LogSubstance::Pointless(log) => log.timestamp.millis,
+        }
+    }
+
+    pub fn span(&self) -> Option<&Uuid> {
+        match self {
+            LogSubstance::Log(log) => log.span.as_ref(),
+            LogSubstance::Span(span) => Some(&span.id),
+            LogSubstance::Event(event) => Some(&event.span),
+            LogSubstance::Audit(_) => None,
+            LogSubstance::Pointless(_) => None,
+        }
+    }
+
+    /// only messages carry a level
+    pub fn level(&self) -> Option<&Level> {
+        match self {
+            LogSubstance::Log(log) => Some(&log.level),
+            LogSubstance::Pointless(log) => Some(&log.level),
+            _ => None,
+        }
+    }
+}
+
+/// selects stored logs from `GLOBAL::logger`. Every criteria that is set must match;
+/// `level` matches messages of that level or more severe and `from`/`to` are an
+/// inclusive range of millis since the epoch.  The point `selector` is resolved by the
+/// logger since it requires the kinds of the log's point hierarchy
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub struct LogQuery {
+    pub selector: Option<Selector>,
+    pub level: Option<Level>,
+    pub span: Option<Uuid>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    /// return at most this many of the most recent matches
+    pub limit: Option<usize>,
+}
+
+impl LogQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// true if `log` matches every criteria except the `selector`
+    pub fn matches(&self, log: &LogSubstance) -> bool {
+        if let Some(level) = &self.level {
+            match log.level() {
+                Some(l) if l >= level => {}
+                _ => return false,
+            }
+        }
+        if let Some(span) = &self.span {
+            if log.span() != Some(span) {
+                return false;
+            }
+        }
+        let timestamp = log.timestamp();
+        if let Some(from) = self.from {
+            if timestamp < from {
+                return false;
+            }
+        }
+        if let Some(to) = self.to {
+            if timestamp > to {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn to_bin(&self) -> Result<Bin, SpaceErr> {
+        Ok(Arc::new(bincode::serialize(self)?))
+    }
+
+    pub fn from_bin(bin: &[u8]) -> Result<Self, SpaceErr> {
+        Ok(bincode::deserialize(bin)?)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub enum LogPayload {
     Message(String),
@@ -344,6 +495,7 @@ pub trait LogAppender: Send + Sync {
 pub struct RootLogger {
     source: LogSource,
     appender: Arc<dyn LogAppender>,
+    tracks: Tracks,
 }
 
 impl Default for RootLogger {
@@ -354,7 +506,11 @@ impl Default for RootLogger {
 
 impl RootLogger {
     pub fn new(source: LogSource, appender: Arc<dyn LogAppender>) -> Self {
-        Self { source, appender }
+        Self {
+            source,
+            appender,
+            tracks: Tracks::default(),
+        }
     }
 
     pub fn stdout(source: LogSource) -> Self {
@@ -368,6 +524,23 @@ impl RootLogger {
         self.source.clone()
     }
 
+    /// a logger of the same source whose appender is `f` applied to this logger's appender
+    pub fn wrap<F>(&self, f: F) -> RootLogger
+    where
+        F: FnOnce(Arc<dyn LogAppender>) -> Arc<dyn LogAppender>,
+    {
+        RootLogger {
+            source: self.source.clone(),
+            appender: f(self.appender.clone()),
+            tracks: self.tracks.clone(),
+        }
+    }
+
+    /// the runtime tracking rules shared by every logger derived from this one
+    pub fn tracks(&self) -> &Tracks {
+        &self.tracks
+    }
+
     fn log(&self, log: Log) {
         self.appender.log(log);
     }
@@ -572,21 +745,12 @@ impl PointLogger {
     }
 
     pub fn span(&self) -> SpanLogger {
-        let span = LogSpan::new(self.point.clone());
-        let logger = SpanLogger {
-            root_logger: self.logger.clone(),
-            span: span.clone(),
-            commit_on_drop: true,
-        };
-
-        self.logger.span_event(LogSpanEvent::new(
-            &span,
-            &self.point,
-            LogSpanEventKind::Entry,
-            Default::default(),
-        ));
+        SpanLogger::enter(self.logger.clone(), LogSpan::new(self.point.clone()))
+    }
 
-        logger
+    /// a span continuing the trace of `ctx` (usually received with a wave)
+    pub fn trace_span(&self, ctx: &TraceCtx) -> SpanLogger {
+        SpanLogger::enter(self.logger.clone(), LogSpan::child(self.point.clone(), ctx))
     }
 
     pub fn span_async(&self) -> SpanLogger {
@@ -595,16 +759,16 @@ impl PointLogger {
         span
     }
 
+    /// a span for `spannable` that continues its trace if it carries one
     pub fn spanner<S>(&self, spannable: &S) -> SpanLogger
     where
         S: Spannable,
     {
-        let logger = self.span();
-        let mut attrs = HashMap::new();
-        attrs.insert("type".to_string(), spannable.span_type().to_string());
-        attrs.insert("id".to_string(), spannable.span_id().to_string());
-        logger.span_attr(attrs);
-        logger
+        let span = match spannable.trace_ctx() {
+            Some(ctx) => LogSpan::child(self.point.clone(), &ctx),
+            None => LogSpan::new(self.point.clone()),
+        };
+        SpanLogger::enter(self.logger.clone(), span.spannable(spannable))
     }
 
     pub fn point(&self, point: Point) -> PointLogger {
@@ -780,8 +944,7 @@ impl PointLogger {
         T: Trackable,
         F: FnOnce() -> Tracker,
     {
-        if trackable.track() {
-            let tracker = f();
+        if let Some(tracker) = self.tracker(trackable, f) {
             self.msg(tracker.level.clone(), trackable.track_fmt(&tracker));
         }
     }
@@ -793,8 +956,7 @@ impl PointLogger {
         M: FnOnce() -> S,
         S: ToString,
     {
-        if trackable.track() {
-            let tracker = f();
+        if let Some(tracker) = self.tracker(trackable, f) {
             let message = m().to_string();
             self.msg(
                 tracker.level.clone(),
@@ -802,6 +964,26 @@ impl PointLogger {
             );
         }
     }
+
+    /// a `Tracker` if `trackable` asked to be tracked or a runtime `TrackRule` matches it
+    fn tracker<T, F>(&self, trackable: &T, f: F) -> Option<Tracker>
+    where
+        T: Trackable,
+        F: FnOnce() -> Tracker,
+    {
+        if trackable.track() {
+            Some(f())
+        } else if self.logger.tracks.is_empty() {
+            None
+        } else {
+            let tracker = f();
+            if self.logger.tracks.matches(trackable, &tracker) {
+                Some(tracker)
+            } else {
+                None
+            }
+        }
+    }
 }
 
 pub struct SpanLogBuilder {
@@ -826,43 +1008,52 @@ pub struct SpanLogger {
 }
 
 impl SpanLogger {
+    /// log the `Entry` of `span` & return its logger, which logs the `Exit` when dropped
+    fn enter(root_logger: RootLogger, span: LogSpan) -> SpanLogger {
+        root_logger.span_event(LogSpanEvent::new(
+            &span,
+            &span.point,
+            LogSpanEventKind::Entry,
+            span.attributes.clone(),
+        ));
+        SpanLogger {
+            root_logger,
+            span,
+            commit_on_drop: true,
+        }
+    }
+
     pub fn span_uuid(&self) -> Uuid {
         self.span.id.clone()
     }
 
+    pub fn trace_ctx(&self) -> TraceCtx {
+        self.span.trace_ctx()
+    }
+
     pub fn point(&self) -> &Point {
         &self.span.point
     }
 
+    /// a child of this span
     pub fn span(&self) -> SpanLogger {
-        let span = LogSpan::new(self.point().clone());
-        SpanLogger {
-            root_logger: self.root_logger.clone(),
-            span,
-            commit_on_drop: true,
-        }
+        let span = LogSpan::child(self.point().clone(), &self.trace_ctx());
+        SpanLogger::enter(self.root_logger.clone(), span)
     }
 
     pub fn spanner<S>(&self, spannable: &S) -> SpanLogger
     where
         S: Spannable,
     {
-        let logger = self.span();
-        let mut attrs = HashMap::new();
-        attrs.insert("type".to_string(), spannable.span_type().to_string());
-        attrs.insert("id".to_string(), spannable.span_id().to_string());
-        logger.span_attr(attrs);
-        logger
+        let ctx = spannable.trace_ctx().unwrap_or_else(|| self.trace_ctx());
+        let span = LogSpan::child(self.point().clone(), &ctx).spannable(spannable);
+        SpanLogger::enter(self.root_logger.clone(), span)
     }
 
     pub fn span_attr(&self, attr: HashMap<String, String>) -> SpanLogger {
-        let mut span = LogSpan::new(self.point().clone());
+        let mut span = LogSpan::child(self.point().clone(), &self.trace_ctx());
         span.attributes = attr;
-        SpanLogger {
-            root_logger: self.root_logger.clone(),
-            span,
-            commit_on_drop: true,
-        }
+        SpanLogger::enter(self.root_logger.clone(), span)
     }
 
     pub fn span_async(&self) -> SpanLogger {
@@ -1120,6 +1311,11 @@ pub struct AuditLog {
 pub trait Spannable {
     fn span_id(&self) -> String;
     fn span_type(&self) -> &'static str;
+
+    /// the trace this spannable belongs to, if any
+    fn trace_ctx(&self) -> Option<TraceCtx> {
+        None
+    }
 }
 
 pub trait Trackable {
@@ -1130,6 +1326,16 @@ pub trait Trackable {
     fn track_to(&self) -> String;
     fn track(&self) -> bool;
 
+    /// the points this is headed for, which runtime `TrackRule` selectors are matched against
+    fn track_to_points(&self) -> Vec<Point> {
+        vec![]
+    }
+
+    /// the wave a transport or hop carries, which runtime `TrackRule`s also match against
+    fn track_carried(&self) -> Option<&UltraWave> {
+        None
+    }
+
     fn track_payload_fmt(&self) -> String {
         self.track_payload()
     }
@@ -1174,38 +1380,88 @@ impl Tracker {
 pub type Track = TrackDef<String>;
 pub type TrackRegex = TrackDef<Regex>;
 
+/// selects the waves to track: `selector` is matched against the destination point,
+/// `method` against the wave's method (i.e. `Http<Post>`) and `stop` & `action` against
+/// the `Tracker` of each stop along the way
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
 pub struct TrackDef<R> {
     selector: Selector,
+    method: R,
     stop: R,
     action: R,
 }
 
 impl TrackRegex {
+    /// track waves of any method
     pub fn new<S: ToString>(selector: S, stop: S, action: S) -> Result<Self, SpaceErr> {
+        Self::with_method(selector.to_string(), ".*".to_string(), stop.to_string(), action.to_string())
+    }
+
+    pub fn with_method<S: ToString>(
+        selector: S,
+        method: S,
+        stop: S,
+        action: S,
+    ) -> Result<Self, SpaceErr> {
         let selector = Selector::from_str(selector.to_string().as_str())?;
+        let method = Regex::from_str(method.to_string().as_str())?;
         let stop = Regex::from_str(stop.to_string().as_str())?;
         let action = Regex::from_str(action.to_string().as_str())?;
 
         Ok(Self {
             selector,
+            method,
             stop,
             action,
         })
     }
+
+    pub fn matches<T: Trackable>(&self, trackable: &T, tracker: &Tracker) -> bool {
+        if !self.stop.is_match(tracker.parsec.as_str())
+            || !self.action.is_match(tracker.action.as_str())
+        {
+            return false;
+        }
+        self.selects(trackable)
+    }
+
+    fn selects<T: Trackable>(&self, trackable: &T) -> bool {
+        let selected = self.method.is_match(trackable.track_method().as_str())
+            && trackable
+                .track_to_points()
+                .iter()
+                .any(|point| self.selector.matches_point(point));
+        selected
+            || trackable
+                .track_carried()
+                .map_or(false, |carried| self.selects(carried))
+    }
 }
 
 impl TrackDef<String> {
+    /// track waves of any method
     pub fn new<S: ToString>(selector: S, stop: S, action: S) -> Result<Self, SpaceErr> {
+        Self::with_method(selector.to_string(), ".*".to_string(), stop.to_string(), action.to_string())
+    }
+
+    pub fn with_method<S: ToString>(
+        selector: S,
+        method: S,
+        stop: S,
+        action: S,
+    ) -> Result<Self, SpaceErr> {
         let selector = Selector::from_str(selector.to_string().as_str())?;
+        Regex::from_str(method.to_string().as_str())?;
         Regex::from_str(stop.to_string().as_str())?;
         Regex::from_str(action.to_string().as_str())?;
 
+        let method = method.to_string();
         let stop = stop.to_string();
         let action = action.to_string();
 
         Ok(Self {
             selector,
+            method,
             stop,
             action,
         })
@@ -1214,8 +1470,77 @@ impl TrackDef<String> {
     pub fn to_regex(&self) -> Result<TrackRegex, SpaceErr> {
         Ok(TrackRegex {
             selector: self.selector.clone(),
+            method: Regex::from_str(self.method.as_str())?,
             stop: Regex::from_str(self.stop.as_str())?,
             action: Regex::from_str(self.action.as_str())?,
         })
     }
 }
+
+/// the body of a `Cmd<Track>`: log every stop matching `track` for `ttl` seconds
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct TrackRule {
+    pub track: Track,
+    pub ttl: u64,
+}
+
+impl TrackRule {
+    pub fn new(track: Track, ttl: u64) -> Self {
+        Self { track, ttl }
+    }
+
+    pub fn to_bin(&self) -> Result<Bin, SpaceErr> {
+        Ok(Arc::new(bincode::serialize(self)?))
+    }
+
+    pub fn from_bin(bin: &[u8]) -> Result<Self, SpaceErr> {
+        Ok(bincode::deserialize(bin)?)
+    }
+}
+
+/// the tracking rules installed at runtime, each with the timestamp (millis) it expires at.
+/// Clones share the same rules
+#[derive(Clone, Default)]
+pub struct Tracks {
+    rules: Arc<RwLock<Vec<(TrackRegex, i64)>>>,
+}
+
+impl Tracks {
+    pub fn add(&self, rule: &TrackRule) -> Result<(), SpaceErr> {
+        let track = rule.track.to_regex()?;
+        // a ttl too large to represent simply never expires
+        let ttl = i64::try_from(rule.ttl).unwrap_or(i64::MAX).saturating_mul(1000);
+        let expires = timestamp().millis.saturating_add(ttl);
+        let mut rules = self.rules.write()?;
+        let now = timestamp().millis;
+        rules.retain(|(_, expires)| *expires > now);
+        rules.push((track, expires));
+        Ok(())
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut rules) = self.rules.write() {
+            rules.clear();
+        }
+    }
+
+    /// true when no rule is live
+    pub fn is_empty(&self) -> bool {
+        let now = timestamp().millis;
+        match self.rules.read() {
+            Ok(rules) => rules.iter().all(|(_, expires)| *expires <= now),
+            Err(_) => true,
+        }
+    }
+
+    /// expired rules never match, they are dropped when the next rule is added
+    pub fn matches<T: Trackable>(&self, trackable: &T, tracker: &Tracker) -> bool {
+        let now = timestamp().millis;
+        match self.rules.read() {
+            Ok(rules) => rules
+                .iter()
+                .any(|(track, expires)| *expires > now && track.matches(trackable, tracker)),
+            Err(_) => false,
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+lazy_static! {
+    /// the registry every part of a process records its metrics in
+    pub static ref METRICS: Metrics = Metrics::new();
+}
+
+/// upper bounds (in seconds) of the buckets of every `Histogram`
+pub const HISTOGRAM_BUCKETS: [f64; 12] = [
+    0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0, 30.0,
+];
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, strum_macros::Display)]
+pub enum MetricKind {
+    #[strum(serialize = "counter")]
+    Counter,
+    #[strum(serialize = "gauge")]
+    Gauge,
+    #[strum(serialize = "histogram")]
+    Histogram,
+}
+
+/// counters, gauges & histograms grouped into families by name & rendered in the
+/// Prometheus text exposition format.  A series is created the first time its name &
+/// labels are asked for; asking for an existing name as a different kind returns a
+/// series that is never rendered
+#[derive(Clone)]
+pub struct Metrics {
+    families: Arc<Mutex<BTreeMap<String, Family>>>,
+}
+
+struct Family {
+    help: String,
+    kind: MetricKind,
+    series: BTreeMap<String, Series>,
+}
+
+#[derive(Clone)]
+enum Series {
+    Counter(Counter),
+    Gauge(Gauge),
+    Histogram(Histogram),
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            families: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    pub fn counter(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Counter {
+        match self.series(name, help, MetricKind::Counter, labels, || {
+            Series::Counter(Counter::new())
+        }) {
+            Some(Series::Counter(counter)) => counter,
+            _ => Counter::new(),
+        }
+    }
+
+    pub fn gauge(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Gauge {
+        match self.series(name, help, MetricKind::Gauge, labels, || {
+            Series::Gauge(Gauge::new())
+        }) {
+            Some(Series::Gauge(gauge)) => gauge,
+            _ => Gauge::new(),
+        }
+    }
+
+    pub fn histogram(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Histogram {
+        match self.series(name, help, MetricKind::Histogram, labels, || {
+            Series::Histogram(Histogram::new())
+        }) {
+            Some(Series::Histogram(histogram)) => histogram,
+            _ => Histogram::new(),
+        }
+    }
+
+    fn series<F>(
+        &self,
+        name: &str,
+        help: &str,
+        kind: MetricKind,
+        labels: &[(&str, &str)],
+        f: F,
+    ) -> Option<Series>
+    where
+        F: FnOnce() -> Series,
+    {
+        let mut families = self.families.lock().ok()?;
+        let family = families.entry(name.to_string()).or_insert_with(|| Family {
+            help: help.to_string(),
+            kind,
+            series: BTreeMap::new(),
+        });
+        if family.kind != kind {
+            return None;
+        }
+        Some(
+            family
+                .series
+                .entry(render_labels(labels))
+                .or_insert_with(f)
+                .clone(),
+        )
+    }
+
+    /// every family in the Prometheus text exposition format (version 0.0.4)
+    pub fn to_prometheus(&self) -> String {
+        let families = match self.families.lock() {
+            Ok(families) => families,
+            Err(_) => return String::new(),
+        };
+        let mut rtn = String::new();
+        for (name, family) in families.iter() {
+            rtn.push_str(format!("# HELP {} {}\n", name, escape_help(&family.help)).as_str());
+            rtn.push_str(format!("# TYPE {} {}\n", name, family.kind.to_string()).as_str());
+            for (labels, series) in family.series.iter() {
+                match series {
+                    Series::Counter(counter) => {
+                        rtn.push_str(format!("{}{} {}\n", name, labels, counter.get()).as_str());
+                    }
+                    Series::Gauge(gauge) => {
+                        rtn.push_str(format!("{}{} {}\n", name, labels, gauge.get()).as_str());
+                    }
+                    Series::Histogram(histogram) => histogram.render(name, labels, &mut rtn),
+                }
+            }
+        }
+        rtn
+    }
+}
+
+#[derive(Clone)]
+pub struct Counter {
+    value: Arc<AtomicU64>,
+}
+
+impl Counter {
+    fn new() -> Self {
+        Self {
+            value: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, count: u64) {
+        self.value.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Clone)]
+pub struct Gauge {
+    value: Arc<AtomicI64>,
+}
+
+impl Gauge {
+    fn new() -> Self {
+        Self {
+            value: Arc::new(AtomicI64::new(0)),
+        }
+    }
+
+    pub fn set(&self, value: i64) {
+        self.value.store(value, Ordering::Relaxed);
+    }
+
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn dec(&self) {
+        self.add(-1);
+    }
+
+    pub fn add(&self, delta: i64) {
+        self.value.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+/// observations in seconds, bucketed by `HISTOGRAM_BUCKETS`
+#[derive(Clone)]
+pub struct Histogram {
+    data: Arc<Mutex<HistogramData>>,
+}
+
+struct HistogramData {
+    buckets: [u64; HISTOGRAM_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            data: Arc::new(Mutex::new(HistogramData {
+                buckets: [0; HISTOGRAM_BUCKETS.len()],
+                count: 0,
+                sum: 0.0,
+            })),
+        }
+    }
+
+    pub fn observe(&self, seconds: f64) {
+        if let Ok(mut data) = self.data.lock() {
+            if let Some(index) = HISTOGRAM_BUCKETS.iter().position(|le| seconds <= *le) {
+                data.buckets[index] += 1;
+            }
+            data.count += 1;
+            data.sum += seconds;
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.data.lock().map(|data| data.count).unwrap_or_default()
+    }
+
+    fn render(&self, name: &str, labels: &str, rtn: &mut String) {
+        let data = match self.data.lock() {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        let mut cumulative = 0;
+        for (le, count) in HISTOGRAM_BUCKETS.iter().zip(data.buckets.iter()) {
+            cumulative += count;
+            rtn.push_str(
+                format!(
+                    "{}_bucket{} {}\n",
+                    name,
+                    with_le(labels, le.to_string().as_str()),
+                    cumulative
+                )
+                .as_str(),
+            );
+        }
+        rtn.push_str(
+            format!("{}_bucket{} {}\n", name, with_le(labels, "+Inf"), data.count).as_str(),
+        );
+        rtn.push_str(format!("{}_sum{} {}\n", name, labels, data.sum).as_str());
+        rtn.push_str(format!("{}_count{} {}\n", name, labels, data.count).as_str());
+    }
+}
+
+pub(crate) fn render_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape_label(value)))
+        .collect();
+    format!("{{{}}}", labels.join(","))
+}
+
+fn with_le(labels: &str, le: &str) -> String {
+    match labels.strip_suffix('}') {
+        Some(labels) => format!("{},le=\"{}\"}}", labels, le),
+        None => format!("{{le=\"{}\"}}", le),
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::metrics::Metrics;
+
+    #[test]
+    pub fn test_prometheus() {
+        let metrics = Metrics::new();
+        metrics
+            .counter("cosmic_waves_total", "waves", &[("layer", "Core")])
+            .add(3);
+        metrics.gauge("cosmic_queued", "queued", &[]).set(-2);
+        let histogram = metrics.histogram("cosmic_latency_seconds", "latency", &[("a", "\"b\"")]);
+        histogram.observe(0.002);
+        histogram.observe(60.0);
+
+        // a name already registered as another kind is not rendered twice
+        metrics.gauge("cosmic_waves_total", "waves", &[]).set(7);
+
+        let text = metrics.to_prometheus();
+        assert!(text.contains("# TYPE cosmic_waves_total counter\n"));
+        assert!(text.contains("cosmic_waves_total{layer=\"Core\"} 3\n"));
+        assert!(!text.contains(" 7\n"));
+        assert!(text.contains("cosmic_queued -2\n"));
+        assert!(text.contains("cosmic_latency_seconds_bucket{a=\"\\\"b\\\"\",le=\"0.005\"} 1\n"));
+        assert!(text.contains("cosmic_latency_seconds_bucket{a=\"\\\"b\\\"\",le=\"30\"} 1\n"));
+        assert!(text.contains("cosmic_latency_seconds_bucket{a=\"\\\"b\\\"\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("cosmic_latency_seconds_count{a=\"\\\"b\\\"\"} 2\n"));
+    }
+}
@@ -1772,11 +1772,19 @@ pub fn get<I: Span>(input: I) -> Res<I, GetVar> {
 }
 
 pub fn select<I: Span>(input: I) -> Res<I, SelectVar> {
-    point_selector(input).map(|(next, point_kind_pattern)| {
+    tuple((
+        point_selector,
+        opt(tuple((space1, tag("into"), space1, tag("records")))),
+    ))(input)
+    .map(|(next, (point_kind_pattern, records))| {
+        let into_substance = match records {
+            None => SelectIntoSubstance::Stubs,
+            Some(_) => SelectIntoSubstance::Records,
+        };
         let select = SelectVar {
             pattern: point_kind_pattern,
             properties: Default::default(),
-            into_substance: SelectIntoSubstance::Stubs,
+            into_substance,
             kind: SelectKind::Initial,
         };
         (next, select)
@@ -6475,8 +6483,27 @@ pub fn text_payload_block<I:Span>(input: Span) -> Res<Span, PayloadBlock> {
     })
 }*/
 
+/// a local file or directory path, i.e. `bundle.zip` or `./bundle/`
+pub fn upload_path_chars<T: Span>(i: T) -> Res<T, T>
+where
+    T: InputTakeAtPosition + nom::InputLength,
+    <T as InputTakeAtPosition>::Item: AsChar,
+{
+    i.split_at_position1_complete(
+        |item| {
+            let char_item = item.as_char();
+            !(char_item == '-')
+                && !(char_item == '.')
+                && !(char_item == '_')
+                && !(char_item == '/')
+                && !(char_item.is_alpha() || char_item.is_dec_digit())
+        },
+        ErrorKind::AlphaNumeric,
+    )
+}
+
 pub fn upload_payload_block<I: Span>(input: I) -> Res<I, UploadBlock> {
-    delimited(multispace0, file_chars, multispace0)(input).map(|(next, filename)| {
+    delimited(multispace0, upload_path_chars, multispace0)(input).map(|(next, filename)| {
         (
             next,
             UploadBlock {
@@ -7283,7 +7310,7 @@ pub mod test {
         lex_nested_block, lex_scope, lex_scope_pipeline_step_and_block, lex_scope_selector,
         lex_scopes, lowercase1, mesh_eos, mesh_seg, nested_block, nested_block_content,
         next_stacked_name, no_comment, parse_bind_config, parse_include_blocks, parse_inner_block,
-        parse_mechtron_config, path_regex, pipeline, pipeline_segment, pipeline_step_var,
+        mechtron_config, parse_mechtron_config, path_regex, pipeline, pipeline_segment, pipeline_step_var,
         pipeline_stop_var, point_non_root_var, point_template, point_var, pop, rec_version,
         root_ctx_seg, root_scope, root_scope_selector, route_attribute, route_selector,
         scope_filter, scope_filters, skewer_case_chars, skewer_dot, space_chars,
@@ -7314,6 +7341,26 @@ pub mod test {
         }
     }
 
+    #[test]
+    pub fn test_mechtron_config_limits() {
+        let config = r#"Mechtron(version=1.0.0) {
+                              Wasm {
+                                bin=some:bin:somewhere
+                                name=freddy
+                                fuel=1000000
+                                max-memory-pages=64
+                                call-timeout=5
+                              }
+                             }
+
+         "#;
+
+        let config = log(mechtron_config(config)).unwrap();
+        assert_eq!(config.limits.fuel, Some(1000000));
+        assert_eq!(config.limits.max_memory_pages, Some(64));
+        assert_eq!(config.limits.call_timeout, Some(5));
+    }
+
     #[test]
     pub fn test_message_selector() {
         let route =
@@ -8409,6 +8456,73 @@ pub fn script<I: Span>(input: I) -> Res<I, Vec<CommandVar>> {
     many0(script_line)(input)
 }
 
+/// a `;` terminated statement of a script, the line it starts on & its byte offset
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ScriptLine {
+    pub line: u32,
+    pub offset: usize,
+    pub text: String,
+}
+
+/// split a script into statements, validating each as a command.
+/// Unlike `script` a bad statement does not end the parse: parsing resumes after the
+/// next `;` so every bad statement is reported in the returned `ParseErrs`.
+/// An `exit;` statement ends the script
+pub fn script_lines(src: &str) -> Result<Vec<ScriptLine>, SpaceErr> {
+    let span = new_span(src);
+    let mut lines = vec![];
+    let mut errs = vec![];
+    let mut offset = 0;
+    loop {
+        let rest = &src[offset..];
+        let trimmed = rest.trim_start();
+        if trimmed.is_empty() {
+            break;
+        }
+        if trimmed == "exit" || trimmed.starts_with("exit;") || trimmed.starts_with("exit ") {
+            break;
+        }
+        let input = span.slice(offset..);
+        match script_line(input.clone()) {
+            Ok((next, _)) => {
+                let start = offset + (rest.len() - trimmed.len());
+                let end = next.location_offset();
+                let statement = src[start..end].trim();
+                let text = statement.strip_suffix(';').unwrap_or(statement).trim();
+                // the offset of `text` itself, past any whitespace the trims removed
+                let text_offset =
+                    start + (src[start..end].len() - src[start..end].trim_start().len());
+                lines.push(ScriptLine {
+                    line: span.slice(text_offset..).location_line(),
+                    offset: text_offset,
+                    text: text.to_string(),
+                });
+                offset = end;
+            }
+            Err(err) => {
+                match find_parse_err(&err) {
+                    SpaceErr::ParseErrs(parse_errs) => errs.push(parse_errs),
+                    SpaceErr::Status { message, .. } => errs.push(
+                        match ParseErrs::from_loc_span(message.as_str(), "here", input) {
+                            SpaceErr::ParseErrs(parse_errs) => parse_errs,
+                            _ => unreachable!(),
+                        },
+                    ),
+                }
+                match rest.find(';') {
+                    Some(index) => offset = offset + index + 1,
+                    None => break,
+                }
+            }
+        }
+    }
+    if errs.is_empty() {
+        Ok(lines)
+    } else {
+        Err(ParseErrs::fold(errs).into())
+    }
+}
+
 pub fn consume_command_line<I: Span>(input: I) -> Res<I, CommandVar> {
     all_consuming(command_line)(input)
 }
@@ -8429,8 +8543,11 @@ pub mod cmd_test {
     use crate::command::{Command, CommandVar};
     use crate::err::SpaceErr;
     use crate::parse::error::result;
+    use crate::command::direct::select::SelectIntoSubstance;
+    use crate::loc::Point;
     use crate::parse::{
-        command, create_command, publish_command, script, upload_blocks, CamelCase,
+        command, command_line, create_command, publish_command, script, script_lines,
+        upload_blocks, CamelCase, Env,
     };
     use crate::util::ToResolved;
     use crate::{BaseKind, KindTemplate, SetProperties};
@@ -8500,10 +8617,52 @@ pub mod cmd_test {
         Ok(())
     }
 
+    #[test]
+    pub fn test_script_lines() -> Result<(), SpaceErr> {
+        let input = r#"create? localhost<Space>;
+create? localhost:repo<Base<Repo>>;
+set localhost{ +bind=localhost:repo:tutorial:1.0.0:/bind/localhost.bind };
+exit;
+create localhost:never<Space>;
+"#;
+        let lines = script_lines(input)?;
+        assert_eq!(3, lines.len());
+        assert_eq!("create? localhost<Space>", lines[0].text.as_str());
+        assert_eq!(3, lines[2].line);
+
+        // each offset points at the text of its statement, past any leading whitespace
+        let input = "  create? localhost<Space>;\n\n\t  create? localhost:repo<Base<Repo>>  ;  ";
+        let lines = script_lines(input)?;
+        assert_eq!(2, lines.len());
+        for line in lines.iter() {
+            assert_eq!(
+                &input[line.offset..line.offset + line.text.len()],
+                line.text.as_str()
+            );
+        }
+        assert_eq!(3, lines[1].line);
+
+        let input = r#"create? localhost<Space>;
+Xcrete localhost:repo<Base<Repo>>;
+create? localhost:repo:tutorial<ArtifactBundleSeries>;
+Xublish ^[ bundle.zip ]-> localhost:repo:tutorial:1.0.0;
+"#;
+        match script_lines(input) {
+            Err(SpaceErr::ParseErrs(errs)) => assert!(errs.report.len() >= 2),
+            _ => panic!("expected ParseErrs for both bad lines"),
+        }
+        Ok(())
+    }
+
     #[test]
     pub fn test_publish() -> Result<(), SpaceErr> {
         let input = r#"publish ^[ bundle.zip ]-> localhost:repo:tutorial:1.0.0"#;
         publish_command(new_span(input))?;
+
+        let input = r#"publish ^[ ./bundle/ ]-> localhost:repo:tutorial:1.0.0"#;
+        publish_command(new_span(input))?;
+        let blocks = result(upload_blocks(new_span(input)))?;
+        assert_eq!("./bundle/", blocks[0].name.as_str());
         Ok(())
     }
 
@@ -8560,6 +8719,53 @@ pub mod cmd_test {
 
         Ok(())
     }
+
+    #[test]
+    pub fn test_select_into_records() -> Result<(), SpaceErr> {
+        // the original grammar without an `into` clause still selects Stubs
+        let mut command = result(command_line(new_span("select localhost:app:**")))?;
+        let stubs = match command.collapse()? {
+            Command::Select(select) => select,
+            _ => panic!("expected a select"),
+        };
+        assert_eq!(stubs.into_substance, SelectIntoSubstance::Stubs);
+
+        let mut command = result(command_line(new_span("select localhost:app:** into records;")))?;
+        let records = match command.collapse()? {
+            Command::Select(select) => select,
+            _ => panic!("expected a select"),
+        };
+        assert_eq!(records.into_substance, SelectIntoSubstance::Records);
+        assert_eq!(records.pattern, stubs.pattern);
+
+        // render the selector back out & parse it again
+        for (select, into) in [(&stubs, ""), (&records, " into records")] {
+            let line = format!("select {}{}", select.pattern.to_string(), into);
+            let mut command = result(command_line(new_span(line.as_str())))?;
+            match command.collapse()? {
+                Command::Select(reparsed) => assert_eq!(&reparsed, select),
+                _ => panic!("expected a select"),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_relative_command() -> Result<(), SpaceErr> {
+        // the shell resolves relative points against the working point of `RawCommand`
+        let env = Env::new(Point::from_str("localhost:app")?);
+        let absolute: Command =
+            result(command_line(new_span("get localhost:app:child")))?.to_resolved(&env)?;
+        let relative: Command = result(command_line(new_span("get .:child")))?.to_resolved(&env)?;
+        assert_eq!(relative, absolute);
+
+        let parent: Command = result(command_line(new_span("get ..")))?.to_resolved(&env)?;
+        let localhost: Command =
+            result(command_line(new_span("get localhost")))?.to_resolved(&env)?;
+        assert_eq!(parent, localhost);
+        Ok(())
+    }
 }
 
 pub fn layer<I: Span>(input: I) -> Res<I, Layer> {
@@ -1,5 +1,6 @@
 use core::str::FromStr;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use nom::bytes::complete::tag;
 use nom::combinator::all_consuming;
@@ -11,7 +12,7 @@ use crate::kind::{Kind, KindParts};
 use crate::loc::{PointCtx, PointVar};
 use crate::parse::error::result;
 use crate::parse::{parse_alpha1_str, point_and_kind, Env};
-use crate::substance::Substance;
+use crate::substance::{Bin, Substance};
 use crate::util::ToResolved;
 use crate::{BaseKind, Point, SpaceErr};
 
@@ -75,6 +76,21 @@ impl ToString for Progress {
     }
 }
 
+impl FromStr for Progress {
+    type Err = SpaceErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (step, total) = s.split_once('/').ok_or(SpaceErr::bad_request(format!(
+            "expected Progress 'step/total' encountered: '{}'",
+            s
+        )))?;
+        Ok(Self {
+            step: u16::from_str(step.trim())?,
+            total: u16::from_str(total.trim())?,
+        })
+    }
+}
+
 pub fn ok_code<I: Span>(input: I) -> Res<I, Code> {
     tag("Ok")(input).map(|(next, code)| (next, Code::Ok))
 }
@@ -243,6 +259,20 @@ pub struct Watch {
     pub aspect: Aspect,
 }
 
+impl Watch {
+    pub fn new(point: Point, aspect: Aspect) -> Self {
+        Self { point, aspect }
+    }
+
+    pub fn to_bin(&self) -> Result<Bin, SpaceErr> {
+        Ok(Arc::new(bincode::serialize(self)?))
+    }
+
+    pub fn from_bin(bin: &[u8]) -> Result<Self, SpaceErr> {
+        Ok(bincode::deserialize(bin)?)
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, strum_macros::Display)]
 pub enum Aspect {
     Log,
@@ -4,6 +4,7 @@ use std::ops::Deref;
 
 use validator::validate_email;
 
+use crate::artifact::ArtifactReq;
 use crate::command::common::PropertyMod;
 use crate::loc::Point;
 use crate::parse::SkewerCase;
@@ -84,6 +85,18 @@ impl PropertyPattern for PointPattern {
     }
 }
 
+/// a Point or a bundle version requirement i.e. `repo:hello:^1.0:/config/x.mechtron`
+#[derive(Clone)]
+pub struct ArtifactReqPattern {}
+
+impl PropertyPattern for ArtifactReqPattern {
+    fn is_match(&self, value: &String) -> Result<(), SpaceErr> {
+        use std::str::FromStr;
+        ArtifactReq::from_str(value.as_str())?;
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 pub struct U64Pattern {}
 
@@ -327,7 +340,7 @@ impl PropertiesConfigBuilder {
             kind: None,
             properties: HashMap::new(),
         };
-        rtn.add_point("bind", false, true).unwrap();
+        rtn.add_artifact_req("bind", false, true).unwrap();
         rtn
     }
 
@@ -389,4 +402,37 @@ impl PropertiesConfigBuilder {
         self.properties.insert(name.to_string(), def);
         Ok(())
     }
+
+    pub fn add_artifact_req(
+        &mut self,
+        name: &str,
+        required: bool,
+        mutable: bool,
+    ) -> Result<(), SpaceErr> {
+        let def = PropertyDef::new(
+            Box::new(ArtifactReqPattern {}),
+            required,
+            mutable,
+            PropertySource::Shell,
+            None,
+            false,
+            vec![],
+        )?;
+        self.properties.insert(name.to_string(), def);
+        Ok(())
+    }
+
+    pub fn add_u64(&mut self, name: &str, required: bool, mutable: bool) -> Result<(), SpaceErr> {
+        let def = PropertyDef::new(
+            Box::new(U64Pattern {}),
+            required,
+            mutable,
+            PropertySource::Shell,
+            None,
+            false,
+            vec![],
+        )?;
+        self.properties.insert(name.to_string(), def);
+        Ok(())
+    }
 }
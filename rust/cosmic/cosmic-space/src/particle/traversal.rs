@@ -195,6 +195,14 @@ where
         self.payload.track_to()
     }
 
+    fn track_to_points(&self) -> Vec<Point> {
+        self.payload.track_to_points()
+    }
+
+    fn track_carried(&self) -> Option<&UltraWave> {
+        self.payload.track_carried()
+    }
+
     fn track(&self) -> bool {
         self.payload.track()
     }
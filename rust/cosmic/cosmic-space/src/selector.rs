@@ -291,6 +291,32 @@ impl Selector {
             false
         }
     }
+
+    /// match the segments of `point` alone, as if every hop selected any kind
+    pub fn matches_point(&self, point: &Point) -> bool {
+        let selector = Selector {
+            hops: self
+                .hops
+                .iter()
+                .map(|hop| Hop {
+                    kind_selector: KindSelector::any(),
+                    ..hop.clone()
+                })
+                .collect(),
+        };
+        let hierarchy = PointHierarchy::new(
+            point.route.clone(),
+            point
+                .segments
+                .iter()
+                .map(|segment| PointKindSeg {
+                    segment: segment.clone(),
+                    kind: Kind::Root,
+                })
+                .collect(),
+        );
+        selector.matches(&hierarchy)
+    }
 }
 
 impl ToString for Selector {
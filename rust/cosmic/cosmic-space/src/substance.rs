@@ -114,6 +114,20 @@ pub enum Substance {
 }
 
 impl Substance {
+    /// the first hyperlane protocol revision that can carry this substance
+    pub fn protocol(&self) -> u16 {
+        match self {
+            Substance::UltraWave(wave) => wave.protocol(),
+            Substance::Hyper(HyperSubstance::Interchanges(_)) => 2,
+            // a revision 1 peer would resolve the relative points against its own
+            // working point
+            Substance::RawCommand(command) if command.working.is_some() => 2,
+            Substance::List(list) => list.iter().map(|s| s.protocol()).max().unwrap_or(1),
+            Substance::Map(map) => map.values().map(|s| s.protocol()).max().unwrap_or(1),
+            _ => 1,
+        }
+    }
+
     pub fn ultrawave(&self) -> Option<&UltraWave> {
         if let Substance::UltraWave(wave) = self {
             Some(wave.as_ref())
@@ -30,7 +30,8 @@ use crate::loc::{
     Layer, Point, PointSeg, RouteSeg, Surface, SurfaceSelector, ToPoint, ToSurface, Topic, Uuid,
 };
 use crate::log::{
-    LogSpan, LogSpanEvent, PointLogger, RootLogger, SpanLogger, Spannable, Trackable, TrailSpanId,
+    LogSpan, LogSpanEvent, PointLogger, RootLogger, SpanLogger, Spannable, TraceCtx, Trackable,
+    TrailSpanId,
 };
 use crate::parse::model::Subst;
 use crate::parse::sub;
@@ -169,6 +170,10 @@ impl Trackable for UltraWave {
         self.to().to_string()
     }
 
+    fn track_to_points(&self) -> Vec<Point> {
+        self.to().points()
+    }
+
     fn track(&self) -> bool {
         match self {
             UltraWave::Ping(ping) => ping.track,
@@ -187,6 +192,16 @@ impl Trackable for UltraWave {
             UltraWave::Echo(_) => self.track_payload(),
         }
     }
+
+    fn track_carried(&self) -> Option<&UltraWave> {
+        match self {
+            UltraWave::Signal(signal) => signal.track_carried(),
+            UltraWave::Ping(ping) => ping.track_carried(),
+            UltraWave::Pong(_) => None,
+            UltraWave::Ripple(_) => None,
+            UltraWave::Echo(_) => None,
+        }
+    }
 }
 
 impl<T> UltraWaveDef<T>
@@ -429,6 +444,24 @@ impl UltraWave {
         }
     }
 
+    /// the first hyperlane protocol revision that can carry this wave (and any wave
+    /// it carries)
+    pub fn protocol(&self) -> u16 {
+        let body = self.body().protocol();
+        match self.method() {
+            Some(method) => {
+                let to = self.to();
+                // administration methods are only ever sent to a single control driver
+                let revision = match to.is_single() {
+                    true => method.protocol(&to.unwrap_single().point),
+                    false => method.protocol(&Point::root()),
+                };
+                revision.max(body)
+            }
+            None => body,
+        }
+    }
+
     pub fn is_directed(&self) -> bool {
         match self {
             UltraWave::Ping(_) => true,
@@ -489,6 +522,16 @@ impl UltraWave {
         }
     }
 
+    pub fn set_id(&mut self, id: WaveId) {
+        match self {
+            UltraWave::Ping(ping) => ping.id = id,
+            UltraWave::Pong(pong) => pong.id = id,
+            UltraWave::Ripple(ripple) => ripple.id = id,
+            UltraWave::Echo(echo) => echo.id = id,
+            UltraWave::Signal(signal) => signal.id = id,
+        }
+    }
+
     pub fn set_from(&mut self, from: Surface) {
         match self {
             UltraWave::Ping(ping) => ping.from = from,
@@ -529,6 +572,16 @@ impl UltraWave {
         }
     }
 
+    pub fn trace(&self) -> Option<&TraceCtx> {
+        match self {
+            UltraWave::Ping(ping) => ping.trace.as_ref(),
+            UltraWave::Pong(pong) => pong.trace.as_ref(),
+            UltraWave::Ripple(ripple) => ripple.trace.as_ref(),
+            UltraWave::Echo(echo) => echo.trace.as_ref(),
+            UltraWave::Signal(signal) => signal.trace.as_ref(),
+        }
+    }
+
     pub fn set_track(&mut self, track: bool) {
         match self {
             UltraWave::Ping(ping) => ping.track = track,
@@ -1266,6 +1319,7 @@ pub struct DirectedProto {
     pub bounce_backs: Option<BounceBacks>,
     pub via: Option<Surface>,
     pub track: bool,
+    pub trace: Option<TraceCtx>,
     pub history: HashSet<Point>,
 }
 impl Trackable for DirectedProto {
@@ -1295,6 +1349,13 @@ impl Trackable for DirectedProto {
         }
     }
 
+    fn track_to_points(&self) -> Vec<Point> {
+        match &self.to {
+            None => vec![],
+            Some(to) => to.points(),
+        }
+    }
+
     fn track(&self) -> bool {
         self.track
     }
@@ -1328,6 +1389,7 @@ impl DirectedProto {
                 wave.scope = self.scope.unwrap_or_else(|| Scope::None);
                 wave.via = self.via;
                 wave.track = self.track;
+                wave.trace = self.trace;
                 wave.to_directed()
             }
             DirectedKind::Ripple => {
@@ -1345,6 +1407,7 @@ impl DirectedProto {
                 wave.scope = self.scope.unwrap_or_else(|| Scope::None);
                 wave.via = self.via;
                 wave.track = self.track;
+                wave.trace = self.trace;
                 wave.to_directed()
             }
             DirectedKind::Signal => {
@@ -1363,6 +1426,7 @@ impl DirectedProto {
                 wave.scope = self.scope.unwrap_or_else(|| Scope::None);
                 wave.via = self.via;
                 wave.track = self.track;
+                wave.trace = self.trace;
                 wave.to_directed()
             }
         };
@@ -1418,6 +1482,16 @@ impl DirectedProto {
         }
     }
 
+    pub fn fill_trace(&mut self, trace: &TraceCtx) {
+        if self.trace.is_none() {
+            self.trace.replace(trace.clone());
+        }
+    }
+
+    pub fn trace(&mut self, trace: TraceCtx) {
+        self.trace.replace(trace);
+    }
+
     pub fn agent(&mut self, agent: Agent) {
         self.agent.replace(agent);
     }
@@ -1569,6 +1643,7 @@ impl Default for DirectedProto {
             bounce_backs: None,
             via: None,
             track: false,
+            trace: None,
             history: Default::default(),
         }
     }
@@ -1795,6 +1870,7 @@ impl Into<DirectedProto> for DirectedWave {
         proto.scope(self.scope().clone());
         proto.handling(self.handling().clone());
         proto.track = self.track();
+        proto.trace = self.trace().cloned();
         proto.bounce_backs(self.bounce_backs());
         proto.agent(self.agent().clone());
         if let Some(via) = self.via() {
@@ -1833,6 +1909,10 @@ impl Trackable for DirectedWave {
         self.to().to_string()
     }
 
+    fn track_to_points(&self) -> Vec<Point> {
+        self.to().points()
+    }
+
     fn track(&self) -> bool {
         match self {
             Self::Ping(ping) => ping.track,
@@ -1847,6 +1927,14 @@ impl Trackable for DirectedWave {
             Self::Ripple(_) => self.track_payload(),
         }
     }
+
+    fn track_carried(&self) -> Option<&UltraWave> {
+        match self {
+            Self::Signal(signal) => signal.track_carried(),
+            Self::Ping(ping) => ping.track_carried(),
+            Self::Ripple(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -1908,6 +1996,14 @@ where
     fn span_type(&self) -> &'static str {
         "Wave"
     }
+
+    fn trace_ctx(&self) -> Option<TraceCtx> {
+        match self {
+            DirectedWaveDef::Ping(ping) => ping.trace.clone(),
+            DirectedWaveDef::Ripple(ripple) => ripple.trace.clone(),
+            DirectedWaveDef::Signal(signal) => signal.trace.clone(),
+        }
+    }
 }
 
 impl DirectedWave {
@@ -2011,6 +2107,10 @@ impl Trackable for SingularDirectedWave {
         self.to().to_string()
     }
 
+    fn track_to_points(&self) -> Vec<Point> {
+        vec![self.to().point]
+    }
+
     fn track(&self) -> bool {
         match self {
             Self::Ping(ping) => ping.track,
@@ -2025,6 +2125,14 @@ impl Trackable for SingularDirectedWave {
             Self::Ripple(_) => self.track_payload(),
         }
     }
+
+    fn track_carried(&self) -> Option<&UltraWave> {
+        match self {
+            Self::Signal(signal) => signal.track_carried(),
+            Self::Ping(ping) => ping.track_carried(),
+            Self::Ripple(_) => None,
+        }
+    }
 }
 impl SingularDirectedWave {
     pub fn to(&self) -> Surface {
@@ -2170,6 +2278,14 @@ where
         }
     }
 
+    pub fn trace(&self) -> Option<&TraceCtx> {
+        match self {
+            DirectedWaveDef::Ping(ping) => ping.trace.as_ref(),
+            DirectedWaveDef::Ripple(ripple) => ripple.trace.as_ref(),
+            DirectedWaveDef::Signal(signal) => signal.trace.as_ref(),
+        }
+    }
+
     pub fn reflect_to(&self) -> &Surface {
         self.via().as_ref().unwrap_or(self.from())
     }
@@ -2313,6 +2429,10 @@ impl Trackable for ReflectedWave {
         self.to().to_string()
     }
 
+    fn track_to_points(&self) -> Vec<Point> {
+        vec![self.to().point.clone()]
+    }
+
     fn track(&self) -> bool {
         match self {
             ReflectedWave::Pong(pong) => pong.track,
@@ -2457,6 +2577,16 @@ impl ToRecipients for Recipients {
 }
 
 impl Recipients {
+    /// the points of single & multi recipients
+    pub fn points(&self) -> Vec<Point> {
+        match self {
+            Recipients::Single(surface) => vec![surface.point.clone()],
+            Recipients::Multi(surfaces) => surfaces.iter().map(|s| s.point.clone()).collect(),
+            Recipients::Watchers(_) => vec![],
+            Recipients::Stars => vec![],
+        }
+    }
+
     pub fn to_single(self) -> Result<Surface, SpaceErr> {
         match self {
             Recipients::Single(surface) => Ok(surface),
@@ -2614,6 +2744,12 @@ pub struct Wave<V> {
     pub via: Option<Surface>,
     pub hops: u16,
     pub track: bool,
+    #[serde(
+        default,
+        serialize_with = "crate::hyper::since2::serialize",
+        deserialize_with = "crate::hyper::since2::deserialize"
+    )]
+    pub trace: Option<TraceCtx>,
 }
 
 impl<S, V> ToSubstance<S> for Wave<V>
@@ -2688,6 +2824,10 @@ impl Trackable for Wave<Signal> {
         self.to.to_string()
     }
 
+    fn track_to_points(&self) -> Vec<Point> {
+        vec![self.to.point.clone()]
+    }
+
     fn track(&self) -> bool {
         self.track
     }
@@ -2700,6 +2840,13 @@ impl Trackable for Wave<Signal> {
             _ => self.track_payload(),
         }
     }
+
+    fn track_carried(&self) -> Option<&UltraWave> {
+        match &self.core.body {
+            Substance::UltraWave(wave) => Some(wave.as_ref()),
+            _ => None,
+        }
+    }
 }
 
 impl Wave<Signal> {
@@ -2797,6 +2944,10 @@ impl Trackable for Wave<Ping> {
         self.to.to_string()
     }
 
+    fn track_to_points(&self) -> Vec<Point> {
+        vec![self.to.point.clone()]
+    }
+
     fn track(&self) -> bool {
         self.track
     }
@@ -2809,6 +2960,13 @@ impl Trackable for Wave<Ping> {
             _ => self.track_payload(),
         }
     }
+
+    fn track_carried(&self) -> Option<&UltraWave> {
+        match &self.core.body {
+            Substance::UltraWave(wave) => Some(wave.as_ref()),
+            _ => None,
+        }
+    }
 }
 
 impl Wave<Ping> {
@@ -2909,6 +3067,7 @@ impl<V> Wave<V> {
             from,
             hops: 0,
             track: false,
+            trace: None,
             via: None,
         }
     }
@@ -2927,6 +3086,7 @@ impl<V> Wave<V> {
             from: self.from,
             hops: self.hops,
             track: false,
+            trace: self.trace,
             via: self.via,
         }
     }
@@ -3018,7 +3178,7 @@ impl<V> DerefMut for Wave<V> {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
 pub enum Agent {
     Anonymous,
     HyperUser,
@@ -7,7 +7,7 @@ use cosmic_macros_primitive::Autobox;
 
 use crate::command::Command;
 use crate::err::StatusErr;
-use crate::loc::ToSurface;
+use crate::loc::{Point, ToSurface};
 use crate::substance::FormErrs;
 use crate::util::{ValueMatcher, ValuePattern};
 use crate::wave::core::cmd::CmdMethod;
@@ -266,7 +266,24 @@ pub enum Method {
     Ext(ExtMethod),
 }
 
+/// the last segments of the point of a star's control driver: `<star>:drivers:control`
+pub const CONTROL_DRIVER_SEGMENTS: [&str; 2] = ["drivers", "control"];
+
 impl Method {
+    /// the first hyperlane protocol revision that knows this method when it is sent `to`.
+    /// Any particle may handle an Ext method of the same name so only the administration
+    /// methods of a star's control driver need revision 2
+    pub fn protocol(&self, to: &Point) -> u16 {
+        match self {
+            Method::Cmd(CmdMethod::Track) => 2,
+            Method::Ext(ext) if is_control_driver(to) => match ext.as_str() {
+                "Interchanges" | "Kick" | "Ban" | "Unban" => 2,
+                _ => 1,
+            },
+            _ => 1,
+        }
+    }
+
     pub fn to_deep_string(&self) -> String {
         match self {
             Method::Hyp(x) => format!("Hyp<{}>", x.to_string()),
@@ -277,6 +294,15 @@ impl Method {
     }
 }
 
+fn is_control_driver(point: &Point) -> bool {
+    let len = point.segments.len();
+    len >= CONTROL_DRIVER_SEGMENTS.len()
+        && point.segments[len - CONTROL_DRIVER_SEGMENTS.len()..]
+            .iter()
+            .zip(CONTROL_DRIVER_SEGMENTS.iter())
+            .all(|(segment, expected)| segment.to_string() == *expected)
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum MethodPattern {
     Hyp(ValuePattern<HypMethod>),
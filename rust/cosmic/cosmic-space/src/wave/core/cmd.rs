@@ -23,6 +23,7 @@ pub enum CmdMethod {
     Command,
     RawCommand,
     Log,
+    Track,
 }
 
 impl ValueMatcher<CmdMethod> for CmdMethod {
@@ -15,7 +15,7 @@ use tokio::sync::{broadcast, mpsc, oneshot};
 
 use crate::config::bind::RouteSelector;
 use crate::loc::{ToPoint, ToSurface, Topic};
-use crate::log::{PointLogger, RootLogger, SpanLogger};
+use crate::log::{PointLogger, RootLogger, SpanLogger, TraceCtx};
 use crate::settings::Timeouts;
 use crate::wave::core::cmd::CmdMethod;
 use crate::wave::core::http2::StatusCode;
@@ -299,6 +299,7 @@ pub struct ProtoTransmitterBuilderDef<R, E> {
     pub via: SetStrategy<Surface>,
     pub from: SetStrategy<Surface>,
     pub to: SetStrategy<Recipients>,
+    pub trace: SetStrategy<TraceCtx>,
     pub router: R,
     pub exchanger: E,
 }
@@ -313,6 +314,7 @@ impl<R, E> ProtoTransmitterBuilderDef<R, E> {
             from: self.from,
             to: self.to,
             via: self.via,
+            trace: self.trace,
             router: self.router,
             exchanger: self.exchanger,
         }
@@ -328,6 +330,7 @@ pub struct ProtoTransmitterDef<R, E> {
     from: SetStrategy<Surface>,
     to: SetStrategy<Recipients>,
     via: SetStrategy<Surface>,
+    trace: SetStrategy<TraceCtx>,
     router: R,
     exchanger: E,
 }
@@ -388,6 +391,12 @@ impl<R, E> ProtoTransmitterDef<R, E> {
             SetStrategy::Fill(method) => wave.fill_method(method),
             SetStrategy::Override(handling) => wave.method(handling.clone()),
         }
+
+        match &self.trace {
+            SetStrategy::None => {}
+            SetStrategy::Fill(trace) => wave.fill_trace(trace),
+            SetStrategy::Override(trace) => wave.trace(trace.clone()),
+        }
     }
 
     fn prep_reflect(&self, wave: &mut ReflectedProto) {
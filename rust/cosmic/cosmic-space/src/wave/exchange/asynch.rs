@@ -1,5 +1,6 @@
 use crate::loc::{ToPoint, ToSurface};
 use crate::log::{PointLogger, RootLogger, Trackable, Tracker};
+use crate::metrics::{Counter, Histogram, METRICS};
 use crate::particle::traversal::Traversal;
 use crate::settings::Timeouts;
 use crate::wave::core::cmd::CmdMethod;
@@ -19,7 +20,7 @@ use alloc::borrow::Cow;
 use dashmap::{DashMap, DashSet};
 use std::collections::HashSet;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot};
 
 #[async_trait]
@@ -76,6 +77,7 @@ impl ProtoTransmitter {
             scope: SetStrategy::Fill(Scope::None),
             handling: SetStrategy::Fill(Handling::default()),
             method: SetStrategy::None,
+            trace: SetStrategy::None,
             via: SetStrategy::None,
             router,
             exchanger,
@@ -174,6 +176,25 @@ impl ProtoTransmitter {
         self.router.route(wave).await
     }
 
+    /// route `directed` as it is, no `SetStrategy` is applied.  Unless it bounces
+    /// nothing back the receiver yields its reflections
+    pub async fn route_directed(
+        &self,
+        directed: DirectedWave,
+    ) -> Option<oneshot::Receiver<ReflectedAggregate>> {
+        match directed.bounce_backs() {
+            BounceBacks::None => {
+                self.router.route(directed.to_ultra()).await;
+                None
+            }
+            _ => {
+                let reflected_rx = self.exchanger.exchange(&directed).await;
+                self.router.route(directed.to_ultra()).await;
+                Some(reflected_rx)
+            }
+        }
+    }
+
     pub async fn reflect<W>(&self, wave: W) -> Result<(), SpaceErr>
     where
         W: Into<ReflectedProto>,
@@ -203,6 +224,7 @@ impl ProtoTransmitterBuilder {
             scope: SetStrategy::Fill(Scope::None),
             handling: SetStrategy::Fill(Handling::default()),
             method: SetStrategy::None,
+            trace: SetStrategy::None,
             router,
             exchanger,
         }
@@ -218,6 +240,7 @@ impl TraversalTransmitter {
             scope: SetStrategy::None,
             handling: SetStrategy::None,
             method: SetStrategy::None,
+            trace: SetStrategy::None,
             from: SetStrategy::None,
             to: SetStrategy::None,
             via: SetStrategy::None,
@@ -294,6 +317,8 @@ pub struct Exchanger {
     pub singles: Arc<DashMap<WaveId, oneshot::Sender<ReflectedAggregate>>>,
     pub timeouts: Timeouts,
     pub logger: PointLogger,
+    /// when each exchange still awaiting its reflection was sent
+    pub started: Arc<DashMap<WaveId, Instant>>,
     #[cfg(test)]
     pub claimed: Arc<DashSet<String>>,
 }
@@ -307,6 +332,7 @@ impl Exchanger {
             multis: Arc::new(DashMap::new()),
             timeouts,
             logger,
+            started: Arc::new(DashMap::new()),
             #[cfg(test)]
             claimed: Arc::new(DashSet::new()),
         }
@@ -320,6 +346,7 @@ impl Exchanger {
             multis: self.multis.clone(),
             timeouts: self.timeouts.clone(),
             logger,
+            started: self.started.clone(),
             #[cfg(test)]
             claimed: self.claimed.clone(),
         }
@@ -332,6 +359,9 @@ impl Exchanger {
         if let Some(multi) = self.multis.get(reflect.reflection_of()) {
             multi.value().send(reflect).await;
         } else if let Some((_, tx)) = self.singles.remove(reflect.reflection_of()) {
+            if let Some((_, started)) = self.started.remove(reflect.reflection_of()) {
+                EXCHANGE_SECONDS.observe(started.elapsed().as_secs_f64());
+            }
             #[cfg(test)]
             self.claimed.insert(reflect.reflection_of().to_string());
             tx.send(ReflectedAggregate::Single(reflect));
@@ -388,16 +418,20 @@ impl Exchanger {
 
         let timeout = self.timeouts.from(directed.handling().wait.clone());
         self.singles.insert(directed.id().clone(), tx);
+        self.started.insert(directed.id().clone(), Instant::now());
         match directed.bounce_backs() {
             BounceBacks::None => {
                 panic!("we already dealt with this")
             }
             BounceBacks::Single => {
                 let singles = self.singles.clone();
+                let started = self.started.clone();
                 tokio::spawn(async move {
                     tokio::time::sleep(Duration::from_secs(timeout)).await;
                     let id = reflected.reflection_of.as_ref().unwrap();
                     if let Some((_, tx)) = singles.remove(id) {
+                        started.remove(id);
+                        EXCHANGE_TIMEOUTS.inc();
                         reflected.status = Some(StatusCode::from_u16(408).unwrap());
                         reflected.body = Some(Substance::Empty);
                         reflected.intended = Some(reflection.intended);
@@ -410,6 +444,7 @@ impl Exchanger {
                 let (tx, mut rx) = mpsc::channel(count);
                 self.multis.insert(directed.id().clone(), tx);
                 let singles = self.singles.clone();
+                let started = self.started.clone();
                 let id = directed.id().clone();
                 tokio::spawn(async move {
                     let mut agg = vec![];
@@ -418,6 +453,10 @@ impl Exchanger {
                             agg.push(reflected);
                             if count == agg.len() {
                                 if let Some((_, tx)) = singles.remove(&id) {
+                                    if let Some((_, started)) = started.remove(&id) {
+                                        EXCHANGE_SECONDS
+                                            .observe(started.elapsed().as_secs_f64());
+                                    }
                                     tx.send(ReflectedAggregate::Multi(agg));
                                     break;
                                 }
@@ -425,6 +464,8 @@ impl Exchanger {
                         } else {
                             // this would occur in a timeout scenario
                             if let Some((_, tx)) = singles.remove(&id) {
+                                started.remove(&id);
+                                EXCHANGE_TIMEOUTS.inc();
                                 reflected.status = Some(StatusCode::from_u16(408).unwrap());
                                 reflected.body = Some(Substance::Empty);
                                 reflected.intended = Some(reflection.intended);
@@ -448,6 +489,7 @@ impl Exchanger {
                 let (tx, mut rx) = mpsc::channel(32);
                 self.multis.insert(directed.id().clone(), tx);
                 let singles = self.singles.clone();
+                let started = self.started.clone();
                 let id = directed.id().clone();
                 tokio::spawn(async move {
                     let mut agg = vec![];
@@ -457,6 +499,8 @@ impl Exchanger {
                         } else {
                             // this would occur in a timeout scenario
                             if let Some((_, tx)) = singles.remove(&id) {
+                                // a timer always runs its course so it is not a timeout
+                                started.remove(&id);
                                 tx.send(ReflectedAggregate::Multi(agg));
                                 break;
                             }
@@ -479,6 +523,19 @@ impl Exchanger {
     }
 }
 
+lazy_static! {
+    static ref EXCHANGE_SECONDS: Histogram = METRICS.histogram(
+        "cosmic_exchange_seconds",
+        "time from sending a directed wave until all of its reflections were received",
+        &[],
+    );
+    static ref EXCHANGE_TIMEOUTS: Counter = METRICS.counter(
+        "cosmic_exchange_timeouts_total",
+        "directed waves whose reflections did not arrive in time",
+        &[],
+    );
+}
+
 impl Default for Exchanger {
     fn default() -> Self {
         Self::new(
@@ -500,7 +557,10 @@ where
             .logger
             .point(self.surface.clone().to_point())
             .spanner(&wave);
-        let mut transmitter = self.builder.clone().build();
+        // waves sent while handling continue this span's trace
+        let mut builder = self.builder.clone();
+        builder.trace = SetStrategy::Fill(logger.trace_ctx());
+        let mut transmitter = builder.build();
         let reflection = wave.reflection();
         let ctx = RootInCtx::new(wave, self.surface.clone(), logger, transmitter);
         match self.handler.handle(ctx).await {
@@ -53,6 +53,7 @@ impl ProtoTransmitter {
             scope: SetStrategy::Fill(Scope::None),
             handling: SetStrategy::Fill(Handling::default()),
             method: SetStrategy::None,
+            trace: SetStrategy::None,
             router,
             exchanger: (),
         }
@@ -171,6 +172,7 @@ impl ProtoTransmitterBuilder {
             scope: SetStrategy::Fill(Scope::None),
             handling: SetStrategy::Fill(Handling::default()),
             method: SetStrategy::None,
+            trace: SetStrategy::None,
             router,
             exchanger: (),
         }
@@ -243,7 +245,10 @@ impl DirectedHandlerShell {
             .logger
             .point(self.surface.clone().to_point())
             .spanner(&wave);
-        let mut transmitter = self.builder.clone().build();
+        // waves sent while handling continue this span's trace
+        let mut builder = self.builder.clone();
+        builder.trace = SetStrategy::Fill(logger.trace_ctx());
+        let mut transmitter = builder.build();
         let reflection = wave.reflection();
         let ctx = RootInCtx::new(wave, self.surface.clone(), logger, transmitter.clone());
         match self.handler.handle(ctx) {
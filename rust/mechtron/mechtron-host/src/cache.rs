@@ -0,0 +1,342 @@
+use crate::err::DefaultHostErr;
+use crate::limits::{limited_store, metered};
+use cosmic_space::config::mechtron::WasmLimits;
+use cosmic_space::loc::Point;
+use cosmic_space::metrics::{Counter, Gauge, METRICS};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use wasmer::{Module, Target};
+
+/// the engine & compiler every Module is compiled with. Serialized Modules are only
+/// valid for the engine that produced them so this is part of every cache key
+const ENGINE: &str = "wasmer-2.3/universal/singlepass";
+
+/// the bytes of serialized Modules kept on disk by default
+pub const DEFAULT_MODULE_DISK_CAP: u64 = 512 * 1024 * 1024;
+
+/// hit & miss counters of the `ModuleCache`
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ModuleCacheStats {
+    pub hits: u64,
+    pub disk_hits: u64,
+    pub misses: u64,
+    pub modules: usize,
+}
+
+/// the `ModuleCacheStats` as published in the `METRICS` registry
+struct ModuleCacheMetrics {
+    hits: Counter,
+    disk_hits: Counter,
+    misses: Counter,
+    modules: Gauge,
+}
+
+impl ModuleCacheMetrics {
+    fn new() -> Self {
+        let hits = |source| {
+            METRICS.counter(
+                "cosmic_wasm_module_cache_hits_total",
+                "compiled wasm modules served by the module cache",
+                &[("source", source)],
+            )
+        };
+        Self {
+            hits: hits("memory"),
+            disk_hits: hits("disk"),
+            misses: METRICS.counter(
+                "cosmic_wasm_module_cache_misses_total",
+                "wasm modules the module cache had to compile",
+                &[],
+            ),
+            modules: METRICS.gauge(
+                "cosmic_wasm_module_cache_modules",
+                "compiled wasm modules held by the module cache",
+                &[],
+            ),
+        }
+    }
+}
+
+/// compiled guest Modules shared by every Host of a HostsRunner.
+/// Modules are keyed by the wasm Point plus a hash of the wasm content and the
+/// `WasmLimits` (since fuel metering & memory caps are compiled into the Module).
+/// If a `dir` is provided compiled Modules are also serialized to disk so a restarted
+/// Star does not have to compile them again. Once the serialized Modules exceed
+/// `disk_cap` bytes the oldest are removed
+pub struct ModuleCache {
+    modules: HashMap<(Point, String), Module>,
+    dir: Option<PathBuf>,
+    disk_cap: u64,
+    stats: ModuleCacheStats,
+    metrics: ModuleCacheMetrics,
+}
+
+impl ModuleCache {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self::with_disk_cap(dir, DEFAULT_MODULE_DISK_CAP)
+    }
+
+    pub fn with_disk_cap(dir: Option<PathBuf>, disk_cap: u64) -> Self {
+        let dir = dir.and_then(|dir| fs::create_dir_all(&dir).ok().map(|_| dir));
+        Self {
+            modules: HashMap::new(),
+            dir,
+            disk_cap,
+            stats: Default::default(),
+            metrics: ModuleCacheMetrics::new(),
+        }
+    }
+
+    pub fn stats(&self) -> ModuleCacheStats {
+        let mut stats = self.stats.clone();
+        stats.modules = self.modules.len();
+        stats
+    }
+
+    /// forget the Modules compiled from the wasm of `bundle`
+    pub fn invalidate(&mut self, bundle: &Point) {
+        self.modules.retain(|(point, _), _| {
+            point
+                .clone()
+                .to_bundle()
+                .map(|b| b != *bundle)
+                .unwrap_or(true)
+        });
+        self.metrics.modules.set(self.modules.len() as i64);
+    }
+
+    /// the Sha256 of everything a compiled Module depends on: the engine & target it
+    /// is compiled for, the wasm and the limits compiled into it.  `call_timeout` only
+    /// matters in that it turns on metering, its deadline is kept by a `Watchdog`
+    fn hash(wasm: &[u8], limits: &WasmLimits) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(ENGINE.as_bytes());
+        hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher.update(Target::default().triple().to_string().as_bytes());
+        hasher.update(&(wasm.len() as u64).to_be_bytes());
+        hasher.update(wasm);
+        hasher.update(&[metered(limits) as u8]);
+        match limits.fuel {
+            None => hasher.update(&[0u8]),
+            Some(fuel) => {
+                hasher.update(&[1u8]);
+                hasher.update(&fuel.to_be_bytes());
+            }
+        }
+        match limits.max_memory_pages {
+            None => hasher.update(&[0u8]),
+            Some(pages) => {
+                hasher.update(&[1u8]);
+                hasher.update(&pages.to_be_bytes());
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// remove the oldest serialized Modules until they fit in `disk_cap`
+    fn evict(&self) {
+        let dir = match &self.dir {
+            Some(dir) => dir,
+            None => return,
+        };
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        let mut serialized: Vec<(SystemTime, u64, PathBuf)> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map_or(false, |ext| ext == "module"))
+            .filter_map(|path| {
+                let metadata = fs::metadata(&path).ok()?;
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                Some((modified, metadata.len(), path))
+            })
+            .collect();
+        let mut total: u64 = serialized.iter().map(|(_, len, _)| len).sum();
+        serialized.sort();
+        for (_, len, path) in serialized {
+            if total <= self.disk_cap {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total -= len;
+            }
+        }
+    }
+
+    pub fn get(
+        &mut self,
+        point: &Point,
+        wasm: &[u8],
+        limits: &WasmLimits,
+    ) -> Result<Module, DefaultHostErr> {
+        let hash = Self::hash(wasm, limits);
+        let key = (point.clone(), hash.clone());
+        if let Some(module) = self.modules.get(&key) {
+            self.stats.hits += 1;
+            self.metrics.hits.inc();
+            return Ok(module.clone());
+        }
+
+        // a Module with a different hash means the bundle was republished
+        self.modules
+            .retain(|(p, h), _| p != point || *h == hash);
+
+        let store = limited_store(limits);
+        let path = self.dir.as_ref().map(|dir| dir.join(format!("{}.module", hash)));
+
+        if let Some(path) = &path {
+            if let Ok(serialized) = fs::read(path) {
+                // the file name is the hash of the wasm & limits it was compiled from
+                if let Ok(module) = unsafe { Module::deserialize(&store, serialized.as_slice()) } {
+                    self.stats.disk_hits += 1;
+                    self.metrics.disk_hits.inc();
+                    self.modules.insert(key, module.clone());
+                    self.metrics.modules.set(self.modules.len() as i64);
+                    return Ok(module);
+                }
+            }
+        }
+
+        self.stats.misses += 1;
+        self.metrics.misses.inc();
+        let module = Module::new(&store, wasm)?;
+        if let Some(path) = &path {
+            if let Ok(serialized) = module.serialize() {
+                let tmp = path.with_extension("tmp");
+                if fs::write(&tmp, serialized).is_ok() {
+                    fs::rename(&tmp, path).unwrap_or_default();
+                }
+            }
+            self.evict();
+        }
+        self.modules.insert(key, module.clone());
+        self.metrics.modules.set(self.modules.len() as i64);
+        Ok(module)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::cache::{ModuleCache, ModuleCacheStats};
+    use cosmic_space::config::mechtron::WasmLimits;
+    use cosmic_space::loc::Point;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    const WAT: &str = r#"(module (func (export "answer") (result i32) (i32.const 42)))"#;
+    const OTHER_WAT: &str = r#"(module (func (export "answer") (result i32) (i32.const 7)))"#;
+
+    fn wasm() -> Point {
+        Point::from_str("localhost:repo:app:1.0.0:/wasm/app.wasm").unwrap()
+    }
+
+    fn dir() -> PathBuf {
+        std::env::temp_dir().join(format!("mechtron-modules-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn stats(hits: u64, disk_hits: u64, misses: u64, modules: usize) -> ModuleCacheStats {
+        ModuleCacheStats {
+            hits,
+            disk_hits,
+            misses,
+            modules,
+        }
+    }
+
+    #[test]
+    pub fn test_hash() {
+        let limits = WasmLimits::default();
+        let hash = ModuleCache::hash(WAT.as_bytes(), &limits);
+        assert_eq!(hash, ModuleCache::hash(WAT.as_bytes(), &limits));
+        assert_eq!(hash.len(), 64);
+        assert_ne!(hash, ModuleCache::hash(OTHER_WAT.as_bytes(), &limits));
+
+        let mut fuel = WasmLimits::default();
+        fuel.fuel = Some(1000);
+        assert_ne!(hash, ModuleCache::hash(WAT.as_bytes(), &fuel));
+        let mut pages = WasmLimits::default();
+        pages.max_memory_pages = Some(1000);
+        assert_ne!(
+            ModuleCache::hash(WAT.as_bytes(), &fuel),
+            ModuleCache::hash(WAT.as_bytes(), &pages)
+        );
+    }
+
+    #[test]
+    pub fn test_hit_miss_invalidate() {
+        let mut cache = ModuleCache::new(None);
+        let limits = WasmLimits::default();
+        cache.get(&wasm(), WAT.as_bytes(), &limits).unwrap();
+        assert_eq!(cache.stats(), stats(0, 0, 1, 1));
+        cache.get(&wasm(), WAT.as_bytes(), &limits).unwrap();
+        assert_eq!(cache.stats(), stats(1, 0, 1, 1));
+
+        // other limits are compiled into another Module
+        let mut fuel = WasmLimits::default();
+        fuel.fuel = Some(1000);
+        cache.get(&wasm(), WAT.as_bytes(), &fuel).unwrap();
+        assert_eq!(cache.stats(), stats(1, 0, 2, 2));
+
+        // a republished wasm replaces the Modules of the previous one
+        cache.get(&wasm(), OTHER_WAT.as_bytes(), &limits).unwrap();
+        assert_eq!(cache.stats(), stats(1, 0, 3, 1));
+
+        // other bundles are left alone
+        cache.invalidate(&Point::from_str("localhost:repo:other:1.0.0").unwrap());
+        assert_eq!(cache.stats().modules, 1);
+        cache.invalidate(&Point::from_str("localhost:repo:app:1.0.0").unwrap());
+        assert_eq!(cache.stats().modules, 0);
+        cache.get(&wasm(), OTHER_WAT.as_bytes(), &limits).unwrap();
+        assert_eq!(cache.stats(), stats(1, 0, 4, 1));
+    }
+
+    #[test]
+    pub fn test_disk() {
+        let dir = dir();
+        let limits = WasmLimits::default();
+        let mut cache = ModuleCache::new(Some(dir.clone()));
+        cache.get(&wasm(), WAT.as_bytes(), &limits).unwrap();
+
+        // a restarted star deserializes the Module instead of compiling it
+        let mut cache = ModuleCache::new(Some(dir.clone()));
+        cache.get(&wasm(), WAT.as_bytes(), &limits).unwrap();
+        assert_eq!(cache.stats(), stats(0, 1, 0, 1));
+        fs::remove_dir_all(dir).unwrap_or_default();
+    }
+
+    #[test]
+    pub fn test_disk_cap() {
+        let dir = dir();
+        let limits = WasmLimits::default();
+        let mut cache = ModuleCache::new(Some(dir.clone()));
+        cache.get(&wasm(), WAT.as_bytes(), &limits).unwrap();
+        let len = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.metadata().unwrap().len())
+            .sum::<u64>();
+
+        // room for a single serialized Module
+        let mut cache = ModuleCache::with_disk_cap(Some(dir.clone()), len + len / 2);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        cache.get(&wasm(), OTHER_WAT.as_bytes(), &limits).unwrap();
+        let serialized: Vec<PathBuf> = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        assert_eq!(serialized.len(), 1);
+        // the newest Module is the one kept
+        assert_eq!(
+            serialized[0].file_stem().unwrap().to_str().unwrap(),
+            ModuleCache::hash(OTHER_WAT.as_bytes(), &limits)
+        );
+        fs::remove_dir_all(dir).unwrap_or_default();
+    }
+}
@@ -1,25 +1,34 @@
 #![allow(warnings)]
+pub mod cache;
 pub mod err;
+pub mod limits;
+pub mod requests;
+pub mod state;
 
 #[macro_use]
 extern crate lazy_static;
 
+use crate::cache::{ModuleCache, ModuleCacheStats};
 use crate::err::{DefaultHostErr, HostErr};
+use crate::limits::{metered, GuestFault, GuestLimit, Watchdog};
+use crate::requests::HostRequests;
+use crate::state::{Frame, MechtronStateStore, MechtronStates};
 use cosmic_space::artifact::asynch::{ArtifactApi, ReadArtifactFetcher};
 use cosmic_space::artifact::ArtRef;
-use cosmic_space::config::mechtron::MechtronConfig;
+use cosmic_space::config::mechtron::{MechtronConfig, WasmLimits};
 use cosmic_space::err::SpaceErr;
 use cosmic_space::loc::{Layer, Point, ToSurface};
 use cosmic_space::particle::{Details, Property};
 use cosmic_space::substance::Bin;
 use cosmic_space::wave::DirectedWave;
-use cosmic_space::wave::{DirectedKind, UltraWave, WaveKind};
+use cosmic_space::wave::{DirectedKind, Signal, UltraWave, Wave, WaveKind};
 
 use wasmer::Function;
 use wasmer_compiler_singlepass::Singlepass;
 
 use cosmic_space::hyper::{HostCmd, HyperSubstance};
 use cosmic_space::log::{LogSource, PointLogger, RootLogger, StdOutAppender};
+use cosmic_space::metrics::{Gauge, METRICS};
 use cosmic_space::substance::Substance;
 use cosmic_space::wasm::Timestamp;
 use cosmic_space::wave::core::hyp::HypMethod;
@@ -29,16 +38,40 @@ use cosmic_space::{loc, VERSION};
 use cosmic_space::wave::core::cmd::CmdMethod;
 use cosmic_space::wave::core::Method;
 use cosmic_space::wave::exchange::asynch::ProtoTransmitter;
+use rand::rngs::OsRng;
+use rand::RngCore;
 use cosmic_space::wave::exchange::asynch::ProtoTransmitterBuilder;
 use cosmic_space::wave::exchange::SetStrategy;
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use std::{sync, thread};
 use threadpool::ThreadPool;
 use tokio::runtime::Handle;
 use tokio::sync::mpsc;
-use wasmer::{imports, Array, Instance, Module, Store, Value, WasmPtr, WasmerEnv};
+use wasmer::{imports, Array, ImportObject, Instance, Module, Store, Value, WasmPtr, WasmerEnv};
+use wasmer_middlewares::metering::{get_remaining_points, set_remaining_points, MeteringPoints};
+
+/// the newest membrane ABI this Host understands. Guests report their ABI via
+/// `mechtron_guest_version` and are refused if it is newer than this.
+/// version 2 added `mechtron_state_load`, `mechtron_state_save`, `mechtron_random_bytes`,
+/// `mechtron_timer`, `mechtron_request` & `mechtron_request_poll`
+pub const HOST_ABI_VERSION: i32 = 2;
+
+/// the most random bytes a guest may request in one call
+pub const MAX_RANDOM_BYTES: usize = 64 * 1024;
+
+/// the exports a guest module must provide for a Host to instantiate it
+pub const REQUIRED_GUEST_EXPORTS: [&str; 7] = [
+    "mechtron_guest_version",
+    "mechtron_guest_init",
+    "mechtron_frame_to_guest",
+    "mechtron_guest_alloc_buffer",
+    "mechtron_guest_dealloc_buffer",
+    "mechtron_guest_get_buffer_ptr",
+    "mechtron_guest_get_buffer_len",
+];
 
 #[derive(Clone)]
 pub struct HostsApi {
@@ -75,6 +108,20 @@ impl HostsApi {
             .await?;
         rtn_rx.await?
     }
+
+    /// the same figures are published in the `METRICS` registry as `cosmic_wasm_hosts`
+    /// and `cosmic_wasm_module_cache_*`
+    pub async fn diagnostics(&self) -> Result<HostsDiagnostics, SpaceErr> {
+        let (rtn, mut rtn_rx) = tokio::sync::oneshot::channel();
+        self.tx.send(HostsCall::Diagnostics(rtn)).await?;
+        Ok(rtn_rx.await?)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HostsDiagnostics {
+    pub hosts: usize,
+    pub module_cache: ModuleCacheStats,
 }
 
 pub enum HostsCall {
@@ -91,16 +138,24 @@ pub enum HostsCall {
         wasm: Point,
         rtn: tokio::sync::oneshot::Sender<Result<WasmHostApi, SpaceErr>>,
     },
+    Diagnostics(tokio::sync::oneshot::Sender<HostsDiagnostics>),
 }
 
 pub struct HostsRunner {
-    store: Store,
     artifacts: ArtifactApi,
     wasm_to_host: HashMap<Point, WasmHostApi>,
+    wasm_to_details: HashMap<Point, Details>,
     point_to_host: HashMap<Point, WasmHostApi>,
     mechtron_to_host: HashMap<Point, Point>,
     transmitter: ProtoTransmitterBuilder,
     logger: RootLogger,
+    states: MechtronStates,
+    panic_tx: mpsc::Sender<Point>,
+    modules: Arc<Mutex<ModuleCache>>,
+    /// the latest reload of each wasm so a slower, older reload does not replace a newer one
+    reloads: Arc<Mutex<HashMap<Point, u64>>>,
+    /// the number of Hosts as published in the `METRICS` registry
+    hosts: Gauge,
     rx: tokio::sync::mpsc::Receiver<HostsCall>,
 }
 
@@ -109,17 +164,25 @@ impl HostsRunner {
         artifacts: ArtifactApi,
         transmitter: ProtoTransmitterBuilder,
         logger: RootLogger,
+        store: Arc<dyn MechtronStateStore>,
+        panic_tx: mpsc::Sender<Point>,
+        modules: ModuleCache,
     ) -> HostsApi {
         let (tx, rx) = mpsc::channel(1024);
         let runner = Self {
             rx,
-            store: Store::default(),
             artifacts,
             wasm_to_host: Default::default(),
+            wasm_to_details: Default::default(),
             point_to_host: Default::default(),
             mechtron_to_host: Default::default(),
             transmitter,
             logger,
+            states: MechtronStates::new(store),
+            panic_tx,
+            modules: Arc::new(Mutex::new(modules)),
+            reloads: Default::default(),
+            hosts: METRICS.gauge("cosmic_wasm_hosts", "wasm hosts running guests", &[]),
         };
         tokio::spawn(async move {
             runner.start().await;
@@ -128,7 +191,22 @@ impl HostsRunner {
     }
 
     async fn start(mut self) {
-        while let Some(call) = self.rx.recv().await {
+        let mut invalidations = self.artifacts.invalidations();
+        loop {
+            let call = tokio::select! {
+                call = self.rx.recv() => match call {
+                    Some(call) => call,
+                    None => break,
+                },
+                Ok(bundle) = invalidations.recv() => {
+                    match self.modules.lock() {
+                        Ok(mut modules) => modules.invalidate(&bundle),
+                        Err(_) => self.logger.error("module cache lock poisoned"),
+                    }
+                    self.reload(&bundle);
+                    continue;
+                }
+            };
             match call {
                 HostsCall::Create { details, wasm, rtn } => {
                     rtn.send(
@@ -147,6 +225,17 @@ impl HostsRunner {
                         format!("could not get host via point: {}", point.to_string()).into(),
                     ));
                 }
+                HostsCall::Diagnostics(rtn) => {
+                    rtn.send(HostsDiagnostics {
+                        hosts: self.point_to_host.len(),
+                        module_cache: self
+                            .modules
+                            .lock()
+                            .map(|modules| modules.stats())
+                            .unwrap_or_default(),
+                    })
+                    .unwrap_or_default();
+                }
             }
         }
     }
@@ -162,16 +251,119 @@ impl HostsRunner {
 
         let logger = self.logger.point(details.stub.point.clone());
         let bin = self.artifacts.wasm(&wasm).await?;
-        let host = WasmHostRunner::new(details.clone(), &mut self.store, bin, transmitter, logger)
+        let limits = WasmLimits::from_properties(&details.properties)?;
+        let module = self
+            .modules
+            .lock()
+            .map_err(|_| SpaceErr::server_error("module cache lock poisoned"))?
+            .get(&wasm, bin.as_slice(), &limits)
             .map_err(|e| e.to_space_err())?;
+        let host = WasmHostRunner::new(
+            details.clone(),
+            module,
+            transmitter,
+            logger,
+            self.states.clone(),
+            limits,
+            self.panic_tx.clone(),
+        )
+        .map_err(|e| e.to_space_err())?;
         self.wasm_to_host.insert(wasm.clone(), host.clone());
+        self.wasm_to_details.insert(wasm.clone(), details.clone());
         self.point_to_host.insert(details.stub.point, host.clone());
+        self.hosts.set(self.point_to_host.len() as i64);
 
         host.init().await;
 
         Ok(host)
     }
 
+    /// reload every Host running wasm from `bundle` so a republished (or locally edited)
+    /// bundle takes effect without reassigning its Mechtrons. Each Host is reloaded on
+    /// its own task so a slow guest does not hold up the calls to this runner
+    fn reload(&self, bundle: &Point) {
+        let hosts: Vec<(Point, WasmHostApi)> = self
+            .wasm_to_host
+            .iter()
+            .filter(|(wasm, _)| {
+                wasm.clone()
+                    .to_bundle()
+                    .map(|b| b == *bundle)
+                    .unwrap_or(false)
+            })
+            .map(|(wasm, host)| (wasm.clone(), host.clone()))
+            .collect();
+        for (wasm, host) in hosts {
+            let logger = self.logger.point(wasm.clone());
+            let details = match self.wasm_to_details.get(&wasm) {
+                Some(details) => details.clone(),
+                None => {
+                    logger.error("could not reload host: no details");
+                    continue;
+                }
+            };
+            let generation = match self.reloads.lock() {
+                Ok(mut reloads) => {
+                    let generation = reloads.entry(wasm.clone()).or_default();
+                    *generation += 1;
+                    *generation
+                }
+                Err(_) => {
+                    logger.error("could not reload host: reload lock poisoned");
+                    continue;
+                }
+            };
+            let artifacts = self.artifacts.clone();
+            let modules = self.modules.clone();
+            let reloads = self.reloads.clone();
+            tokio::spawn(async move {
+                match Self::reload_host(artifacts, modules, reloads, generation, &wasm, details, &host)
+                    .await
+                {
+                    Ok(true) => logger.info("reloaded host"),
+                    Ok(false) => {}
+                    Err(err) => logger.error(format!("could not reload host: {}", err.to_string())),
+                }
+            });
+        }
+    }
+
+    /// swap the freshly compiled Module into `host` unless a newer reload of `wasm`
+    /// started meanwhile. Returns `false` if this reload was superseded
+    async fn reload_host(
+        artifacts: ArtifactApi,
+        modules: Arc<Mutex<ModuleCache>>,
+        reloads: Arc<Mutex<HashMap<Point, u64>>>,
+        generation: u64,
+        wasm: &Point,
+        details: Details,
+        host: &WasmHostApi,
+    ) -> Result<bool, SpaceErr> {
+        let limits = WasmLimits::from_properties(&details.properties)?;
+        let bin = artifacts.wasm(wasm).await?;
+        let module = tokio::task::spawn_blocking({
+            let wasm = wasm.clone();
+            move || {
+                modules
+                    .lock()
+                    .map_err(|_| SpaceErr::server_error("module cache lock poisoned"))?
+                    .get(&wasm, bin.as_slice(), &limits)
+                    .map_err(|e| e.to_space_err())
+            }
+        })
+        .await
+        .map_err(|e| SpaceErr::server_error(e.to_string()))??;
+        let superseded = reloads
+            .lock()
+            .map(|reloads| reloads.get(wasm) != Some(&generation))
+            .unwrap_or(false);
+        if superseded {
+            return Ok(false);
+        }
+        host.reload(module).await.map_err(|e| e.to_space_err())?;
+        Ok(true)
+    }
+
     pub fn get(&self, point: &Point) -> Result<WasmHostApi, SpaceErr> {
         self.point_to_host
             .get(point)
@@ -190,6 +382,7 @@ pub enum WasmHostCall {
     Point(tokio::sync::oneshot::Sender<Point>),
     HostCmd {
         cmd: HostCmd,
+        frame: Frame,
         rtn: tokio::sync::oneshot::Sender<Result<(), DefaultHostErr>>,
     },
     WriteString {
@@ -214,7 +407,8 @@ pub enum WasmHostCall {
     },
     GuestConsumeWave {
         wave: i32,
-        rtn: tokio::sync::oneshot::Sender<Result<Option<UltraWave>, DefaultHostErr>>,
+        frame: Option<Frame>,
+        rtn: tokio::sync::oneshot::Sender<Result<Option<UltraWave>, GuestFault>>,
     },
     ConsumeString {
         buffer_id: i32,
@@ -224,16 +418,66 @@ pub enum WasmHostCall {
         buffer_id: i32,
         rtn: tokio::sync::oneshot::Sender<Result<Vec<u8>, DefaultHostErr>>,
     },
+    Reload {
+        module: Module,
+        rtn: tokio::sync::oneshot::Sender<Result<(), DefaultHostErr>>,
+    },
+    /// the reloaded guest has been initialized & its mechtrons created again
+    Reloaded,
+    /// forget a mechtron so it is not created again when the host is reloaded
+    Unhost(Point),
+    /// replace a guest that breached a limit with a fresh instance of its module
+    Reinstantiate,
+}
+
+impl WasmHostCall {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WasmHostCall::Init(_) => "Init",
+            WasmHostCall::Point(_) => "Point",
+            WasmHostCall::HostCmd { .. } => "HostCmd",
+            WasmHostCall::WriteString { .. } => "WriteString",
+            WasmHostCall::WriteBuffer { .. } => "WriteBuffer",
+            WasmHostCall::SerializeWaveToGuest { .. } => "SerializeWaveToGuest",
+            WasmHostCall::DeSerializeWaveToHost { .. } => "DeSerializeWaveToHost",
+            WasmHostCall::WaveToHost { .. } => "WaveToHost",
+            WasmHostCall::GuestConsumeWave { .. } => "GuestConsumeWave",
+            WasmHostCall::ConsumeString { .. } => "ConsumeString",
+            WasmHostCall::ConsumeBuffer { .. } => "ConsumeBuffer",
+            WasmHostCall::Reload { .. } => "Reload",
+            WasmHostCall::Reloaded => "Reloaded",
+            WasmHostCall::Unhost(_) => "Unhost",
+            WasmHostCall::Reinstantiate => "Reinstantiate",
+        }
+    }
 }
 
 #[derive(WasmerEnv, Clone)]
 pub struct WasmHostApi {
     tx: mpsc::Sender<WasmHostCall>,
+    states: MechtronStates,
+    logger: PointLogger,
+    panic_tx: mpsc::Sender<Point>,
+    transmitter: ProtoTransmitter,
+    requests: HostRequests,
 }
 
 impl WasmHostApi {
-    pub fn new(tx: mpsc::Sender<WasmHostCall>) -> Self {
-        Self { tx }
+    pub fn new(
+        tx: mpsc::Sender<WasmHostCall>,
+        states: MechtronStates,
+        logger: PointLogger,
+        panic_tx: mpsc::Sender<Point>,
+        transmitter: ProtoTransmitter,
+    ) -> Self {
+        Self {
+            tx,
+            states,
+            logger,
+            panic_tx,
+            transmitter,
+            requests: HostRequests::new(),
+        }
     }
 
     pub async fn point(&self) -> Result<Point, DefaultHostErr> {
@@ -251,12 +495,65 @@ impl WasmHostApi {
         rtn
     }
 
+    /// create the mechtron of `cmd` in the guest within a frame of its own so the State
+    /// the guest saves while creating it is persisted
     pub async fn create_mechtron(&self, cmd: HostCmd) -> Result<(), DefaultHostErr> {
+        let frame = self.states.open(&cmd.details.stub.point)?;
+        let created = async {
+            let (rtn, mut rtn_rx) = tokio::sync::oneshot::channel();
+            self.tx
+                .send(WasmHostCall::HostCmd {
+                    cmd,
+                    frame: frame.clone(),
+                    rtn,
+                })
+                .await?;
+            rtn_rx.await?
+        }
+        .await;
+        match created {
+            Ok(_) => Ok(self.states.commit(&frame)?),
+            Err(err) => {
+                self.states.discard(&frame);
+                Err(err)
+            }
+        }
+    }
+
+    /// replace the guest with an instance of `module` (i.e. after its wasm was republished)
+    pub async fn reload(&self, module: Module) -> Result<(), DefaultHostErr> {
         let (rtn, mut rtn_rx) = tokio::sync::oneshot::channel();
-        self.tx.send(WasmHostCall::HostCmd { cmd, rtn }).await?;
+        self.tx.send(WasmHostCall::Reload { module, rtn }).await?;
         rtn_rx.await?
     }
 
+    /// stop hosting mechtron `point`: it will not be created again when the host reloads
+    pub async fn unhost(&self, point: &Point) -> Result<(), DefaultHostErr> {
+        self.tx.send(WasmHostCall::Unhost(point.clone())).await?;
+        Ok(())
+    }
+
+    /// init a freshly instantiated guest and create `hosted` in it again. Runs outside
+    /// of the runner loop since the guest calls back into the host through it
+    async fn replay(&self, hosted: Vec<HostCmd>) -> Result<(), DefaultHostErr> {
+        self.init().await?;
+        let mut rtn = Ok(());
+        for cmd in hosted {
+            let point = cmd.details.stub.point.clone();
+            if let Err(err) = self.create_mechtron(cmd).await {
+                self.logger.error(format!(
+                    "could not create mechtron {} in reloaded host: {}",
+                    point.to_string(),
+                    err.to_string()
+                ));
+                if rtn.is_ok() {
+                    rtn = Err(err);
+                }
+            }
+        }
+        rtn
+    }
+
     pub fn write_string<S: ToString>(&self, string: S) -> Result<i32, DefaultHostErr> {
         tokio::task::block_in_place(move || {
             Handle::current().block_on(async move {
@@ -337,48 +634,277 @@ impl WasmHostApi {
         })
     }
 
-    pub fn guest_consume_wave(&self, wave: i32) -> Result<Option<UltraWave>, DefaultHostErr> {
+    /// hand the wave in buffer `wave` to the guest within `frame`
+    pub fn guest_consume_wave(
+        &self,
+        wave: i32,
+        frame: Option<Frame>,
+    ) -> Result<Option<UltraWave>, GuestFault> {
         let api = self.clone();
         tokio::task::block_in_place(move || {
             Handle::current().block_on(async move {
                 let (rtn, mut rtn_rx) = tokio::sync::oneshot::channel();
-                api.tx.send(WasmHostCall::GuestConsumeWave{ wave, rtn }).await?;
-                rtn_rx.await?
+                api.tx
+                    .send(WasmHostCall::GuestConsumeWave { wave, frame, rtn })
+                    .await
+                    .map_err(DefaultHostErr::from)?;
+                rtn_rx.await.map_err(DefaultHostErr::from)?
             })
         })
     }
 
+    /// deliver a wave to the guest. A frame of the recipient mechtron is opened first,
+    /// loading its State before the guest restores the mechtron, and whatever State the
+    /// guest hands back is saved when the frame commits. If the State was modified
+    /// concurrently the save is rejected and a directed wave is reflected with a 409.
+    /// If the guest breaches one of its `WasmLimits` the mechtron is reported as panicked,
+    /// its staged State is discarded, a directed wave is reflected with a 503 (or 408
+    /// if the deadline expired) and the guest is replaced by a fresh instance since the
+    /// trap may have left it in an inconsistent state
     pub fn transmit_to_guest(&self, wave: UltraWave) -> Result<Option<UltraWave>, DefaultHostErr> {
-        let wave_id = self.serialize_wave_to_guest(wave)?;
-        let rtn = self.guest_consume_wave(wave_id)?;
+        let point = wave.to().clone().to_single().map(|to| to.point).ok();
+        let frame = match &point {
+            Some(point) => Some(self.states.open(point)?),
+            None => None,
+        };
+        let directed = wave.clone().to_directed().ok();
+
+        let delivered = self
+            .serialize_wave_to_guest(wave)
+            .map_err(GuestFault::from)
+            .and_then(|wave_id| self.guest_consume_wave(wave_id, frame.clone()));
+        let rtn = match delivered {
+            Ok(rtn) => rtn,
+            Err(GuestFault::Err(err)) => {
+                if let Some(frame) = &frame {
+                    self.states.discard(frame);
+                }
+                return Err(err);
+            }
+            Err(GuestFault::Limit(limit)) => {
+                let err = SpaceErr::new(limit.status(), limit.message());
+                if let Some(frame) = &frame {
+                    self.states.discard(frame);
+                }
+                if let Some(point) = &point {
+                    self.logger.error(format!(
+                        "mechtron {} breached a limit: {}",
+                        point.to_string(),
+                        limit.message()
+                    ));
+                    self.panic_tx.try_send(point.clone()).unwrap_or_default();
+                    // a panicked mechtron is not created again if the host reloads
+                    if self.tx.try_send(WasmHostCall::Unhost(point.clone())).is_err() {
+                        self.logger.warn(format!(
+                            "could not unhost panicked mechtron {}",
+                            point.to_string()
+                        ));
+                    }
+                }
+                if self.tx.try_send(WasmHostCall::Reinstantiate).is_err() {
+                    self.logger
+                        .error("could not reinstantiate a guest that breached a limit");
+                }
+                if let Some(point) = &point {
+                    if let Some(directed) = directed {
+                        if let Ok(reflection) = directed.reflection() {
+                            return Ok(Some(reflection.make(err.into(), point.to_surface()).to_ultra()));
+                        }
+                    }
+                }
+                return Err(err.into());
+            }
+        };
+
+        if let Some(frame) = &frame {
+            let point = &frame.point;
+            if let Err(err) = self.states.commit(frame) {
+                if let Some(directed) = directed {
+                    if let Ok(reflection) = directed.reflection() {
+                        return Ok(Some(reflection.make(err.into(), point.to_surface()).to_ultra()));
+                    }
+                }
+                return Err(err.into());
+            }
+        }
         Ok(rtn)
     }
 
     pub fn host_mechtron(&self, cmd: HostCmd) {}
+
+    /// a buffer holding the State of `point` as loaded when its frame opened or 0 if it
+    /// has none. Only the mechtron whose frame is being processed may be loaded
+    pub fn state_load(&self, point: i32) -> Result<i32, DefaultHostErr> {
+        let point = self.consume_string(point)?;
+        let point = Point::from_str(point.as_str())?;
+        let frame = state::check_frame(&point)?;
+        match self.states.load(&frame)? {
+            Some(data) => self.write_buffer(data),
+            None => Ok(0),
+        }
+    }
+
+    /// stage the State in buffer `state` to be committed once the frame of `point` completes
+    pub fn state_save(&self, point: i32, state: i32) -> Result<(), DefaultHostErr> {
+        let point = self.consume_string(point)?;
+        let point = Point::from_str(point.as_str())?;
+        let state = self.consume_buffer(state)?;
+        let frame = state::check_frame(&point)?;
+        self.states.stage(&frame, state)?;
+        Ok(())
+    }
+
+    /// fill a guest buffer with `len` bytes from the OS's secure random source
+    pub fn random_bytes(&self, len: i32) -> Result<i32, DefaultHostErr> {
+        if len < 0 || len as usize > MAX_RANDOM_BYTES {
+            return Err(format!(
+                "random bytes length must be between 0 and {} found {}",
+                MAX_RANDOM_BYTES, len
+            )
+            .into());
+        }
+        let mut bytes = vec![0u8; len as usize];
+        OsRng.fill_bytes(bytes.as_mut_slice());
+        self.write_buffer(bytes)
+    }
+
+    /// deliver the Signal in buffer `wave` back to the guest after `delay` millis
+    pub fn timer(&self, wave: i32, delay: i64) -> Result<(), DefaultHostErr> {
+        let wave = self.consume_buffer(wave)?;
+        let wave: UltraWave = bincode::deserialize(wave.as_slice())?;
+        let signal = Self::timer_signal(wave, &state::current_frame()?)?;
+        let delay = Duration::from_millis(delay.max(0) as u64);
+        let api = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if let Err(err) = api.transmit_to_guest(signal.to_ultra()) {
+                api.logger
+                    .error(format!("timer signal failed: {}", err.to_string()));
+            }
+        });
+        Ok(())
+    }
+
+    /// a mechtron may only set a timer for itself: the Signal must be addressed to the
+    /// mechtron of `frame` and is always sent from it whatever the guest claims
+    fn timer_signal(wave: UltraWave, frame: &Frame) -> Result<Wave<Signal>, DefaultHostErr> {
+        let wave = wave.to_directed()?;
+        if !matches!(wave.directed_kind(), DirectedKind::Signal) {
+            return Err("timer wave must be a Signal".into());
+        }
+        let mut signal = wave.to_signal()?;
+        if signal.to.point != frame.point {
+            return Err(SpaceErr::forbidden(format!(
+                "mechtron {} cannot set a timer for {}",
+                frame.point.to_string(),
+                signal.to.point.to_string()
+            ))
+            .into());
+        }
+        signal.from = frame.point.to_surface();
+        Ok(signal)
+    }
+
+    /// send the Http directed wave in buffer `wave` without blocking the guest.
+    /// returns a request id the guest can later hand to `request_poll`
+    pub fn request(&self, wave: i32) -> Result<i32, DefaultHostErr> {
+        let wave = self.consume_buffer(wave)?;
+        let wave: UltraWave = bincode::deserialize(wave.as_slice())?;
+        let wave = wave.to_directed()?;
+        if !matches!(wave.core().method, Method::Http(_)) {
+            return Err("async requests must be Http directed waves".into());
+        }
+        let to = wave.to().clone().to_single()?;
+        let reflection = wave.reflection()?;
+        let id = self
+            .requests
+            .begin()
+            .ok_or("too many outstanding async requests")?;
+        let api = self.clone();
+        tokio::spawn(async move {
+            let wave: DirectedProto = wave.into();
+            let pong = match api.transmitter.ping(wave).await {
+                Ok(pong) => pong.to_ultra(),
+                Err(err) => reflection.make(err.into(), to).to_ultra(),
+            };
+            api.requests.complete(id, pong);
+        });
+        Ok(id)
+    }
+
+    /// a buffer holding the Pong of request `id` or `None` if it hasn't arrived yet
+    pub fn request_poll(&self, id: i32) -> Result<Option<i32>, DefaultHostErr> {
+        match self.requests.poll(id) {
+            Err(()) => Err(format!("unknown async request: {}", id).into()),
+            Ok(None) => Ok(None),
+            Ok(Some(pong)) => Ok(Some(self.serialize_wave_to_guest(pong)?)),
+        }
+    }
 }
 
 pub struct WasmHostRunner {
     pub rx: mpsc::Receiver<WasmHostCall>,
     pub host: WasmHost,
+    api: WasmHostApi,
+    /// the module the current instance was created from
+    module: Module,
+    /// the Mechtrons created in this host, replayed when the host is reloaded
+    hosted: HashMap<Point, HostCmd>,
+    /// frames that arrived while a reloaded guest was not yet initialized
+    deferred: Option<Vec<WasmHostCall>>,
 }
 
 impl WasmHostRunner {
     pub fn new(
         details: Details,
-        store: &mut Store,
-        wasm: ArtRef<Bin>,
+        module: Module,
         transmitter: ProtoTransmitter,
         logger: PointLogger,
+        states: MechtronStates,
+        limits: WasmLimits,
+        panic_tx: mpsc::Sender<Point>,
     ) -> Result<WasmHostApi, DefaultHostErr> {
-        let module = Module::new(store, wasm.as_slice())?;
-
         let (tx, rx) = mpsc::channel(1024);
 
         let handle = Handle::current();
 
-        let api = WasmHostApi::new(tx);
+        let api = WasmHostApi::new(
+            tx,
+            states,
+            logger.clone(),
+            panic_tx,
+            transmitter.clone(),
+        );
+
+        let imports = Self::imports(&module, &api);
+        let instance = Some(Instance::new(&module, &imports)?);
+
+        let host = WasmHost {
+            details,
+            instance,
+            transmitter,
+            handle,
+            logger,
+            limits,
+        };
+
+        let runner = Self {
+            rx,
+            host,
+            api: api.clone(),
+            module,
+            hosted: HashMap::new(),
+            deferred: None,
+        };
+        tokio::spawn(async move {
+            runner.start().await;
+        });
+
+        Ok(api)
+    }
 
-        let imports = imports! {
+    fn imports(module: &Module, api: &WasmHostApi) -> ImportObject {
+        imports! {
 
                 "env"=>{
                      "mechtron_timestamp"=>Function::new_native_with_env(module.store(),api.clone(),|env:&WasmHostApi| {
@@ -395,6 +921,67 @@ impl WasmHostRunner {
                   }),
 
 
+                "mechtron_state_load"=>Function::new_native_with_env(module.store(),api.clone(),|env:&WasmHostApi,point:i32| -> i32 {
+                      match env.state_load(point) {
+                          Ok(buffer_id) => buffer_id,
+                          Err(err) => {
+                              env.logger.error(format!("mechtron_state_load: {}", err.to_string()));
+                              -1
+                          }
+                      }
+                  }),
+
+                "mechtron_state_save"=>Function::new_native_with_env(module.store(),api.clone(),|env:&WasmHostApi,point:i32,state:i32| -> i32 {
+                      match env.state_save(point, state) {
+                          Ok(_) => 0,
+                          Err(err) => {
+                              env.logger.error(format!("mechtron_state_save: {}", err.to_string()));
+                              -1
+                          }
+                      }
+                  }),
+
+                "mechtron_random_bytes"=>Function::new_native_with_env(module.store(),api.clone(),|env:&WasmHostApi,len:i32| -> i32 {
+                      match env.random_bytes(len) {
+                          Ok(buffer_id) => buffer_id,
+                          Err(err) => {
+                              env.logger.error(format!("mechtron_random_bytes: {}", err.to_string()));
+                              -1
+                          }
+                      }
+                  }),
+
+                "mechtron_timer"=>Function::new_native_with_env(module.store(),api.clone(),|env:&WasmHostApi,wave:i32,delay:i64| -> i32 {
+                      match env.timer(wave, delay) {
+                          Ok(_) => 0,
+                          Err(err) => {
+                              env.logger.error(format!("mechtron_timer: {}", err.to_string()));
+                              -1
+                          }
+                      }
+                  }),
+
+                "mechtron_request"=>Function::new_native_with_env(module.store(),api.clone(),|env:&WasmHostApi,wave:i32| -> i32 {
+                      match env.request(wave) {
+                          Ok(id) => id,
+                          Err(err) => {
+                              env.logger.error(format!("mechtron_request: {}", err.to_string()));
+                              -1
+                          }
+                      }
+                  }),
+
+                "mechtron_request_poll"=>Function::new_native_with_env(module.store(),api.clone(),|env:&WasmHostApi,id:i32| -> i32 {
+                      match env.request_poll(id) {
+                          Ok(Some(buffer_id)) => buffer_id,
+                          Ok(None) => 0,
+                          Err(err) => {
+                              env.logger.error(format!("mechtron_request_poll: {}", err.to_string()));
+                              -1
+                          }
+                      }
+                  }),
+
                 "mechtron_frame_to_host"=>Function::new_native_with_env(module.store(),api.clone(),|env:&WasmHostApi,buffer_id:i32| -> i32 {
                             match env.wave_to_host(buffer_id).unwrap() {
                                 Some( wave ) => {
@@ -405,29 +992,92 @@ impl WasmHostRunner {
                             }
                     }),
 
-                } };
-        let instance = Some(Instance::new(&module, &imports)?);
+                } }
+    }
 
-        let host = WasmHost {
-            details,
-            instance,
-            transmitter,
-            handle,
-            logger,
+    /// instantiate `module` in place of the current instance, then init the guest and
+    /// create again every Mechtron it was hosting.  Waves already being processed
+    /// finish against the previous instance while new frames wait for the replay.
+    /// The replay runs on its own task: the guest calls back into the host while
+    /// it initializes and those calls are answered by this runner's loop
+    fn reload(&mut self, module: Module, rtn: tokio::sync::oneshot::Sender<Result<(), DefaultHostErr>>) {
+        let imports = Self::imports(&module, &self.api);
+        let instance = match Instance::new(&module, &imports) {
+            Ok(instance) => instance,
+            Err(err) => {
+                rtn.send(Err(err.into()));
+                return;
+            }
         };
-
-        tokio::spawn( async move {
-            Self { host, rx }.start().await;
+        self.host.instance = Some(instance);
+        self.module = module;
+        self.deferred.get_or_insert_with(Vec::new);
+        let api = self.api.clone();
+        let hosted: Vec<HostCmd> = self.hosted.values().cloned().collect();
+        tokio::spawn(async move {
+            let result = api.replay(hosted).await;
+            api.tx.send(WasmHostCall::Reloaded).await.unwrap_or_default();
+            rtn.send(result);
         });
-
-        Ok(api)
     }
 
     pub async fn start(mut self) {
-        let handle = Handle::current();
         while let Some(call) = self.rx.recv().await {
-            let host = self.host.clone();
-            handle.spawn_blocking(move || match call {
+            let call = match call {
+                WasmHostCall::Reload { module, rtn } => {
+                    self.reload(module, rtn);
+                    continue;
+                }
+                WasmHostCall::Reloaded => {
+                    for call in self.deferred.take().unwrap_or_default() {
+                        self.spawn(call);
+                    }
+                    continue;
+                }
+                WasmHostCall::Unhost(point) => {
+                    self.hosted.remove(&point);
+                    continue;
+                }
+                WasmHostCall::Reinstantiate => {
+                    let (rtn, rtn_rx) = tokio::sync::oneshot::channel();
+                    self.reload(self.module.clone(), rtn);
+                    let logger = self.host.logger.clone();
+                    tokio::spawn(async move {
+                        if let Ok(Err(err)) = rtn_rx.await {
+                            logger.error(format!(
+                                "could not reinstantiate guest: {}",
+                                err.to_string()
+                            ));
+                        }
+                    });
+                    continue;
+                }
+                WasmHostCall::HostCmd { cmd, frame, rtn } => {
+                    self.hosted
+                        .insert(cmd.details.stub.point.clone(), cmd.clone());
+                    WasmHostCall::HostCmd { cmd, frame, rtn }
+                }
+                call @ WasmHostCall::GuestConsumeWave { .. } if self.deferred.is_some() => {
+                    self.deferred.as_mut().unwrap().push(call);
+                    continue;
+                }
+                call => call,
+            };
+            self.spawn(call);
+        }
+    }
+
+    /// handle `call` against the current instance on a blocking thread
+    fn spawn(&self, call: WasmHostCall) {
+        let host = self.host.clone();
+        let duration = METRICS.histogram(
+            "cosmic_wasm_host_call_seconds",
+            "duration of the calls handled by wasm hosts",
+            &[("call", call.name())],
+        );
+        Handle::current().spawn_blocking(move || {
+            let start = Instant::now();
+            match call {
                 WasmHostCall::Init(rtn) => {
                     rtn.send(host.init());
                 }
@@ -455,19 +1105,27 @@ impl WasmHostRunner {
                 WasmHostCall::WaveToHost { wave, rtn } => {
                     rtn.send(host.wave_to_host(wave));
                 }
-                WasmHostCall::HostCmd { cmd, rtn } => {
-                    rtn.send(host.create_mechtron(cmd));
+                WasmHostCall::HostCmd { cmd, frame, rtn } => {
+                    rtn.send(host.create_mechtron(cmd, frame));
                 }
-                WasmHostCall::GuestConsumeWave { wave, rtn } => {
-                    let frame = host.mechtron_frame_to_guest(wave).unwrap();
-                    if frame > 0 {
-                        rtn.send(Ok(Some(host.deserialize_wave_to_host(frame).unwrap())));
-                    } else {
-                        rtn.send(Ok(None));
-                    }
+                WasmHostCall::Reload { .. }
+                | WasmHostCall::Reloaded
+                | WasmHostCall::Unhost(_)
+                | WasmHostCall::Reinstantiate => {}
+                WasmHostCall::GuestConsumeWave { wave, frame, rtn } => {
+                    let result = match state::in_frame(frame, || host.mechtron_frame_to_guest(wave)) {
+                        Ok(frame) if frame > 0 => host
+                            .deserialize_wave_to_host(frame)
+                            .map(|wave| Some(wave))
+                            .map_err(GuestFault::from),
+                        Ok(_) => Ok(None),
+                        Err(fault) => Err(fault),
+                    };
+                    rtn.send(result);
                 }
-            });
-        }
+            }
+            duration.observe(start.elapsed().as_secs_f64());
+        });
     }
 }
 
@@ -478,6 +1136,7 @@ pub struct WasmHost {
     pub transmitter: ProtoTransmitter,
     handle: Handle,
     logger: PointLogger,
+    limits: WasmLimits,
 }
 
 impl WasmHost {
@@ -571,12 +1230,57 @@ impl WasmHost {
             .exports
             .get_native_function::<(i32, i32), i32>("mechtron_guest_init")
         {
-            Ok(func) => {
-                self.logger.info("verified: mechtron_guest_init()");
+            Ok(_) => {
+                self.logger.info("verified: mechtron_guest_init( i32, i32 ) -> i32");
+            }
+            Err(_) => {
+                self.logger
+                    .info("failed: mechtron_guest_init( i32, i32 ) -> i32");
+                pass = false
+            }
+        }
+
+        match self
+            .instance
+            .as_ref()
+            .unwrap()
+            .exports
+            .get_native_function::<i32, i32>("mechtron_frame_to_guest")
+        {
+            Ok(_) => {
+                self.logger
+                    .info("verified: mechtron_frame_to_guest( i32 ) -> i32");
             }
             Err(_) => {
                 self.logger
-                    .info("failed: mechtron_guest_init() [NOT REQUIRED]");
+                    .info("failed: mechtron_frame_to_guest( i32 ) -> i32");
+                pass = false
+            }
+        }
+
+        match self
+            .instance
+            .as_ref()
+            .unwrap()
+            .exports
+            .get_native_function::<(), i32>("mechtron_guest_version")
+        {
+            Ok(func) => {
+                let version = func.call()?;
+                if version > HOST_ABI_VERSION {
+                    self.logger.error(format!(
+                        "failed: mechtron_guest_version() -> {} (host supports up to {})",
+                        version, HOST_ABI_VERSION
+                    ));
+                    pass = false
+                } else {
+                    self.logger
+                        .info(format!("verified: mechtron_guest_version() -> {}", version));
+                }
+            }
+            Err(_) => {
+                self.logger.info("failed: mechtron_guest_version() -> i32");
+                pass = false
             }
         }
 
@@ -804,17 +1508,49 @@ impl WasmHost {
     }
 
 
-    fn mechtron_frame_to_guest(&self, buffer_id: i32) -> Result<i32, DefaultHostErr> {
-        let rtn = self.instance
-            .as_ref()
-            .unwrap()
+    /// deliver a frame to the guest with a fresh allotment of fuel. A `Watchdog` drains
+    /// the fuel if the call overruns its deadline so the guest stops inside wasm.
+    /// A trap is translated into the `GuestLimit` that caused it (if any)
+    fn mechtron_frame_to_guest(&self, buffer_id: i32) -> Result<i32, GuestFault> {
+        let instance = self.instance.as_ref().unwrap();
+        if metered(&self.limits) {
+            set_remaining_points(instance, self.limits.fuel.unwrap_or(u64::MAX));
+        }
+        let func = instance
             .exports
-            .get_native_function::<i32, i32>("mechtron_frame_to_guest")?
-            .call(buffer_id.clone())?;
-        Ok(rtn)
+            .get_native_function::<i32, i32>("mechtron_frame_to_guest")
+            .map_err(DefaultHostErr::from)?;
+        let watchdog = self.limits.call_timeout.map(|timeout| {
+            Watchdog::start(&self.handle, instance.clone(), Duration::from_secs(timeout))
+        });
+        let result = func.call(buffer_id.clone());
+        let deadline = watchdog.map(|watchdog| watchdog.stop()).unwrap_or(false);
+        match result {
+            Ok(rtn) => Ok(rtn),
+            Err(err) => {
+                if deadline {
+                    return Err(GuestFault::Limit(GuestLimit::Deadline));
+                }
+                if self.limits.fuel.is_some()
+                    && get_remaining_points(instance) == MeteringPoints::Exhausted
+                {
+                    return Err(GuestFault::Limit(GuestLimit::Fuel));
+                }
+                if self.limits.max_memory_pages.is_some() {
+                    // the tunables cap the memory's maximum so a guest that trapped with
+                    // its memory grown to that maximum ran out of memory
+                    if let Ok(memory) = instance.exports.get_memory("memory") {
+                        if Some(memory.size()) == memory.ty().maximum {
+                            return Err(GuestFault::Limit(GuestLimit::Memory));
+                        }
+                    }
+                }
+                Err(DefaultHostErr::from(err).into())
+            }
+        }
     }
 
-    fn create_mechtron(&self, host_cmd: HostCmd) -> Result<(), DefaultHostErr> {
+    fn create_mechtron(&self, host_cmd: HostCmd, frame: Frame) -> Result<(), DefaultHostErr> {
         let mut wave = DirectedProto::ping();
         wave.to(self.details.stub.point.to_surface().with_layer(Layer::Core));
         wave.from(self.details.stub.point.to_surface().with_layer(Layer::Host));
@@ -822,21 +1558,331 @@ impl WasmHost {
         wave.body(Substance::Hyper(HyperSubstance::Host(host_cmd)));
         let wave = self.logger.result(wave.build())?;
         let wave = wave.to_ultra();
-        self.logger.result(self.route(wave))?;
+        self.logger
+            .result(state::in_frame(Some(frame), || self.route(wave)))?;
         Ok(())
     }
 }
 
 #[cfg(test)]
 pub mod test {
-    use crate::HostsRunner;
+    use crate::err::DefaultHostErr;
+    use crate::limits::limited_store;
+    use crate::state::{self, Frame, MechtronStates, MemStateStore};
+    use crate::{HostsRunner, WasmHostApi, WasmHostRunner, REQUIRED_GUEST_EXPORTS};
     use cosmic_space::artifact::asynch::MapFetcher;
-    use cosmic_space::loc::Point;
+    use cosmic_space::command::common::StateSrc;
+    use cosmic_space::config::mechtron::{MechtronConfig, WasmLimits};
+    use cosmic_space::hyper::{AssignmentKind, HostCmd};
+    use cosmic_space::loc::{Point, ToSurface};
+    use cosmic_space::log::RootLogger;
     use cosmic_space::particle::Details;
+    use cosmic_space::settings::Timeouts;
+    use cosmic_space::wave::core::ext::ExtMethod;
+    use cosmic_space::wave::exchange::asynch::{Exchanger, ProtoTransmitter, TxRouter};
+    use cosmic_space::wave::{DirectedProto, UltraWave};
     use std::fs;
     use std::str::FromStr;
     use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use wasmer::Module;
 
     #[tokio::test]
     pub async fn test() {}
+
+    /// a guest that calls back into the host (via `mechtron_uuid`) when it is handed a frame
+    pub const CALLBACK_FRAME: &str = "(drop (call $uuid)) (i32.const 0)";
+
+    /// a guest that saves the State of `localhost:app:one` (its own point) when it is
+    /// handed a frame. Buffers 500 & 501 both point at the point string in the data segment
+    pub const SAVE_FRAME: &str = r#"
+        (i32.store (i32.const 4000) (i32.const 3000))
+        (i32.store (i32.const 4004) (i32.const 17))
+        (i32.store (i32.const 4008) (i32.const 3000))
+        (i32.store (i32.const 4012) (i32.const 17))
+        (drop (call $save (i32.const 500) (i32.const 501)))
+        (i32.const 0)"#;
+
+    /// a minimal guest: a bump allocator for buffers and a membrane that calls back
+    /// into the host whenever it is initialized. `frame` is the body of
+    /// `mechtron_frame_to_guest`
+    pub fn guest_wat(frame: &str) -> String {
+        format!(
+            r#"
+    (module
+      (import "env" "mechtron_uuid" (func $uuid (result i32)))
+      (import "env" "mechtron_state_save" (func $save (param i32 i32) (result i32)))
+      (memory (export "memory") 4)
+      (data (i32.const 3000) "localhost:app:one")
+      (global $next (mut i32) (i32.const 4096))
+      (global $id (mut i32) (i32.const 1))
+      (func (export "mechtron_guest_alloc_buffer") (param $len i32) (result i32)
+        (local $id i32)
+        (local.set $id (global.get $id))
+        (global.set $id (i32.add (global.get $id) (i32.const 1)))
+        (i32.store (i32.mul (local.get $id) (i32.const 8)) (global.get $next))
+        (i32.store (i32.add (i32.mul (local.get $id) (i32.const 8)) (i32.const 4)) (local.get $len))
+        (global.set $next (i32.add (global.get $next) (local.get $len)))
+        (local.get $id))
+      (func (export "mechtron_guest_dealloc_buffer") (param i32))
+      (func (export "mechtron_guest_get_buffer_ptr") (param $id i32) (result i32)
+        (i32.load (i32.mul (local.get $id) (i32.const 8))))
+      (func (export "mechtron_guest_get_buffer_len") (param $id i32) (result i32)
+        (i32.load (i32.add (i32.mul (local.get $id) (i32.const 8)) (i32.const 4))))
+      (func (export "mechtron_guest_version") (result i32) (i32.const 2))
+      (func (export "mechtron_guest_init") (param i32 i32) (result i32)
+        (drop (call $uuid))
+        (i32.const 0))
+      (func (export "mechtron_frame_to_guest") (param i32) (result i32)
+        {})
+    )
+    "#,
+            frame
+        )
+    }
+
+    pub fn guest_host(wat: &str, limits: WasmLimits) -> Result<(WasmHostApi, Module), DefaultHostErr> {
+        let point = Point::from_str("localhost:host").unwrap();
+        let logger = RootLogger::default().point(point.clone());
+        let (tx, _rx) = mpsc::channel(1024);
+        let exchanger = Exchanger::new(point.to_surface(), Timeouts::default(), logger.clone());
+        let transmitter = ProtoTransmitter::new(Arc::new(TxRouter::new(tx)), exchanger);
+        let mut details = Details::default();
+        details.stub.point = point;
+        let store = limited_store(&limits);
+        let module = Module::new(&store, wat)?;
+        let states = MechtronStates::new(Arc::new(MemStateStore::new()));
+        let (panic_tx, _panic_rx) = mpsc::channel(1024);
+        let api = WasmHostRunner::new(
+            details,
+            module.clone(),
+            transmitter,
+            logger,
+            states,
+            limits,
+            panic_tx,
+        )?;
+        Ok((api, module))
+    }
+
+    fn host_cmd(point: &str) -> HostCmd {
+        let point = Point::from_str(point).unwrap();
+        let mut details = Details::default();
+        details.stub.point = point.clone();
+        HostCmd {
+            kind: AssignmentKind::Create,
+            details,
+            state: StateSrc::None,
+            config: MechtronConfig {
+                wasm: Point::from_str("localhost:repo:app:1.0.0:/wasm/app.wasm").unwrap(),
+                name: "app".to_string(),
+                limits: Default::default(),
+            },
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn test_reload_hosting_mechtrons() {
+        let (host, module) =
+            guest_host(guest_wat(CALLBACK_FRAME).as_str(), WasmLimits::default()).unwrap();
+        host.init().await.unwrap();
+        host.create_mechtron(host_cmd("localhost:app:one")).await.unwrap();
+        host.create_mechtron(host_cmd("localhost:app:two")).await.unwrap();
+        host.unhost(&Point::from_str("localhost:app:two").unwrap())
+            .await
+            .unwrap();
+
+        // the guest calls back into the host while it is initialized & its mechtrons
+        // are created again, which must not stall the reload
+        tokio::time::timeout(Duration::from_secs(10), host.reload(module.clone()))
+            .await
+            .expect("reload deadlocked")
+            .unwrap();
+
+        // the reloaded host is still answering
+        tokio::time::timeout(Duration::from_secs(10), host.reload(module))
+            .await
+            .expect("reload deadlocked")
+            .unwrap();
+        assert_eq!(
+            host.point().await.unwrap(),
+            Point::from_str("localhost:host").unwrap()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn test_missing_guest_export() {
+        let wat = guest_wat(CALLBACK_FRAME);
+        assert!(guest_host(wat.as_str(), WasmLimits::default())
+            .unwrap()
+            .0
+            .init()
+            .await
+            .is_ok());
+        for export in REQUIRED_GUEST_EXPORTS {
+            // rename the export so the guest still compiles but no longer provides it
+            let wat = wat.replace(
+                format!("(export \"{}\")", export).as_str(),
+                format!("(export \"{}_gone\")", export).as_str(),
+            );
+            let (host, _) = guest_host(wat.as_str(), WasmLimits::default()).unwrap();
+            assert!(host.init().await.is_err(), "{} should be required", export);
+        }
+    }
+
+    #[test]
+    pub fn test_memory_maximum() {
+        use wasmer::{imports, Instance, Pages};
+        let mut limits = WasmLimits::default();
+        limits.max_memory_pages = Some(2);
+        let store = limited_store(&limits);
+        let module = Module::new(
+            &store,
+            r#"
+            (module
+              (memory (export "memory") 1)
+              (func (export "grow") (param i32) (result i32)
+                (memory.grow (local.get 0))))
+            "#,
+        )
+        .unwrap();
+        let instance = Instance::new(&module, &imports! {}).unwrap();
+        let memory = instance.exports.get_memory("memory").unwrap();
+        assert_eq!(memory.ty().maximum, Some(Pages(2)));
+        let grow = instance
+            .exports
+            .get_native_function::<i32, i32>("grow")
+            .unwrap();
+        assert_eq!(grow.call(1).unwrap(), 1);
+        // growing past the maximum is refused
+        assert_eq!(grow.call(1).unwrap(), -1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn test_deadline_stops_guest() {
+        use crate::limits::{GuestFault, GuestLimit};
+        let mut limits = WasmLimits::default();
+        limits.call_timeout = Some(1);
+        // a guest that never returns from its frame
+        let wat = guest_wat("(loop $forever (br $forever)) (i32.const 0)");
+        let (host, _) = guest_host(wat.as_str(), limits).unwrap();
+        host.init().await.unwrap();
+
+        let frame = {
+            let host = host.clone();
+            tokio::task::spawn_blocking(move || {
+                let buffer = host.write_buffer(vec![])?;
+                Ok::<_, DefaultHostErr>(host.guest_consume_wave(buffer, None))
+            })
+        };
+        let fault = tokio::time::timeout(Duration::from_secs(10), frame)
+            .await
+            .expect("guest ran past its deadline")
+            .unwrap()
+            .unwrap();
+        match fault {
+            Err(GuestFault::Limit(limit)) => assert_eq!(limit, GuestLimit::Deadline),
+            other => panic!("expected a deadline fault found {:?}", other),
+        }
+    }
+
+    fn timer_signal(to: &str, from: &str) -> UltraWave {
+        let mut signal = DirectedProto::signal();
+        signal.to(Point::from_str(to).unwrap().to_surface());
+        signal.from(Point::from_str(from).unwrap().to_surface());
+        signal.method(ExtMethod::new("Tick").unwrap());
+        signal.build().unwrap().to_ultra()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn test_timer_fires() {
+        let (host, _) = guest_host(guest_wat(SAVE_FRAME).as_str(), WasmLimits::default()).unwrap();
+        host.init().await.unwrap();
+        let point = Point::from_str("localhost:app:one").unwrap();
+
+        let set = {
+            let host = host.clone();
+            let point = point.clone();
+            tokio::task::spawn_blocking(move || {
+                let wave = bincode::serialize(&timer_signal("localhost:app:one", "localhost:app:one"))?;
+                let wave = host.write_buffer(wave)?;
+                let frame = host.states.open(&point)?;
+                let rtn = state::in_frame(Some(frame.clone()), || host.timer(wave, 10));
+                host.states.discard(&frame);
+                rtn
+            })
+        };
+        set.await.unwrap().unwrap();
+
+        // the guest saves its State when the timer delivers the signal
+        let saved = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let frame = host.states.open(&point).unwrap();
+                let saved = host.states.load(&frame).unwrap();
+                host.states.discard(&frame);
+                match saved {
+                    Some(saved) => return saved,
+                    None => tokio::time::sleep(Duration::from_millis(10)).await,
+                }
+            }
+        })
+        .await
+        .expect("timer never fired");
+        assert_eq!(saved, b"localhost:app:one".to_vec());
+    }
+
+    #[test]
+    pub fn test_timer_signal() {
+        let one = Point::from_str("localhost:app:one").unwrap();
+        let frame = Frame {
+            id: 0,
+            point: one.clone(),
+        };
+
+        let signal = WasmHostApi::timer_signal(
+            timer_signal("localhost:app:one", "localhost:app:one"),
+            &frame,
+        )
+        .unwrap();
+        assert_eq!(signal.from, one.to_surface());
+
+        // a guest cannot forge the sender of its timer
+        let signal = WasmHostApi::timer_signal(
+            timer_signal("localhost:app:one", "localhost:app:two"),
+            &frame,
+        )
+        .unwrap();
+        assert_eq!(signal.from, one.to_surface());
+
+        // nor set a timer for another mechtron
+        assert!(WasmHostApi::timer_signal(
+            timer_signal("localhost:app:two", "localhost:app:one"),
+            &frame,
+        )
+        .is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn test_timer_outside_frame() {
+        let (host, _) = guest_host(guest_wat(CALLBACK_FRAME).as_str(), WasmLimits::default()).unwrap();
+        host.init().await.unwrap();
+        let set = tokio::task::spawn_blocking(move || {
+            let wave = bincode::serialize(&timer_signal("localhost:app:one", "localhost:app:one"))?;
+            let wave = host.write_buffer(wave)?;
+            host.timer(wave, 0)
+        });
+        assert!(set.await.unwrap().is_err());
+    }
+
+    #[test]
+    pub fn test_requests_expire() {
+        use crate::requests::HostRequests;
+        let requests = HostRequests::with_timeout(Duration::from_millis(10));
+        let id = requests.begin().unwrap();
+        assert!(matches!(requests.poll(id), Ok(None)));
+        std::thread::sleep(Duration::from_millis(20));
+        // a request the guest never collected is swept
+        assert!(requests.poll(id).is_err());
+    }
 }
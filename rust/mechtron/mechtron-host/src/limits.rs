@@ -0,0 +1,193 @@
+use crate::err::DefaultHostErr;
+use cosmic_space::config::mechtron::WasmLimits;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use wasmer::vm::{self, MemoryError, MemoryStyle, TableStyle, VMMemoryDefinition, VMTableDefinition};
+use wasmer::wasmparser::Operator;
+use wasmer::{
+    BaseTunables, Instance, MemoryType, Pages, Store, TableType, Target, Tunables, Universal,
+};
+use wasmer_compiler_singlepass::Singlepass;
+use wasmer_middlewares::metering::set_remaining_points;
+use wasmer_middlewares::Metering;
+
+/// a limit of `WasmLimits` that a guest has exceeded
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GuestLimit {
+    Fuel,
+    Memory,
+    Deadline,
+}
+
+impl GuestLimit {
+    pub fn status(&self) -> u16 {
+        match self {
+            GuestLimit::Fuel => 503,
+            GuestLimit::Memory => 503,
+            GuestLimit::Deadline => 408,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            GuestLimit::Fuel => "guest exhausted its fuel".to_string(),
+            GuestLimit::Memory => "guest exceeded its max memory pages".to_string(),
+            GuestLimit::Deadline => "guest call exceeded its deadline".to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GuestFault {
+    Limit(GuestLimit),
+    Err(DefaultHostErr),
+}
+
+impl From<DefaultHostErr> for GuestFault {
+    fn from(err: DefaultHostErr) -> Self {
+        GuestFault::Err(err)
+    }
+}
+
+/// `true` if guests compiled for `limits` are metered. A deadline needs metering too
+/// since an overrunning guest is stopped by draining its fuel
+pub fn metered(limits: &WasmLimits) -> bool {
+    limits.fuel.is_some() || limits.call_timeout.is_some()
+}
+
+/// create a Store whose compiler meters fuel and whose tunables cap linear memory
+pub fn limited_store(limits: &WasmLimits) -> Store {
+    let mut compiler = Singlepass::default();
+    if metered(limits) {
+        let fuel = limits.fuel.unwrap_or(u64::MAX);
+        compiler.push_middleware(Arc::new(Metering::new(fuel, |_: &Operator| -> u64 { 1 })));
+    }
+    let engine = Universal::new(compiler).engine();
+    match limits.max_memory_pages {
+        None => Store::new(&engine),
+        Some(pages) => {
+            let base = BaseTunables::for_target(&Target::default());
+            Store::new_with_tunables(&engine, LimitingTunables::new(base, Pages(pages)))
+        }
+    }
+}
+
+/// stops a guest call that overruns its deadline: once `timeout` passes the remaining
+/// fuel of the instance is drained so the guest traps at its next metering check
+pub struct Watchdog {
+    cancel: Option<tokio::sync::oneshot::Sender<()>>,
+    tripped: Arc<AtomicBool>,
+}
+
+impl Watchdog {
+    pub fn start(handle: &Handle, instance: Instance, timeout: Duration) -> Self {
+        let (cancel, cancelled) = tokio::sync::oneshot::channel::<()>();
+        let tripped = Arc::new(AtomicBool::new(false));
+        {
+            let tripped = tripped.clone();
+            handle.spawn(async move {
+                tokio::select! {
+                    _ = tokio::time::sleep(timeout) => {
+                        tripped.store(true, Ordering::SeqCst);
+                        set_remaining_points(&instance, 0);
+                    }
+                    _ = cancelled => {}
+                }
+            });
+        }
+        Self {
+            cancel: Some(cancel),
+            tripped,
+        }
+    }
+
+    /// stop watching the call. Returns `true` if the deadline had already passed
+    pub fn stop(mut self) -> bool {
+        if let Some(cancel) = self.cancel.take() {
+            cancel.send(()).unwrap_or_default();
+        }
+        self.tripped.load(Ordering::SeqCst)
+    }
+}
+
+/// Tunables that refuse to create or grow a memory beyond `limit`
+pub struct LimitingTunables<T: Tunables> {
+    limit: Pages,
+    base: T,
+}
+
+impl<T: Tunables> LimitingTunables<T> {
+    pub fn new(base: T, limit: Pages) -> Self {
+        Self { limit, base }
+    }
+
+    fn adjust_memory(&self, requested: &MemoryType) -> MemoryType {
+        let mut adjusted = requested.clone();
+        if requested.maximum.is_none() || requested.maximum.unwrap() > self.limit {
+            adjusted.maximum = Some(self.limit);
+        }
+        adjusted
+    }
+
+    fn validate_memory(&self, ty: &MemoryType) -> Result<(), MemoryError> {
+        if ty.minimum > self.limit {
+            return Err(MemoryError::Generic(
+                "Minimum exceeds the allowed memory limit".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<T: Tunables> Tunables for LimitingTunables<T> {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        let adjusted = self.adjust_memory(memory);
+        self.base.memory_style(&adjusted)
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        self.base.create_host_memory(&adjusted, style)
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        self.base
+            .create_vm_memory(&adjusted, style, vm_definition_location)
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
@@ -0,0 +1,83 @@
+use cosmic_space::wave::UltraWave;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// the max number of async requests a Host will track before refusing new ones
+pub const MAX_PENDING_REQUESTS: usize = 1024;
+
+/// how long a Host tracks an async request. A request the guest has not collected
+/// by then is forgotten so it can't hold a slot forever
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+struct Pending {
+    began: Instant,
+    pong: Option<UltraWave>,
+}
+
+/// async requests a guest has handed to the Host via `mechtron_request`.
+/// The Pong is held here until the guest collects it via `mechtron_request_poll`
+#[derive(Clone)]
+pub struct HostRequests {
+    seq: Arc<AtomicI32>,
+    timeout: Duration,
+    pending: Arc<Mutex<HashMap<i32, Pending>>>,
+}
+
+impl HostRequests {
+    pub fn new() -> Self {
+        Self::with_timeout(REQUEST_TIMEOUT)
+    }
+
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            // request ids start at 1 since 0 & negatives have special meaning to the guest
+            seq: Arc::new(AtomicI32::new(1)),
+            timeout,
+            pending: Default::default(),
+        }
+    }
+
+    fn sweep(&self, pending: &mut HashMap<i32, Pending>) {
+        let timeout = self.timeout;
+        pending.retain(|_, request| request.began.elapsed() < timeout);
+    }
+
+    /// reserve a request id or `None` if too many requests are outstanding
+    pub fn begin(&self) -> Option<i32> {
+        let mut pending = self.pending.lock().unwrap();
+        self.sweep(&mut pending);
+        if pending.len() >= MAX_PENDING_REQUESTS {
+            return None;
+        }
+        let id = self.seq.fetch_add(1, Ordering::Relaxed);
+        pending.insert(
+            id,
+            Pending {
+                began: Instant::now(),
+                pong: None,
+            },
+        );
+        Some(id)
+    }
+
+    pub fn complete(&self, id: i32, pong: UltraWave) {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(request) = pending.get_mut(&id) {
+            request.pong.replace(pong);
+        }
+    }
+
+    /// `Err` if `id` is unknown (or expired), `Ok(None)` if the Pong has not arrived yet.
+    /// A Pong is removed once it has been returned
+    pub fn poll(&self, id: i32) -> Result<Option<UltraWave>, ()> {
+        let mut pending = self.pending.lock().unwrap();
+        self.sweep(&mut pending);
+        match pending.get(&id) {
+            None => Err(()),
+            Some(Pending { pong: None, .. }) => Ok(None),
+            Some(Pending { pong: Some(_), .. }) => Ok(pending.remove(&id).unwrap().pong),
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use cosmic_space::err::SpaceErr;
+use cosmic_space::loc::Point;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+thread_local! {
+    /// the frame being delivered on this thread. Guest imports run on the thread that
+    /// called into the guest so the State of its mechtron is the only one they may touch
+    static FRAME: RefCell<Option<Frame>> = RefCell::new(None);
+}
+
+/// one call into the guest on behalf of mechtron `point`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Frame {
+    pub id: u64,
+    pub point: Point,
+}
+
+/// run `f` (a call into the guest) within `frame`
+pub fn in_frame<F, R>(frame: Option<Frame>, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let prev = FRAME.with(|current| current.replace(frame));
+    let rtn = f();
+    FRAME.with(|current| current.replace(prev));
+    rtn
+}
+
+/// the frame the guest is currently processing on this thread
+pub fn current_frame() -> Result<Frame, SpaceErr> {
+    FRAME.with(|frame| {
+        frame
+            .borrow()
+            .clone()
+            .ok_or_else(|| SpaceErr::forbidden("guest called the host outside of a frame"))
+    })
+}
+
+/// the guest may only load & save the State of the mechtron it is processing a frame for
+pub fn check_frame(point: &Point) -> Result<Frame, SpaceErr> {
+    FRAME.with(|frame| match &*frame.borrow() {
+        Some(current) if current.point == *point => Ok(current.clone()),
+        Some(current) => Err(SpaceErr::forbidden(format!(
+            "mechtron {} cannot access the state of {}",
+            current.point.to_string(),
+            point.to_string()
+        ))),
+        None => Err(SpaceErr::forbidden(format!(
+            "state of {} cannot be accessed outside of a frame",
+            point.to_string()
+        ))),
+    })
+}
+
+fn poisoned<T>(err: PoisonError<T>) -> SpaceErr {
+    SpaceErr::server_error(format!("state lock poisoned: {}", err.to_string()))
+}
+
+/// a persisted Mechtron State. `version` is incremented on every save
+/// and is used for optimistic concurrency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateRecord {
+    pub version: u64,
+    pub data: Vec<u8>,
+}
+
+/// durable storage for Mechtron States.
+/// `save` must fail with a 409 if the stored version no longer matches `expected`
+pub trait MechtronStateStore: Send + Sync {
+    fn load(&self, point: &Point) -> Result<Option<StateRecord>, SpaceErr>;
+    fn save(&self, point: &Point, expected: u64, data: Vec<u8>) -> Result<u64, SpaceErr>;
+}
+
+fn conflict(point: &Point, expected: u64, found: u64) -> SpaceErr {
+    SpaceErr::new(
+        409,
+        format!(
+            "state of mechtron {} was modified concurrently (expected version {} found {})",
+            point.to_string(),
+            expected,
+            found
+        ),
+    )
+}
+
+#[derive(Clone, Default)]
+pub struct MemStateStore {
+    states: Arc<Mutex<HashMap<Point, StateRecord>>>,
+}
+
+impl MemStateStore {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl MechtronStateStore for MemStateStore {
+    fn load(&self, point: &Point) -> Result<Option<StateRecord>, SpaceErr> {
+        Ok(self.states.lock().map_err(poisoned)?.get(point).cloned())
+    }
+
+    fn save(&self, point: &Point, expected: u64, data: Vec<u8>) -> Result<u64, SpaceErr> {
+        let mut states = self.states.lock().map_err(poisoned)?;
+        let found = states.get(point).map(|r| r.version).unwrap_or(0);
+        if found != expected {
+            return Err(conflict(point, expected, found));
+        }
+        let version = expected + 1;
+        states.insert(point.clone(), StateRecord { version, data });
+        Ok(version)
+    }
+}
+
+/// stores each State as a file in the Star's data directory. The file is named after
+/// the point with every character but ASCII letters, digits, `-` & `_` percent escaped
+/// so no two points share a file
+#[derive(Clone)]
+pub struct FileStateStore {
+    dir: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl FileStateStore {
+    pub fn new<D: Into<PathBuf>>(dir: D) -> Result<Self, SpaceErr> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| SpaceErr::server_error(e.to_string()))?;
+        Ok(Self {
+            dir,
+            lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    fn path(&self, point: &Point) -> PathBuf {
+        let mut name = String::new();
+        for byte in point.to_string().bytes() {
+            match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => name.push(byte as char),
+                _ => name.push_str(format!("%{:02X}", byte).as_str()),
+            }
+        }
+        self.dir.join(name)
+    }
+
+    fn read(&self, point: &Point) -> Result<Option<StateRecord>, SpaceErr> {
+        match fs::read(self.path(point)) {
+            Ok(bin) => Ok(Some(bincode::deserialize(bin.as_slice())?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(SpaceErr::server_error(err.to_string())),
+        }
+    }
+}
+
+impl MechtronStateStore for FileStateStore {
+    fn load(&self, point: &Point) -> Result<Option<StateRecord>, SpaceErr> {
+        let _lock = self.lock.lock().map_err(poisoned)?;
+        self.read(point)
+    }
+
+    fn save(&self, point: &Point, expected: u64, data: Vec<u8>) -> Result<u64, SpaceErr> {
+        let _lock = self.lock.lock().map_err(poisoned)?;
+        let found = self.read(point)?.map(|r| r.version).unwrap_or(0);
+        if found != expected {
+            return Err(conflict(point, expected, found));
+        }
+        let version = expected + 1;
+        let bin = bincode::serialize(&StateRecord { version, data })?;
+        let path = self.path(point);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, bin).map_err(|e| SpaceErr::server_error(e.to_string()))?;
+        fs::rename(&tmp, &path).map_err(|e| SpaceErr::server_error(e.to_string()))?;
+        Ok(version)
+    }
+}
+
+/// the Host's view of Mechtron States: the store plus the State of every open frame.
+/// A frame's State is loaded when the frame opens, before the guest restores its
+/// mechtron, & what the guest hands back is saved when the frame commits provided the
+/// State is still at the version the frame loaded. Concurrent frames of one mechtron
+/// each hold their own State so the later commit is refused with a 409
+#[derive(Clone)]
+pub struct MechtronStates {
+    store: Arc<dyn MechtronStateStore>,
+    frames: Arc<Mutex<HashMap<u64, OpenFrame>>>,
+    next: Arc<AtomicU64>,
+}
+
+struct OpenFrame {
+    point: Point,
+    loaded: Option<StateRecord>,
+    staged: Option<Vec<u8>>,
+}
+
+impl MechtronStates {
+    pub fn new(store: Arc<dyn MechtronStateStore>) -> Self {
+        Self {
+            store,
+            frames: Default::default(),
+            next: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// open a frame of `point` & load its State
+    pub fn open(&self, point: &Point) -> Result<Frame, SpaceErr> {
+        let loaded = self.store.load(point)?;
+        let frame = Frame {
+            id: self.next.fetch_add(1, Ordering::Relaxed),
+            point: point.clone(),
+        };
+        self.frames.lock().map_err(poisoned)?.insert(
+            frame.id,
+            OpenFrame {
+                point: point.clone(),
+                loaded,
+                staged: None,
+            },
+        );
+        Ok(frame)
+    }
+
+    /// the State `frame` loaded when it opened, called by the guest import
+    /// `mechtron_state_load`
+    pub fn load(&self, frame: &Frame) -> Result<Option<Vec<u8>>, SpaceErr> {
+        let frames = self.frames.lock().map_err(poisoned)?;
+        let open = frames.get(&frame.id).ok_or_else(|| closed(frame))?;
+        Ok(open.loaded.as_ref().map(|record| record.data.clone()))
+    }
+
+    /// called by the guest import `mechtron_state_save`
+    pub fn stage(&self, frame: &Frame, data: Vec<u8>) -> Result<(), SpaceErr> {
+        let mut frames = self.frames.lock().map_err(poisoned)?;
+        let open = frames.get_mut(&frame.id).ok_or_else(|| closed(frame))?;
+        open.staged = Some(data);
+        Ok(())
+    }
+
+    /// close `frame` without persisting its staged State
+    pub fn discard(&self, frame: &Frame) {
+        if let Ok(mut frames) = self.frames.lock() {
+            frames.remove(&frame.id);
+        }
+    }
+
+    /// close `frame` & persist its staged State (if any) provided the stored State is
+    /// still at the version the frame loaded
+    pub fn commit(&self, frame: &Frame) -> Result<(), SpaceErr> {
+        let open = self
+            .frames
+            .lock()
+            .map_err(poisoned)?
+            .remove(&frame.id)
+            .ok_or_else(|| closed(frame))?;
+        match open.staged {
+            None => Ok(()),
+            Some(data) => {
+                let expected = open.loaded.map(|record| record.version).unwrap_or(0);
+                self.store.save(&open.point, expected, data)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+fn closed(frame: &Frame) -> SpaceErr {
+    SpaceErr::server_error(format!(
+        "frame {} of mechtron {} is closed",
+        frame.id,
+        frame.point.to_string()
+    ))
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use cosmic_space::err::StatusErr;
+    use cosmic_space::loc::Point;
+
+    use crate::state::{
+        check_frame, in_frame, FileStateStore, MechtronStateStore, MechtronStates,
+        MemStateStore,
+    };
+
+    #[test]
+    pub fn test_optimistic_versioning() {
+        let store = MemStateStore::new();
+        let point = Point::from_str("localhost:app").unwrap();
+        assert!(store.load(&point).unwrap().is_none());
+        assert_eq!(store.save(&point, 0, vec![1]).unwrap(), 1);
+        // a second writer that also loaded version 0 must not overwrite
+        assert_eq!(store.save(&point, 0, vec![2]).unwrap_err().status(), 409);
+        assert_eq!(store.load(&point).unwrap().unwrap().data, vec![1]);
+    }
+
+    #[test]
+    pub fn test_frame_isolation() {
+        let states = MechtronStates::new(Arc::new(MemStateStore::new()));
+        let app = Point::from_str("localhost:app").unwrap();
+        let other = Point::from_str("localhost:other").unwrap();
+        // outside of a frame no State may be touched
+        assert_eq!(check_frame(&app).unwrap_err().status(), 403);
+        let frame = states.open(&app).unwrap();
+        in_frame(Some(frame.clone()), || {
+            assert_eq!(check_frame(&app).unwrap(), frame);
+            assert_eq!(check_frame(&other).unwrap_err().status(), 403);
+        });
+        assert!(check_frame(&app).is_err());
+    }
+
+    #[test]
+    pub fn test_concurrent_frames() {
+        let states = MechtronStates::new(Arc::new(MemStateStore::new()));
+        let app = Point::from_str("localhost:app").unwrap();
+        let a = states.open(&app).unwrap();
+        let b = states.open(&app).unwrap();
+        states.stage(&a, vec![1]).unwrap();
+        states.stage(&b, vec![2]).unwrap();
+
+        // b staging its State does not replace the State a commits
+        states.commit(&a).unwrap();
+        assert_eq!(states.commit(&b).unwrap_err().status(), 409);
+        let c = states.open(&app).unwrap();
+        assert_eq!(states.load(&c).unwrap(), Some(vec![1]));
+
+        // a frame that stages nothing commits nothing
+        states.commit(&c).unwrap();
+        let d = states.open(&app).unwrap();
+        states.stage(&d, vec![3]).unwrap();
+        states.discard(&d);
+        assert!(states.commit(&d).is_err());
+        let e = states.open(&app).unwrap();
+        assert_eq!(states.load(&e).unwrap(), Some(vec![1]));
+    }
+
+    #[test]
+    pub fn test_file_names() {
+        let dir = std::env::temp_dir().join(format!("mechtron-state-{}", uuid::Uuid::new_v4()));
+        let store = FileStateStore::new(&dir).unwrap();
+        // both were once named `localhost_repo_app_1_0_0__state_1`
+        let underscore = Point::from_str("localhost:repo:app:1.0.0:/state_1").unwrap();
+        let dot = Point::from_str("localhost:repo:app:1.0.0:/state.1").unwrap();
+        store.save(&underscore, 0, vec![1]).unwrap();
+        store.save(&dot, 0, vec![2]).unwrap();
+        assert_eq!(store.load(&underscore).unwrap().unwrap().data, vec![1]);
+        assert_eq!(store.load(&dot).unwrap().unwrap().data, vec![2]);
+        assert_eq!(store.save(&dot, 0, vec![3]).unwrap_err().status(), 409);
+        std::fs::remove_dir_all(dir).unwrap_or_default();
+    }
+}
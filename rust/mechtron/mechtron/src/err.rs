@@ -139,3 +139,9 @@ impl Display for MembraneErr {
         f.write_fmt(format_args!("{}", self.error))
     }
 }
+
+impl From<MembraneErr> for SpaceErr {
+    fn from(e: MembraneErr) -> Self {
+        SpaceErr::server_error(e.error)
+    }
+}
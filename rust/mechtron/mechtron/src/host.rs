@@ -0,0 +1,82 @@
+//! Host functions added in membrane ABI version 2.
+//! A guest compiled against this crate reports version 2 via `mechtron_guest_version`
+//! and therefore will only be instantiated by a Host that supports them.
+
+use crate::membrane::{
+    mechtron_consume_buffer, mechtron_random_bytes, mechtron_request, mechtron_request_poll,
+    mechtron_timer, mechtron_write_wave_to_host,
+};
+use cosmic_space::err::SpaceErr;
+use cosmic_space::wave::core::Method;
+use cosmic_space::wave::{DirectedKind, DirectedProto, ReflectedWave, UltraWave};
+use std::time::Duration;
+
+/// `len` bytes from the Host's secure random source
+pub fn random_bytes(len: usize) -> Result<Vec<u8>, SpaceErr> {
+    let buffer_id = unsafe { mechtron_random_bytes(len as i32) };
+    if buffer_id < 0 {
+        return Err(SpaceErr::server_error("host refused to provide random bytes"));
+    }
+    Ok(mechtron_consume_buffer(buffer_id)?)
+}
+
+/// ask the Host to deliver `signal` (created via `DirectedProto::signal()`) after `delay`.
+/// The signal must be addressed to the Mechtron whose frame sets the timer, the Host
+/// refuses any other recipient and sends the signal from that Mechtron
+pub fn timer(delay: Duration, signal: DirectedProto) -> Result<(), SpaceErr> {
+    let wave = signal.build()?;
+    if !matches!(wave.directed_kind(), DirectedKind::Signal) {
+        return Err(SpaceErr::bad_request("timer wave must be a Signal"));
+    }
+    let wave_id = mechtron_write_wave_to_host(wave.to_ultra())?;
+    if unsafe { mechtron_timer(wave_id, delay.as_millis() as i64) } < 0 {
+        return Err(SpaceErr::server_error("host refused to set timer"));
+    }
+    Ok(())
+}
+
+/// an Http request sent by the Host on behalf of the guest without blocking the guest.
+/// Requests are intended for egress particles on a `Fold` star.
+/// The Pong is held by the Host until it is collected via `poll()`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AsyncRequest {
+    pub id: i32,
+}
+
+impl AsyncRequest {
+    /// `request` must be a Ping (`DirectedProto::ping()`) with an Http method
+    pub fn send(request: DirectedProto) -> Result<Self, SpaceErr> {
+        let wave = request.build()?;
+        if !matches!(wave.directed_kind(), DirectedKind::Ping)
+            || !matches!(wave.core().method, Method::Http(_))
+        {
+            return Err(SpaceErr::bad_request(
+                "async requests must be Http directed waves",
+            ));
+        }
+        let wave_id = mechtron_write_wave_to_host(wave.to_ultra())?;
+        let id = unsafe { mechtron_request(wave_id) };
+        if id < 0 {
+            return Err(SpaceErr::server_error("host refused async request"));
+        }
+        Ok(Self { id })
+    }
+
+    /// the Pong of this request or `None` if it hasn't arrived yet.
+    /// A Pong can only be collected once
+    pub fn poll(&self) -> Result<Option<ReflectedWave>, SpaceErr> {
+        let buffer_id = unsafe { mechtron_request_poll(self.id) };
+        if buffer_id < 0 {
+            return Err(SpaceErr::not_found(format!(
+                "unknown async request: {}",
+                self.id
+            )));
+        }
+        if buffer_id == 0 {
+            return Ok(None);
+        }
+        let buffer = mechtron_consume_buffer(buffer_id)?;
+        let wave: UltraWave = bincode::deserialize(buffer.as_slice())?;
+        Ok(Some(wave.to_reflected()?))
+    }
+}
@@ -2,8 +2,10 @@
 
 pub mod err;
 pub mod guest;
+pub mod host;
 pub mod membrane;
 pub mod space;
+pub mod state;
 #[cfg(test)]
 pub mod test;
 
@@ -35,7 +37,9 @@ use cosmic_space::parse::SkewerCase;
 use cosmic_space::particle::{Details, Stub};
 use cosmic_space::wasm::Timestamp;
 use cosmic_space::wave::exchange::SetStrategy;
-use cosmic_space::wave::{Agent, DirectedWave, ReflectedAggregate, ReflectedWave, UltraWave};
+use cosmic_space::wave::{
+    Agent, DirectedProto, DirectedWave, ReflectedAggregate, ReflectedWave, UltraWave,
+};
 use cosmic_space::{loc, VERSION};
 use std::collections::HashMap;
 use std::marker::PhantomData;
@@ -54,6 +58,7 @@ use cosmic_space::artifact::ArtRef;
 
 use crate::err::{GuestErr, MechErr};
 use crate::guest::GuestCtx;
+use crate::state::MechtronState;
 use crate::membrane::{mechtron_frame_to_host, mechtron_timestamp, mechtron_uuid};
 
 #[no_mangle]
@@ -170,6 +175,22 @@ where
         let point = self.bundle()?.push(path)?;
         Ok(self.artifacts.raw(&point)?)
     }
+
+    /// deliver `signal` back to this Mechtron after `delay`
+    pub fn timer(&self, delay: std::time::Duration, mut signal: DirectedProto) -> Result<(), P::Err> {
+        signal.to(self.details.stub.point.to_surface());
+        signal.from(self.details.stub.point.to_surface());
+        Ok(crate::host::timer(delay, signal)?)
+    }
+
+    /// load the persistent State of this Mechtron from the Host.
+    /// If the State has never been saved then `S::default()` is returned
+    pub fn state<S>(&self) -> Result<MechtronState<S>, P::Err>
+    where
+        S: serde::Serialize + serde::de::DeserializeOwned + Default + Send + Sync + 'static,
+    {
+        Ok(MechtronState::load(&self.details.stub.point)?)
+    }
 }
 
 /// MechtronLifecycle is the interface used by Guest
@@ -184,7 +205,9 @@ where
 }
 
 /// Create a Mechtron by implementing this trait.
-/// Mechtrons are created per request and disposed of afterwards...
+/// Mechtrons are created per request and disposed of afterwards,
+/// any State that must survive between requests should be loaded via
+/// `MechtronSkel::state()` so it is persisted by the Host...
 /// Implementers of this trait should only hold references to
 /// Mechtron::Skel, Mechtron::Cache & Mechtron::State at most.
 pub trait Mechtron<P>: MechtronLifecycle<P> + Sync + Send + 'static
@@ -203,8 +226,9 @@ where
     /// then implement ```type Cache=()``
     type Cache;
 
-    /// State is the aspect of the Mechtron that is changeable.  It is recommended
-    /// to wrap State in a Mutex or RwLock if used.  If you are implementing
+    /// State is the aspect of the Mechtron that is changeable.  A persistent
+    /// State should be a `MechtronState<S>` (from `MechtronSkel::state()`) which the
+    /// Host loads before `restore` and saves after the handler returns. If you are implementing
     /// a stateless mechtron then implement ```type State=();```
     type State;
 
@@ -20,6 +20,12 @@ extern "C" {
     pub fn mechtron_frame_to_host(frame: i32) -> i32;
     pub fn mechtron_uuid() -> i32;
     pub fn mechtron_timestamp() -> i64;
+    pub fn mechtron_state_load(point: i32) -> i32;
+    pub fn mechtron_state_save(point: i32, state: i32) -> i32;
+    pub fn mechtron_random_bytes(len: i32) -> i32;
+    pub fn mechtron_timer(wave: i32, delay: i64) -> i32;
+    pub fn mechtron_request(wave: i32) -> i32;
+    pub fn mechtron_request_poll(request: i32) -> i32;
 }
 
 #[no_mangle]
@@ -67,7 +73,13 @@ pub fn mechtron_frame_to_guest(frame: i32) -> i32 {
                 .unwrap()
         };
 
-        match handler.handle(wave) {
+        let bounce = handler.handle(wave);
+
+        // the handler is done with its states so hand them back to the host.
+        // a refused State is logged by the host & simply not persisted
+        crate::state::mechtron_commit_states().unwrap_or_default();
+
+        match bounce {
             Bounce::Absorbed => 0,
             Bounce::Reflected(wave) => {
                 let wave = mechtron_write_wave_to_host(wave.to_ultra()).unwrap();
@@ -118,9 +130,13 @@ extern "C" {
 
  */
 
+/// the membrane ABI version of this guest. The Host refuses guests whose
+/// version is newer than what it supports
+pub const MECHTRON_ABI_VERSION: i32 = 2;
+
 #[no_mangle]
 pub extern "C" fn mechtron_guest_version() -> i32 {
-    1
+    MECHTRON_ABI_VERSION
 }
 
 #[no_mangle]
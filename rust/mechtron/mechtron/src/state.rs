@@ -0,0 +1,135 @@
+use crate::membrane::{
+    mechtron_consume_buffer, mechtron_state_load, mechtron_state_save, mechtron_write_buffer,
+    mechtron_write_string,
+};
+use cosmic_space::err::SpaceErr;
+use cosmic_space::loc::Point;
+use dashmap::DashMap;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+lazy_static! {
+    /// states opened during the current frame, they are committed back to the host
+    /// after the handler returns
+    static ref OPEN_STATES: DashMap<Point, Box<dyn OpenState>> = DashMap::new();
+}
+
+/// Persistent State of a Mechtron.  The Host loads the State from the Star's durable
+/// store when it opens the frame, before the Mechtron is restored, and `load` hands
+/// the guest that copy.  It is saved back to the Host after the handler returns.
+/// The State is only saved if it was accessed via `write()`
+pub struct MechtronState<S>
+where
+    S: Serialize + DeserializeOwned + Default + Send + Sync + 'static,
+{
+    point: Point,
+    state: Arc<RwLock<S>>,
+    dirty: Arc<AtomicBool>,
+}
+
+impl<S> Clone for MechtronState<S>
+where
+    S: Serialize + DeserializeOwned + Default + Send + Sync + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            point: self.point.clone(),
+            state: self.state.clone(),
+            dirty: self.dirty.clone(),
+        }
+    }
+}
+
+impl<S> MechtronState<S>
+where
+    S: Serialize + DeserializeOwned + Default + Send + Sync + 'static,
+{
+    pub fn load(point: &Point) -> Result<Self, SpaceErr> {
+        let point_id = mechtron_write_string(point.to_string());
+        let state_id = unsafe { mechtron_state_load(point_id) };
+        if state_id < 0 {
+            return Err(SpaceErr::server_error(format!(
+                "host refused to load the state of {}",
+                point.to_string()
+            )));
+        }
+        let state: S = if state_id == 0 {
+            S::default()
+        } else {
+            let bin = mechtron_consume_buffer(state_id)?;
+            bincode::deserialize(bin.as_slice())?
+        };
+
+        let state = Self {
+            point: point.clone(),
+            state: Arc::new(RwLock::new(state)),
+            dirty: Arc::new(AtomicBool::new(false)),
+        };
+
+        OPEN_STATES.insert(point.clone(), Box::new(state.clone()));
+
+        Ok(state)
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, S> {
+        self.state.read().unwrap()
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, S> {
+        self.dirty.store(true, Ordering::Relaxed);
+        self.state.write().unwrap()
+    }
+}
+
+trait OpenState: Send + Sync {
+    fn is_dirty(&self) -> bool;
+    fn to_bin(&self) -> Result<Vec<u8>, SpaceErr>;
+}
+
+impl<S> OpenState for MechtronState<S>
+where
+    S: Serialize + DeserializeOwned + Default + Send + Sync + 'static,
+{
+    fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::Relaxed)
+    }
+
+    fn to_bin(&self) -> Result<Vec<u8>, SpaceErr> {
+        Ok(bincode::serialize(&*self.state.read().unwrap())?)
+    }
+}
+
+/// hand every dirty State opened during this frame back to the Host.
+/// Every open State is closed even if one of them cannot be serialized or the Host
+/// refuses it
+pub fn mechtron_commit_states() -> Result<(), SpaceErr> {
+    let points: Vec<Point> = OPEN_STATES.iter().map(|e| e.key().clone()).collect();
+    let mut refused = vec![];
+    for point in points {
+        if let Some((point, state)) = OPEN_STATES.remove(&point) {
+            if state.is_dirty() {
+                let saved = match state.to_bin() {
+                    Ok(bin) => {
+                        let point_id = mechtron_write_string(point.to_string());
+                        let state_id = mechtron_write_buffer(bin);
+                        unsafe { mechtron_state_save(point_id, state_id) } >= 0
+                    }
+                    Err(_) => false,
+                };
+                if !saved {
+                    refused.push(point.to_string());
+                }
+            }
+        }
+    }
+    if refused.is_empty() {
+        Ok(())
+    } else {
+        Err(SpaceErr::server_error(format!(
+            "host refused to save the state of {}",
+            refused.join(", ")
+        )))
+    }
+}
@@ -31,6 +31,7 @@ use cosmic_hyperspace::driver::artifact::{
 use cosmic_hyperspace::driver::base::BaseDriverFactory;
 use cosmic_hyperspace::driver::control::ControlDriverFactory;
 use cosmic_hyperspace::driver::mechtron::{HostDriverFactory, MechtronDriverFactory};
+use cosmic_hyperspace::driver::portal::PortalDriverFactory;
 use cosmic_hyperspace::driver::root::RootDriverFactory;
 use cosmic_hyperspace::driver::space::SpaceDriverFactory;
 use cosmic_hyperspace::driver::{DriverAvail, DriversBuilder};
@@ -218,6 +219,7 @@ impl Cosmos for Starlane {
             }
             StarSub::Jump => {
                 builder.add_post(Arc::new(WebDriverFactory::new()));
+                builder.add_post(Arc::new(PortalDriverFactory::new()));
                 // builder.add_post(Arc::new(ControlDriverFactory::new()));
             }
             StarSub::Fold => {}